@@ -0,0 +1,193 @@
+//! Lets a conductor pause guest execution at a chosen address: a vectored exception handler
+//! installs an `int3` trap there (the same trap-and-resume mechanism `crate::rdtsc` uses to
+//! virtualize timestamp reads), blocking the faulting thread on an OS event until [`resume`] wakes
+//! it back up. Pausing a thread this way costs no virtual time - nothing in the simulated clock
+//! (see `crate::state`) advances while a thread merely sits blocked waiting to be resumed.
+
+use shared::{
+    ipc::{message, Sender},
+    windows::{event::ManualResetEvent, process},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex, OnceLock},
+};
+use winapi::um::{
+    errhandlingapi::AddVectoredExceptionHandler,
+    processthreadsapi::GetCurrentThreadId,
+    winnt::{CONTEXT, EXCEPTION_POINTERS},
+};
+
+// avoids depending on the exact winapi re-export paths for these, which vary by crate version
+const EXCEPTION_BREAKPOINT: u32 = 0x8000_0003;
+const EXCEPTION_SINGLE_STEP: u32 = 0x8000_0004;
+const EXCEPTION_CONTINUE_EXECUTION: i32 = -1;
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+const TRAP_FLAG: u32 = 1 << 8;
+
+struct PauseState {
+    address: usize,
+    resume_event: Mutex<ManualResetEvent>,
+    single_step: Mutex<bool>,
+}
+
+/// `address` -> the original byte overwritten with `0xcc` to trap on it.
+static BREAKPOINTS: Mutex<BTreeMap<usize, u8>> = Mutex::new(BTreeMap::new());
+/// Threads a one-shot single-step trap (see [`arm_single_step`]) was armed for.
+static SINGLE_STEPPING: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+/// Threads currently paused at a trap, waiting on [`resume`].
+static PAUSED: Mutex<BTreeMap<u32, Arc<PauseState>>> = Mutex::new(BTreeMap::new());
+/// A conductor's outstanding `WaitForPause` response sender, answered the next time any thread
+/// pauses. See `shared::ipc::message::FromConductor::WaitForPause`.
+static PENDING_WAITER: Mutex<Option<Sender<message::DebugPause>>> = Mutex::new(None);
+static HANDLER_INSTALLED: OnceLock<()> = OnceLock::new();
+
+fn install_handler() {
+    HANDLER_INSTALLED.get_or_init(|| unsafe {
+        // installed first so that it is consulted before any other handlers the guest installs
+        AddVectoredExceptionHandler(1, Some(exception_handler));
+    });
+}
+
+unsafe extern "system" fn exception_handler(exception_pointers: *mut EXCEPTION_POINTERS) -> i32 {
+    let record = unsafe { &*(*exception_pointers).ExceptionRecord };
+    let thread_id = unsafe { GetCurrentThreadId() };
+    let context = unsafe { &mut *(*exception_pointers).ContextRecord };
+
+    if record.ExceptionCode == EXCEPTION_BREAKPOINT {
+        let address = record.ExceptionAddress as usize;
+        let Some(original_byte) = BREAKPOINTS.lock().unwrap().remove(&address) else {
+            return EXCEPTION_CONTINUE_SEARCH;
+        };
+        let _ = process::Process::get_current().write(address as *mut u8, &[original_byte]);
+        rewind_to_address(context, address);
+        if pause_and_wait(thread_id, address) {
+            arm_single_step(context, thread_id);
+        }
+        return EXCEPTION_CONTINUE_EXECUTION;
+    }
+
+    let is_our_single_step = record.ExceptionCode == EXCEPTION_SINGLE_STEP
+        && SINGLE_STEPPING.lock().unwrap().remove(&thread_id);
+    if is_our_single_step {
+        let address = current_instruction_pointer(context);
+        if pause_and_wait(thread_id, address) {
+            arm_single_step(context, thread_id);
+        }
+        return EXCEPTION_CONTINUE_EXECUTION;
+    }
+
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+#[cfg(target_arch = "x86")]
+fn rewind_to_address(context: &mut CONTEXT, address: usize) {
+    #[expect(clippy::cast_possible_truncation)]
+    {
+        context.Eip = address as u32;
+    }
+}
+
+#[cfg(target_arch = "x86")]
+fn current_instruction_pointer(context: &CONTEXT) -> usize {
+    context.Eip as usize
+}
+
+#[cfg(target_arch = "x86_64")]
+fn rewind_to_address(context: &mut CONTEXT, address: usize) {
+    context.Rip = address as u64;
+}
+
+#[cfg(target_arch = "x86_64")]
+fn current_instruction_pointer(context: &CONTEXT) -> usize {
+    context.Rip as usize
+}
+
+fn arm_single_step(context: &mut CONTEXT, thread_id: u32) {
+    SINGLE_STEPPING.lock().unwrap().insert(thread_id);
+    context.EFlags |= TRAP_FLAG;
+}
+
+/// Registers `thread_id` as paused at `address`, notifies a waiting conductor (if any), and blocks
+/// the calling thread until [`resume`] is called for it. Returns whether the resumer asked for a
+/// single step rather than free execution.
+fn pause_and_wait(thread_id: u32, address: usize) -> bool {
+    let pause_state = Arc::new(PauseState {
+        address,
+        resume_event: Mutex::new(ManualResetEvent::new().unwrap()),
+        single_step: Mutex::new(false),
+    });
+    PAUSED
+        .lock()
+        .unwrap()
+        .insert(thread_id, Arc::clone(&pause_state));
+
+    if let Some(mut response_sender) = PENDING_WAITER.lock().unwrap().take() {
+        let _ = futures::executor::block_on(
+            response_sender.send(message::DebugPause { thread_id, address }),
+        );
+    }
+
+    let resume_event = pause_state.resume_event.lock().unwrap().try_clone().unwrap();
+    futures::executor::block_on(resume_event.wait()).unwrap();
+
+    PAUSED.lock().unwrap().remove(&thread_id);
+    *pause_state.single_step.lock().unwrap()
+}
+
+/// Patches a breakpoint trap at `address`, replacing (and remembering) whatever byte was there.
+/// Does nothing if a breakpoint is already armed there.
+pub(crate) fn set_breakpoint(address: usize) {
+    install_handler();
+
+    let process = process::Process::get_current();
+    let mut breakpoints = BREAKPOINTS.lock().unwrap();
+    if breakpoints.contains_key(&address) {
+        return;
+    }
+    let Ok(original_bytes) = process.read_to_vec(address as *const u8, 1) else {
+        return;
+    };
+    if process.write(address as *mut u8, &[0xcc]).is_err() {
+        return;
+    }
+    breakpoints.insert(address, original_bytes[0]);
+}
+
+/// Removes a not-yet-hit breakpoint previously armed by [`set_breakpoint`], restoring the
+/// original byte. Has no effect on a breakpoint that has already fired (and so already removed
+/// itself from the breakpoint map) or one that was never armed.
+pub(crate) fn clear_breakpoint(address: usize) {
+    if let Some(original_byte) = BREAKPOINTS.lock().unwrap().remove(&address) {
+        let _ = process::Process::get_current().write(address as *mut u8, &[original_byte]);
+    }
+}
+
+/// Registers `response_sender` to be answered the next time any thread pauses at a trap, or
+/// immediately if a thread is already paused waiting for a waiter.
+pub(crate) fn wait_for_pause(mut response_sender: Sender<message::DebugPause>) {
+    let already_paused = PAUSED
+        .lock()
+        .unwrap()
+        .iter()
+        .next()
+        .map(|(&thread_id, pause_state)| (thread_id, pause_state.address));
+    match already_paused {
+        Some((thread_id, address)) => {
+            let _ = futures::executor::block_on(
+                response_sender.send(message::DebugPause { thread_id, address }),
+            );
+        }
+        None => *PENDING_WAITER.lock().unwrap() = Some(response_sender),
+    }
+}
+
+/// Wakes the thread paused at `thread_id` (see [`pause_and_wait`]); if `single_step` is set, it
+/// pauses again after exactly one more instruction instead of running freely.
+pub(crate) fn resume(thread_id: u32, single_step: bool) {
+    let Some(pause_state) = PAUSED.lock().unwrap().get(&thread_id).map(Arc::clone) else {
+        return;
+    };
+    *pause_state.single_step.lock().unwrap() = single_step;
+    pause_state.resume_event.lock().unwrap().set().unwrap();
+}
@@ -0,0 +1,145 @@
+use shared::windows::process::{self, MemoryPermissions, MemoryPermissionsRwe};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::c_void,
+    sync::{Mutex, OnceLock},
+};
+use winapi::um::{
+    errhandlingapi::AddVectoredExceptionHandler,
+    memoryapi::VirtualProtect,
+    winnt::{EXCEPTION_POINTERS, EXCEPTION_RECORD},
+};
+
+// avoids depending on the exact winapi re-export paths for these, which vary by crate version
+const EXCEPTION_ACCESS_VIOLATION: u32 = 0xc000_0005;
+const EXCEPTION_CONTINUE_EXECUTION: i32 = -1;
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+struct TrackedRegion {
+    size: usize,
+    original_permissions: MemoryPermissions,
+}
+
+static TRACKED_REGIONS: Mutex<BTreeMap<usize, TrackedRegion>> = Mutex::new(BTreeMap::new());
+static DIRTY_PAGES: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+static HANDLER_INSTALLED: OnceLock<()> = OnceLock::new();
+
+fn page_size() -> usize {
+    shared::windows::system::get_info().dwPageSize as usize
+}
+
+fn install_handler() {
+    HANDLER_INSTALLED.get_or_init(|| unsafe {
+        // installed first so that it is consulted before any other handlers the guest installs
+        AddVectoredExceptionHandler(1, Some(exception_handler));
+    });
+}
+
+unsafe extern "system" fn exception_handler(exception_pointers: *mut EXCEPTION_POINTERS) -> i32 {
+    let record = unsafe { &*(*exception_pointers).ExceptionRecord };
+    if record.ExceptionCode != EXCEPTION_ACCESS_VIOLATION {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    let is_write = record.ExceptionInformation[0] == 1;
+    if !is_write {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    let faulting_address = record.ExceptionInformation[1] as usize;
+    let page_size = page_size();
+    let page_address = faulting_address & !(page_size - 1);
+
+    let mut tracked_regions = TRACKED_REGIONS.lock().unwrap();
+    let Some((&region_address, region)) = tracked_regions
+        .range(..=page_address)
+        .next_back()
+        .filter(|(&address, region)| page_address < address + region.size)
+    else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+    let _ = region_address;
+
+    unsafe {
+        let mut previous_protection = 0;
+        if VirtualProtect(
+            page_address as *mut c_void,
+            page_size,
+            region.original_permissions.to_winapi_constant(),
+            &mut previous_protection,
+        ) == 0
+        {
+            return EXCEPTION_CONTINUE_SEARCH;
+        }
+    }
+
+    DIRTY_PAGES.lock().unwrap().insert(page_address);
+
+    EXCEPTION_CONTINUE_EXECUTION
+}
+
+/// Marks every region in `regions` (address, size) as read-only, remembering its original
+/// permissions, and starts accumulating the set of pages subsequently written to. Call again to
+/// re-arm after [`take_dirty_pages`].
+pub(crate) fn arm(regions: &[(usize, usize)]) {
+    install_handler();
+
+    let process = process::Process::get_current();
+    let mut tracked_regions = TRACKED_REGIONS.lock().unwrap();
+    tracked_regions.clear();
+    DIRTY_PAGES.lock().unwrap().clear();
+
+    for &(address, size) in regions {
+        let Ok(original_permissions) = process.set_memory_permissions(
+            address as *mut c_void,
+            size,
+            MemoryPermissions {
+                rwe: MemoryPermissionsRwe::Read,
+                is_guard: false,
+            },
+        ) else {
+            // pages owned by the kernel (async I/O, DMA, ...) may not be protectable; leave them
+            // untracked so that they are conservatively re-read in full on every save
+            continue;
+        };
+        tracked_regions.insert(
+            address,
+            TrackedRegion {
+                size,
+                original_permissions,
+            },
+        );
+    }
+}
+
+/// Returns the base addresses of every page written to since the last [`arm`] or
+/// [`take_dirty_pages`] call, then re-protects those pages and resumes tracking.
+pub(crate) fn take_dirty_pages() -> Vec<usize> {
+    let process = process::Process::get_current();
+    let dirty_pages = std::mem::take(&mut *DIRTY_PAGES.lock().unwrap());
+    let page_size = page_size();
+    let tracked_regions = TRACKED_REGIONS.lock().unwrap();
+
+    let mut result = Vec::with_capacity(dirty_pages.len());
+    for page_address in dirty_pages {
+        result.push(page_address);
+
+        if let Some((&region_address, region)) = tracked_regions
+            .range(..=page_address)
+            .next_back()
+            .filter(|(&address, region)| page_address < address + region.size)
+        {
+            let _ = region_address;
+            let _ = process.set_memory_permissions(
+                page_address as *mut c_void,
+                page_size,
+                MemoryPermissions {
+                    rwe: MemoryPermissionsRwe::Read,
+                    is_guard: false,
+                },
+            );
+        }
+    }
+
+    result
+}
@@ -1,24 +1,33 @@
+mod console;
+mod gamepad;
 mod input;
 mod library;
 mod misc;
+mod process;
+mod raw_input;
+mod registry;
+mod socket;
+mod sync_objects;
+mod synchronization;
+mod thread;
 mod time;
 mod window;
 
-use crate::log;
-use minhook::MinHook;
-use shared::{
-    ipc::message::LogLevel,
-    windows::{module, process},
-};
+use registry::HookRegistry;
+use shared::windows::{module, process};
 use std::{
     collections::{BTreeMap, BTreeSet},
-    ffi::{OsStr, OsString},
-    sync::{LazyLock, Mutex, RwLock},
+    ffi::OsString,
+    sync::{LazyLock, Mutex},
 };
 use winapi::ctypes::c_void;
 
-pub(crate) static TRAMPOLINES: RwLock<BTreeMap<String, usize>> = RwLock::new(BTreeMap::new());
+pub(crate) static REGISTRY: HookRegistry = HookRegistry::new();
 
+/// Fetches the trampoline installed for `$name` (the original function MinHook redirected,
+/// callable to invoke real behavior), type-checked against `$name`'s own signature. Panics if
+/// `$name` was never successfully hooked; use [`REGISTRY`]'s [`HookRegistry::trampoline`] directly
+/// for a non-panicking lookup.
 macro_rules! get_trampoline {
     ($name:expr, $type:ty $(,)?) => {{
         let mut f: $type;
@@ -26,35 +35,30 @@ macro_rules! get_trampoline {
         {
             f = $name; // type check
         }
-        unsafe {
-            f = std::mem::transmute::<usize, $type>(
-                *crate::hooks::TRAMPOLINES
-                    .read()
-                    .unwrap()
-                    .get(stringify!($name))
-                    .unwrap(),
-            )
-        };
+        f = crate::hooks::REGISTRY
+            .trampoline::<$type>(stringify!($name))
+            .unwrap();
         f
     }};
 }
 pub(crate) use get_trampoline;
 
-fn set_trampoline(name: impl AsRef<str>, pointer: *const c_void) {
-    TRAMPOLINES
-        .write()
-        .unwrap()
-        .insert(name.as_ref().to_string(), pointer as usize);
-}
-
 static HOOKS: LazyLock<BTreeMap<OsString, Vec<(&str, usize)>>> = LazyLock::new(|| {
     let mut map = BTreeMap::<_, Vec<_>>::new();
     for (module_name, function_name, hook) in [
         library::HOOKS,
         input::HOOKS,
+        gamepad::HOOKS,
         time::HOOKS,
         window::HOOKS,
         misc::HOOKS,
+        process::HOOKS,
+        raw_input::HOOKS,
+        socket::HOOKS,
+        sync_objects::HOOKS,
+        synchronization::HOOKS,
+        thread::HOOKS,
+        console::HOOKS,
     ]
     .concat()
     {
@@ -70,6 +74,7 @@ pub(crate) fn initialize() {
     for module in process.get_modules().unwrap() {
         apply_to_module(&module);
     }
+    window::subclass_existing_windows();
 }
 
 static HOOKED_MODULE_ADDRESSES: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
@@ -83,33 +88,11 @@ pub(crate) fn apply_to_module(module: &module::Module) {
     }
 
     let module_name = module.get_name().unwrap().to_ascii_lowercase();
-    log!(LogLevel::Debug, "applying hooks to {:?}", module_name);
+    log::debug!("applying hooks to {:?}", module_name);
+    crate::rdtsc::virtualize_module(module);
     for &(function_name, hook) in HOOKS.get(&module_name).unwrap_or(&vec![]) {
-        fn hook_function(
-            module_name: &OsStr,
-            function_name: &str,
-            hook: *const c_void,
-        ) -> Result<(), Box<dyn std::error::Error>> {
-            let process = process::Process::get_current();
-            let function_address = process
-                .get_module(module_name)?
-                .ok_or("module not found")?
-                .get_export_address(function_name)?;
-            unsafe {
-                let original_function =
-                    MinHook::create_hook(function_address, hook as *mut std::ffi::c_void).unwrap();
-                MinHook::enable_hook(function_address).unwrap();
-                set_trampoline(function_name, original_function.cast());
-            }
-            Ok(())
-        }
-
-        let result = hook_function(&module_name, function_name, hook as *const c_void);
-        if let Err(error) = result {
-            log!(
-                LogLevel::Debug,
-                "failed to hook: {function_name}; error: {error}"
-            );
+        if let Err(error) = REGISTRY.register(&module_name, function_name, hook as *const c_void) {
+            log::debug!("failed to hook: {function_name}; error: {error}");
         }
     }
 
@@ -0,0 +1,13 @@
+use crate::state;
+use hooks_macros::{hook, hooks};
+use winapi::{ctypes::c_void, shared::minwindef::BOOL, um::wincon::PHANDLER_ROUTINE};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![SetConsoleCtrlHandler];
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn SetConsoleCtrlHandler(
+    handler: PHANDLER_ROUTINE,
+    add: BOOL,
+) -> BOOL {
+    i32::from(state::set_console_ctrl_handler(handler, add != 0))
+}
@@ -0,0 +1,148 @@
+//! `XInputGetState`/`XInputGetCapabilities`, read back from the virtual controllers
+//! [`state::State::set_gamepad_button`]/`set_gamepad_axis`/`set_gamepad_trigger` populate. Unlike
+//! the window-message input hooks, there's no event to synthesize here - XInput is a polling API,
+//! so these just snapshot whatever [`STATE`] currently holds.
+
+use crate::state::{self, STATE};
+use hooks_macros::{hook, hooks};
+use winapi::{
+    ctypes::c_void,
+    shared::winerror::{ERROR_DEVICE_NOT_CONNECTED, ERROR_SUCCESS},
+    um::xinput::{XINPUT_CAPABILITIES, XINPUT_GAMEPAD, XINPUT_STATE},
+};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] =
+    &hooks![XInputGetState, XInputGetCapabilities];
+
+fn gamepad_to_xinput(gamepad: &state::GamepadState) -> XINPUT_GAMEPAD {
+    let (left_stick_x, left_stick_y, right_stick_x, right_stick_y) = gamepad.thumbsticks();
+    let (left_trigger, right_trigger) = gamepad.triggers();
+    XINPUT_GAMEPAD {
+        wButtons: gamepad.buttons(),
+        bLeftTrigger: left_trigger,
+        bRightTrigger: right_trigger,
+        sThumbLX: left_stick_x,
+        sThumbLY: left_stick_y,
+        sThumbRX: right_stick_x,
+        sThumbRY: right_stick_y,
+    }
+}
+
+#[hook("xinput1_4.dll")]
+unsafe extern "system" fn XInputGetState(user_index: u32, out_state: *mut XINPUT_STATE) -> u32 {
+    let Some(gamepad) = user_index
+        .try_into()
+        .ok()
+        .and_then(|user_index: u8| STATE.lock().unwrap().gamepad(user_index).copied())
+    else {
+        return ERROR_DEVICE_NOT_CONNECTED;
+    };
+    unsafe {
+        *out_state = XINPUT_STATE {
+            dwPacketNumber: gamepad.packet_number,
+            Gamepad: gamepad_to_xinput(&gamepad),
+        };
+    }
+    ERROR_SUCCESS
+}
+
+#[hook("xinput1_4.dll")]
+unsafe extern "system" fn XInputGetCapabilities(
+    user_index: u32,
+    _flags: u32,
+    capabilities: *mut XINPUT_CAPABILITIES,
+) -> u32 {
+    let Some(gamepad) = user_index
+        .try_into()
+        .ok()
+        .and_then(|user_index: u8| STATE.lock().unwrap().gamepad(user_index).copied())
+    else {
+        return ERROR_DEVICE_NOT_CONNECTED;
+    };
+    unsafe {
+        *capabilities = XINPUT_CAPABILITIES {
+            Type: winapi::um::xinput::XINPUT_DEVTYPE_GAMEPAD,
+            SubType: winapi::um::xinput::XINPUT_DEVSUBTYPE_GAMEPAD,
+            Flags: 0,
+            Gamepad: gamepad_to_xinput(&gamepad),
+            Vibration: winapi::um::xinput::XINPUT_VIBRATION {
+                wLeftMotorSpeed: 0,
+                wRightMotorSpeed: 0,
+            },
+        };
+    }
+    ERROR_SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::input::{GamepadAxis, GamepadButton, GamepadTrigger};
+
+    const XINPUT_GAMEPAD_A: u16 = 0x1000;
+
+    /// Exercises `State::set_gamepad_button`/`set_gamepad_axis`/`set_gamepad_trigger` and
+    /// [`gamepad_to_xinput`] together - the same path `XInputGetState`/`XInputGetCapabilities` read
+    /// through - since no C test program drives XInput for a `test_for` integration test to cover.
+    /// Each test claims its own gamepad slot so they can run concurrently against the shared
+    /// [`STATE`] without one test's packet-number bump confusing another's assertions.
+    #[test]
+    fn button_press_sets_bit_and_bumps_packet_number() {
+        const INDEX: u8 = 0;
+        let mut state = STATE.lock().unwrap();
+        let packet_number_before = state.gamepad(INDEX).unwrap().packet_number;
+
+        state.set_gamepad_button(INDEX, GamepadButton::A, true);
+        let gamepad = *state.gamepad(INDEX).unwrap();
+        assert_eq!(gamepad.packet_number, packet_number_before + 1);
+        assert_eq!(gamepad_to_xinput(&gamepad).wButtons & XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_A);
+
+        // Setting the same state again is a no-op on the packet number.
+        state.set_gamepad_button(INDEX, GamepadButton::A, true);
+        assert_eq!(state.gamepad(INDEX).unwrap().packet_number, gamepad.packet_number);
+
+        state.set_gamepad_button(INDEX, GamepadButton::A, false);
+        let gamepad = *state.gamepad(INDEX).unwrap();
+        assert_eq!(gamepad.packet_number, packet_number_before + 2);
+        assert_eq!(gamepad_to_xinput(&gamepad).wButtons & XINPUT_GAMEPAD_A, 0);
+    }
+
+    #[test]
+    fn axis_change_updates_thumbstick_and_bumps_packet_number() {
+        const INDEX: u8 = 1;
+        let mut state = STATE.lock().unwrap();
+        let packet_number_before = state.gamepad(INDEX).unwrap().packet_number;
+
+        state.set_gamepad_axis(INDEX, GamepadAxis::LeftX, 12345);
+        let gamepad = *state.gamepad(INDEX).unwrap();
+        assert_eq!(gamepad.packet_number, packet_number_before + 1);
+        assert_eq!(gamepad_to_xinput(&gamepad).sThumbLX, 12345);
+
+        state.set_gamepad_axis(INDEX, GamepadAxis::LeftX, 12345);
+        assert_eq!(state.gamepad(INDEX).unwrap().packet_number, gamepad.packet_number);
+    }
+
+    #[test]
+    fn trigger_change_updates_value_and_bumps_packet_number() {
+        const INDEX: u8 = 2;
+        let mut state = STATE.lock().unwrap();
+        let packet_number_before = state.gamepad(INDEX).unwrap().packet_number;
+
+        state.set_gamepad_trigger(INDEX, GamepadTrigger::Right, 200);
+        let gamepad = *state.gamepad(INDEX).unwrap();
+        assert_eq!(gamepad.packet_number, packet_number_before + 1);
+        assert_eq!(gamepad_to_xinput(&gamepad).bRightTrigger, 200);
+
+        state.set_gamepad_trigger(INDEX, GamepadTrigger::Right, 200);
+        assert_eq!(state.gamepad(INDEX).unwrap().packet_number, gamepad.packet_number);
+    }
+
+    #[test]
+    fn out_of_range_index_is_ignored() {
+        let mut state = STATE.lock().unwrap();
+        state.set_gamepad_button(200, GamepadButton::A, true);
+        state.set_gamepad_axis(200, GamepadAxis::LeftX, 1);
+        state.set_gamepad_trigger(200, GamepadTrigger::Left, 1);
+        assert!(state.gamepad(200).is_none());
+    }
+}
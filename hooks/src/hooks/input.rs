@@ -1,20 +1,26 @@
-use crate::state::STATE;
+use crate::state::{self, STATE};
 use hooks_macros::{hook, hooks};
 use winapi::{
     ctypes::c_void,
-    um::winuser::{GetAsyncKeyState, GetKeyState, GetKeyboardState},
+    shared::{minwindef::{BOOL, DWORD, TRUE}, windef::POINT},
+    um::winuser::{
+        GetAsyncKeyState, GetCursorPos, GetKeyState, GetKeyboardState, GetMessagePos, SetCursorPos,
+    },
 };
 
-pub(crate) const HOOKS: &[(&str, &str, *const c_void)] =
-    &hooks![GetKeyboardState, GetKeyState, GetAsyncKeyState];
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
+    GetKeyboardState,
+    GetKeyState,
+    GetAsyncKeyState,
+    GetCursorPos,
+    SetCursorPos,
+    GetMessagePos,
+];
 
 #[hook("user32.dll")]
 unsafe extern "system" fn GetKeyboardState(key_states: *mut u8) -> i32 {
-    let state = STATE.lock().unwrap();
-    for i in 0u8..=255u8 {
-        unsafe {
-            *(key_states.offset(isize::from(i))) = u8::from(state.get_key_state(i)) << 7;
-        }
+    unsafe {
+        std::ptr::copy_nonoverlapping(state::key_state_array().as_ptr(), key_states, 256);
     }
     1
 }
@@ -23,10 +29,43 @@ unsafe extern "system" fn GetKeyboardState(key_states: *mut u8) -> i32 {
 #[expect(clippy::cast_sign_loss)]
 #[hook("user32.dll")]
 unsafe extern "system" fn GetKeyState(id: i32) -> i16 {
-    i16::from(STATE.lock().unwrap().get_key_state(id as u8)) << 15
+    let state = STATE.lock().unwrap();
+    let id = id as u8;
+    (i16::from(state.get_key_state(id)) << 15) | i16::from(state.toggle_state(id))
 }
 
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
 #[hook("user32.dll")]
 unsafe extern "system" fn GetAsyncKeyState(id: i32) -> i16 {
-    unsafe { GetKeyState(id) }
+    i16::from(STATE.lock().unwrap().get_key_state(id as u8)) << 15
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn GetCursorPos(point: *mut POINT) -> BOOL {
+    let state = STATE.lock().unwrap();
+    unsafe {
+        (*point).x = i32::from(state.mouse.x);
+        (*point).y = i32::from(state.mouse.y);
+    }
+    TRUE
+}
+
+/// Warps the virtual cursor instead of the real desktop one, so programs that poll-and-warp the
+/// cursor stay deterministic: the next [`GetCursorPos`] echoes whatever the guest just set here.
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+#[hook("user32.dll")]
+unsafe extern "system" fn SetCursorPos(x: i32, y: i32) -> BOOL {
+    let mut state = STATE.lock().unwrap();
+    state.mouse.x = x as u16;
+    state.mouse.y = y as u16;
+    TRUE
+}
+
+#[expect(clippy::cast_sign_loss)]
+#[hook("user32.dll")]
+unsafe extern "system" fn GetMessagePos() -> DWORD {
+    let state = STATE.lock().unwrap();
+    (u32::from(state.mouse.y) << 16) | u32::from(state.mouse.x)
 }
@@ -1,30 +1,101 @@
-use crate::state::{self, State, STATE};
+use super::get_trampoline;
+use crate::state::{self, EmulatedHandle, State, STATE};
 use hooks_macros::{hook, hooks};
 use ntapi::ntpsapi::{NtSetInformationThread, ThreadHideFromDebugger, THREADINFOCLASS};
-use std::sync::Arc;
 use winapi::{
     ctypes::c_void,
     shared::{ntdef::HANDLE, ntstatus::STATUS_SUCCESS, winerror::WAIT_TIMEOUT},
     um::{
-        handleapi::CloseHandle,
-        synchapi::WaitForSingleObject,
-        winbase::WAIT_OBJECT_0,
-        winsock2::{socket, INVALID_SOCKET},
+        handleapi::{CloseHandle, DuplicateHandle},
+        processthreadsapi::{ExitThread, GetCurrentProcess, GetCurrentThreadId},
+        synchapi::{
+            WaitForMultipleObjects, WaitForMultipleObjectsEx, WaitForSingleObject,
+            WaitForSingleObjectEx,
+        },
+        winbase::{INFINITE, WAIT_IO_COMPLETION, WAIT_OBJECT_0},
+        winuser::{MsgWaitForMultipleObjects, MsgWaitForMultipleObjectsEx, MWMO_ALERTABLE, MWMO_WAITALL},
     },
 };
 
 pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
     CloseHandle,
+    DuplicateHandle,
     WaitForSingleObject,
-    socket,
-    NtSetInformationThread
+    WaitForSingleObjectEx,
+    WaitForMultipleObjects,
+    WaitForMultipleObjectsEx,
+    MsgWaitForMultipleObjects,
+    MsgWaitForMultipleObjectsEx,
+    NtSetInformationThread,
+    ExitThread,
 ];
 
+/// Drops `handle` from the emulated handle registry (see [`EmulatedHandle`]) if it's tracked there,
+/// then always forwards to the real `CloseHandle` - unlike the old leak-everything stub, the real
+/// handle is actually closed now, since removing the registry entry (and, if it was the last one
+/// sharing the underlying `Arc`, the emulated object itself) no longer depends on the real handle
+/// staying open.
 #[hook("kernel32.dll")]
-unsafe extern "system" fn CloseHandle(_handle: *mut c_void) -> i32 {
-    // TODO: temporary solution; leak all handles to ensure that they still exist
-    // after loading a state
-    1
+unsafe extern "system" fn CloseHandle(handle: *mut c_void) -> i32 {
+    STATE.lock().unwrap().handles.remove(&(handle as u32));
+    let trampoline = get_trampoline!(CloseHandle, unsafe extern "system" fn(*mut c_void) -> i32);
+    unsafe { trampoline(handle) }
+}
+
+/// Forwards to the real `DuplicateHandle` to obtain a genuine new real handle, then, if the source
+/// handle is a tracked [`EmulatedHandle`] and both the source and target process handles refer to
+/// the current process, registers the new handle against a clone of the same underlying `Arc` so it
+/// shares state with the handle it was duplicated from. Duplicating into another process is left
+/// untracked - that process doesn't have this hooks DLL's registry entry to share, and isn't
+/// necessarily hooked at all - so a cross-process duplicate of an emulated handle behaves from here
+/// on like any other un-emulated one.
+#[hook("kernel32.dll")]
+unsafe extern "system" fn DuplicateHandle(
+    source_process: *mut c_void,
+    source_handle: *mut c_void,
+    target_process: *mut c_void,
+    target_handle: *mut *mut c_void,
+    desired_access: u32,
+    inherit_handle: i32,
+    options: u32,
+) -> i32 {
+    let trampoline = get_trampoline!(
+        DuplicateHandle,
+        unsafe extern "system" fn(
+            *mut c_void,
+            *mut c_void,
+            *mut c_void,
+            *mut *mut c_void,
+            u32,
+            i32,
+            u32,
+        ) -> i32
+    );
+    let result = unsafe {
+        trampoline(
+            source_process,
+            source_handle,
+            target_process,
+            target_handle,
+            desired_access,
+            inherit_handle,
+            options,
+        )
+    };
+    if result != 0 {
+        let current_process = unsafe { GetCurrentProcess() };
+        if source_process == current_process && target_process == current_process {
+            let object = STATE.lock().unwrap().handles.get(&(source_handle as u32)).cloned();
+            if let Some(object) = object {
+                STATE
+                    .lock()
+                    .unwrap()
+                    .handles
+                    .insert(unsafe { *target_handle } as u32, object);
+            }
+        }
+    }
+    result
 }
 
 #[hook("kernel32.dll")]
@@ -32,44 +103,344 @@ unsafe extern "system" fn WaitForSingleObject(
     object: *mut c_void,
     timeout_in_milliseconds: u32,
 ) -> u32 {
-    let waitable_timer = STATE
-        .lock()
-        .unwrap()
-        .waitable_timer_handles
-        .get(&(object as u32))
-        .map(Arc::clone);
-    if let Some(waitable_timer) = waitable_timer {
-        let sleep_time;
+    match try_wait_for_objects(&[object], false, timeout_in_milliseconds) {
+        Some(result) => result,
+        None => {
+            let trampoline = get_trampoline!(
+                WaitForSingleObject,
+                unsafe extern "system" fn(*mut c_void, u32) -> u32
+            );
+            wait_for_real_objects(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+                trampoline(object, poll_timeout_in_milliseconds)
+            })
+        }
+    }
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn WaitForSingleObjectEx(
+    object: *mut c_void,
+    timeout_in_milliseconds: u32,
+    alertable: i32,
+) -> u32 {
+    let result = match try_wait_for_objects(&[object], false, timeout_in_milliseconds) {
+        Some(result) => result,
+        None => {
+            let trampoline = get_trampoline!(
+                WaitForSingleObjectEx,
+                unsafe extern "system" fn(*mut c_void, u32, i32) -> u32
+            );
+            wait_for_real_objects(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+                trampoline(object, poll_timeout_in_milliseconds, alertable)
+            })
+        }
+    };
+    if alertable != 0 && state::drain_timer_apcs() {
+        WAIT_IO_COMPLETION
+    } else {
+        result
+    }
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn WaitForMultipleObjects(
+    count: u32,
+    objects: *const *mut c_void,
+    wait_all: i32,
+    timeout_in_milliseconds: u32,
+) -> u32 {
+    let objects = unsafe { std::slice::from_raw_parts(objects, count as usize) };
+    match try_wait_for_objects(objects, wait_all != 0, timeout_in_milliseconds) {
+        Some(result) => result,
+        None => {
+            let trampoline = get_trampoline!(
+                WaitForMultipleObjects,
+                unsafe extern "system" fn(u32, *const *mut c_void, i32, u32) -> u32
+            );
+            wait_for_real_objects(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+                trampoline(count, objects.as_ptr(), wait_all, poll_timeout_in_milliseconds)
+            })
+        }
+    }
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn WaitForMultipleObjectsEx(
+    count: u32,
+    objects: *const *mut c_void,
+    wait_all: i32,
+    timeout_in_milliseconds: u32,
+    alertable: i32,
+) -> u32 {
+    let objects = unsafe { std::slice::from_raw_parts(objects, count as usize) };
+    let result = match try_wait_for_objects(objects, wait_all != 0, timeout_in_milliseconds) {
+        Some(result) => result,
+        None => {
+            let trampoline = get_trampoline!(
+                WaitForMultipleObjectsEx,
+                unsafe extern "system" fn(u32, *const *mut c_void, i32, u32, i32) -> u32
+            );
+            wait_for_real_objects(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+                trampoline(
+                    count,
+                    objects.as_ptr(),
+                    wait_all,
+                    poll_timeout_in_milliseconds,
+                    alertable,
+                )
+            })
+        }
+    };
+    if alertable != 0 && state::drain_timer_apcs() {
+        WAIT_IO_COMPLETION
+    } else {
+        result
+    }
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn MsgWaitForMultipleObjects(
+    count: u32,
+    objects: *const *mut c_void,
+    wait_all: i32,
+    timeout_in_milliseconds: u32,
+    wake_mask: u32,
+) -> u32 {
+    let objects = unsafe { std::slice::from_raw_parts(objects, count as usize) };
+    match try_wait_for_message_or_objects(objects, wait_all != 0, timeout_in_milliseconds) {
+        Some(result) => result,
+        None => {
+            let trampoline = get_trampoline!(
+                MsgWaitForMultipleObjects,
+                unsafe extern "system" fn(u32, *const *mut c_void, i32, u32, u32) -> u32
+            );
+            wait_for_real_objects(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+                trampoline(
+                    count,
+                    objects.as_ptr(),
+                    wait_all,
+                    poll_timeout_in_milliseconds,
+                    wake_mask,
+                )
+            })
+        }
+    }
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn MsgWaitForMultipleObjectsEx(
+    count: u32,
+    objects: *const *mut c_void,
+    timeout_in_milliseconds: u32,
+    wake_mask: u32,
+    flags: u32,
+) -> u32 {
+    let objects = unsafe { std::slice::from_raw_parts(objects, count as usize) };
+    let wait_all = flags & MWMO_WAITALL != 0;
+    let result = match try_wait_for_message_or_objects(objects, wait_all, timeout_in_milliseconds) {
+        Some(result) => result,
+        None => {
+            let trampoline = get_trampoline!(
+                MsgWaitForMultipleObjectsEx,
+                unsafe extern "system" fn(u32, *const *mut c_void, u32, u32, u32) -> u32
+            );
+            wait_for_real_objects(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+                trampoline(count, objects.as_ptr(), poll_timeout_in_milliseconds, wake_mask, flags)
+            })
+        }
+    };
+    if flags & MWMO_ALERTABLE != 0 && state::drain_timer_apcs() {
+        WAIT_IO_COMPLETION
+    } else {
+        result
+    }
+}
+
+/// Cooperatively waits for `poll` (a real wait call run with the given timeout, in milliseconds)
+/// to stop returning `WAIT_TIMEOUT`, for objects [`try_wait_for_objects`] can't simulate on its
+/// own. Polls with a zero timeout and, on `WAIT_TIMEOUT`, yields this thread's turn to the
+/// scheduler (see [`state::yield_to_next`]) instead of blocking it for real, so other target
+/// threads keep making progress while this one waits. Gives up with `WAIT_TIMEOUT` once the
+/// simulated clock reaches `timeout_in_milliseconds` from now, or immediately if the scheduler
+/// reports a deadlock.
+fn wait_for_real_objects(timeout_in_milliseconds: u32, poll: impl Fn(u32) -> u32) -> u32 {
+    let deadline_ticks = (timeout_in_milliseconds != INFINITE).then(|| {
+        STATE.lock().unwrap().ticks()
+            + u64::from(timeout_in_milliseconds) * State::TICKS_PER_SECOND / 1000
+    });
+    loop {
+        let result = poll(0);
+        if result != WAIT_TIMEOUT {
+            return result;
+        }
+        if deadline_ticks.is_some_and(|deadline_ticks| STATE.lock().unwrap().ticks() >= deadline_ticks)
         {
-            let waitable_timer = waitable_timer.lock().unwrap();
-            let timeout_in_ticks =
-                u64::from(timeout_in_milliseconds) * State::TICKS_PER_SECOND / 1000;
-            if waitable_timer.signaled {
-                sleep_time = 0;
-            } else if waitable_timer.running() {
-                sleep_time = timeout_in_ticks.min(waitable_timer.remaining_ticks);
-            } else {
-                sleep_time = timeout_in_ticks;
+            return WAIT_TIMEOUT;
+        }
+        if state::yield_to_next(deadline_ticks).is_err() {
+            return WAIT_TIMEOUT;
+        }
+    }
+}
+
+/// If every handle in `objects` is a tracked [`EmulatedHandle`], resolves the wait entirely against
+/// emulated state and returns the real `WaitForSingleObject`-family result code; returns `None` if
+/// any handle isn't tracked, so the caller can fall through to the real trampoline instead.
+/// Waitable timers advance the simulated clock on their own, the same mechanism [`state::sleep`]
+/// uses; events, mutexes and semaphores only become satisfied once some other thread calls
+/// `SetEvent`/`ReleaseMutex`/`ReleaseSemaphore`, so between checks this cooperatively yields this
+/// thread's turn to the scheduler (see [`state::yield_to_next`]) instead of blocking it for real,
+/// giving that other thread a chance to do so. Consumes whichever handle(s) satisfied the wait
+/// (auto-reset events clear, mutexes record the calling thread as owner, semaphores decrement),
+/// mirroring the real API's side effects.
+fn try_wait_for_objects(
+    objects: &[*mut c_void],
+    wait_all: bool,
+    timeout_in_milliseconds: u32,
+) -> Option<u32> {
+    let handles = objects
+        .iter()
+        .map(|&object| state::emulated_handle(object))
+        .collect::<Option<Vec<_>>>()?;
+    let current_thread_id = unsafe { GetCurrentThreadId() };
+    let deadline_ticks = (timeout_in_milliseconds != INFINITE).then(|| {
+        STATE.lock().unwrap().ticks() + u64::from(timeout_in_milliseconds) * State::TICKS_PER_SECOND / 1000
+    });
+
+    loop {
+        let timer_deadline_ticks = handles
+            .iter()
+            .filter_map(|handle| match handle {
+                EmulatedHandle::WaitableTimer(timer) => {
+                    let timer = timer.lock().unwrap();
+                    (!timer.signaled && timer.running()).then_some(timer.remaining_ticks)
+                }
+                _ => None,
+            })
+            .min();
+        if let Some(mut ticks) = timer_deadline_ticks {
+            if let Some(deadline_ticks) = deadline_ticks {
+                ticks = ticks.min(deadline_ticks.saturating_sub(STATE.lock().unwrap().ticks()));
             }
+            state::sleep(ticks);
         }
-        state::sleep(sleep_time);
-        let mut waitable_timer = waitable_timer.lock().unwrap();
-        if waitable_timer.signaled {
-            if waitable_timer.reset_automatically {
-                waitable_timer.signaled = false;
+
+        if wait_all {
+            if handles.iter().all(|handle| handle_is_satisfied(handle, current_thread_id)) {
+                handles.iter().for_each(|handle| consume_handle(handle, current_thread_id));
+                return Some(WAIT_OBJECT_0);
             }
-            WAIT_OBJECT_0
-        } else {
-            WAIT_TIMEOUT
+        } else if let Some(index) =
+            handles.iter().position(|handle| handle_is_satisfied(handle, current_thread_id))
+        {
+            consume_handle(&handles[index], current_thread_id);
+            return Some(WAIT_OBJECT_0 + u32::try_from(index).unwrap());
+        }
+
+        if deadline_ticks.is_some_and(|deadline_ticks| STATE.lock().unwrap().ticks() >= deadline_ticks)
+        {
+            return Some(WAIT_TIMEOUT);
+        }
+        if state::yield_to_next(deadline_ticks).is_err() {
+            return Some(WAIT_TIMEOUT);
         }
-    } else {
-        unsafe { get_self_trampoline()(object, timeout_in_milliseconds) }
     }
 }
 
-#[hook("ws2_32.dll")]
-unsafe extern "system" fn socket(_address_family: i32, _type: i32, _protocol: i32) -> usize {
-    INVALID_SOCKET
+/// The per-handle-kind "has this become signaled" check shared by [`try_wait_for_objects`] and
+/// [`try_wait_for_message_or_objects`].
+fn handle_is_satisfied(handle: &EmulatedHandle, current_thread_id: u32) -> bool {
+    match handle {
+        EmulatedHandle::WaitableTimer(timer) => timer.lock().unwrap().signaled,
+        EmulatedHandle::EmulatedEvent(event) => event.lock().unwrap().signaled,
+        EmulatedHandle::Mutex(mutex) => mutex
+            .lock()
+            .unwrap()
+            .owner_thread_id
+            .is_none_or(|owner| owner == current_thread_id),
+        EmulatedHandle::Semaphore(semaphore) => semaphore.lock().unwrap().count > 0,
+    }
+}
+
+/// The per-handle-kind side effect of a satisfied wait consuming `handle`, shared by
+/// [`try_wait_for_objects`] and [`try_wait_for_message_or_objects`].
+fn consume_handle(handle: &EmulatedHandle, current_thread_id: u32) {
+    match handle {
+        EmulatedHandle::WaitableTimer(timer) => {
+            let mut timer = timer.lock().unwrap();
+            if timer.reset_automatically {
+                timer.signaled = false;
+            }
+        }
+        EmulatedHandle::EmulatedEvent(event) => {
+            let mut event = event.lock().unwrap();
+            if !event.manual_reset {
+                event.signaled = false;
+            }
+        }
+        EmulatedHandle::Mutex(mutex) => {
+            let mut mutex = mutex.lock().unwrap();
+            mutex.owner_thread_id = Some(current_thread_id);
+            mutex.recursion_count += 1;
+        }
+        EmulatedHandle::Semaphore(semaphore) => semaphore.lock().unwrap().count -= 1,
+    }
+}
+
+/// Like [`try_wait_for_objects`], but also wakes once `custom_message_queue` gains a message,
+/// returning `WAIT_OBJECT_0 + handles.len()` the same way the real `MsgWaitForMultipleObjects(Ex)`
+/// signals `WAIT_OBJECT_0 + count` for a ready message. Unlike a plain object wait, nothing ever
+/// posts to `custom_message_queue` on its own initiative - it only grows as [`state::sleep`]'s
+/// underlying scheduler machinery drains queued input (see `state::poll_events_for_sleep`) - so
+/// this can't just fall back to [`state::yield_to_next`] the way [`try_wait_for_objects`] does when
+/// no timer bounds the wait: with no other thread to hand the token to, that would deadlock
+/// instead of ever draining new input. [`state::sleep_indefinitely`] is what the ordinary
+/// `GetMessage`/`PeekMessage` loop (see `hooks::window::get_message`) already uses to do exactly
+/// that draining, so this reuses it here too - which is also why, like `sleep_indefinitely`, this
+/// only does the message-aware wait on the main thread; `custom_message_queue` is only ever
+/// drained by the main thread's message pump, so waiting on it from any other thread falls back to
+/// the plain, object-only [`try_wait_for_objects`].
+fn try_wait_for_message_or_objects(
+    objects: &[*mut c_void],
+    wait_all: bool,
+    timeout_in_milliseconds: u32,
+) -> Option<u32> {
+    if !state::in_main_thread() {
+        return try_wait_for_objects(objects, wait_all, timeout_in_milliseconds);
+    }
+
+    let handles = objects
+        .iter()
+        .map(|&object| state::emulated_handle(object))
+        .collect::<Option<Vec<_>>>()?;
+    let current_thread_id = unsafe { GetCurrentThreadId() };
+    let deadline_ticks = (timeout_in_milliseconds != INFINITE).then(|| {
+        STATE.lock().unwrap().ticks() + u64::from(timeout_in_milliseconds) * State::TICKS_PER_SECOND / 1000
+    });
+
+    loop {
+        if !STATE.lock().unwrap().custom_message_queue.is_empty() {
+            return Some(WAIT_OBJECT_0 + u32::try_from(handles.len()).unwrap());
+        }
+
+        if wait_all {
+            if handles.iter().all(|handle| handle_is_satisfied(handle, current_thread_id)) {
+                handles.iter().for_each(|handle| consume_handle(handle, current_thread_id));
+                return Some(WAIT_OBJECT_0);
+            }
+        } else if let Some(index) =
+            handles.iter().position(|handle| handle_is_satisfied(handle, current_thread_id))
+        {
+            consume_handle(&handles[index], current_thread_id);
+            return Some(WAIT_OBJECT_0 + u32::try_from(index).unwrap());
+        }
+
+        if deadline_ticks.is_some_and(|deadline_ticks| STATE.lock().unwrap().ticks() >= deadline_ticks)
+        {
+            return Some(WAIT_TIMEOUT);
+        }
+        state::sleep_indefinitely();
+    }
 }
 
 #[hook("ntdll.dll")]
@@ -85,3 +456,14 @@ unsafe extern "system" fn NtSetInformationThread(
         unsafe { get_self_trampoline()(thread, information_class, information, information_length) }
     }
 }
+
+/// Drops the exiting thread from the cooperative scheduler (see
+/// [`state::unregister_scheduler_thread`]) before it actually exits, so its run token isn't left
+/// stranded with every other thread waiting on one that will never be set again.
+#[hook("kernel32.dll")]
+unsafe extern "system" fn ExitThread(exit_code: u32) -> ! {
+    state::unregister_scheduler_thread();
+    let trampoline =
+        get_trampoline!(ExitThread, unsafe extern "system" fn(u32) -> !);
+    unsafe { trampoline(exit_code) }
+}
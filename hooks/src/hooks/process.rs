@@ -0,0 +1,134 @@
+//! Forces every child process a hooked target spawns to start suspended and reports its
+//! `(process_id, main_thread_id)` to the conductor (see [`crate::SPAWNED_PROCESS_QUEUE`]), so it
+//! gets a chance to inject the hooks DLL and bring the child under the same deterministic control
+//! as the root process before anything in it actually runs. See [`state::SpawnedProcess`] for why
+//! this also rides along with save state for free, and `winter::Conductor`'s child-process fan-out
+//! for what happens with the notification on the conductor side.
+
+use super::get_trampoline;
+use crate::state;
+use hooks_macros::{hook, hooks};
+use shared::ipc::message::SpawnedProcess;
+use winapi::{
+    ctypes::c_void,
+    um::{
+        minwinbase::SECURITY_ATTRIBUTES,
+        processthreadsapi::{PROCESS_INFORMATION, STARTUPINFOA, STARTUPINFOW},
+        winbase::CREATE_SUSPENDED,
+    },
+};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![CreateProcessA, CreateProcessW];
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn CreateProcessA(
+    application_name: *const i8,
+    command_line: *mut i8,
+    process_attributes: *mut SECURITY_ATTRIBUTES,
+    thread_attributes: *mut SECURITY_ATTRIBUTES,
+    inherit_handles: i32,
+    creation_flags: u32,
+    environment: *mut c_void,
+    current_directory: *const i8,
+    startup_info: *mut STARTUPINFOA,
+    process_information: *mut PROCESS_INFORMATION,
+) -> i32 {
+    let trampoline = get_trampoline!(
+        CreateProcessA,
+        unsafe extern "system" fn(
+            *const i8,
+            *mut i8,
+            *mut SECURITY_ATTRIBUTES,
+            *mut SECURITY_ATTRIBUTES,
+            i32,
+            u32,
+            *mut c_void,
+            *const i8,
+            *mut STARTUPINFOA,
+            *mut PROCESS_INFORMATION,
+        ) -> i32
+    );
+    let result = unsafe {
+        trampoline(
+            application_name,
+            command_line,
+            process_attributes,
+            thread_attributes,
+            inherit_handles,
+            creation_flags | CREATE_SUSPENDED,
+            environment,
+            current_directory,
+            startup_info,
+            process_information,
+        )
+    };
+    if result != 0 {
+        unsafe { notify_spawned_process(&*process_information) };
+    }
+    result
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn CreateProcessW(
+    application_name: *const u16,
+    command_line: *mut u16,
+    process_attributes: *mut SECURITY_ATTRIBUTES,
+    thread_attributes: *mut SECURITY_ATTRIBUTES,
+    inherit_handles: i32,
+    creation_flags: u32,
+    environment: *mut c_void,
+    current_directory: *const u16,
+    startup_info: *mut STARTUPINFOW,
+    process_information: *mut PROCESS_INFORMATION,
+) -> i32 {
+    let trampoline = get_trampoline!(
+        CreateProcessW,
+        unsafe extern "system" fn(
+            *const u16,
+            *mut u16,
+            *mut SECURITY_ATTRIBUTES,
+            *mut SECURITY_ATTRIBUTES,
+            i32,
+            u32,
+            *mut c_void,
+            *const u16,
+            *mut STARTUPINFOW,
+            *mut PROCESS_INFORMATION,
+        ) -> i32
+    );
+    let result = unsafe {
+        trampoline(
+            application_name,
+            command_line,
+            process_attributes,
+            thread_attributes,
+            inherit_handles,
+            creation_flags | CREATE_SUSPENDED,
+            environment,
+            current_directory,
+            startup_info,
+            process_information,
+        )
+    };
+    if result != 0 {
+        unsafe { notify_spawned_process(&*process_information) };
+    }
+    result
+}
+
+/// Records `process_information`'s process/main-thread id in [`state`] and enqueues the same pair
+/// for the conductor to pick up and inject into, mirroring the log batch queue's fire-and-forget
+/// handoff (see [`crate::SPAWNED_PROCESS_QUEUE`]) so a hook running on an arbitrary thread never
+/// blocks waiting for the conductor to act on it.
+///
+/// Whichever of the caller or the conductor resumes the child's main thread first wins: if the
+/// caller resumes it (because it didn't ask for `CREATE_SUSPENDED` itself, or because it's done
+/// with whatever it wanted to do to a child it did ask suspended) before the conductor finishes
+/// injecting the hooks DLL, the child runs unhooked for a while.
+unsafe fn notify_spawned_process(process_information: &PROCESS_INFORMATION) {
+    state::report_spawned_process(process_information.dwProcessId, process_information.dwThreadId);
+    unsafe { crate::SPAWNED_PROCESS_QUEUE.assume_init_ref() }.enqueue(SpawnedProcess {
+        process_id: process_information.dwProcessId,
+        main_thread_id: process_information.dwThreadId,
+    });
+}
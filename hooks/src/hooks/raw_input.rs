@@ -0,0 +1,74 @@
+//! `RegisterRawInputDevices`/`GetRawInputData` emulation. Real raw input bypasses the
+//! window-message input stack entirely and reads relative deltas straight off the device; here
+//! it's just another view onto the same [`state::State`] the window-message hooks already
+//! populate - see `state::post_raw_mouse_move_message`/`post_raw_mouse_button_message`/
+//! `post_raw_keyboard_message` for where the matching `WM_INPUT` is actually enqueued.
+
+use crate::state::STATE;
+use hooks_macros::{hook, hooks};
+use winapi::{
+    ctypes::c_void,
+    shared::{
+        minwindef::{BOOL, FALSE, TRUE, UINT},
+        windef::HRAWINPUT,
+    },
+    um::winuser::{RAWINPUTDEVICE, RAWINPUTHEADER, RID_HEADER},
+};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] =
+    &hooks![RegisterRawInputDevices, GetRawInputData];
+
+#[expect(clippy::cast_sign_loss)]
+#[hook("user32.dll")]
+unsafe extern "system" fn RegisterRawInputDevices(
+    devices: *const RAWINPUTDEVICE,
+    num_devices: UINT,
+    size: UINT,
+) -> BOOL {
+    if size as usize != std::mem::size_of::<RAWINPUTDEVICE>() {
+        return FALSE;
+    }
+    let devices = unsafe { std::slice::from_raw_parts(devices, num_devices as usize) };
+    let mut state = STATE.lock().unwrap();
+    for device in devices {
+        state.register_raw_input_device(device.usUsagePage, device.usUsage, device.dwFlags);
+    }
+    TRUE
+}
+
+#[expect(clippy::cast_possible_truncation)]
+#[hook("user32.dll")]
+unsafe extern "system" fn GetRawInputData(
+    raw_input: HRAWINPUT,
+    command: UINT,
+    data: *mut c_void,
+    size: *mut UINT,
+    _header_size: UINT,
+) -> UINT {
+    let Some(buffer) = STATE.lock().unwrap().raw_input_buffer(raw_input as usize) else {
+        return u32::MAX;
+    };
+    // `_header_size` is caller-supplied and not to be trusted for how much of `buffer` actually
+    // exists - a `RID_HEADER` request only ever wants the fixed-size `RAWINPUTHEADER` prefix every
+    // buffer starts with (see `state::RawInputHeaderBytes`), clamped to `buffer.len()` in case a
+    // future buffer kind ever came back shorter than that.
+    let needed_size = if command == RID_HEADER {
+        (std::mem::size_of::<RAWINPUTHEADER>() as u32).min(buffer.len() as u32)
+    } else {
+        buffer.len() as u32
+    };
+
+    if data.is_null() {
+        unsafe { *size = needed_size };
+        return 0;
+    }
+    if unsafe { *size } < needed_size {
+        return u32::MAX;
+    }
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(buffer.as_ptr(), data.cast::<u8>(), needed_size as usize);
+        *size = needed_size;
+    }
+    needed_size
+}
@@ -0,0 +1,123 @@
+//! Tracks hooks installed via MinHook by name, replacing a bare `TRAMPOLINES` address map with
+//! one that also remembers each hook's target address (so it can be re-enabled/disabled later)
+//! and reports a typed error instead of panicking when asked about a name that was never
+//! installed.
+
+use minhook::MinHook;
+use shared::windows::{module, process};
+use std::{
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    sync::RwLock,
+};
+use thiserror::Error;
+use winapi::ctypes::c_void;
+
+struct InstalledHook {
+    target_address: usize,
+    trampoline_address: usize,
+}
+
+/// Every hook installed so far, keyed by function name.
+#[derive(Default)]
+pub(crate) struct HookRegistry {
+    hooks: RwLock<BTreeMap<String, InstalledHook>>,
+}
+
+impl HookRegistry {
+    pub(crate) const fn new() -> Self {
+        Self {
+            hooks: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Looks up `function` in `module_name` and installs `hook` as a MinHook detour on it,
+    /// enabling it immediately. Unlike the ad hoc closure this replaces, a failed lookup is
+    /// returned instead of only logged, so a caller can tell which function a target binary
+    /// didn't export.
+    pub(crate) fn register(
+        &self,
+        module_name: &OsStr,
+        function: &str,
+        hook: *const c_void,
+    ) -> Result<(), HookError> {
+        let target_address = process::Process::get_current()
+            .get_module(module_name)?
+            .ok_or_else(|| HookError::ModuleNotFound(module_name.to_os_string()))?
+            .get_export_address(function)?;
+
+        let trampoline_address = unsafe {
+            MinHook::create_hook(target_address, hook as *mut std::ffi::c_void).unwrap_or_else(
+                |error| panic!("failed to create hook for {function}: {error:?}"),
+            )
+        };
+        unsafe {
+            MinHook::enable_hook(target_address)
+                .unwrap_or_else(|error| panic!("failed to enable hook for {function}: {error:?}"));
+        }
+
+        self.hooks.write().unwrap().insert(
+            function.to_string(),
+            InstalledHook {
+                target_address: target_address as usize,
+                trampoline_address: trampoline_address as usize,
+            },
+        );
+        Ok(())
+    }
+
+    /// Re-enables a hook previously installed by [`Self::register`] and since [`Self::disable`]d.
+    pub(crate) fn enable(&self, name: &str) -> Result<(), HookError> {
+        let hooks = self.hooks.read().unwrap();
+        let hook = hooks
+            .get(name)
+            .ok_or_else(|| HookError::UnknownHook(name.to_string()))?;
+        unsafe {
+            MinHook::enable_hook(hook.target_address as *mut std::ffi::c_void)
+                .unwrap_or_else(|error| panic!("failed to enable hook for {name}: {error:?}"));
+        }
+        Ok(())
+    }
+
+    /// Disables a hook previously installed by [`Self::register`], restoring the target
+    /// function's original behavior until it's [`Self::enable`]d again.
+    pub(crate) fn disable(&self, name: &str) -> Result<(), HookError> {
+        let hooks = self.hooks.read().unwrap();
+        let hook = hooks
+            .get(name)
+            .ok_or_else(|| HookError::UnknownHook(name.to_string()))?;
+        unsafe {
+            MinHook::disable_hook(hook.target_address as *mut std::ffi::c_void)
+                .unwrap_or_else(|error| panic!("failed to disable hook for {name}: {error:?}"));
+        }
+        Ok(())
+    }
+
+    /// The trampoline installed for `name` (the original function, callable to invoke real
+    /// behavior), transmuted to `T`. Returns [`HookError::UnknownHook`] instead of panicking if
+    /// `name` was never registered, e.g. because the target binary doesn't export it.
+    pub(crate) fn trampoline<T: Copy>(&self, name: &str) -> Result<T, HookError> {
+        let hooks = self.hooks.read().unwrap();
+        let hook = hooks
+            .get(name)
+            .ok_or_else(|| HookError::UnknownHook(name.to_string()))?;
+        assert_eq!(
+            size_of::<T>(),
+            size_of::<usize>(),
+            "trampoline type must be pointer-sized"
+        );
+        Ok(unsafe { std::mem::transmute_copy(&hook.trampoline_address) })
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum HookError {
+    #[error("failed to enumerate modules")]
+    GetModules(#[from] process::GetModulesError),
+    #[error("module {0:?} is not loaded in the current process")]
+    ModuleNotFound(OsString),
+    #[error("failed to resolve hook target")]
+    GetExportAddress(#[from] module::GetExportAddressError),
+    #[error("no hook named {0:?} is registered")]
+    UnknownHook(String),
+}
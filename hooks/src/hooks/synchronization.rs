@@ -0,0 +1,97 @@
+use super::get_trampoline;
+use crate::state::{self, State, STATE};
+use hooks_macros::{hook, hooks};
+use winapi::{
+    ctypes::c_void,
+    shared::{minwindef::FALSE, winerror::ERROR_TIMEOUT},
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        minwinbase::{CONDITION_VARIABLE, CRITICAL_SECTION},
+        winbase::INFINITE,
+        winnt::SRWLOCK,
+    },
+};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
+    SleepConditionVariableCS,
+    SleepConditionVariableSRW,
+    WaitOnAddress,
+];
+
+#[hook("kernelbase.dll")]
+unsafe extern "system" fn SleepConditionVariableCS(
+    condition_variable: *mut CONDITION_VARIABLE,
+    critical_section: *mut CRITICAL_SECTION,
+    timeout_in_milliseconds: u32,
+) -> i32 {
+    let trampoline = get_trampoline!(
+        SleepConditionVariableCS,
+        unsafe extern "system" fn(*mut CONDITION_VARIABLE, *mut CRITICAL_SECTION, u32) -> i32
+    );
+    wait_for_real_condition(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+        trampoline(condition_variable, critical_section, poll_timeout_in_milliseconds)
+    })
+}
+
+#[hook("kernelbase.dll")]
+unsafe extern "system" fn SleepConditionVariableSRW(
+    condition_variable: *mut CONDITION_VARIABLE,
+    srw_lock: *mut SRWLOCK,
+    timeout_in_milliseconds: u32,
+    flags: u32,
+) -> i32 {
+    let trampoline = get_trampoline!(
+        SleepConditionVariableSRW,
+        unsafe extern "system" fn(*mut CONDITION_VARIABLE, *mut SRWLOCK, u32, u32) -> i32
+    );
+    wait_for_real_condition(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+        trampoline(condition_variable, srw_lock, poll_timeout_in_milliseconds, flags)
+    })
+}
+
+#[hook("kernelbase.dll")]
+unsafe extern "system" fn WaitOnAddress(
+    address: *const c_void,
+    compare_address: *mut c_void,
+    address_size: usize,
+    timeout_in_milliseconds: u32,
+) -> i32 {
+    let trampoline = get_trampoline!(
+        WaitOnAddress,
+        unsafe extern "system" fn(*const c_void, *mut c_void, usize, u32) -> i32
+    );
+    wait_for_real_condition(timeout_in_milliseconds, |poll_timeout_in_milliseconds| unsafe {
+        trampoline(address, compare_address, address_size, poll_timeout_in_milliseconds)
+    })
+}
+
+/// Cooperatively waits for `poll` (a real condition-variable/address wait run with the given
+/// timeout, in milliseconds, returning the raw `BOOL` result) to stop timing out. Polls with a
+/// zero timeout and, on `FALSE`/`ERROR_TIMEOUT`, yields this thread's turn to the scheduler (see
+/// [`state::yield_to_next`]) instead of blocking it for real, so a real `WakeConditionVariable`/
+/// `WakeByAddress*` from another target thread short-circuits the wait the next time this thread
+/// is scheduled. Times out with `FALSE`/`ERROR_TIMEOUT` once the simulated clock reaches
+/// `timeout_in_milliseconds` from now, translated to ticks via [`State::TICKS_PER_SECOND`], or
+/// immediately if the scheduler reports a deadlock. A `FALSE` result for any other reason is
+/// returned as-is, since it isn't a timeout this hook can make progress on.
+fn wait_for_real_condition(timeout_in_milliseconds: u32, poll: impl Fn(u32) -> i32) -> i32 {
+    let deadline_ticks = (timeout_in_milliseconds != INFINITE).then(|| {
+        STATE.lock().unwrap().ticks()
+            + u64::from(timeout_in_milliseconds) * State::TICKS_PER_SECOND / 1000
+    });
+    loop {
+        let result = poll(0);
+        if result != FALSE || unsafe { GetLastError() } != ERROR_TIMEOUT {
+            return result;
+        }
+        if deadline_ticks.is_some_and(|deadline_ticks| STATE.lock().unwrap().ticks() >= deadline_ticks)
+        {
+            unsafe { SetLastError(ERROR_TIMEOUT) };
+            return FALSE;
+        }
+        if state::yield_to_next(deadline_ticks).is_err() {
+            unsafe { SetLastError(ERROR_TIMEOUT) };
+            return FALSE;
+        }
+    }
+}
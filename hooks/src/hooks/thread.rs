@@ -0,0 +1,66 @@
+//! Routes every new thread a hooked target spawns through the cooperative scheduler (see
+//! `state::yield_to_next`) before it runs any of its own code, closing the race window between a
+//! real `CreateThread` returning and the new thread reaching its first yield point, which would
+//! otherwise let it run unscheduled in parallel with whichever thread currently holds the run
+//! token.
+
+use super::get_trampoline;
+use crate::state;
+use hooks_macros::{hook, hooks};
+use winapi::{ctypes::c_void, um::minwinbase::SECURITY_ATTRIBUTES};
+
+pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![CreateThread];
+
+struct ThreadEntryParameters {
+    start_address: unsafe extern "system" fn(*mut c_void) -> u32,
+    parameter: *mut c_void,
+}
+
+#[hook("kernel32.dll")]
+unsafe extern "system" fn CreateThread(
+    thread_attributes: *mut SECURITY_ATTRIBUTES,
+    stack_size: usize,
+    start_address: unsafe extern "system" fn(*mut c_void) -> u32,
+    parameter: *mut c_void,
+    creation_flags: u32,
+    thread_id: *mut u32,
+) -> *mut c_void {
+    let trampoline = get_trampoline!(
+        CreateThread,
+        unsafe extern "system" fn(
+            *mut SECURITY_ATTRIBUTES,
+            usize,
+            unsafe extern "system" fn(*mut c_void) -> u32,
+            *mut c_void,
+            u32,
+            *mut u32,
+        ) -> *mut c_void
+    );
+    let entry_parameters = Box::into_raw(Box::new(ThreadEntryParameters {
+        start_address,
+        parameter,
+    }));
+    unsafe {
+        trampoline(
+            thread_attributes,
+            stack_size,
+            scheduled_thread_entry,
+            entry_parameters.cast(),
+            creation_flags,
+            thread_id,
+        )
+    }
+}
+
+/// Every hooked thread's real entry point, in place of whatever the target passed to
+/// `CreateThread`: registers with the cooperative scheduler and waits for its first run token
+/// (see [`state::register_and_await_turn`]) before jumping to the target's actual start routine,
+/// which by then runs exactly as scheduled as any other instrumented yield point.
+unsafe extern "system" fn scheduled_thread_entry(parameter: *mut c_void) -> u32 {
+    let ThreadEntryParameters {
+        start_address,
+        parameter,
+    } = *unsafe { Box::from_raw(parameter.cast()) };
+    state::register_and_await_turn();
+    unsafe { start_address(parameter) }
+}
@@ -1,5 +1,8 @@
-use super::common::get_trampoline;
-use crate::state::{self, State, WaitableTimer, STATE};
+use super::get_trampoline;
+use crate::state::{
+    self, EmulatedHandle, MultimediaTimer, MultimediaTimerCallback, State, TimerCompletionRoutine,
+    WaitableTimer, STATE,
+};
 use hooks_macros::{hook, hooks};
 use std::{
     num::NonZeroU64,
@@ -7,25 +10,30 @@ use std::{
 };
 use winapi::{
     ctypes::c_void,
-    shared::minwindef::FILETIME,
+    shared::{basetsd::DWORD_PTR, minwindef::FILETIME},
     um::{
         minwinbase::{REASON_CONTEXT, SECURITY_ATTRIBUTES},
+        mmsystem::{
+            LPTIMECALLBACK, TIMECAPS, TIME_CALLBACK_EVENT_PULSE, TIME_CALLBACK_EVENT_SET,
+            TIME_PERIODIC,
+        },
         profileapi::{QueryPerformanceCounter, QueryPerformanceFrequency},
         synchapi::{
             CreateWaitableTimerExW, CreateWaitableTimerW, SetWaitableTimer, SetWaitableTimerEx,
-            Sleep, CREATE_WAITABLE_TIMER_MANUAL_RESET,
+            Sleep, SleepEx, CREATE_WAITABLE_TIMER_MANUAL_RESET,
         },
         sysinfoapi::{
             GetSystemTimeAsFileTime, GetSystemTimePreciseAsFileTime, GetTickCount, GetTickCount64,
         },
         timeapi::timeGetTime,
-        winbase::{CreateWaitableTimerA, CreateWaitableTimerExA},
+        winbase::{CreateWaitableTimerA, CreateWaitableTimerExA, WAIT_IO_COMPLETION},
         winnt::{LARGE_INTEGER, TIMER_ALL_ACCESS},
     },
 };
 
 pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
     Sleep,
+    SleepEx,
     GetTickCount,
     GetTickCount64,
     timeGetTime,
@@ -39,24 +47,55 @@ pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
     CreateWaitableTimerExW,
     SetWaitableTimer,
     SetWaitableTimerEx,
+    timeSetEvent,
+    timeKillEvent,
+    timeBeginPeriod,
+    timeEndPeriod,
+    timeGetDevCaps,
 ];
 
+/// Minimum and maximum period, in milliseconds, the simulated multimedia timer will accept from
+/// `timeBeginPeriod`; reported by [`timeGetDevCaps`]. `timeSetEvent` does not check its delay
+/// against them. There's no real underlying
+/// timer resolution to report here, so these are arbitrary but generous bounds.
+const MULTIMEDIA_TIMER_PERIOD_MIN_IN_MILLISECONDS: u32 = 1;
+const MULTIMEDIA_TIMER_PERIOD_MAX_IN_MILLISECONDS: u32 = 1_000_000;
+
+const TIMERR_NOERROR: u32 = 0;
+const TIMERR_NOCANDO: u32 = 97;
+
 #[hook("kernel32.dll")]
 unsafe extern "system" fn Sleep(milliseconds: u32) {
-    state::sleep(u64::from(milliseconds) * State::TICKS_PER_SECOND / 1000);
+    state::sleep(state::units_to_ticks(u64::from(milliseconds), 1000));
 }
 
+/// Like [`Sleep`], but when `alertable` is set, any timer completion routine queued while sleeping
+/// (see [`state::drain_timer_apcs`]) runs before returning, reported via `WAIT_IO_COMPLETION`
+/// rather than a plain `0`.
+#[hook("kernel32.dll")]
+unsafe extern "system" fn SleepEx(milliseconds: u32, alertable: i32) -> u32 {
+    state::sleep(state::units_to_ticks(u64::from(milliseconds), 1000));
+    if alertable != 0 && state::drain_timer_apcs() {
+        WAIT_IO_COMPLETION
+    } else {
+        0
+    }
+}
+
+/// Milliseconds elapsed on the virtual clock, derived from the same tick counter
+/// [`SetWaitableTimer`]/[`Sleep`] advance - a busy-polling program that only ever reads the wall
+/// clock through this (rather than blocking on it) still sees virtual time pass, via
+/// [`get_ticks_with_busy_wait`](state::get_ticks_with_busy_wait)'s forced yield.
 #[expect(clippy::cast_possible_truncation)]
 #[hook("kernel32.dll")]
 unsafe extern "system" fn GetTickCount() -> u32 {
-    (state::get_ticks_with_busy_wait() * 1000 / State::TICKS_PER_SECOND) as u32
+    state::ticks_to_units(state::active_time_source().ticks(), 1000) as u32
 }
 
-#[expect(clippy::cast_possible_truncation)]
+/// Like [`GetTickCount`], but without its 49.7-day wraparound.
 #[hook("kernel32.dll")]
 unsafe extern "system" fn GetTickCount64() -> u64 {
-    (u128::from(state::get_ticks_with_busy_wait()) * 1000 / u128::from(State::TICKS_PER_SECOND))
-        as u64
+    state::ticks_to_units(state::active_time_source().ticks(), 1000)
 }
 
 #[hook("winmm.dll")]
@@ -64,25 +103,31 @@ unsafe extern "system" fn timeGetTime() -> u32 {
     unsafe { GetTickCount() }
 }
 
-const SIMULATED_PERFORMANCE_COUNTER_FREQUENCY: u64 = 1 << 32;
-
+/// Reports a fixed synthetic frequency (see [`state::performance_counter_frequency`]) rather than
+/// the real one, so [`QueryPerformanceCounter`]'s tick-to-count conversion stays reproducible
+/// across the different real counter frequencies of whatever machine a recording is replayed on.
 #[hook("kernel32.dll")]
 unsafe extern "system" fn QueryPerformanceFrequency(frequency: *mut LARGE_INTEGER) -> i32 {
     #[expect(clippy::cast_possible_wrap)]
     unsafe {
-        *(*frequency).QuadPart_mut() = SIMULATED_PERFORMANCE_COUNTER_FREQUENCY as i64;
+        *(*frequency).QuadPart_mut() = state::performance_counter_frequency() as i64;
     }
 
     1
 }
 
+/// The virtual clock's tick counter rescaled to [`QueryPerformanceFrequency`]'s synthetic
+/// frequency, by the same [`state::ticks_to_units`] conversion [`GetTickCount`] uses for
+/// milliseconds - both track the one tick counter, so a program comparing the two never observes
+/// them drift apart.
 #[hook("kernel32.dll")]
 unsafe extern "system" fn QueryPerformanceCounter(count: *mut LARGE_INTEGER) -> i32 {
     #[expect(clippy::cast_possible_wrap)]
     unsafe {
-        let simulated_performance_counter = state::get_ticks_with_busy_wait()
-            * SIMULATED_PERFORMANCE_COUNTER_FREQUENCY
-            / State::TICKS_PER_SECOND;
+        let simulated_performance_counter = state::ticks_to_units(
+            state::active_time_source().ticks(),
+            state::performance_counter_frequency(),
+        );
         *(*count).QuadPart_mut() = simulated_performance_counter as i64;
     }
 
@@ -91,10 +136,8 @@ unsafe extern "system" fn QueryPerformanceCounter(count: *mut LARGE_INTEGER) ->
 
 #[hook("kernel32.dll")]
 unsafe extern "system" fn GetSystemTimeAsFileTime(file_time: *mut FILETIME) {
-    #[expect(clippy::cast_possible_truncation)]
-    let one_hundred_nanosecond_intervals = (u128::from(state::get_ticks_with_busy_wait())
-        * 10_000_000
-        / u128::from(State::TICKS_PER_SECOND)) as u64;
+    let one_hundred_nanosecond_intervals =
+        state::ticks_to_units(state::active_time_source().ticks(), 10_000_000);
 
     unsafe {
         (*file_time).dwLowDateTime = (one_hundred_nanosecond_intervals & ((1 << 32) - 1)) as u32;
@@ -223,14 +266,15 @@ unsafe fn create_waitable_timer(
         }
     };
     if !result.is_null() {
-        STATE.lock().unwrap().waitable_timer_handles.insert(
-            result as u32,
-            Arc::new(Mutex::new(WaitableTimer {
+        state::register_handle(
+            result,
+            EmulatedHandle::WaitableTimer(Arc::new(Mutex::new(WaitableTimer {
                 reset_automatically: flags != CREATE_WAITABLE_TIMER_MANUAL_RESET,
                 signaled: false,
                 remaining_ticks: 0,
                 period_in_ticks: None,
-            })),
+                completion_routine: None,
+            }))),
         );
     }
     result
@@ -267,7 +311,13 @@ unsafe extern "system" fn SetWaitableTimer(
         )
     };
     if result != 0 {
-        set_waitable_timer_shared(timer, due_time, period);
+        set_waitable_timer_shared(
+            timer,
+            due_time,
+            period,
+            completion_routine,
+            completion_routine_argument,
+        );
     }
     result
 }
@@ -306,21 +356,39 @@ unsafe extern "system" fn SetWaitableTimerEx(
         )
     };
     if result != 0 {
-        set_waitable_timer_shared(timer, due_time, period);
+        set_waitable_timer_shared(
+            timer,
+            due_time,
+            period,
+            completion_routine,
+            completion_routine_argument,
+        );
     }
     result
 }
 
 #[expect(clippy::cast_sign_loss)]
-fn set_waitable_timer_shared(timer: *mut c_void, due_time: *const LARGE_INTEGER, period: i32) {
+fn set_waitable_timer_shared(
+    timer: *mut c_void,
+    due_time: *const LARGE_INTEGER,
+    period: i32,
+    completion_routine: Option<unsafe extern "system" fn(*mut c_void, u32, u32)>,
+    completion_routine_argument: *mut c_void,
+) {
     let state = STATE.lock().unwrap();
-    let Some(waitable_timer) = state.waitable_timer_handles.get(&(timer as u32)) else {
+    let Some(EmulatedHandle::WaitableTimer(waitable_timer)) = state.handles.get(&(timer as u32))
+    else {
         return;
     };
     let mut waitable_timer = waitable_timer.lock().unwrap();
     waitable_timer.signaled = false;
     waitable_timer.period_in_ticks =
         NonZeroU64::new(period as u64 * State::TICKS_PER_SECOND / 1000);
+    waitable_timer.completion_routine =
+        completion_routine.map(|routine| TimerCompletionRoutine {
+            routine,
+            argument: completion_routine_argument,
+        });
 
     let due_time = unsafe { *(*due_time).QuadPart() };
     waitable_timer.remaining_ticks = if due_time >= 0 {
@@ -329,3 +397,88 @@ fn set_waitable_timer_shared(timer: *mut c_void, due_time: *const LARGE_INTEGER,
         -due_time as u64 * State::TICKS_PER_SECOND / 10_000_000
     };
 }
+
+/// Fully simulated: unlike the waitable timer hooks above, this never touches a real multimedia
+/// timer, so the callback can only ever run from the simulated clock advancing (see
+/// `state::advance_multimedia_timers`) rather than racing it on a real timer thread.
+#[hook("winmm.dll")]
+unsafe extern "system" fn timeSetEvent(
+    delay_in_milliseconds: u32,
+    _resolution_in_milliseconds: u32,
+    callback: LPTIMECALLBACK,
+    user_data: DWORD_PTR,
+    event_type: u32,
+) -> u32 {
+    let delay_in_ticks = u64::from(delay_in_milliseconds) * State::TICKS_PER_SECOND / 1000;
+    let mut state = STATE.lock().unwrap();
+    let timer_id = state.next_multimedia_timer_id;
+    state.next_multimedia_timer_id += 1;
+    state.multimedia_timer_handles.insert(
+        timer_id,
+        Arc::new(Mutex::new(MultimediaTimer {
+            remaining_ticks: delay_in_ticks,
+            period_in_ticks: if event_type & TIME_PERIODIC != 0 {
+                NonZeroU64::new(delay_in_ticks)
+            } else {
+                None
+            },
+            callback: if event_type & TIME_CALLBACK_EVENT_SET != 0 {
+                MultimediaTimerCallback::SetEvent(user_data as *mut c_void)
+            } else if event_type & TIME_CALLBACK_EVENT_PULSE != 0 {
+                MultimediaTimerCallback::PulseEvent(user_data as *mut c_void)
+            } else {
+                MultimediaTimerCallback::Function {
+                    callback,
+                    user_data,
+                }
+            },
+        })),
+    );
+    timer_id
+}
+
+#[hook("winmm.dll")]
+unsafe extern "system" fn timeKillEvent(timer_id: u32) -> u32 {
+    STATE
+        .lock()
+        .unwrap()
+        .multimedia_timer_handles
+        .remove(&timer_id);
+    TIMERR_NOERROR
+}
+
+#[hook("winmm.dll")]
+unsafe extern "system" fn timeBeginPeriod(period_in_milliseconds: u32) -> u32 {
+    let mut capabilities = TIMECAPS {
+        wPeriodMin: 0,
+        wPeriodMax: 0,
+    };
+    unsafe {
+        timeGetDevCaps(&mut capabilities, std::mem::size_of::<TIMECAPS>() as u32);
+    }
+    if (capabilities.wPeriodMin..=capabilities.wPeriodMax).contains(&period_in_milliseconds) {
+        TIMERR_NOERROR
+    } else {
+        TIMERR_NOCANDO
+    }
+}
+
+#[hook("winmm.dll")]
+unsafe extern "system" fn timeEndPeriod(_period_in_milliseconds: u32) -> u32 {
+    TIMERR_NOERROR
+}
+
+#[hook("winmm.dll")]
+unsafe extern "system" fn timeGetDevCaps(
+    capabilities: *mut TIMECAPS,
+    capabilities_size: u32,
+) -> u32 {
+    if (capabilities_size as usize) < std::mem::size_of::<TIMECAPS>() {
+        return TIMERR_NOCANDO;
+    }
+    unsafe {
+        (*capabilities).wPeriodMin = MULTIMEDIA_TIMER_PERIOD_MIN_IN_MILLISECONDS;
+        (*capabilities).wPeriodMax = MULTIMEDIA_TIMER_PERIOD_MAX_IN_MILLISECONDS;
+    }
+    TIMERR_NOERROR
+}
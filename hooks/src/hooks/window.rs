@@ -1,16 +1,27 @@
 use super::get_trampoline;
-use crate::state::{self, STATE};
+use crate::state::{self, MSGSend, STATE};
 use hooks_macros::{hook, hooks};
 use shared::windows::process;
+use std::{collections::BTreeMap, sync::Mutex};
 use winapi::{
     ctypes::c_void,
-    shared::{ntdef::NULL, windef::HWND},
-    um::winuser::{
-        GetMessageA, GetMessageW, PeekMessageA, PeekMessageW, RegisterClassExA, RegisterClassExW,
-        MSG, PM_REMOVE, WM_ACTIVATE, WM_ACTIVATEAPP, WM_CHAR, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS,
-        WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_QUIT,
-        WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SETFOCUS, WM_XBUTTONDOWN, WM_XBUTTONUP, WNDCLASSEXA,
-        WNDCLASSEXW,
+    shared::{
+        ntdef::NULL,
+        windef::{HKL, HWND},
+    },
+    um::{
+        processthreadsapi::GetCurrentProcessId,
+        winuser::{
+            EnumWindows, GetMessageA, GetMessageW, GetWindowLongPtrA, GetWindowThreadProcessId,
+            KillTimer, PeekMessageA, PeekMessageW, RegisterClassExA, RegisterClassExW,
+            SetClassLongPtrA, SetClassLongPtrW, SetTimer, SetWindowLongPtrA, SetWindowLongPtrW,
+            ToUnicodeEx, TranslateMessage, GCLP_WNDPROC, GWLP_WNDPROC, MSG, PM_REMOVE, TIMERPROC,
+            WM_ACTIVATE, WM_ACTIVATEAPP, WM_CHAR, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS,
+            WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL,
+            WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_QUIT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SETFOCUS,
+            WM_SYSCHAR, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_TIMER, WM_XBUTTONDOWN, WM_XBUTTONUP,
+            WNDCLASSEXA, WNDCLASSEXW,
+        },
     },
 };
 
@@ -21,8 +32,22 @@ pub(crate) const HOOKS: &[(&str, &str, *const c_void)] = &hooks![
     PeekMessageW,
     GetMessageA,
     GetMessageW,
+    TranslateMessage,
+    SetTimer,
+    KillTimer,
+    SetWindowLongPtrA,
+    SetWindowLongPtrW,
+    SetClassLongPtrA,
+    SetClassLongPtrW,
 ];
 
+/// The window procedure address the target application believes is currently installed on a given
+/// window (keyed by `HWND`), from before our wrapper (see [`wrap_window_procedure`]) was spliced
+/// in front of it - either because [`subclass_existing_windows`] found it already set at startup,
+/// or because a later `SetWindowLongPtr`/`SetClassLongPtr` call installed it. Lets those hooks
+/// report back the address the application actually set rather than leaking our wrapper's.
+static SUBCLASSED_WINDOW_PROCEDURES: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
 #[hook("user32.dll")]
 unsafe extern "system" fn RegisterClassExA(information: *const WNDCLASSEXA) -> u16 {
     unsafe { register_class_ex(information, false) }
@@ -38,62 +63,7 @@ unsafe extern "system" fn RegisterClassExW(information: *const WNDCLASSEXW) -> u
 // u16 fields in WNDCLASSEXW
 unsafe fn register_class_ex(information: *const WNDCLASSEXA, unicode_strings: bool) -> u16 {
     let mut new_information = unsafe { *information };
-    new_information.lpfnWndProc = new_information
-        .lpfnWndProc
-        .map(|original_window_procedure| {
-            // a wrapper which prepends the address of the trampoline as the first argument
-            #[cfg(target_pointer_width = "64")]
-            let hook_wrapper = {
-                let mut function = vec![
-                    // 0xeb, 0xfe,
-                    0x41, 0x51, // push r9
-                    0x48, 0x83, 0xec, 0x20, // sub rsp, 0x20
-                    0x4d, 0x89, 0xc1, // mov r9, r8
-                    0x49, 0x89, 0xd0, // mov r8, rdx
-                    0x48, 0x89, 0xca, // mov rdx, rcx
-                    0x48, 0xb9, 0, 0, 0, 0, 0, 0, 0, 0, // mov rcx, original_window_procedure
-                    0x48, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, // mov rax, window_procedure
-                    0xff, 0xd0, // call rax
-                    0x48, 0x83, 0xc4, 0x28, // add rsp, 0x28
-                    0xc3,
-                ];
-                function[17..][..8]
-                    .copy_from_slice(&(original_window_procedure as usize).to_le_bytes());
-                function[27..][..8].copy_from_slice(&(window_procedure as usize).to_le_bytes());
-                function
-            };
-            #[cfg(target_pointer_width = "32")]
-            let hook_wrapper = {
-                let mut function = vec![
-                    0x58, // pop eax
-                    0x68, 0, 0, 0, 0,    // push original_window_procedure
-                    0x50, // push eax
-                    0xb8, 0, 0, 0, 0, // mov eax, window_procedure
-                    0xff, 0xe0, // jmp eax
-                ];
-                function[2..][..4]
-                    .copy_from_slice(&(original_window_procedure as usize).to_le_bytes());
-                function[8..][..4].copy_from_slice(&(window_procedure as usize).to_le_bytes());
-                function
-            };
-
-            let current_process = process::Process::get_current();
-            let hook_wrapper_address = current_process
-                .allocate_memory(
-                    hook_wrapper.len(),
-                    process::MemoryPermissions {
-                        rwe: process::MemoryPermissionsRwe::ReadExecute,
-                        is_guard: false,
-                    },
-                )
-                .unwrap()
-                .cast();
-            current_process
-                .write(hook_wrapper_address, &hook_wrapper)
-                .unwrap();
-
-            unsafe { std::mem::transmute(hook_wrapper_address) }
-        });
+    new_information.lpfnWndProc = new_information.lpfnWndProc.map(wrap_window_procedure);
 
     if unicode_strings {
         let trampoline = get_trampoline!(
@@ -110,6 +80,65 @@ unsafe fn register_class_ex(information: *const WNDCLASSEXA, unicode_strings: bo
     }
 }
 
+/// Builds a fresh machine-code trampoline that prepends `original_window_procedure` as the first
+/// argument of [`window_procedure`], the same way [`register_class_ex`] wraps a class's `WndProc`
+/// at registration time. Shared with the retroactive subclassing in [`subclass_existing_windows`]
+/// and the `SetWindowLongPtr`/`SetClassLongPtr` hooks below, so every way a window's procedure can
+/// end up installed goes through the same wrapper.
+fn wrap_window_procedure(
+    original_window_procedure: unsafe extern "system" fn(HWND, u32, usize, isize) -> isize,
+) -> unsafe extern "system" fn(HWND, u32, usize, isize) -> isize {
+    #[cfg(target_pointer_width = "64")]
+    let hook_wrapper = {
+        let mut function = vec![
+            // 0xeb, 0xfe,
+            0x41, 0x51, // push r9
+            0x48, 0x83, 0xec, 0x20, // sub rsp, 0x20
+            0x4d, 0x89, 0xc1, // mov r9, r8
+            0x49, 0x89, 0xd0, // mov r8, rdx
+            0x48, 0x89, 0xca, // mov rdx, rcx
+            0x48, 0xb9, 0, 0, 0, 0, 0, 0, 0, 0, // mov rcx, original_window_procedure
+            0x48, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, // mov rax, window_procedure
+            0xff, 0xd0, // call rax
+            0x48, 0x83, 0xc4, 0x28, // add rsp, 0x28
+            0xc3,
+        ];
+        function[17..][..8].copy_from_slice(&(original_window_procedure as usize).to_le_bytes());
+        function[27..][..8].copy_from_slice(&(window_procedure as usize).to_le_bytes());
+        function
+    };
+    #[cfg(target_pointer_width = "32")]
+    let hook_wrapper = {
+        let mut function = vec![
+            0x58, // pop eax
+            0x68, 0, 0, 0, 0, // push original_window_procedure
+            0x50, // push eax
+            0xb8, 0, 0, 0, 0, // mov eax, window_procedure
+            0xff, 0xe0, // jmp eax
+        ];
+        function[2..][..4].copy_from_slice(&(original_window_procedure as usize).to_le_bytes());
+        function[8..][..4].copy_from_slice(&(window_procedure as usize).to_le_bytes());
+        function
+    };
+
+    let current_process = process::Process::get_current();
+    let hook_wrapper_address = current_process
+        .allocate_memory(
+            hook_wrapper.len(),
+            process::MemoryPermissions {
+                rwe: process::MemoryPermissionsRwe::ReadExecute,
+                is_guard: false,
+            },
+        )
+        .unwrap()
+        .cast();
+    current_process
+        .write(hook_wrapper_address, &hook_wrapper)
+        .unwrap();
+
+    unsafe { std::mem::transmute(hook_wrapper_address) }
+}
+
 unsafe extern "system" fn window_procedure(
     trampoline: unsafe extern "system" fn(HWND, u32, usize, isize) -> isize,
     window: HWND,
@@ -229,6 +258,9 @@ unsafe fn peek_message(
                 WM_KEYDOWN
                     | WM_KEYUP
                     | WM_CHAR
+                    | WM_SYSKEYDOWN
+                    | WM_SYSKEYUP
+                    | WM_SYSCHAR
                     | WM_MOUSEMOVE
                     | WM_LBUTTONDOWN
                     | WM_LBUTTONUP
@@ -238,6 +270,8 @@ unsafe fn peek_message(
                     | WM_MBUTTONUP
                     | WM_XBUTTONDOWN
                     | WM_XBUTTONUP
+                    | WM_MOUSEWHEEL
+                    | WM_MOUSEHWHEEL
             )
         {
             0
@@ -309,6 +343,16 @@ unsafe fn get_message(
                 if (*message).message == WM_QUIT {
                     return 0;
                 }
+                if (*message).message == WM_TIMER && (*message).lParam != 0 {
+                    // matches real GetMessage/PeekMessage: a WM_TIMER armed with a TimerProc is
+                    // consumed here and the callback invoked directly, rather than ever being
+                    // returned to the caller.
+                    let timer_proc: TIMERPROC = std::mem::transmute((*message).lParam);
+                    if let Some(timer_proc) = timer_proc {
+                        timer_proc((*message).hwnd, WM_TIMER, (*message).wParam, (*message).time);
+                    }
+                    continue;
+                }
                 return 1;
             }
         }
@@ -316,3 +360,212 @@ unsafe fn get_message(
         state::sleep_indefinitely();
     }
 }
+
+/// Real `TranslateMessage` reads the live, machine-specific keyboard state and active layout -
+/// nondeterministic inputs we can't allow near a message that originated from our own injected
+/// `custom_message_queue`. For `WM_KEYDOWN`/`WM_SYSKEYDOWN`, which is all [`get_message`] and
+/// [`peek_message`] ever hand the target (real key messages are filtered out before they get
+/// this far), this instead runs `ToUnicodeEx` against [`state::key_state_array`] and
+/// [`state::keyboard_layout`] - both TAS-owned - and posts the resulting `WM_CHAR`/`WM_SYSCHAR`
+/// itself. Every other message (and so, in practice, every real message) falls through to the
+/// trampoline, which can't produce characters from them either.
+#[hook("user32.dll")]
+unsafe extern "system" fn TranslateMessage(message: *const MSG) -> i32 {
+    let message_ref = unsafe { &*message };
+    if matches!(message_ref.message, WM_KEYDOWN | WM_SYSKEYDOWN) {
+        unsafe { translate_key_message(message_ref) };
+        1
+    } else {
+        let trampoline =
+            get_trampoline!(TranslateMessage, unsafe extern "system" fn(*const MSG) -> i32);
+        unsafe { trampoline(message) }
+    }
+}
+
+#[expect(clippy::cast_possible_truncation)]
+unsafe fn translate_key_message(message: &MSG) {
+    let virtual_key = message.wParam as u32;
+    let scan_code = (message.lParam >> 16) as u32 & 0xff;
+    let key_state = state::key_state_array();
+
+    let mut characters = [0u16; 8];
+    let character_count = unsafe {
+        ToUnicodeEx(
+            virtual_key,
+            scan_code,
+            key_state.as_ptr(),
+            characters.as_mut_ptr(),
+            characters.len() as i32,
+            0,
+            state::keyboard_layout() as HKL,
+        )
+    };
+    if character_count <= 0 {
+        // Either no mapping exists, or (a negative count) the key started a dead-key sequence -
+        // either way, real `TranslateMessage` posts no `WM_CHAR` for this keystroke.
+        return;
+    }
+
+    let message_id = if message.message == WM_SYSKEYDOWN {
+        WM_SYSCHAR
+    } else {
+        WM_CHAR
+    };
+    let mut state = STATE.lock().unwrap();
+    for &character in &characters[..character_count as usize] {
+        state.custom_message_queue.push_back(MSGSend(MSG {
+            hwnd: message.hwnd,
+            message: message_id,
+            wParam: usize::from(character),
+            lParam: message.lParam,
+            time: message.time,
+            pt: message.pt,
+        }));
+    }
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn SetTimer(
+    window: HWND,
+    id_event: usize,
+    elapse: u32,
+    timer_proc: TIMERPROC,
+) -> usize {
+    state::set_window_timer(window, id_event, elapse, timer_proc)
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn KillTimer(window: HWND, id_event: usize) -> i32 {
+    i32::from(state::kill_window_timer(window, id_event))
+}
+
+/// Wraps every window already open in the current process when this DLL was injected, the way
+/// [`register_class_ex`] wraps windows created afterwards from a class registered afterwards.
+/// Called once, from [`crate::hooks::initialize`].
+pub(crate) fn subclass_existing_windows() {
+    unsafe extern "system" fn callback(window: HWND, _: isize) -> i32 {
+        let mut process_id = 0;
+        unsafe { GetWindowThreadProcessId(window, &mut process_id) };
+        if process_id == unsafe { GetCurrentProcessId() } {
+            unsafe { subclass_window(window) };
+        }
+        1
+    }
+
+    unsafe {
+        EnumWindows(Some(callback), 0);
+    }
+}
+
+unsafe fn subclass_window(window: HWND) {
+    let original_window_procedure = unsafe { GetWindowLongPtrA(window, GWLP_WNDPROC) };
+    if original_window_procedure == 0 {
+        return;
+    }
+
+    let wrapped_window_procedure =
+        wrap_window_procedure(unsafe { std::mem::transmute(original_window_procedure) });
+    unsafe {
+        SetWindowLongPtrA(window, GWLP_WNDPROC, wrapped_window_procedure as isize);
+    }
+    SUBCLASSED_WINDOW_PROCEDURES
+        .lock()
+        .unwrap()
+        .insert(window as usize, original_window_procedure as usize);
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn SetWindowLongPtrA(window: HWND, index: i32, new_long: isize) -> isize {
+    unsafe { set_window_long_ptr(window, index, new_long, false) }
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn SetWindowLongPtrW(window: HWND, index: i32, new_long: isize) -> isize {
+    unsafe { set_window_long_ptr(window, index, new_long, true) }
+}
+
+/// Re-wraps a `SetWindowLongPtr`-installed `WndProc` the same way [`register_class_ex`] and
+/// [`subclass_existing_windows`] do, so a subclassing library loaded into the target can install
+/// its own `WndProc` on top of ours without ever running unwrapped code. Reports back the address
+/// the caller itself last installed (via [`SUBCLASSED_WINDOW_PROCEDURES`]) rather than our
+/// wrapper's, since the real previous value the trampoline returns is only genuine the first time
+/// a given window is subclassed.
+unsafe fn set_window_long_ptr(
+    window: HWND,
+    index: i32,
+    new_long: isize,
+    unicode_strings: bool,
+) -> isize {
+    let trampoline = if unicode_strings {
+        get_trampoline!(
+            SetWindowLongPtrW,
+            unsafe extern "system" fn(HWND, i32, isize) -> isize
+        )
+    } else {
+        get_trampoline!(
+            SetWindowLongPtrA,
+            unsafe extern "system" fn(HWND, i32, isize) -> isize
+        )
+    };
+
+    if index != GWLP_WNDPROC || new_long == 0 {
+        return unsafe { trampoline(window, index, new_long) };
+    }
+
+    let wrapped_window_procedure =
+        wrap_window_procedure(unsafe { std::mem::transmute(new_long) });
+    let real_previous = unsafe { trampoline(window, index, wrapped_window_procedure as isize) };
+
+    SUBCLASSED_WINDOW_PROCEDURES
+        .lock()
+        .unwrap()
+        .insert(window as usize, new_long as usize)
+        .map_or(real_previous, |previous| previous as isize)
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn SetClassLongPtrA(window: HWND, index: i32, new_long: isize) -> isize {
+    unsafe { set_class_long_ptr(window, index, new_long, false) }
+}
+
+#[hook("user32.dll")]
+unsafe extern "system" fn SetClassLongPtrW(window: HWND, index: i32, new_long: isize) -> isize {
+    unsafe { set_class_long_ptr(window, index, new_long, true) }
+}
+
+/// Like [`set_window_long_ptr`], but for `SetClassLongPtr`'s `GCLP_WNDPROC`. Win32 scopes a
+/// class's `WndProc` to every window of that class rather than to the one `HWND` passed in, but we
+/// key [`SUBCLASSED_WINDOW_PROCEDURES`] by that `HWND` anyway like everything else here - simpler,
+/// and sufficient for intercepting the swap itself rather than modeling class-wide propagation.
+unsafe fn set_class_long_ptr(
+    window: HWND,
+    index: i32,
+    new_long: isize,
+    unicode_strings: bool,
+) -> isize {
+    let trampoline = if unicode_strings {
+        get_trampoline!(
+            SetClassLongPtrW,
+            unsafe extern "system" fn(HWND, i32, isize) -> isize
+        )
+    } else {
+        get_trampoline!(
+            SetClassLongPtrA,
+            unsafe extern "system" fn(HWND, i32, isize) -> isize
+        )
+    };
+
+    if index != GCLP_WNDPROC || new_long == 0 {
+        return unsafe { trampoline(window, index, new_long) };
+    }
+
+    let wrapped_window_procedure =
+        wrap_window_procedure(unsafe { std::mem::transmute(new_long) });
+    let real_previous = unsafe { trampoline(window, index, wrapped_window_procedure as isize) };
+
+    SUBCLASSED_WINDOW_PROCEDURES
+        .lock()
+        .unwrap()
+        .insert(window as usize, new_long as usize)
+        .map_or(real_previous, |previous| previous as isize)
+}
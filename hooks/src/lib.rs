@@ -1,22 +1,32 @@
 #![allow(clippy::missing_panics_doc)]
 
+mod debugger;
+mod dirty_tracking;
 mod hooks;
+mod logging;
+mod rdtsc;
 mod state;
+mod takeover;
 
-use futures::executor::block_on;
-pub use shared::ipc::message::LogLevel;
+use futures::{
+    executor::{block_on, LocalPool},
+    task::LocalSpawnExt,
+};
 use shared::{
-    input::MouseButton,
+    input::{ConsoleCtrlEvent, GamepadAxis, GamepadButton, GamepadTrigger, MouseButton},
     ipc::{
         message::{self, Message},
-        Sender,
+        rpc, Sender,
     },
     windows::{event::ManualResetEvent, process, thread},
 };
 use std::{collections::VecDeque, mem::MaybeUninit, sync::Mutex, time::Duration};
 
-static mut LOG_MESSAGE_SENDER: MaybeUninit<Mutex<Sender<message::Log>>> = MaybeUninit::uninit();
-static mut IDLE_MESSAGE_SENDER: MaybeUninit<Mutex<Sender<message::Idle>>> = MaybeUninit::uninit();
+static mut LOG_MESSAGE_SENDER: MaybeUninit<Mutex<Sender<message::LogBatch>>> =
+    MaybeUninit::uninit();
+static mut RPC_RESPONSE_SENDER: MaybeUninit<Mutex<Sender<rpc::Response>>> = MaybeUninit::uninit();
+static mut SPAWNED_PROCESS_SENDER: MaybeUninit<Mutex<Sender<message::SpawnedProcess>>> =
+    MaybeUninit::uninit();
 
 #[derive(Debug)]
 #[non_exhaustive]
@@ -25,69 +35,76 @@ pub enum Event {
     SetKeyState { id: u8, state: bool },
     SetMousePosition { x: u16, y: u16 },
     SetMouseButtonState { button: MouseButton, state: bool },
-    Idle,
+    ScrollMouseWheel { delta: i32, horizontal: bool },
+    SetGamepadButton { index: u8, button: GamepadButton, state: bool },
+    SetGamepadAxis { index: u8, axis: GamepadAxis, value: i16 },
+    SetGamepadTrigger { index: u8, trigger: GamepadTrigger, value: u8 },
+    DeliverConsoleCtrlEvent(ConsoleCtrlEvent),
+    /// The request that should be answered once this event is reached: by the time the event
+    /// loop dequeues it, every event queued before it (and whatever real work they triggered) has
+    /// already drained, so the target is idle.
+    Idle(rpc::Token),
 }
 
-struct EventQueueInner {
-    queue: VecDeque<Event>,
+struct QueueInner<T> {
+    items: VecDeque<T>,
     pending_event: ManualResetEvent,
 }
 
-pub struct EventQueue(Mutex<EventQueueInner>);
+/// A queue that lets producers on any thread hand off items without waiting for them to be
+/// drained. Pairs with [`Self::dequeue`], an `async fn` that a single-threaded executor task can
+/// await cooperatively alongside other work, so a slow consumer never blocks a producer.
+pub struct Queue<T>(Mutex<QueueInner<T>>);
 
-impl EventQueue {
+impl<T> Queue<T> {
     #[must_use]
     pub fn new() -> Self {
-        Self(Mutex::new(EventQueueInner {
-            queue: VecDeque::new(),
+        Self(Mutex::new(QueueInner {
+            items: VecDeque::new(),
             pending_event: ManualResetEvent::new().unwrap(),
         }))
     }
 
-    pub fn enqueue(&self, event: Event) {
+    pub fn enqueue(&self, item: T) {
         let mut inner = self.0.lock().unwrap();
-        inner.queue.push_back(event);
+        inner.items.push_back(item);
         inner.pending_event.set().unwrap();
     }
 
-    pub fn dequeue_blocking(&self) -> Event {
-        let mut inner = self.0.lock().unwrap();
-        if inner.queue.is_empty() {
+    pub async fn dequeue(&self) -> T {
+        loop {
+            let mut inner = self.0.lock().unwrap();
+            if let Some(item) = inner.items.pop_front() {
+                if inner.items.is_empty() {
+                    inner.pending_event.reset().unwrap();
+                }
+                return item;
+            }
             let pending_event = inner.pending_event.try_clone().unwrap();
             drop(inner);
-            block_on(pending_event.wait()).unwrap();
-            inner = self.0.lock().unwrap();
+            pending_event.wait().await.unwrap();
         }
-        let event = inner.queue.pop_front().unwrap();
-        if inner.queue.is_empty() {
-            inner.pending_event.reset().unwrap();
-        }
-        event
+    }
+
+    pub fn dequeue_blocking(&self) -> T {
+        block_on(self.dequeue())
     }
 }
 
-impl Default for EventQueue {
+impl<T> Default for Queue<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-static mut EVENT_QUEUE: MaybeUninit<EventQueue> = MaybeUninit::uninit();
+pub type EventQueue = Queue<Event>;
 
-macro_rules! log {
-    ($level:expr, $($format_args:expr $(,)?),+) => {
-        let log_message_sender = unsafe { crate::LOG_MESSAGE_SENDER.assume_init_ref() };
-        futures::executor::block_on(log_message_sender
-            .lock()
-            .unwrap()
-            .send(shared::ipc::message::Log {
-                level: $level,
-                message: format!($($format_args),+),
-            }))
-            .unwrap();
-    };
-}
-pub(crate) use log;
+static mut EVENT_QUEUE: MaybeUninit<EventQueue> = MaybeUninit::uninit();
+static mut LOG_BATCH_QUEUE: MaybeUninit<Queue<message::LogBatch>> = MaybeUninit::uninit();
+static mut IDLE_QUEUE: MaybeUninit<Queue<rpc::Token>> = MaybeUninit::uninit();
+static mut SPAWNED_PROCESS_QUEUE: MaybeUninit<Queue<message::SpawnedProcess>> =
+    MaybeUninit::uninit();
+static mut LOGGER: MaybeUninit<logging::RingBufferLogger> = MaybeUninit::uninit();
 
 #[expect(clippy::missing_safety_doc)]
 #[no_mangle]
@@ -113,15 +130,25 @@ pub unsafe extern "system" fn initialize(initial_message_pointer: *mut u8) {
             .unwrap();
         initialized_message_sender = initial_message.initialized_message_sender;
         LOG_MESSAGE_SENDER = MaybeUninit::new(Mutex::new(initial_message.log_message_sender));
-        IDLE_MESSAGE_SENDER = MaybeUninit::new(Mutex::new(initial_message.idle_message_sender));
+        RPC_RESPONSE_SENDER =
+            MaybeUninit::new(Mutex::new(initial_message.rpc_response_sender));
+        SPAWNED_PROCESS_SENDER =
+            MaybeUninit::new(Mutex::new(initial_message.spawned_process_sender));
         message_receiver = initial_message.message_receiver;
         state::MAIN_THREAD_ID.write(initial_message.main_thread_id);
         EVENT_QUEUE.write(EventQueue::new());
+        LOG_BATCH_QUEUE.write(Queue::new());
+        IDLE_QUEUE.write(Queue::new());
+        SPAWNED_PROCESS_QUEUE.write(Queue::new());
+        LOGGER.write(logging::RingBufferLogger::new(
+            LOG_BATCH_QUEUE.assume_init_ref(),
+        ));
+        log::set_logger(LOGGER.assume_init_ref()).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
     }
 
     std::panic::set_hook(Box::new(|panic_info| {
-        log!(
-            LogLevel::Error,
+        log::error!(
             "panicked{}{}",
             match panic_info.location() {
                 Some(location) => format!(" at {location}"),
@@ -141,41 +168,223 @@ pub unsafe extern "system" fn initialize(initial_message_pointer: *mut u8) {
 
     block_on(initialized_message_sender.send(message::Initialized)).unwrap();
 
-    log!(
-        LogLevel::Debug,
+    log::debug!(
         "assuming thread with id {:#x} to be the main thread",
         unsafe { state::MAIN_THREAD_ID.assume_init_ref() }
     );
-    loop {
-        let event_queue = unsafe { EVENT_QUEUE.assume_init_ref() };
-        match block_on(message_receiver.receive()).unwrap() {
-            message::FromConductor::Resume => {
-                for thread in process::Process::get_current()
-                    .iter_thread_ids()
-                    .unwrap()
-                    .map(thread::Thread::from_id)
-                    .collect::<Result<Vec<_>, _>>()
+
+    // A single lightweight, single-threaded executor drives the rest of this function: the
+    // message-receive loop, the log and idle senders, and each per-message response all run as
+    // independent cooperatively-scheduled tasks, so a slow send on one of them can never stall
+    // intake on another.
+    let mut executor = LocalPool::new();
+    let spawner = executor.spawner();
+
+    spawner
+        .spawn_local(async move {
+            let log_batch_queue = unsafe { LOG_BATCH_QUEUE.assume_init_ref() };
+            let log_message_sender = unsafe { LOG_MESSAGE_SENDER.assume_init_ref() };
+            loop {
+                let log_batch = log_batch_queue.dequeue().await;
+                log_message_sender
+                    .lock()
                     .unwrap()
-                {
-                    thread.decrement_suspend_count().unwrap();
-                }
+                    .send(log_batch)
+                    .await
+                    .unwrap();
             }
-            message::FromConductor::AdvanceTime(duration) => {
-                event_queue.enqueue(Event::AdvanceTime(duration));
-            }
-            message::FromConductor::SetKeyState { id, state } => {
-                event_queue.enqueue(Event::SetKeyState { id, state });
-            }
-            message::FromConductor::SetMousePosition { x, y } => {
-                event_queue.enqueue(Event::SetMousePosition { x, y });
+        })
+        .unwrap();
+
+    spawner
+        .spawn_local(async move {
+            let spawned_process_queue = unsafe { SPAWNED_PROCESS_QUEUE.assume_init_ref() };
+            let spawned_process_sender = unsafe { SPAWNED_PROCESS_SENDER.assume_init_ref() };
+            loop {
+                let spawned_process = spawned_process_queue.dequeue().await;
+                spawned_process_sender
+                    .lock()
+                    .unwrap()
+                    .send(spawned_process)
+                    .await
+                    .unwrap();
             }
-            message::FromConductor::SetMouseButtonState { button, state } => {
-                event_queue.enqueue(Event::SetMouseButtonState { button, state });
+        })
+        .unwrap();
+
+    spawner
+        .spawn_local(async move {
+            let idle_queue = unsafe { IDLE_QUEUE.assume_init_ref() };
+            let rpc_response_sender = unsafe { RPC_RESPONSE_SENDER.assume_init_ref() };
+            loop {
+                let token = idle_queue.dequeue().await;
+                let payload = bincode::serialize(&message::IsIdleResponse).unwrap();
+                rpc_response_sender
+                    .lock()
+                    .unwrap()
+                    .send(rpc::Response { token, payload })
+                    .await
+                    .unwrap();
             }
-            message::FromConductor::IdleRequest => {
-                event_queue.enqueue(Event::Idle);
+        })
+        .unwrap();
+
+    let response_spawner = spawner.clone();
+    spawner
+        .spawn_local(async move {
+            loop {
+                let event_queue = unsafe { EVENT_QUEUE.assume_init_ref() };
+                match message_receiver.receive().await.unwrap() {
+                    message::FromConductor::Resume => {
+                        for thread in process::Process::get_current()
+                            .iter_thread_ids()
+                            .unwrap()
+                            .map(thread::Thread::from_id)
+                            .collect::<Result<Vec<_>, _>>()
+                            .unwrap()
+                        {
+                            thread.decrement_suspend_count().unwrap();
+                        }
+                    }
+                    message::FromConductor::AdvanceTime(duration) => {
+                        event_queue.enqueue(Event::AdvanceTime(duration));
+                    }
+                    message::FromConductor::SetKeyState { id, state } => {
+                        event_queue.enqueue(Event::SetKeyState { id, state });
+                    }
+                    message::FromConductor::SetMousePosition { x, y } => {
+                        event_queue.enqueue(Event::SetMousePosition { x, y });
+                    }
+                    message::FromConductor::SetMouseButtonState { button, state } => {
+                        event_queue.enqueue(Event::SetMouseButtonState { button, state });
+                    }
+                    message::FromConductor::ScrollMouseWheel { delta, horizontal } => {
+                        event_queue.enqueue(Event::ScrollMouseWheel { delta, horizontal });
+                    }
+                    message::FromConductor::SetGamepadButton {
+                        index,
+                        button,
+                        state,
+                    } => {
+                        event_queue.enqueue(Event::SetGamepadButton {
+                            index,
+                            button,
+                            state,
+                        });
+                    }
+                    message::FromConductor::SetGamepadAxis { index, axis, value } => {
+                        event_queue.enqueue(Event::SetGamepadAxis { index, axis, value });
+                    }
+                    message::FromConductor::SetGamepadTrigger {
+                        index,
+                        trigger,
+                        value,
+                    } => {
+                        event_queue.enqueue(Event::SetGamepadTrigger {
+                            index,
+                            trigger,
+                            value,
+                        });
+                    }
+                    message::FromConductor::DeliverConsoleCtrlEvent(event) => {
+                        event_queue.enqueue(Event::DeliverConsoleCtrlEvent(event));
+                    }
+                    message::FromConductor::Rpc { token, payload } => {
+                        // `IsIdleRequest` is the only request carried over this channel so far;
+                        // once a second one exists this will need to branch on the payload.
+                        let message::IsIdleRequest = bincode::deserialize(&payload).unwrap();
+                        event_queue.enqueue(Event::Idle(token));
+                    }
+                    message::FromConductor::ArmDirtyTracking {
+                        regions,
+                        mut response_sender,
+                    } => {
+                        dirty_tracking::arm(&regions);
+                        response_spawner
+                            .spawn_local(async move {
+                                response_sender.send(message::Armed).await.unwrap();
+                            })
+                            .unwrap();
+                    }
+                    message::FromConductor::TakeDirtyPages {
+                        mut response_sender,
+                    } => {
+                        let base_addresses = dirty_tracking::take_dirty_pages();
+                        response_spawner
+                            .spawn_local(async move {
+                                response_sender
+                                    .send(message::DirtyPages { base_addresses })
+                                    .await
+                                    .unwrap();
+                            })
+                            .unwrap();
+                    }
+                    message::FromConductor::SetBreakpoint {
+                        address,
+                        mut response_sender,
+                    } => {
+                        debugger::set_breakpoint(address);
+                        response_spawner
+                            .spawn_local(async move {
+                                response_sender.send(message::BreakpointArmed).await.unwrap();
+                            })
+                            .unwrap();
+                    }
+                    message::FromConductor::ClearBreakpoint {
+                        address,
+                        mut response_sender,
+                    } => {
+                        debugger::clear_breakpoint(address);
+                        response_spawner
+                            .spawn_local(async move {
+                                response_sender
+                                    .send(message::BreakpointCleared)
+                                    .await
+                                    .unwrap();
+                            })
+                            .unwrap();
+                    }
+                    message::FromConductor::WaitForPause { response_sender } => {
+                        debugger::wait_for_pause(response_sender);
+                    }
+                    message::FromConductor::ResumeFromPause {
+                        thread_id,
+                        single_step,
+                    } => {
+                        debugger::resume(thread_id, single_step);
+                    }
+                    message::FromConductor::Takeover { new_process_id } => {
+                        let log_message_sender = unsafe { LOG_MESSAGE_SENDER.assume_init_ref() };
+                        let rpc_response_sender = unsafe { RPC_RESPONSE_SENDER.assume_init_ref() };
+                        takeover::handle(
+                            new_process_id,
+                            &log_message_sender.lock().unwrap(),
+                            &rpc_response_sender.lock().unwrap(),
+                        )
+                        .unwrap();
+                    }
+                    message::FromConductor::SetTimeConfiguration {
+                        performance_counter_frequency,
+                        time_scale_numerator,
+                        time_scale_denominator,
+                    } => {
+                        state::set_time_configuration(
+                            performance_counter_frequency,
+                            time_scale_numerator,
+                            std::num::NonZeroU64::new(time_scale_denominator).unwrap(),
+                        );
+                    }
+                    message::FromConductor::SetKeyboardLayout(hkl) => {
+                        state::set_keyboard_layout(hkl);
+                    }
+                    message::FromConductor::SetSocketMode(mode) => {
+                        state::set_socket_mode(mode);
+                    }
+                    message => unimplemented!("handle message {message:?}"),
+                }
             }
-            message => unimplemented!("handle message {message:?}"),
-        }
-    }
+        })
+        .unwrap();
+
+    executor.run();
 }
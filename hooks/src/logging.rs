@@ -0,0 +1,109 @@
+use crate::Queue;
+use shared::ipc::message::{Log, LogBatch, LogLevel};
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Bounds how many log entries [`RingBufferLogger`] holds before it starts evicting the oldest
+/// to make room for new ones, so a burst of logging can never grow without bound.
+const CAPACITY: usize = 1024;
+
+/// A flush is triggered once this many entries have accumulated, rather than waiting for
+/// [`FLUSH_INTERVAL`] to pass.
+const SIZE_THRESHOLD: usize = 64;
+
+/// A log call made at least this long after the last flush triggers a flush, so a slow trickle
+/// of log calls doesn't wait for [`SIZE_THRESHOLD`]. The check only runs on a log call: entries
+/// recorded after the last one stay buffered until the next log call or an explicit flush.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+struct Inner {
+    entries: VecDeque<Log>,
+    dropped: u32,
+    last_flush: Instant,
+}
+
+impl Inner {
+    fn take_batch(&mut self) -> LogBatch {
+        self.last_flush = Instant::now();
+        LogBatch {
+            entries: self.entries.drain(..).collect(),
+            dropped: std::mem::take(&mut self.dropped),
+        }
+    }
+}
+
+/// A [`log::Log`] implementation that records into a fixed-capacity ring buffer instead of
+/// sending each record to the conductor as it happens, so verbose tracing from inside the traced
+/// process doesn't cost a pipe round trip per line and can never block the traced thread.
+/// Accumulated entries are pushed onto `queue` as a single [`LogBatch`] once
+/// [`SIZE_THRESHOLD`] entries have built up or a log call finds [`FLUSH_INTERVAL`] has passed
+/// since the last flush, whichever comes first, or when [`Self::flush_now`] is called.
+pub(crate) struct RingBufferLogger {
+    queue: &'static Queue<LogBatch>,
+    inner: Mutex<Inner>,
+}
+
+impl RingBufferLogger {
+    pub(crate) fn new(queue: &'static Queue<LogBatch>) -> Self {
+        Self {
+            queue,
+            inner: Mutex::new(Inner {
+                entries: VecDeque::with_capacity(CAPACITY),
+                dropped: 0,
+                last_flush: Instant::now(),
+            }),
+        }
+    }
+
+    /// Flushes whatever is currently buffered, regardless of the size/time thresholds. Does
+    /// nothing if nothing is buffered.
+    pub(crate) fn flush_now(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.is_empty() {
+            return;
+        }
+        self.queue.enqueue(inner.take_batch());
+    }
+}
+
+impl log::Log for RingBufferLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = Log {
+            level: match record.level() {
+                log::Level::Trace => LogLevel::Trace,
+                log::Level::Debug => LogLevel::Debug,
+                log::Level::Info => LogLevel::Info,
+                log::Level::Warn => LogLevel::Warning,
+                log::Level::Error => LogLevel::Error,
+            },
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.len() >= CAPACITY {
+            inner.entries.pop_front();
+            inner.dropped += 1;
+        }
+        inner.entries.push_back(entry);
+
+        if inner.entries.len() >= SIZE_THRESHOLD || inner.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.queue.enqueue(inner.take_batch());
+        }
+    }
+
+    fn flush(&self) {
+        self.flush_now();
+    }
+}
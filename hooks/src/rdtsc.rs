@@ -0,0 +1,197 @@
+use crate::state;
+use shared::windows::{
+    module::Module,
+    process::{self, MemoryPermissions, MemoryPermissionsRwe, MemoryRegion, Process},
+};
+use std::{
+    collections::BTreeMap,
+    ffi::c_void,
+    sync::{Mutex, OnceLock},
+};
+use winapi::um::{
+    errhandlingapi::AddVectoredExceptionHandler,
+    winnt::{CONTEXT, EXCEPTION_POINTERS},
+};
+
+// avoids depending on the exact winapi re-export paths for these, which vary by crate version
+const EXCEPTION_BREAKPOINT: u32 = 0x8000_0003;
+const EXCEPTION_CONTINUE_EXECUTION: i32 = -1;
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+#[derive(Clone, Copy)]
+enum TrappedInstruction {
+    Rdtsc,
+    Rdtscp,
+}
+
+impl TrappedInstruction {
+    const fn opcode(self) -> &'static [u8] {
+        match self {
+            Self::Rdtsc => &[0x0f, 0x31],
+            Self::Rdtscp => &[0x0f, 0x01, 0xf9],
+        }
+    }
+}
+
+static TRAPS: Mutex<BTreeMap<usize, TrappedInstruction>> = Mutex::new(BTreeMap::new());
+static HANDLER_INSTALLED: OnceLock<()> = OnceLock::new();
+
+fn install_handler() {
+    HANDLER_INSTALLED.get_or_init(|| unsafe {
+        // installed first so that it is consulted before any other handlers the guest installs
+        AddVectoredExceptionHandler(1, Some(exception_handler));
+    });
+}
+
+unsafe extern "system" fn exception_handler(exception_pointers: *mut EXCEPTION_POINTERS) -> i32 {
+    let record = unsafe { &*(*exception_pointers).ExceptionRecord };
+    if record.ExceptionCode != EXCEPTION_BREAKPOINT {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    let address = record.ExceptionAddress as usize;
+    let Some(&instruction) = TRAPS.lock().unwrap().get(&address) else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+
+    // the same virtual clock the time hooks (see `QueryPerformanceCounter`) derive from, scaled
+    // to a synthetic TSC frequency so a direct `rdtsc` read stays in lockstep with them
+    let tsc = state::ticks_to_units(
+        state::get_ticks_with_busy_wait(),
+        state::performance_counter_frequency(),
+    );
+
+    let context = unsafe { &mut *(*exception_pointers).ContextRecord };
+    write_tsc(context, tsc, instruction);
+    advance_past_instruction(context, address, instruction);
+
+    EXCEPTION_CONTINUE_EXECUTION
+}
+
+#[cfg(target_arch = "x86")]
+fn write_tsc(context: &mut CONTEXT, tsc: u64, instruction: TrappedInstruction) {
+    #[expect(clippy::cast_possible_truncation)]
+    {
+        context.Eax = tsc as u32;
+        context.Edx = (tsc >> 32) as u32;
+    }
+    if matches!(instruction, TrappedInstruction::Rdtscp) {
+        context.Ecx = 0; // processor id; this environment only ever simulates one core
+    }
+}
+
+#[cfg(target_arch = "x86")]
+fn advance_past_instruction(context: &mut CONTEXT, address: usize, instruction: TrappedInstruction) {
+    #[expect(clippy::cast_possible_truncation)]
+    {
+        context.Eip = (address + instruction.opcode().len()) as u32;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn write_tsc(context: &mut CONTEXT, tsc: u64, instruction: TrappedInstruction) {
+    // rdtsc(p) always writes a 32-bit value into each of eax/edx(/ecx), zero-extended into the
+    // full 64-bit register, even in long mode
+    context.Rax = tsc & 0xffff_ffff;
+    context.Rdx = tsc >> 32;
+    if matches!(instruction, TrappedInstruction::Rdtscp) {
+        context.Rcx = 0; // processor id; this environment only ever simulates one core
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn advance_past_instruction(context: &mut CONTEXT, address: usize, instruction: TrappedInstruction) {
+    context.Rip = (address + instruction.opcode().len()) as u64;
+}
+
+/// Scans `module`'s mapped executable regions for `rdtsc`/`rdtscp` opcodes and replaces each
+/// with a breakpoint trap so direct timestamp-counter reads route through the virtual clock (see
+/// [`exception_handler`]) instead of the real CPU counter, which would otherwise bypass every API
+/// hook and desync from the rest of the simulated time.
+pub(crate) fn virtualize_module(module: &Module) {
+    install_handler();
+
+    let Ok(size) = module.get_size() else {
+        return;
+    };
+    let process = process::Process::get_current();
+    let module_start = module.get_base_address() as usize;
+    let module_end = module_start + size;
+
+    let mut region_address = module_start;
+    while region_address < module_end {
+        let Ok(region) = process.get_memory_region(region_address as *mut c_void) else {
+            break;
+        };
+        let region_size = region.size();
+        let is_executable = match &region {
+            MemoryRegion::Reserved(region) => {
+                region.is_committed() && is_executable(region.permissions())
+            }
+            MemoryRegion::Free(_) => false,
+        };
+
+        if is_executable {
+            let scan_start = region_address;
+            let scan_end = (region_address + region_size).min(module_end);
+            if let Ok(bytes) = process.read_to_vec(scan_start as *const u8, scan_end - scan_start)
+            {
+                scan_and_patch(&process, scan_start, &bytes);
+            }
+        }
+
+        region_address += region_size;
+    }
+}
+
+fn is_executable(permissions: MemoryPermissions) -> bool {
+    matches!(
+        permissions.rwe,
+        MemoryPermissionsRwe::Execute
+            | MemoryPermissionsRwe::ReadExecute
+            | MemoryPermissionsRwe::ReadWriteExecute
+    )
+}
+
+fn scan_and_patch(process: &Process, region_start: usize, bytes: &[u8]) {
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let instruction = if bytes[offset..].starts_with(TrappedInstruction::Rdtscp.opcode()) {
+            Some(TrappedInstruction::Rdtscp)
+        } else if bytes[offset..].starts_with(TrappedInstruction::Rdtsc.opcode()) {
+            Some(TrappedInstruction::Rdtsc)
+        } else {
+            None
+        };
+
+        let Some(instruction) = instruction else {
+            offset += 1;
+            continue;
+        };
+        patch(process, (region_start + offset) as *mut c_void, instruction);
+        offset += instruction.opcode().len();
+    }
+}
+
+fn patch(process: &Process, address: *mut c_void, instruction: TrappedInstruction) {
+    let opcode_length = instruction.opcode().len();
+    let Ok(previous_permissions) = process.set_memory_permissions(
+        address,
+        opcode_length,
+        MemoryPermissions {
+            rwe: MemoryPermissionsRwe::ReadWriteExecute,
+            is_guard: false,
+        },
+    ) else {
+        return;
+    };
+
+    // int3 followed by nops, so the instruction stream downstream of the trap stays aligned
+    let mut patched_bytes = vec![0x90; opcode_length];
+    patched_bytes[0] = 0xcc;
+    let _ = process.write(address.cast(), &patched_bytes);
+
+    let _ = process.set_memory_permissions(address, opcode_length, previous_permissions);
+
+    TRAPS.lock().unwrap().insert(address as usize, instruction);
+}
@@ -1,25 +1,37 @@
-use crate::{hooks, log, Event, EVENT_QUEUE, IDLE_MESSAGE_SENDER};
-use futures::executor::block_on;
-use shared::communication::{IdleMessage, LogLevel, MouseButton};
+use crate::{hooks::get_trampoline, Event, EVENT_QUEUE, IDLE_QUEUE};
+use shared::{
+    communication::MouseButton,
+    input::{ConsoleCtrlEvent, GamepadAxis, GamepadButton, GamepadTrigger},
+    ipc::message::SocketMode,
+    windows::event::ManualResetEvent,
+};
 use std::{
     collections::{BTreeMap, VecDeque},
-    mem::MaybeUninit,
+    mem::{size_of, MaybeUninit},
     num::NonZeroU64,
     sync::{Arc, Mutex},
 };
+use thiserror::Error;
 use winapi::{
+    ctypes::c_void,
     shared::{
         ntdef::NULL,
         windef::{HWND, POINT},
     },
     um::{
         processthreadsapi::GetCurrentThreadId,
-        synchapi::Sleep,
+        synchapi::{PulseEvent, SetEvent, WaitForSingleObject},
+        wincon::{
+            PHANDLER_ROUTINE, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT, CTRL_LOGOFF_EVENT,
+            CTRL_SHUTDOWN_EVENT,
+        },
+        winbase::INFINITE,
         winuser::{
-            self, EnumThreadWindows, IsWindowVisible, MSG, VK_CONTROL, VK_LCONTROL, VK_LMENU,
-            VK_LSHIFT, VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_SHIFT, WM_KEYDOWN, WM_KEYUP,
-            WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE,
-            WM_RBUTTONDOWN, WM_RBUTTONUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
+            self, EnumThreadWindows, IsWindowVisible, MSG, TIMERPROC, VK_CONTROL, VK_LCONTROL,
+            VK_LMENU, VK_LSHIFT, VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_SHIFT,
+            WM_INPUT, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN,
+            WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN,
+            WM_RBUTTONUP, WM_TIMER, WM_XBUTTONDOWN, WM_XBUTTONUP,
         },
     },
 };
@@ -40,11 +52,55 @@ pub(crate) struct MouseState {
     x2_button: bool,
 }
 
+/// One of the four virtual `XInputGetState` controller slots (see `hooks::gamepad`). All four
+/// slots always report connected - there's no virtual equivalent of a controller being
+/// physically unplugged, so a game polling indices it isn't actually using just sees an
+/// all-neutral pad rather than `ERROR_DEVICE_NOT_CONNECTED`.
+#[derive(Clone, Copy)]
+pub(crate) struct GamepadState {
+    buttons: u16,
+    left_stick_x: i16,
+    left_stick_y: i16,
+    right_stick_x: i16,
+    right_stick_y: i16,
+    left_trigger: u8,
+    right_trigger: u8,
+    pub(crate) packet_number: u32,
+}
+
+impl GamepadState {
+    pub(crate) fn buttons(&self) -> u16 {
+        self.buttons
+    }
+
+    pub(crate) fn thumbsticks(&self) -> (i16, i16, i16, i16) {
+        (
+            self.left_stick_x,
+            self.left_stick_y,
+            self.right_stick_x,
+            self.right_stick_y,
+        )
+    }
+
+    pub(crate) fn triggers(&self) -> (u8, u8) {
+        (self.left_trigger, self.right_trigger)
+    }
+}
+
+/// Whether this process registered for raw mouse or raw keyboard input (see
+/// `hooks::raw_input::RegisterRawInputDevices`), and whether that registration asked to suppress
+/// the corresponding legacy window messages (`RIDEV_NOLEGACY`).
+#[derive(Clone, Copy)]
+pub(crate) struct RawInputRegistration {
+    pub(crate) suppress_legacy_messages: bool,
+}
+
 pub(crate) struct WaitableTimer {
     pub(crate) reset_automatically: bool,
     pub(crate) signaled: bool,
     pub(crate) remaining_ticks: u64,
     pub(crate) period_in_ticks: Option<NonZeroU64>,
+    pub(crate) completion_routine: Option<TimerCompletionRoutine>,
 }
 
 impl WaitableTimer {
@@ -53,19 +109,178 @@ impl WaitableTimer {
     }
 }
 
+/// A `CreateEvent(Ex)` event: a manual-reset event stays signaled until `ResetEvent` clears it,
+/// while an auto-reset event clears itself the instant a wait is satisfied by it (see
+/// `hooks::misc::try_wait_for_objects`).
+pub(crate) struct EmulatedEvent {
+    pub(crate) manual_reset: bool,
+    pub(crate) signaled: bool,
+}
+
+/// A `CreateMutex(Ex)` mutex. Real Win32 mutexes are recursively owned by a single thread at a
+/// time, so unlike [`EmulatedEvent`]/[`Semaphore`] a wait satisfied by one also records which thread now
+/// owns it (`owner_thread_id`) and how many nested acquisitions it holds (`recursion_count`); the
+/// mutex is only released back to other threads once `ReleaseMutex` has unwound every one of them.
+pub(crate) struct EmulatedMutex {
+    pub(crate) owner_thread_id: Option<u32>,
+    pub(crate) recursion_count: u32,
+}
+
+/// A `CreateSemaphore(Ex)` semaphore. `count` is how many waiters can currently be satisfied
+/// without blocking; a satisfied wait decrements it, `ReleaseSemaphore` increments it back by
+/// however much it's asked to release, capped at `maximum_count`.
+pub(crate) struct Semaphore {
+    pub(crate) count: i64,
+    pub(crate) maximum_count: i64,
+}
+
+/// A kernel object handle this target created that the hooks DLL emulates instead of deferring to
+/// the real OS for it, keyed by its real handle value in [`State::handles`]. A handle reaching here
+/// via `DuplicateHandle` (see `hooks::misc`) shares the same `Arc` as the handle it was duplicated
+/// from, so the emulated object is only actually dropped once `CloseHandle` has removed every
+/// entry referencing it - there's no separate refcount to keep in sync, `Arc` already is one.
+#[derive(Clone)]
+pub(crate) enum EmulatedHandle {
+    WaitableTimer(Arc<Mutex<WaitableTimer>>),
+    EmulatedEvent(Arc<Mutex<EmulatedEvent>>),
+    Mutex(Arc<Mutex<EmulatedMutex>>),
+    Semaphore(Arc<Mutex<Semaphore>>),
+}
+
+/// A `SetWaitableTimer(Ex)` completion routine plus the argument it was armed with, recorded so
+/// [`advance_timers`] can queue it as a pending APC (see [`PendingTimerApc`]) once the timer's
+/// simulated expiry is reached.
+#[derive(Clone, Copy)]
+pub(crate) struct TimerCompletionRoutine {
+    pub(crate) routine: unsafe extern "system" fn(*mut c_void, u32, u32),
+    pub(crate) argument: *mut c_void,
+}
+
+unsafe impl Send for TimerCompletionRoutine {}
+
+/// A [`TimerCompletionRoutine`] that is due to run, along with the low/high `FILETIME` words of
+/// the virtual clock at the moment its timer expired (what the real API would have passed had the
+/// wall clock actually reached that instant).
+struct PendingTimerApc {
+    completion_routine: TimerCompletionRoutine,
+    low: u32,
+    high: u32,
+}
+
+/// A `SetTimer` timer, keyed by `(window, id_event)` the way Win32 scopes window timers: distinct
+/// windows may reuse the same `id_event` independently. Unlike [`WaitableTimer`], it has no
+/// one-shot mode - `SetTimer` always recurs every `interval_ticks` until `KillTimer` removes it.
+pub(crate) struct WindowTimer {
+    interval_ticks: u64,
+    remaining_ticks: u64,
+    timer_proc: TIMERPROC,
+}
+
+pub(crate) struct MultimediaTimer {
+    pub(crate) remaining_ticks: u64,
+    pub(crate) period_in_ticks: Option<NonZeroU64>,
+    pub(crate) callback: MultimediaTimerCallback,
+}
+
+/// What a `timeSetEvent` timer does when it fires, matching the three ways `fuEvent` can tell it
+/// to report completion: call a `LPTIMECALLBACK` directly, or signal/pulse an event object.
+#[derive(Clone, Copy)]
+pub(crate) enum MultimediaTimerCallback {
+    Function {
+        callback: winapi::um::mmsystem::LPTIMECALLBACK,
+        user_data: winapi::shared::basetsd::DWORD_PTR,
+    },
+    SetEvent(*mut c_void),
+    PulseEvent(*mut c_void),
+}
+
+unsafe impl Send for MultimediaTimerCallback {}
+
+/// Bytes a virtual socket actually received at a particular simulated tick, as recorded in
+/// [`VirtualSocket::recv_log`] while [`socket_mode`] is [`SocketMode::Record`] and replayed back
+/// from while it's [`SocketMode::Replay`].
+pub(crate) struct RecvChunk {
+    pub(crate) tick: u64,
+    pub(crate) bytes: Vec<u8>,
+}
+
+/// A child process this target has spawned via a hooked `CreateProcess` (see `hooks::process`),
+/// always forced to start suspended so the conductor gets a chance to inject the hooks DLL into it
+/// before anything in it runs. Tracked here purely so it rides along with save state for free, the
+/// same way [`VirtualSocket`]s do - the actual injection and resume handshake lives entirely on the
+/// conductor side (see `winter::Conductor`).
+pub(crate) struct SpawnedProcess {
+    pub(crate) process_id: u32,
+    pub(crate) main_thread_id: u32,
+}
+
+/// A socket as seen by the hooked `socket`/`connect`/`send`/`recv`/`closesocket` family (see
+/// `hooks::socket`). In [`SocketMode::Record`], `real_socket` is a live Winsock socket every call is
+/// forwarded to, and each successful `recv` is additionally appended to `recv_log` so the run can
+/// be replayed later. In [`SocketMode::Replay`], there is no real socket at all (`connect`
+/// no-ops to success and `send` is discarded): `recv` is served purely from `recv_log`, keyed by
+/// the simulated tick its bytes actually arrived at, so a hook that asks for bytes logged at a
+/// tick still in the future reports `WSAEWOULDBLOCK` exactly as the original run would have.
+pub(crate) struct VirtualSocket {
+    pub(crate) real_socket: usize,
+    pub(crate) recv_log: VecDeque<RecvChunk>,
+    pub(crate) recv_cursor: usize,
+}
+
+/// A thread's bookkeeping in the cooperative scheduler (see [`yield_to_next`]): whether it is
+/// currently blocked (yielded out, waiting for the run token back), the simulated tick at which
+/// it should become runnable again on its own even if no other thread ever yields to it (`None`
+/// if its wait has no timeout of its own), and the event used to hand the run token back to it.
+pub(crate) struct SchedulerThread {
+    blocked: bool,
+    wake_at_ticks: Option<u64>,
+    run_token: ManualResetEvent,
+}
+
+#[derive(Debug, Error)]
+#[error("scheduler deadlock: every thread is blocked and none of their waits has a timeout to wake it on its own")]
+pub(crate) struct DeadlockError;
+
 pub(crate) struct State {
     ticks: u64,
     pending_ticks: u64,
     busy_wait_count: u64,
     key_states: [bool; 256],
     pub(crate) mouse: MouseState,
+    pub(crate) gamepads: [GamepadState; 4],
+    pub(crate) raw_mouse_registration: Option<RawInputRegistration>,
+    pub(crate) raw_keyboard_registration: Option<RawInputRegistration>,
+    raw_input_buffers: BTreeMap<usize, Vec<u8>>,
+    next_raw_input_handle: usize,
     pub(crate) custom_message_queue: VecDeque<MSGSend>,
-    pub(crate) waitable_timer_handles: BTreeMap<u32, Arc<Mutex<WaitableTimer>>>,
+    pub(crate) handles: BTreeMap<u32, EmulatedHandle>,
+    pending_timer_apcs: VecDeque<PendingTimerApc>,
+    window_timer_handles: BTreeMap<(usize, usize), WindowTimer>,
+    pub(crate) multimedia_timer_handles: BTreeMap<u32, Arc<Mutex<MultimediaTimer>>>,
+    pub(crate) next_multimedia_timer_id: u32,
+    pub(crate) sockets: BTreeMap<u32, VirtualSocket>,
+    pub(crate) next_socket_id: u32,
+    socket_mode: SocketMode,
+    pub(crate) spawned_processes: Vec<SpawnedProcess>,
+    performance_counter_frequency: u64,
+    time_scale_numerator: u64,
+    time_scale_denominator: u64,
+    scheduler_threads: BTreeMap<u32, SchedulerThread>,
+    console_ctrl_handlers: Vec<unsafe extern "system" fn(u32) -> i32>,
+    ignore_ctrl_c: bool,
+    keyboard_layout: usize,
+    caps_lock_toggled: bool,
+    num_lock_toggled: bool,
 }
 
 impl State {
     pub(crate) const TICKS_PER_SECOND: u64 = 3000;
     const BUSY_WAIT_THRESHOLD: u64 = 100;
+    const DEFAULT_PERFORMANCE_COUNTER_FREQUENCY: u64 = 1 << 32;
+    /// US English (`MAKELANGID(LANG_ENGLISH, SUBLANG_ENGLISH_US)` in both halves), chosen as a
+    /// deterministic default so a run's `WM_CHAR` output doesn't depend on the target's installed
+    /// layouts unless [`set_keyboard_layout`] pins a different one.
+    const DEFAULT_KEYBOARD_LAYOUT: usize = 0x0409_0409;
 
     pub(crate) fn ticks(&self) -> u64 {
         self.ticks
@@ -78,6 +293,16 @@ impl State {
         const VK_CONTROL: u8 = winuser::VK_CONTROL as u8;
         #[expect(clippy::cast_possible_truncation)]
         const VK_MENU: u8 = winuser::VK_MENU as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_LBUTTON: u8 = winuser::VK_LBUTTON as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_RBUTTON: u8 = winuser::VK_RBUTTON as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_MBUTTON: u8 = winuser::VK_MBUTTON as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_XBUTTON1: u8 = winuser::VK_XBUTTON1 as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_XBUTTON2: u8 = winuser::VK_XBUTTON2 as u8;
 
         match key_code {
             VK_SHIFT => self.key_states[VK_LSHIFT as usize] || self.key_states[VK_RSHIFT as usize],
@@ -85,14 +310,45 @@ impl State {
                 self.key_states[VK_LCONTROL as usize] || self.key_states[VK_RCONTROL as usize]
             }
             VK_MENU => self.key_states[VK_LMENU as usize] || self.key_states[VK_RMENU as usize],
+            VK_LBUTTON => self.mouse.left_button,
+            VK_RBUTTON => self.mouse.right_button,
+            VK_MBUTTON => self.mouse.middle_button,
+            VK_XBUTTON1 => self.mouse.x1_button,
+            VK_XBUTTON2 => self.mouse.x2_button,
             key_code => self.key_states[usize::from(key_code)],
         }
     }
 
     pub(crate) fn set_key_state(&mut self, key_code: u8, state: bool) {
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_CAPITAL: u8 = winuser::VK_CAPITAL as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_NUMLOCK: u8 = winuser::VK_NUMLOCK as u8;
+        if state && !self.key_states[usize::from(key_code)] {
+            match key_code {
+                VK_CAPITAL => self.caps_lock_toggled = !self.caps_lock_toggled,
+                VK_NUMLOCK => self.num_lock_toggled = !self.num_lock_toggled,
+                _ => {}
+            }
+        }
         self.key_states[usize::from(key_code)] = state;
     }
 
+    /// The toggle (sticky, not held-down) state `GetKeyState`'s low-order bit reports for
+    /// `VK_CAPITAL`/`VK_NUMLOCK` - `false` (never toggled) for every other key, same as real
+    /// `GetKeyState` reports for keys with no toggle behavior of their own.
+    pub(crate) fn toggle_state(&self, key_code: u8) -> bool {
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_CAPITAL: u8 = winuser::VK_CAPITAL as u8;
+        #[expect(clippy::cast_possible_truncation)]
+        const VK_NUMLOCK: u8 = winuser::VK_NUMLOCK as u8;
+        match key_code {
+            VK_CAPITAL => self.caps_lock_toggled,
+            VK_NUMLOCK => self.num_lock_toggled,
+            _ => false,
+        }
+    }
+
     pub(crate) fn set_mouse_button_state(&mut self, button: MouseButton, state: bool) {
         *match button {
             MouseButton::Left => &mut self.mouse.left_button,
@@ -102,6 +358,132 @@ impl State {
             MouseButton::X2 => &mut self.mouse.x2_button,
         } = state;
     }
+
+    pub(crate) fn gamepad(&self, index: u8) -> Option<&GamepadState> {
+        self.gamepads.get(usize::from(index))
+    }
+
+    /// Sets `button`'s pressed state on the virtual gamepad at `index`, bumping
+    /// [`GamepadState::packet_number`] if that actually changed anything - out-of-range `index`
+    /// (there are only 4 virtual gamepads) is silently ignored, matching real XInput's tolerance
+    /// for polling a slot nothing is plugged into.
+    pub(crate) fn set_gamepad_button(&mut self, index: u8, button: GamepadButton, state: bool) {
+        const XINPUT_GAMEPAD_DPAD_UP: u16 = 0x0001;
+        const XINPUT_GAMEPAD_DPAD_DOWN: u16 = 0x0002;
+        const XINPUT_GAMEPAD_DPAD_LEFT: u16 = 0x0004;
+        const XINPUT_GAMEPAD_DPAD_RIGHT: u16 = 0x0008;
+        const XINPUT_GAMEPAD_START: u16 = 0x0010;
+        const XINPUT_GAMEPAD_BACK: u16 = 0x0020;
+        const XINPUT_GAMEPAD_LEFT_THUMB: u16 = 0x0040;
+        const XINPUT_GAMEPAD_RIGHT_THUMB: u16 = 0x0080;
+        const XINPUT_GAMEPAD_LEFT_SHOULDER: u16 = 0x0100;
+        const XINPUT_GAMEPAD_RIGHT_SHOULDER: u16 = 0x0200;
+        const XINPUT_GAMEPAD_A: u16 = 0x1000;
+        const XINPUT_GAMEPAD_B: u16 = 0x2000;
+        const XINPUT_GAMEPAD_X: u16 = 0x4000;
+        const XINPUT_GAMEPAD_Y: u16 = 0x8000;
+
+        let bit = match button {
+            GamepadButton::DpadUp => XINPUT_GAMEPAD_DPAD_UP,
+            GamepadButton::DpadDown => XINPUT_GAMEPAD_DPAD_DOWN,
+            GamepadButton::DpadLeft => XINPUT_GAMEPAD_DPAD_LEFT,
+            GamepadButton::DpadRight => XINPUT_GAMEPAD_DPAD_RIGHT,
+            GamepadButton::Start => XINPUT_GAMEPAD_START,
+            GamepadButton::Back => XINPUT_GAMEPAD_BACK,
+            GamepadButton::LeftThumb => XINPUT_GAMEPAD_LEFT_THUMB,
+            GamepadButton::RightThumb => XINPUT_GAMEPAD_RIGHT_THUMB,
+            GamepadButton::LeftShoulder => XINPUT_GAMEPAD_LEFT_SHOULDER,
+            GamepadButton::RightShoulder => XINPUT_GAMEPAD_RIGHT_SHOULDER,
+            GamepadButton::A => XINPUT_GAMEPAD_A,
+            GamepadButton::B => XINPUT_GAMEPAD_B,
+            GamepadButton::X => XINPUT_GAMEPAD_X,
+            GamepadButton::Y => XINPUT_GAMEPAD_Y,
+        };
+        let Some(gamepad) = self.gamepads.get_mut(usize::from(index)) else {
+            return;
+        };
+        let buttons = if state {
+            gamepad.buttons | bit
+        } else {
+            gamepad.buttons & !bit
+        };
+        if buttons != gamepad.buttons {
+            gamepad.buttons = buttons;
+            gamepad.packet_number += 1;
+        }
+    }
+
+    /// Sets `axis`'s value on the virtual gamepad at `index`. See [`Self::set_gamepad_button`] for
+    /// the out-of-range `index` and `dwPacketNumber` behavior.
+    pub(crate) fn set_gamepad_axis(&mut self, index: u8, axis: GamepadAxis, value: i16) {
+        let Some(gamepad) = self.gamepads.get_mut(usize::from(index)) else {
+            return;
+        };
+        let field = match axis {
+            GamepadAxis::LeftX => &mut gamepad.left_stick_x,
+            GamepadAxis::LeftY => &mut gamepad.left_stick_y,
+            GamepadAxis::RightX => &mut gamepad.right_stick_x,
+            GamepadAxis::RightY => &mut gamepad.right_stick_y,
+        };
+        if *field != value {
+            *field = value;
+            gamepad.packet_number += 1;
+        }
+    }
+
+    /// Sets `trigger`'s value on the virtual gamepad at `index`. See [`Self::set_gamepad_button`]
+    /// for the out-of-range `index` and `dwPacketNumber` behavior.
+    pub(crate) fn set_gamepad_trigger(&mut self, index: u8, trigger: GamepadTrigger, value: u8) {
+        let Some(gamepad) = self.gamepads.get_mut(usize::from(index)) else {
+            return;
+        };
+        let field = match trigger {
+            GamepadTrigger::Left => &mut gamepad.left_trigger,
+            GamepadTrigger::Right => &mut gamepad.right_trigger,
+        };
+        if *field != value {
+            *field = value;
+            gamepad.packet_number += 1;
+        }
+    }
+
+    /// Records (or, if `flags` includes `RIDEV_REMOVE`, clears) this process's subscription to
+    /// raw input for the generic-desktop device identified by `(usage_page, usage)` - only mouse
+    /// (usage `0x02`) and keyboard (usage `0x06`) are recognized, since those are the only two
+    /// device classes anything else in this emulator generates events for.
+    pub(crate) fn register_raw_input_device(&mut self, usage_page: u16, usage: u16, flags: u32) {
+        const RIDEV_REMOVE: u32 = 0x0000_0001;
+        const RIDEV_NOLEGACY: u32 = 0x0000_0002;
+        const USAGE_PAGE_GENERIC: u16 = 0x01;
+        const USAGE_MOUSE: u16 = 0x02;
+        const USAGE_KEYBOARD: u16 = 0x06;
+
+        if usage_page != USAGE_PAGE_GENERIC {
+            return;
+        }
+        let registration = (flags & RIDEV_REMOVE == 0).then_some(RawInputRegistration {
+            suppress_legacy_messages: flags & RIDEV_NOLEGACY != 0,
+        });
+        match usage {
+            USAGE_MOUSE => self.raw_mouse_registration = registration,
+            USAGE_KEYBOARD => self.raw_keyboard_registration = registration,
+            _ => {}
+        }
+    }
+
+    /// Stores `buffer` (a serialized `RAWINPUT`) under a freshly minted handle, for
+    /// [`Self::raw_input_buffer`] to hand back out once `hooks::raw_input::GetRawInputData` is
+    /// called with the `WM_INPUT` message this same handle was posted as `lParam` for.
+    fn store_raw_input_buffer(&mut self, buffer: Vec<u8>) -> usize {
+        let handle = self.next_raw_input_handle;
+        self.next_raw_input_handle += 1;
+        self.raw_input_buffers.insert(handle, buffer);
+        handle
+    }
+
+    pub(crate) fn raw_input_buffer(&self, handle: usize) -> Option<Vec<u8>> {
+        self.raw_input_buffers.get(&handle).cloned()
+    }
 }
 
 pub(crate) static STATE: Mutex<State> = Mutex::new(State {
@@ -118,12 +500,131 @@ pub(crate) static STATE: Mutex<State> = Mutex::new(State {
         x1_button: false,
         x2_button: false,
     },
+    gamepads: [GamepadState {
+        buttons: 0,
+        left_stick_x: 0,
+        left_stick_y: 0,
+        right_stick_x: 0,
+        right_stick_y: 0,
+        left_trigger: 0,
+        right_trigger: 0,
+        packet_number: 0,
+    }; 4],
+    raw_mouse_registration: None,
+    raw_keyboard_registration: None,
+    raw_input_buffers: BTreeMap::new(),
+    next_raw_input_handle: 1,
     custom_message_queue: VecDeque::new(),
-    waitable_timer_handles: BTreeMap::new(),
+    handles: BTreeMap::new(),
+    pending_timer_apcs: VecDeque::new(),
+    window_timer_handles: BTreeMap::new(),
+    multimedia_timer_handles: BTreeMap::new(),
+    next_multimedia_timer_id: 1,
+    sockets: BTreeMap::new(),
+    next_socket_id: 1,
+    socket_mode: SocketMode::Record,
+    spawned_processes: Vec::new(),
+    performance_counter_frequency: State::DEFAULT_PERFORMANCE_COUNTER_FREQUENCY,
+    time_scale_numerator: 1,
+    time_scale_denominator: 1,
+    scheduler_threads: BTreeMap::new(),
+    console_ctrl_handlers: Vec::new(),
+    ignore_ctrl_c: false,
+    keyboard_layout: State::DEFAULT_KEYBOARD_LAYOUT,
+    caps_lock_toggled: false,
+    num_lock_toggled: false,
 });
 
+/// Reconfigures the reported `QueryPerformanceFrequency` value and the scale factor applied to
+/// every other time hook (see [`ticks_to_units`]/[`units_to_ticks`]), so the driver can run the
+/// target at an arbitrary fraction or multiple of virtual-real time. `time_scale_denominator` of
+/// `0` is rejected, since it would make every converted duration divide by zero.
+pub(crate) fn set_time_configuration(
+    performance_counter_frequency: u64,
+    time_scale_numerator: u64,
+    time_scale_denominator: NonZeroU64,
+) {
+    let mut state = STATE.lock().unwrap();
+    state.performance_counter_frequency = performance_counter_frequency;
+    state.time_scale_numerator = time_scale_numerator;
+    state.time_scale_denominator = time_scale_denominator.get();
+}
+
+pub(crate) fn performance_counter_frequency() -> u64 {
+    STATE.lock().unwrap().performance_counter_frequency
+}
+
+/// The TAS-owned key state in the packed format `GetKeyboardState`/`ToUnicodeEx` expect: one byte
+/// per virtual-key code, high bit set while the key is down. Used instead of the live
+/// `GetKeyboardState` anywhere a result needs to stay reproducible across machines.
+pub(crate) fn key_state_array() -> [u8; 256] {
+    let state = STATE.lock().unwrap();
+    let mut key_states = [0u8; 256];
+    for (key_code, key_state) in key_states.iter_mut().enumerate() {
+        #[expect(clippy::cast_possible_truncation)]
+        let key_code = key_code as u8;
+        *key_state =
+            (u8::from(state.get_key_state(key_code)) << 7) | u8::from(state.toggle_state(key_code));
+    }
+    key_states
+}
+
+/// Pins the `HKL` that `TranslateMessage` (see `hooks::window::translate_key_message`) computes
+/// characters against, overriding [`State::DEFAULT_KEYBOARD_LAYOUT`].
+pub(crate) fn set_keyboard_layout(hkl: usize) {
+    STATE.lock().unwrap().keyboard_layout = hkl;
+}
+
+pub(crate) fn keyboard_layout() -> usize {
+    STATE.lock().unwrap().keyboard_layout
+}
+
+/// Switches every virtual socket hook (see `hooks::socket`) between recording real network
+/// traffic into `VirtualSocket::recv_log` and replaying `recv`s from that log without touching
+/// the network at all, overriding the default of [`SocketMode::Record`].
+pub(crate) fn set_socket_mode(mode: SocketMode) {
+    STATE.lock().unwrap().socket_mode = mode;
+}
+
+pub(crate) fn socket_mode() -> SocketMode {
+    STATE.lock().unwrap().socket_mode
+}
+
+/// Records a freshly-forced-suspended child process (see `hooks::process`) in [`State`], so it's
+/// preserved across save states the same way [`VirtualSocket`]s are.
+pub(crate) fn report_spawned_process(process_id: u32, main_thread_id: u32) {
+    STATE.lock().unwrap().spawned_processes.push(SpawnedProcess {
+        process_id,
+        main_thread_id,
+    });
+}
+
+/// Converts a tick count to `units_per_second`-denominated units (milliseconds for
+/// `units_per_second: 1000`, 100ns intervals for `10_000_000`, performance counter ticks for
+/// [`performance_counter_frequency`]), applying the configured time-scale factor. Pairs with
+/// [`units_to_ticks`], its inverse, for hooks like `Sleep` that go the other way. Every
+/// intermediate stays in `u128` so neither a large tick count nor an extreme scale factor
+/// overflows before the final truncation back to `u64`.
+#[expect(clippy::cast_possible_truncation)]
+pub(crate) fn ticks_to_units(ticks: u64, units_per_second: u64) -> u64 {
+    let state = STATE.lock().unwrap();
+    (u128::from(ticks) * u128::from(units_per_second) * u128::from(state.time_scale_numerator)
+        / (u128::from(State::TICKS_PER_SECOND) * u128::from(state.time_scale_denominator)))
+        as u64
+}
+
+/// The inverse of [`ticks_to_units`]: converts a duration expressed in `units_per_second`-
+/// denominated units back to ticks, applying the configured time-scale factor.
+#[expect(clippy::cast_possible_truncation)]
+pub(crate) fn units_to_ticks(units: u64, units_per_second: u64) -> u64 {
+    let state = STATE.lock().unwrap();
+    (u128::from(units) * u128::from(State::TICKS_PER_SECOND) * u128::from(state.time_scale_denominator)
+        / (u128::from(units_per_second) * u128::from(state.time_scale_numerator)))
+        as u64
+}
+
 pub(crate) static mut MAIN_THREAD_ID: MaybeUninit<u32> = MaybeUninit::uninit();
-fn in_main_thread() -> bool {
+pub(crate) fn in_main_thread() -> bool {
     unsafe { GetCurrentThreadId() == MAIN_THREAD_ID.assume_init() }
 }
 
@@ -140,35 +641,46 @@ pub(crate) fn get_ticks_with_busy_wait() -> u64 {
     state.ticks
 }
 
-pub(crate) fn sleep(ticks: u64) {
-    if !in_main_thread() {
-        let sleep_trampoline = hooks::get_trampoline!(Sleep, unsafe extern "system" fn(u32));
-        unsafe {
-            #[expect(clippy::cast_possible_truncation)]
-            sleep_trampoline((ticks * 1000 / State::TICKS_PER_SECOND) as u32);
-        }
-        return;
+/// A single source of "what tick count is it right now", queried by every clock-facing WinAPI
+/// hook (see `hooks::time`) instead of each one calling [`get_ticks_with_busy_wait`] on its own.
+/// The only implementation installed here is [`InstantTimeSource`], which *is*
+/// [`get_ticks_with_busy_wait`] - ticks only ever move forward in response to an explicit
+/// `AdvanceTime` message, exactly as before this trait existed. A source that instead advanced
+/// ticks on its own in step with the wall clock would belong on the conductor side, pacing how
+/// often it sends those messages (see `winter::movie::TimeSource`), rather than here: every timer
+/// (`SetTimer`, waitable timers, multimedia timers, ...) only fires as ticks are explicitly
+/// advanced, so swapping this implementation out from under them would desynchronize what the
+/// clock hooks report from when timers actually go off.
+pub(crate) trait TimeSource: Send + Sync {
+    fn ticks(&self) -> u64;
+}
+
+pub(crate) struct InstantTimeSource;
+
+impl TimeSource for InstantTimeSource {
+    fn ticks(&self) -> u64 {
+        get_ticks_with_busy_wait()
     }
+}
 
-    log!(LogLevel::Debug, "sleeping for {ticks} ticks");
+/// The [`TimeSource`] every clock hook reads "now" through.
+pub(crate) fn active_time_source() -> &'static dyn TimeSource {
+    &InstantTimeSource
+}
 
-    let mut remaining_ticks = ticks;
-    while remaining_ticks > 0 {
-        let ticks_advanced_by;
-        {
-            let mut state = STATE.lock().unwrap();
-            ticks_advanced_by = u64::min(state.pending_ticks, remaining_ticks);
-            state.ticks += ticks_advanced_by;
-            state.pending_ticks -= ticks_advanced_by;
-        }
-        remaining_ticks -= ticks_advanced_by;
-        advance_timers(ticks_advanced_by);
-        if remaining_ticks == 0 {
-            STATE.lock().unwrap().busy_wait_count = 0;
-            break;
-        }
-        poll_events_for_sleep();
+/// Sleeps the calling thread for `ticks` ticks by yielding into the cooperative scheduler (see
+/// [`yield_to_next`]) with a deadline of `ticks` from now, rather than blocking any thread for
+/// real: whichever thread ends up driving the simulated clock forward (see
+/// [`advance_scheduler_clock_to`]) does so identically regardless of which real thread called it,
+/// which is what makes multithreaded runs reproduce bit-identically across machines.
+pub(crate) fn sleep(ticks: u64) {
+    log::debug!("sleeping for {ticks} ticks");
+
+    let wake_at_ticks = STATE.lock().unwrap().ticks + ticks;
+    if yield_to_next(Some(wake_at_ticks)).is_err() {
+        panic!("{DeadlockError}");
     }
+    STATE.lock().unwrap().busy_wait_count = 0;
 }
 
 pub(crate) fn sleep_indefinitely() {
@@ -176,7 +688,7 @@ pub(crate) fn sleep_indefinitely() {
         return;
     }
 
-    log!(LogLevel::Debug, "sleeping indefinitely");
+    log::debug!("sleeping indefinitely");
 
     loop {
         {
@@ -188,6 +700,8 @@ pub(crate) fn sleep_indefinitely() {
                 state.busy_wait_count = 0;
                 drop(state);
                 advance_timers(pending_ticks);
+                advance_multimedia_timers(pending_ticks);
+                advance_window_timers(pending_ticks);
                 break;
             }
         }
@@ -196,7 +710,13 @@ pub(crate) fn sleep_indefinitely() {
 }
 
 fn advance_timers(ticks: u64) {
-    for timer in STATE.lock().unwrap().waitable_timer_handles.values() {
+    let mut fired_completion_routines = Vec::new();
+    let mut state = STATE.lock().unwrap();
+    let ticks_now = state.ticks;
+    for handle in state.handles.values() {
+        let EmulatedHandle::WaitableTimer(timer) = handle else {
+            continue;
+        };
         let mut timer = timer.lock().unwrap();
         if timer.remaining_ticks > 0 {
             let mut remaining_ticks = ticks;
@@ -205,6 +725,9 @@ fn advance_timers(ticks: u64) {
             remaining_ticks -= ticks_advanced_by;
             if timer.remaining_ticks == 0 {
                 timer.signaled = true;
+                if let Some(completion_routine) = timer.completion_routine {
+                    fired_completion_routines.push(completion_routine);
+                }
                 if let Some(period_in_ticks) = timer.period_in_ticks {
                     remaining_ticks %= u64::from(period_in_ticks);
                     timer.remaining_ticks = u64::from(period_in_ticks) - remaining_ticks;
@@ -212,6 +735,423 @@ fn advance_timers(ticks: u64) {
             }
         }
     }
+
+    let (low, high) = filetime_from_ticks(ticks_now);
+    state
+        .pending_timer_apcs
+        .extend(
+            fired_completion_routines
+                .into_iter()
+                .map(|completion_routine| PendingTimerApc {
+                    completion_routine,
+                    low,
+                    high,
+                }),
+        );
+}
+
+#[expect(clippy::cast_possible_truncation)]
+fn filetime_from_ticks(ticks: u64) -> (u32, u32) {
+    let one_hundred_nanosecond_intervals =
+        (u128::from(ticks) * 10_000_000 / u128::from(State::TICKS_PER_SECOND)) as u64;
+    (
+        (one_hundred_nanosecond_intervals & ((1 << 32) - 1)) as u32,
+        (one_hundred_nanosecond_intervals >> 32) as u32,
+    )
+}
+
+/// Like [`advance_timers`], but for `timeSetEvent` timers: fires each one whose schedule the
+/// advance crosses (one-shot timers are removed from `multimedia_timer_handles` afterwards,
+/// periodic ones re-armed for `period_in_ticks`), calling its callback or signalling/pulsing its
+/// event immediately rather than queueing it, since multimedia timer callbacks aren't APCs.
+fn advance_multimedia_timers(ticks: u64) {
+    let mut fired_callbacks = Vec::new();
+    let mut finished_timer_ids = Vec::new();
+    let mut state = STATE.lock().unwrap();
+    for (&timer_id, timer) in &state.multimedia_timer_handles {
+        let mut timer = timer.lock().unwrap();
+        let mut remaining_ticks = ticks;
+        let ticks_advanced_by = timer.remaining_ticks.min(remaining_ticks);
+        timer.remaining_ticks -= ticks_advanced_by;
+        remaining_ticks -= ticks_advanced_by;
+        if timer.remaining_ticks == 0 {
+            fired_callbacks.push((timer_id, timer.callback));
+            match timer.period_in_ticks {
+                Some(period_in_ticks) => {
+                    remaining_ticks %= u64::from(period_in_ticks);
+                    timer.remaining_ticks = u64::from(period_in_ticks) - remaining_ticks;
+                }
+                None => finished_timer_ids.push(timer_id),
+            }
+        }
+    }
+    for timer_id in finished_timer_ids {
+        state.multimedia_timer_handles.remove(&timer_id);
+    }
+    drop(state);
+
+    for (timer_id, callback) in fired_callbacks {
+        match callback {
+            MultimediaTimerCallback::Function {
+                callback: Some(callback),
+                user_data,
+            } => unsafe { callback(timer_id, 0, user_data, 0, 0) },
+            MultimediaTimerCallback::Function { callback: None, .. } => {}
+            MultimediaTimerCallback::SetEvent(event) => unsafe {
+                SetEvent(event);
+            },
+            MultimediaTimerCallback::PulseEvent(event) => unsafe {
+                PulseEvent(event);
+            },
+        }
+    }
+}
+
+/// Registers a freshly created real handle as one the hooks DLL emulates (see [`EmulatedHandle`]),
+/// so `CloseHandle`/`DuplicateHandle` (see `hooks::misc`) account for it instead of leaking it.
+pub(crate) fn register_handle(handle: *mut c_void, object: EmulatedHandle) {
+    STATE.lock().unwrap().handles.insert(handle as u32, object);
+}
+
+/// The [`EmulatedHandle`] `handle` refers to, if it's tracked (see [`register_handle`]).
+pub(crate) fn emulated_handle(handle: *mut c_void) -> Option<EmulatedHandle> {
+    STATE.lock().unwrap().handles.get(&(handle as u32)).cloned()
+}
+
+/// Arms (or re-arms, if `(window, id_event)` is already in use) a `SetTimer` timer against the
+/// virtual clock, so its `WM_TIMER`s fire deterministically as ticks advance rather than on the
+/// wall clock. Returns `id_event` unchanged, matching `SetTimer`'s return value for a valid
+/// `window`.
+pub(crate) fn set_window_timer(
+    window: HWND,
+    id_event: usize,
+    elapse_ms: u32,
+    timer_proc: TIMERPROC,
+) -> usize {
+    let interval_ticks = units_to_ticks(u64::from(elapse_ms.max(1)), 1000).max(1);
+    STATE.lock().unwrap().window_timer_handles.insert(
+        (window as usize, id_event),
+        WindowTimer {
+            interval_ticks,
+            remaining_ticks: interval_ticks,
+            timer_proc,
+        },
+    );
+    id_event
+}
+
+/// Disarms a `SetTimer` timer. Returns whether one was found, matching `KillTimer`'s `BOOL`
+/// result.
+pub(crate) fn kill_window_timer(window: HWND, id_event: usize) -> bool {
+    STATE
+        .lock()
+        .unwrap()
+        .window_timer_handles
+        .remove(&(window as usize, id_event))
+        .is_some()
+}
+
+/// Like [`advance_timers`], but for `SetTimer` timers: advances each one, and for each timer whose
+/// period elapses during the advance, enqueues a synthetic `WM_TIMER` into `custom_message_queue` rather
+/// than invoking `timer_proc` immediately - real `SetTimer` posts `WM_TIMER` even when given a
+/// callback, and it's only the message's retrieval (see `hooks::window::get_message`) that
+/// invokes it. Coalesces so at most one `WM_TIMER` is ever pending per timer, the same
+/// backpressure real Win32 applies when a window falls behind processing them.
+fn advance_window_timers(ticks: u64) {
+    let mut fired = Vec::new();
+    let mut state = STATE.lock().unwrap();
+    for (&(window, id_event), timer) in &mut state.window_timer_handles {
+        let mut remaining_ticks = ticks;
+        let ticks_advanced_by = timer.remaining_ticks.min(remaining_ticks);
+        timer.remaining_ticks -= ticks_advanced_by;
+        remaining_ticks -= ticks_advanced_by;
+        if timer.remaining_ticks == 0 {
+            remaining_ticks %= timer.interval_ticks;
+            timer.remaining_ticks = timer.interval_ticks - remaining_ticks;
+            fired.push((window, id_event, timer.timer_proc));
+        }
+    }
+    drop(state);
+
+    for (window, id_event, timer_proc) in fired {
+        post_window_timer_message(window, id_event, timer_proc);
+    }
+}
+
+#[expect(clippy::fn_to_numeric_cast)]
+fn post_window_timer_message(window: usize, id_event: usize, timer_proc: TIMERPROC) {
+    let mut state = STATE.lock().unwrap();
+    let already_pending = state.custom_message_queue.iter().any(|MSGSend(message)| {
+        message.hwnd == window as HWND && message.message == WM_TIMER && message.wParam == id_event
+    });
+    if already_pending {
+        return;
+    }
+
+    #[expect(clippy::cast_possible_truncation)]
+    let time_in_ticks = state.ticks as u32;
+    state
+        .custom_message_queue
+        .push_back(MSGSend(MSG {
+            hwnd: window as HWND,
+            message: WM_TIMER,
+            wParam: id_event,
+            lParam: timer_proc.map_or(0, |timer_proc| timer_proc as isize),
+            time: time_in_ticks,
+            pt: POINT { x: 0, y: 0 },
+        }));
+}
+
+/// Applies a `SetConsoleCtrlHandler` call to the `STATE`-owned handler list. A `None` `handler`
+/// with `add` is the documented special case meaning "ignore `CTRL_C_EVENT`/`CTRL_BREAK_EVENT` in
+/// this process" rather than registering a callback; otherwise `add` pushes `handler` so it's the
+/// first one offered the next event (see [`deliver_console_ctrl_event`]), and `!add` removes it.
+/// Always returns `TRUE`, matching the real function when passed a valid handler.
+pub(crate) fn set_console_ctrl_handler(handler: PHANDLER_ROUTINE, add: bool) -> bool {
+    let mut state = STATE.lock().unwrap();
+    match handler {
+        None => state.ignore_ctrl_c = add,
+        Some(handler) => {
+            if add {
+                state.console_ctrl_handlers.push(handler);
+            } else if let Some(index) = state
+                .console_ctrl_handlers
+                .iter()
+                .position(|&registered| registered as usize == handler as usize)
+            {
+                state.console_ctrl_handlers.remove(index);
+            }
+        }
+    }
+    true
+}
+
+/// Invokes every registered console control handler on a dedicated thread, newest-first (matching
+/// real Win32's LIFO dispatch), stopping at the first one that reports having handled the event -
+/// mirrors real `CTRL_*_EVENT` delivery running on a thread the system creates for the purpose
+/// rather than whichever thread requested it. `CTRL_C_EVENT`/`CTRL_BREAK_EVENT` are dropped
+/// entirely if [`set_console_ctrl_handler`] was last told to ignore them.
+pub(crate) fn deliver_console_ctrl_event(event: ConsoleCtrlEvent) {
+    let (ignore_ctrl_c, handlers) = {
+        let state = STATE.lock().unwrap();
+        (state.ignore_ctrl_c, state.console_ctrl_handlers.clone())
+    };
+    if ignore_ctrl_c && matches!(event, ConsoleCtrlEvent::C | ConsoleCtrlEvent::Break) {
+        return;
+    }
+
+    let ctrl_type = match event {
+        ConsoleCtrlEvent::C => CTRL_C_EVENT,
+        ConsoleCtrlEvent::Break => CTRL_BREAK_EVENT,
+        ConsoleCtrlEvent::Close => CTRL_CLOSE_EVENT,
+        ConsoleCtrlEvent::Logoff => CTRL_LOGOFF_EVENT,
+        ConsoleCtrlEvent::Shutdown => CTRL_SHUTDOWN_EVENT,
+    };
+
+    std::thread::spawn(move || {
+        for handler in handlers.into_iter().rev() {
+            if unsafe { handler(ctrl_type) } != 0 {
+                break;
+            }
+        }
+    });
+}
+
+/// Runs every APC queued by [`advance_timers`] since the last call, for an alertable wait to
+/// invoke once it determines a timer completed during it. Returns whether any ran, so the caller
+/// can return `WAIT_IO_COMPLETION` instead of its normal result.
+pub(crate) fn drain_timer_apcs() -> bool {
+    let pending_timer_apcs = std::mem::take(&mut STATE.lock().unwrap().pending_timer_apcs);
+    let ran_any = !pending_timer_apcs.is_empty();
+    for pending_timer_apc in pending_timer_apcs {
+        unsafe {
+            (pending_timer_apc.completion_routine.routine)(
+                pending_timer_apc.completion_routine.argument,
+                pending_timer_apc.low,
+                pending_timer_apc.high,
+            );
+        }
+    }
+    ran_any
+}
+
+/// Registers the calling thread with the cooperative scheduler (see [`yield_to_next`]) the first
+/// time it reaches a yield point, as runnable with no run token pending on it yet.
+fn register_scheduler_thread(state: &mut State, thread_id: u32) {
+    state
+        .scheduler_threads
+        .entry(thread_id)
+        .or_insert_with(|| SchedulerThread {
+            blocked: false,
+            wake_at_ticks: None,
+            run_token: ManualResetEvent::new().unwrap(),
+        });
+}
+
+/// The next runnable (not [`SchedulerThread::blocked`]) thread after `after_thread_id`, in
+/// ascending thread-id order, wrapping back around to the lowest id if nothing is found past it.
+fn next_runnable_scheduler_thread_id(state: &State, after_thread_id: u32) -> Option<u32> {
+    state
+        .scheduler_threads
+        .range(after_thread_id.wrapping_add(1)..)
+        .chain(state.scheduler_threads.range(..=after_thread_id))
+        .find(|(_, thread)| !thread.blocked)
+        .map(|(&thread_id, _)| thread_id)
+}
+
+/// Advances the simulated clock to `target_ticks`, pumping the conductor's `AdvanceTime` events
+/// the same way [`sleep`] used to do on the main thread whenever it runs out of already-granted
+/// ticks, then un-blocks every [`SchedulerThread`] whose [`SchedulerThread::wake_at_ticks`]
+/// deadline has now been reached so the next scan in [`yield_to_next`] sees them as runnable.
+fn advance_scheduler_clock_to(target_ticks: u64) {
+    loop {
+        let ticks_advanced_by;
+        {
+            let mut state = STATE.lock().unwrap();
+            if state.ticks >= target_ticks {
+                break;
+            }
+            ticks_advanced_by = state.pending_ticks.min(target_ticks - state.ticks);
+            state.ticks += ticks_advanced_by;
+            state.pending_ticks -= ticks_advanced_by;
+        }
+        advance_timers(ticks_advanced_by);
+        advance_multimedia_timers(ticks_advanced_by);
+        advance_window_timers(ticks_advanced_by);
+        if STATE.lock().unwrap().ticks >= target_ticks {
+            break;
+        }
+        poll_events_for_sleep();
+    }
+
+    let mut state = STATE.lock().unwrap();
+    let ticks_now = state.ticks;
+    for thread in state.scheduler_threads.values_mut() {
+        if thread.wake_at_ticks.is_some_and(|wake_at_ticks| wake_at_ticks <= ticks_now) {
+            thread.blocked = false;
+            thread.wake_at_ticks = None;
+        }
+    }
+}
+
+/// Hands this thread's run token in the cooperative scheduler to the next runnable thread (see
+/// [`next_runnable_scheduler_thread_id`]) and blocks until some thread hands it back, so that
+/// only one thread ever executes target code between yield points and multithreaded runs stay
+/// reproducible across machines. `wake_at_ticks`, if set, is the simulated tick at which this
+/// thread should become runnable again on its own even if no other thread ever yields to it
+/// (e.g. a bounded `Sleep` or timed wait); pass `None` for a wait with no timeout of its own.
+///
+/// If every thread (including this one) is blocked, the simulated clock is advanced to the
+/// earliest pending deadline among them instead (see [`advance_scheduler_clock_to`]), which may
+/// make this thread itself runnable again without ever handing off the token. If none of them has
+/// a deadline either, nothing will ever make progress and this returns [`DeadlockError`] without
+/// blocking.
+pub(crate) fn yield_to_next(wake_at_ticks: Option<u64>) -> Result<(), DeadlockError> {
+    let thread_id = unsafe { GetCurrentThreadId() };
+
+    {
+        let mut state = STATE.lock().unwrap();
+        register_scheduler_thread(&mut state, thread_id);
+        let this_thread = state.scheduler_threads.get_mut(&thread_id).unwrap();
+        this_thread.blocked = true;
+        this_thread.wake_at_ticks = wake_at_ticks;
+        this_thread.run_token.reset().unwrap();
+    }
+
+    let next_thread_id = loop {
+        let state = STATE.lock().unwrap();
+        if let Some(next_thread_id) = next_runnable_scheduler_thread_id(&state, thread_id) {
+            break next_thread_id;
+        }
+        if !state.scheduler_threads[&thread_id].blocked {
+            // Our own deadline was the one reached while advancing the clock below, on a
+            // previous iteration of this loop.
+            return Ok(());
+        }
+
+        let Some(nearest_wake_at_ticks) = state
+            .scheduler_threads
+            .values()
+            .filter_map(|thread| thread.wake_at_ticks)
+            .min()
+        else {
+            return Err(DeadlockError);
+        };
+        drop(state);
+        advance_scheduler_clock_to(nearest_wake_at_ticks);
+    };
+
+    let run_token_handle = unsafe {
+        let mut state = STATE.lock().unwrap();
+        state
+            .scheduler_threads
+            .get_mut(&next_thread_id)
+            .unwrap()
+            .run_token
+            .set()
+            .unwrap();
+        state.scheduler_threads[&thread_id].run_token.handle().as_raw()
+    };
+
+    let wait_trampoline = get_trampoline!(
+        WaitForSingleObject,
+        unsafe extern "system" fn(*mut c_void, u32) -> u32
+    );
+    unsafe {
+        wait_trampoline(run_token_handle, INFINITE);
+    }
+
+    STATE
+        .lock()
+        .unwrap()
+        .scheduler_threads
+        .get_mut(&thread_id)
+        .unwrap()
+        .blocked = false;
+    Ok(())
+}
+
+/// Removes the calling thread from the cooperative scheduler (see [`yield_to_next`]) when it's
+/// about to exit, handing its run token to the next runnable thread first (if it's currently
+/// holding it) so its exit can never strand everyone else waiting on a token that will now never
+/// be set again.
+pub(crate) fn unregister_scheduler_thread() {
+    let thread_id = unsafe { GetCurrentThreadId() };
+    let mut state = STATE.lock().unwrap();
+    if let Some(next_thread_id) = next_runnable_scheduler_thread_id(&state, thread_id) {
+        state
+            .scheduler_threads
+            .get_mut(&next_thread_id)
+            .unwrap()
+            .run_token
+            .set()
+            .unwrap();
+    }
+    state.scheduler_threads.remove(&thread_id);
+}
+
+/// Registers a freshly created thread with the cooperative scheduler (see [`yield_to_next`]) and
+/// blocks it until some other thread's [`yield_to_next`]/[`unregister_scheduler_thread`] call
+/// grants it a run token, rather than handing one off itself the way [`yield_to_next`] does - a
+/// brand new thread was never granted a turn to give away in the first place. Meant to be called
+/// from a new thread's very first instruction (see `hooks::thread`'s `CreateThread` hook) so it
+/// plays no part in the target's execution until its first scheduled turn, instead of racing
+/// whichever thread created it for however long it takes to reach this call.
+pub(crate) fn register_and_await_turn() {
+    let thread_id = unsafe { GetCurrentThreadId() };
+    let run_token_handle = unsafe {
+        let mut state = STATE.lock().unwrap();
+        register_scheduler_thread(&mut state, thread_id);
+        state.scheduler_threads[&thread_id].run_token.handle().as_raw()
+    };
+
+    let wait_trampoline = get_trampoline!(
+        WaitForSingleObject,
+        unsafe extern "system" fn(*mut c_void, u32) -> u32
+    );
+    unsafe {
+        wait_trampoline(run_token_handle, INFINITE);
+    }
 }
 
 fn poll_events_for_sleep() {
@@ -220,11 +1160,10 @@ fn poll_events_for_sleep() {
         let event = event_queue.dequeue_blocking();
         match event {
             #[expect(clippy::cast_possible_truncation)]
-            #[expect(clippy::cast_precision_loss)]
-            #[expect(clippy::cast_sign_loss)]
             Event::AdvanceTime(duration) => {
                 STATE.lock().unwrap().pending_ticks +=
-                    (duration.as_secs_f64() * State::TICKS_PER_SECOND as f64).round() as u64;
+                    (duration.as_nanos() * u128::from(State::TICKS_PER_SECOND) / 1_000_000_000)
+                        as u64;
                 break;
             }
             Event::SetKeyState {
@@ -232,62 +1171,110 @@ fn poll_events_for_sleep() {
                 state: key_state,
             } => {
                 let key_previous_state;
+                let raw_keyboard_registration;
                 {
                     let mut state = STATE.lock().unwrap();
                     key_previous_state = state.get_key_state(key_id);
                     state.set_key_state(key_id, key_state);
+                    raw_keyboard_registration = state.raw_keyboard_registration;
                 }
 
-                post_message(
-                    if key_state { WM_KEYDOWN } else { WM_KEYUP },
-                    usize::from(key_id),
-                    (isize::from(!key_state) << 31) | (isize::from(key_previous_state) << 30) | 1,
-                );
+                if raw_keyboard_registration.is_some() {
+                    post_raw_keyboard_message(key_id, key_state);
+                }
+                if !raw_keyboard_registration.is_some_and(|r| r.suppress_legacy_messages) {
+                    post_message(
+                        if key_state { WM_KEYDOWN } else { WM_KEYUP },
+                        usize::from(key_id),
+                        (isize::from(!key_state) << 31)
+                            | (isize::from(key_previous_state) << 30)
+                            | 1,
+                    );
+                }
             }
             Event::SetMousePosition { x, y } => {
+                let previous_x;
+                let previous_y;
+                let raw_mouse_registration;
                 {
                     let mut state = STATE.lock().unwrap();
+                    previous_x = state.mouse.x;
+                    previous_y = state.mouse.y;
                     state.mouse.x = x;
                     state.mouse.y = y;
+                    raw_mouse_registration = state.raw_mouse_registration;
+                }
+                if raw_mouse_registration.is_some() {
+                    post_raw_mouse_move_message(previous_x, previous_y, x, y);
+                }
+                if !raw_mouse_registration.is_some_and(|r| r.suppress_legacy_messages) {
+                    post_mouse_message(WM_MOUSEMOVE, 0);
                 }
-                post_mouse_message(WM_MOUSEMOVE, 0);
             }
             Event::SetMouseButtonState {
                 button,
                 state: button_state,
             } => {
-                STATE
-                    .lock()
-                    .unwrap()
-                    .set_mouse_button_state(button, button_state);
+                let raw_mouse_registration;
+                {
+                    let mut state = STATE.lock().unwrap();
+                    state.set_mouse_button_state(button, button_state);
+                    raw_mouse_registration = state.raw_mouse_registration;
+                }
+                if raw_mouse_registration.is_some() {
+                    post_raw_mouse_button_message(button, button_state);
+                }
+                if !raw_mouse_registration.is_some_and(|r| r.suppress_legacy_messages) {
+                    post_mouse_message(
+                        match (button, button_state) {
+                            (MouseButton::Left, true) => WM_LBUTTONDOWN,
+                            (MouseButton::Left, false) => WM_LBUTTONUP,
+                            (MouseButton::Right, true) => WM_RBUTTONDOWN,
+                            (MouseButton::Right, false) => WM_RBUTTONUP,
+                            (MouseButton::Middle, true) => WM_MBUTTONDOWN,
+                            (MouseButton::Middle, false) => WM_MBUTTONUP,
+                            (MouseButton::X1 | MouseButton::X2, true) => WM_XBUTTONDOWN,
+                            (MouseButton::X1 | MouseButton::X2, false) => WM_XBUTTONUP,
+                        },
+                        match button {
+                            MouseButton::X1 => 1,
+                            MouseButton::X2 => 2,
+                            _ => 0,
+                        },
+                    );
+                }
+            }
+            #[expect(clippy::cast_possible_truncation)]
+            #[expect(clippy::cast_sign_loss)]
+            Event::ScrollMouseWheel { delta, horizontal } => {
                 post_mouse_message(
-                    match (button, button_state) {
-                        (MouseButton::Left, true) => WM_LBUTTONDOWN,
-                        (MouseButton::Left, false) => WM_LBUTTONUP,
-                        (MouseButton::Right, true) => WM_RBUTTONDOWN,
-                        (MouseButton::Right, false) => WM_RBUTTONUP,
-                        (MouseButton::Middle, true) => WM_MBUTTONDOWN,
-                        (MouseButton::Middle, false) => WM_MBUTTONUP,
-                        (MouseButton::X1 | MouseButton::X2, true) => WM_XBUTTONDOWN,
-                        (MouseButton::X1 | MouseButton::X2, false) => WM_XBUTTONUP,
-                    },
-                    match button {
-                        MouseButton::X1 => 1,
-                        MouseButton::X2 => 2,
-                        _ => 0,
-                    },
+                    if horizontal { WM_MOUSEHWHEEL } else { WM_MOUSEWHEEL },
+                    delta as i16 as u16,
                 );
             }
-            Event::Idle => unsafe {
-                block_on(
-                    IDLE_MESSAGE_SENDER
-                        .assume_init_ref()
-                        .lock()
-                        .unwrap()
-                        .send(&IdleMessage),
-                )
-                .unwrap();
-            },
+            Event::SetGamepadButton {
+                index,
+                button,
+                state,
+            } => {
+                STATE.lock().unwrap().set_gamepad_button(index, button, state);
+            }
+            Event::SetGamepadAxis { index, axis, value } => {
+                STATE.lock().unwrap().set_gamepad_axis(index, axis, value);
+            }
+            Event::SetGamepadTrigger {
+                index,
+                trigger,
+                value,
+            } => {
+                STATE.lock().unwrap().set_gamepad_trigger(index, trigger, value);
+            }
+            Event::Idle(token) => {
+                unsafe { IDLE_QUEUE.assume_init_ref() }.enqueue(token);
+            }
+            Event::DeliverConsoleCtrlEvent(event) => {
+                deliver_console_ctrl_event(event);
+            }
             #[expect(unreachable_patterns)] // Event is #[non_exhaustive]
             event => unimplemented!("event {event:?}"),
         }
@@ -347,3 +1334,140 @@ fn post_mouse_message(message_id: u32, w_parameter_high_word: u16) {
     }
     post_message(message_id, w_parameter, l_parameter);
 }
+
+/// Mirrors the real `RAWINPUTHEADER`/`RAWMOUSE`/`RAWKEYBOARD` layout byte-for-byte (natural `#[repr(C)]`
+/// alignment matches the real structs' field order exactly), built by hand instead of going through
+/// `winapi`'s union-typed bindings for these so [`raw_mouse_buffer`]/[`raw_keyboard_buffer`] can
+/// write the wire bytes directly rather than fight a union's accessors.
+#[repr(C)]
+struct RawInputHeaderBytes {
+    message_type: u32,
+    size: u32,
+    device: usize,
+    w_parameter: usize,
+}
+
+#[repr(C)]
+struct RawMouseBytes {
+    flags: u16,
+    _reserved: u16,
+    button_flags: u16,
+    button_data: u16,
+    raw_buttons: u32,
+    last_x: i32,
+    last_y: i32,
+    extra_information: u32,
+}
+
+#[repr(C)]
+struct RawKeyboardBytes {
+    make_code: u16,
+    flags: u16,
+    reserved: u16,
+    virtual_key: u16,
+    message: u32,
+    extra_information: u32,
+}
+
+fn struct_bytes<T>(value: &T) -> Vec<u8> {
+    unsafe {
+        std::slice::from_raw_parts(std::ptr::from_ref(value).cast::<u8>(), size_of::<T>()).to_vec()
+    }
+}
+
+#[expect(clippy::cast_possible_truncation)]
+fn raw_mouse_buffer(flags: u16, button_flags: u16, last_x: i32, last_y: i32) -> Vec<u8> {
+    const RIM_TYPEMOUSE: u32 = 0;
+    let mut buffer = struct_bytes(&RawInputHeaderBytes {
+        message_type: RIM_TYPEMOUSE,
+        size: (size_of::<RawInputHeaderBytes>() + size_of::<RawMouseBytes>()) as u32,
+        device: 0,
+        w_parameter: 0,
+    });
+    buffer.extend(struct_bytes(&RawMouseBytes {
+        flags,
+        _reserved: 0,
+        button_flags,
+        button_data: 0,
+        raw_buttons: 0,
+        last_x,
+        last_y,
+        extra_information: 0,
+    }));
+    buffer
+}
+
+#[expect(clippy::cast_possible_truncation)]
+fn raw_keyboard_buffer(key_id: u8, flags: u16, message: u32) -> Vec<u8> {
+    const RIM_TYPEKEYBOARD: u32 = 1;
+    let mut buffer = struct_bytes(&RawInputHeaderBytes {
+        message_type: RIM_TYPEKEYBOARD,
+        size: (size_of::<RawInputHeaderBytes>() + size_of::<RawKeyboardBytes>()) as u32,
+        device: 0,
+        w_parameter: 0,
+    });
+    buffer.extend(struct_bytes(&RawKeyboardBytes {
+        make_code: 0,
+        flags,
+        reserved: 0,
+        virtual_key: u16::from(key_id),
+        message,
+        extra_information: 0,
+    }));
+    buffer
+}
+
+/// Stores `buffer` under a freshly minted handle and posts `WM_INPUT` with that handle as
+/// `lParam` (`RIM_INPUT`, i.e. "received while in the foreground", as `wParam` - this emulator has
+/// no notion of a background window to report `RIM_INPUTSINK` for).
+#[expect(clippy::cast_possible_wrap)]
+fn post_raw_input_message(buffer: Vec<u8>) {
+    const RIM_INPUT: usize = 0;
+    let handle = STATE.lock().unwrap().store_raw_input_buffer(buffer);
+    post_message(WM_INPUT, RIM_INPUT, handle as isize);
+}
+
+fn post_raw_mouse_move_message(previous_x: u16, previous_y: u16, x: u16, y: u16) {
+    const MOUSE_MOVE_RELATIVE: u16 = 0;
+    let delta_x = i32::from(x) - i32::from(previous_x);
+    let delta_y = i32::from(y) - i32::from(previous_y);
+    post_raw_input_message(raw_mouse_buffer(MOUSE_MOVE_RELATIVE, 0, delta_x, delta_y));
+}
+
+fn post_raw_mouse_button_message(button: MouseButton, button_state: bool) {
+    const MOUSE_MOVE_RELATIVE: u16 = 0;
+    const RI_MOUSE_LEFT_BUTTON_DOWN: u16 = 0x0001;
+    const RI_MOUSE_LEFT_BUTTON_UP: u16 = 0x0002;
+    const RI_MOUSE_RIGHT_BUTTON_DOWN: u16 = 0x0004;
+    const RI_MOUSE_RIGHT_BUTTON_UP: u16 = 0x0008;
+    const RI_MOUSE_MIDDLE_BUTTON_DOWN: u16 = 0x0010;
+    const RI_MOUSE_MIDDLE_BUTTON_UP: u16 = 0x0020;
+    const RI_MOUSE_BUTTON_4_DOWN: u16 = 0x0040;
+    const RI_MOUSE_BUTTON_4_UP: u16 = 0x0080;
+    const RI_MOUSE_BUTTON_5_DOWN: u16 = 0x0100;
+    const RI_MOUSE_BUTTON_5_UP: u16 = 0x0200;
+
+    let button_flags = match (button, button_state) {
+        (MouseButton::Left, true) => RI_MOUSE_LEFT_BUTTON_DOWN,
+        (MouseButton::Left, false) => RI_MOUSE_LEFT_BUTTON_UP,
+        (MouseButton::Right, true) => RI_MOUSE_RIGHT_BUTTON_DOWN,
+        (MouseButton::Right, false) => RI_MOUSE_RIGHT_BUTTON_UP,
+        (MouseButton::Middle, true) => RI_MOUSE_MIDDLE_BUTTON_DOWN,
+        (MouseButton::Middle, false) => RI_MOUSE_MIDDLE_BUTTON_UP,
+        (MouseButton::X1, true) => RI_MOUSE_BUTTON_4_DOWN,
+        (MouseButton::X1, false) => RI_MOUSE_BUTTON_4_UP,
+        (MouseButton::X2, true) => RI_MOUSE_BUTTON_5_DOWN,
+        (MouseButton::X2, false) => RI_MOUSE_BUTTON_5_UP,
+    };
+    post_raw_input_message(raw_mouse_buffer(MOUSE_MOVE_RELATIVE, button_flags, 0, 0));
+}
+
+fn post_raw_keyboard_message(key_id: u8, key_state: bool) {
+    const RI_KEY_MAKE: u16 = 0;
+    const RI_KEY_BREAK: u16 = 1;
+    post_raw_input_message(raw_keyboard_buffer(
+        key_id,
+        if key_state { RI_KEY_MAKE } else { RI_KEY_BREAK },
+        if key_state { WM_KEYDOWN } else { WM_KEYUP },
+    ));
+}
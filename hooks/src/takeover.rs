@@ -0,0 +1,59 @@
+use shared::{
+    ipc::{message, rpc, Sender, SenderCloneError},
+    windows::{
+        process::Process,
+        shared_memory::{self, SharedMemory},
+    },
+};
+use thiserror::Error;
+
+/// Size of the handoff blob: the log and RPC response senders, each as the 16 bytes
+/// [`Sender::serialize_to_bytes`] encodes them as.
+const HANDOFF_SIZE: usize = 32;
+
+/// Re-duplicates this process's log and RPC response senders for `new_process_id` and publishes
+/// the resulting handle bytes in a shared memory mapping named after it, so the new conductor can
+/// open it by its own process id and pick up where the previous one left off.
+///
+/// This only covers the handle handoff for a conductor that is already connected and sends
+/// [`message::FromConductor::Takeover`]. Surviving a conductor that disappeared without sending
+/// it (idling until a replacement shows up, discovering one without a live handle, and the
+/// conductor-side reconnect workflow that would drive all of that) is deliberately out of scope
+/// here.
+pub fn handle(
+    new_process_id: u32,
+    log_message_sender: &Sender<message::LogBatch>,
+    rpc_response_sender: &Sender<rpc::Response>,
+) -> Result<(), TakeoverError> {
+    let new_process = Process::from_id(new_process_id)?;
+    let cloned_log_message_sender = log_message_sender.try_clone_for_process(&new_process)?;
+    let cloned_rpc_response_sender = rpc_response_sender.try_clone_for_process(&new_process)?;
+
+    let shared_memory = SharedMemory::create(&handoff_mapping_name(new_process_id), HANDOFF_SIZE)?;
+    unsafe {
+        let bytes = shared_memory.as_ptr();
+        bytes.copy_from_nonoverlapping(cloned_log_message_sender.serialize_to_bytes().as_ptr(), 16);
+        bytes
+            .add(16)
+            .copy_from_nonoverlapping(cloned_rpc_response_sender.serialize_to_bytes().as_ptr(), 16);
+        cloned_log_message_sender.leak_handles();
+        cloned_rpc_response_sender.leak_handles();
+    }
+
+    Ok(())
+}
+
+/// Name of the shared memory mapping a new conductor running as `new_process_id` should open (via
+/// [`SharedMemory::open`]) to pick up the handles re-duplicated for it by [`handle`].
+#[must_use]
+pub fn handoff_mapping_name(new_process_id: u32) -> String {
+    format!("winter-takeover-{new_process_id}")
+}
+
+#[derive(Debug, Error)]
+#[error("failed to hand off session to new conductor")]
+pub enum TakeoverError {
+    OpenProcess(#[from] std::io::Error),
+    SenderClone(#[from] SenderCloneError),
+    SharedMemory(#[from] shared_memory::CreateError),
+}
@@ -0,0 +1,99 @@
+//! Tracks the encode/decode cost of the per-frame messages `Sender`/`Receiver` carry every time
+//! the target advances, mirroring audioipc2's `benches/serialization.rs`. `ArmDirtyTracking` and
+//! `TakeDirtyPages` are skipped: both embed a live `Sender`, which needs a real process pair to
+//! construct and isn't representative of the steady-state input/time traffic this is meant to
+//! track.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use shared::{
+    input::MouseButton,
+    ipc::{
+        codec::{Bincode, Codec},
+        message::{FromConductor, Log, LogLevel},
+        rpc::Token,
+    },
+};
+use std::time::Duration;
+
+fn bench_codec<T>(c: &mut Criterion, name: &str, value: &T)
+where
+    T: std::fmt::Debug,
+    Bincode: Codec<T>,
+{
+    let encoded = Bincode::encode(value).unwrap();
+    c.bench_function(&format!("{name}/encode"), |b| {
+        b.iter(|| Bincode::encode(value).unwrap());
+    });
+    c.bench_function(&format!("{name}/decode"), |b| {
+        b.iter(|| <Bincode as Codec<T>>::decode(&encoded).unwrap());
+    });
+}
+
+fn from_conductor(c: &mut Criterion) {
+    bench_codec(c, "FromConductor::Resume", &FromConductor::Resume);
+    bench_codec(
+        c,
+        "FromConductor::AdvanceTime",
+        &FromConductor::AdvanceTime(Duration::from_millis(16)),
+    );
+    bench_codec(
+        c,
+        "FromConductor::SetKeyState",
+        &FromConductor::SetKeyState {
+            id: 0x41,
+            state: true,
+        },
+    );
+    bench_codec(
+        c,
+        "FromConductor::SetMousePosition",
+        &FromConductor::SetMousePosition { x: 100, y: 200 },
+    );
+    bench_codec(
+        c,
+        "FromConductor::SetMouseButtonState",
+        &FromConductor::SetMouseButtonState {
+            button: MouseButton::Left,
+            state: true,
+        },
+    );
+    bench_codec(
+        c,
+        "FromConductor::Rpc",
+        &FromConductor::Rpc {
+            token: Token::new(1, 1),
+            payload: vec![0; 8],
+        },
+    );
+    bench_codec(
+        c,
+        "FromConductor::Takeover",
+        &FromConductor::Takeover {
+            new_process_id: 1234,
+        },
+    );
+    bench_codec(
+        c,
+        "FromConductor::SetTimeConfiguration",
+        &FromConductor::SetTimeConfiguration {
+            performance_counter_frequency: 10_000_000,
+            time_scale_numerator: 1,
+            time_scale_denominator: 1,
+        },
+    );
+}
+
+fn log(c: &mut Criterion) {
+    bench_codec(
+        c,
+        "Log",
+        &Log {
+            level: LogLevel::Info,
+            target: "winter::state".to_owned(),
+            message: "advanced virtual clock by 16ms".to_owned(),
+        },
+    );
+}
+
+criterion_group!(benches, from_conductor, log);
+criterion_main!(benches);
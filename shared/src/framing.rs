@@ -0,0 +1,203 @@
+//! A length-prefixed framing layer over any [`Write`]/[`Read`] pair, independent of
+//! [`crate::ipc`]'s semaphore-synchronized transports and serde-based [`crate::ipc::codec`]. Where
+//! those assume a frame is already fully buffered by the time it's read, [`MessageReader`] expects
+//! to be polled against a non-blocking source (e.g. [`crate::windows::pipe::Reader`] with
+//! [`crate::windows::pipe::Reader::set_nonblocking`] enabled, which reports
+//! [`io::ErrorKind::WouldBlock`] rather than blocking when nothing is pending) and accumulates
+//! bytes across calls until a full frame has arrived.
+
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Frames larger than this are rejected before any allocation is made for their body, so a
+/// corrupt or hostile length header can't be used to force an unbounded allocation.
+pub const MAX_FRAME_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Encodes a value onto a byte sink, with no length prefix of its own - [`MessageWriter`] adds
+/// that around the whole value.
+pub trait Writeable {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()>;
+}
+
+/// Decodes a value from a byte source previously written by [`Writeable::write_to`].
+pub trait Readable: Sized {
+    fn read_from(reader: &mut impl Read) -> io::Result<Self>;
+}
+
+macro_rules! impl_primitive {
+    ($ty:ty) => {
+        impl Writeable for $ty {
+            fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+                writer.write_all(&self.to_le_bytes())
+            }
+        }
+
+        impl Readable for $ty {
+            fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+                let mut bytes = [0; size_of::<$ty>()];
+                reader.read_exact(&mut bytes)?;
+                Ok(Self::from_le_bytes(bytes))
+            }
+        }
+    };
+}
+impl_primitive!(u8);
+impl_primitive!(u16);
+impl_primitive!(u32);
+impl_primitive!(u64);
+impl_primitive!(i8);
+impl_primitive!(i16);
+impl_primitive!(i32);
+impl_primitive!(i64);
+
+impl Writeable for Vec<u8> {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        u32::try_from(self.len())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?
+            .write_to(writer)?;
+        writer.write_all(self)
+    }
+}
+
+impl Readable for Vec<u8> {
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let len = u32::read_from(reader)? as usize;
+        let mut bytes = vec![0; len];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Writeable for String {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        self.clone().into_bytes().write_to(writer)
+    }
+}
+
+impl Readable for String {
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        String::from_utf8(Vec::read_from(reader)?)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// Writes [`Writeable`] values to `writer`, prefixing each with a little-endian `u32` byte count.
+#[derive(Debug)]
+pub struct MessageWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> MessageWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_message(&mut self, value: &impl Writeable) -> io::Result<()> {
+        let mut body = Vec::new();
+        value.write_to(&mut body)?;
+        u32::try_from(body.len())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?
+            .write_to(&mut self.writer)?;
+        self.writer.write_all(&body)
+    }
+}
+
+/// Reads [`Readable`] values previously written by [`MessageWriter`] off `reader`, accumulating
+/// bytes across [`Self::read_message`] calls until a complete frame is available. `reader` is
+/// expected to report [`io::ErrorKind::WouldBlock`] (rather than block) when nothing more is
+/// currently available - e.g. a [`crate::windows::pipe::Reader`] with
+/// [`crate::windows::pipe::Reader::set_nonblocking`] enabled - though a plain `Ok(0)` is tolerated
+/// too.
+#[derive(Debug)]
+pub struct MessageReader<R> {
+    reader: R,
+    pending: Vec<u8>,
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Reads the next complete frame and decodes it as a `T`, or `None` if fewer bytes than the
+    /// frame declares (or than its length header requires) are currently available.
+    pub fn read_message<T: Readable>(&mut self) -> Result<Option<T>, FramingError> {
+        self.fill_pending()?;
+
+        if self.pending.len() < size_of::<u32>() {
+            return Ok(None);
+        }
+        let length = u32::from_le_bytes(self.pending[..size_of::<u32>()].try_into().unwrap());
+        if length > MAX_FRAME_SIZE {
+            return Err(FramingError::FrameTooLarge(length));
+        }
+        let length = length as usize;
+        if self.pending.len() < size_of::<u32>() + length {
+            return Ok(None);
+        }
+
+        let mut body = &self.pending[size_of::<u32>()..size_of::<u32>() + length];
+        let value = T::read_from(&mut body)?;
+        self.pending.drain(..size_of::<u32>() + length);
+        Ok(Some(value))
+    }
+
+    fn fill_pending(&mut self) -> io::Result<()> {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(read) => self.pending.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Blocks on `reader` until exactly one full message has arrived, then decodes it - unlike
+    /// [`Self::read_message`], which assumes `reader` reports [`io::ErrorKind::WouldBlock`]
+    /// instead of blocking and so only returns what's immediately available.
+    /// [`Self::fill_pending`]'s "drain everything currently available" loop would instead block
+    /// forever here, waiting for more bytes than the peer has any reason to send past one
+    /// message - e.g. the conductor waiting on a single `set_key_state`/`advance_time`
+    /// acknowledgement without spinning.
+    pub fn read_message_blocking<T: Readable>(&mut self) -> Result<T, FramingError> {
+        while self.pending.len() < size_of::<u32>() {
+            self.read_at_least_one_more()?;
+        }
+        let length = u32::from_le_bytes(self.pending[..size_of::<u32>()].try_into().unwrap());
+        if length > MAX_FRAME_SIZE {
+            return Err(FramingError::FrameTooLarge(length));
+        }
+        let length = length as usize;
+        while self.pending.len() < size_of::<u32>() + length {
+            self.read_at_least_one_more()?;
+        }
+
+        let mut body = &self.pending[size_of::<u32>()..size_of::<u32>() + length];
+        let value = T::read_from(&mut body)?;
+        self.pending.drain(..size_of::<u32>() + length);
+        Ok(value)
+    }
+
+    fn read_at_least_one_more(&mut self) -> io::Result<()> {
+        let mut buffer = [0; 4096];
+        let read = self.reader.read(&mut buffer)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.pending.extend_from_slice(&buffer[..read]);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to frame message")]
+pub enum FramingError {
+    Io(#[from] io::Error),
+    #[error("peer declared a frame of {0} bytes, over the {MAX_FRAME_SIZE} byte limit")]
+    FrameTooLarge(u32),
+}
@@ -8,3 +8,52 @@ pub enum MouseButton {
     X1,
     X2,
 }
+
+/// One of the digital buttons in an `XINPUT_GAMEPAD`'s `wButtons` bitmask. See
+/// `hooks::state::set_gamepad_button`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadButton {
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+    Start,
+    Back,
+    LeftThumb,
+    RightThumb,
+    LeftShoulder,
+    RightShoulder,
+    A,
+    B,
+    X,
+    Y,
+}
+
+/// One of the two thumbsticks' two axes in an `XINPUT_GAMEPAD`. See
+/// `hooks::state::set_gamepad_axis`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+}
+
+/// One of an `XINPUT_GAMEPAD`'s two analog triggers. See `hooks::state::set_gamepad_trigger`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GamepadTrigger {
+    Left,
+    Right,
+}
+
+/// One of the `CTRL_*_EVENT` values a console control handler (installed via
+/// `SetConsoleCtrlHandler`) can be delivered. See `hooks::hooks::console` for where these are
+/// simulated and `hooks::state::deliver_console_ctrl_event` for how they're dispatched.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ConsoleCtrlEvent {
+    C,
+    Break,
+    Close,
+    Logoff,
+    Shutdown,
+}
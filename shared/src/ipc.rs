@@ -1,170 +1,364 @@
+pub mod codec;
 pub mod message;
+pub mod multi_receiver;
+pub mod ring;
+pub mod rpc;
+pub mod transport;
 
-use crate::windows::{
-    event::{self, ManualResetEvent},
-    handle, pipe, process,
-};
+use codec::{Bincode, Codec};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::VecDeque,
     fmt::Debug,
-    io::{self, Write},
+    future::Future,
     marker::PhantomData,
+    task::Poll,
+    time::Duration,
 };
 use thiserror::Error;
+use transport::{
+    pipe::{PipeReceiveTransport, PipeSendTransport},
+    ReceiveTransport, SendTransport, TransportError,
+};
+
+pub use transport::pipe::DEFAULT_RING_BUFFER_CAPACITY;
 
 #[derive(Debug)]
-pub struct Sender<S>
+pub struct Sender<S, T = PipeSendTransport, C = Bincode>
 where
-    S: Serialize + Debug,
+    S: Debug,
+    T: SendTransport,
+    C: Codec<S>,
 {
-    pipe: pipe::Writer,
-    send_event: ManualResetEvent,
-    acknowledge_event: ManualResetEvent,
-    _phantom_data: PhantomData<S>, // circumvents "parameter is never used" error
+    transport: T,
+    _phantom_data: PhantomData<(S, C)>, // circumvents "parameter is never used" error
 }
 
-impl<S: Serialize + Debug> Sender<S> {
+impl<S: Debug, T: SendTransport, C: Codec<S>> Sender<S, T, C> {
     pub fn try_clone(&self) -> Result<Self, SenderCloneError> {
         Ok(Self {
-            pipe: self.pipe.try_clone()?,
-            send_event: self.send_event.try_clone()?,
-            acknowledge_event: self.acknowledge_event.try_clone()?,
+            transport: self.transport.try_clone()?,
             _phantom_data: PhantomData,
         })
     }
 
+    /// Enqueues `message` for sending and returns as soon as it has been handed to the
+    /// transport, without waiting for the receiver to read it. Applies backpressure (awaiting,
+    /// rather than dropping frames) once the transport is at capacity.
+    pub async fn send_async(&mut self, message: &S) -> Result<(), SendError> {
+        let bytes = C::encode(message)?;
+        self.transport.send_frame(&bytes).await?;
+        Ok(())
+    }
+
+    /// Blocks until the receiver has consumed every frame sent so far.
+    pub async fn flush_pending(&self) -> Result<(), SendError> {
+        self.transport.flush_pending().await?;
+        Ok(())
+    }
+
     pub async fn send(&mut self, message: S) -> Result<(), SendError> {
-        self.pipe.write_all(&bincode::serialize(&message)?)?;
-        self.pipe.flush()?;
-        self.send_event.set()?;
-        self.acknowledge_event.wait().await?;
-        self.acknowledge_event.reset()?;
+        self.send_async(&message).await?;
+        self.flush_pending().await
+    }
+
+    /// Like [`Self::send`], but gives up with [`SendError::TimedOut`] once `timeout` elapses,
+    /// rather than waiting forever on a peer that has hung. If `peer` is given, the wait is also
+    /// abandoned as soon as that process exits, since a dead peer will never apply backpressure
+    /// relief on its own.
+    pub async fn send_timeout(
+        &mut self,
+        message: S,
+        timeout: Duration,
+        peer: Option<&crate::windows::process::Process>,
+    ) -> Result<(), SendError> {
+        let timer = crate::windows::timer::WaitableTimer::new(timeout)?;
+        match race(self.send(message), timed_out_or_peer_exited(&timer, peer)).await {
+            Ok(result) => result,
+            Err(()) => Err(SendError::TimedOut),
+        }
+    }
+}
+
+impl<S: Debug + rpc::CarriesCall, T: SendTransport, C: Codec<S>> Sender<S, T, C> {
+    /// Sends `request` wrapped in a [`rpc::Token`]-tagged call, reusing a token the caller
+    /// already allocated (e.g. via [`rpc::TokenSource::next`] or `winter::rpc::Client::begin_call`,
+    /// which must allocate the token before sending so it can register the pending reply slot
+    /// first).
+    pub async fn send_request_with_token<Req: rpc::Request>(
+        &mut self,
+        token: rpc::Token,
+        request: &Req,
+    ) -> Result<(), SendRequestError> {
+        let payload = bincode::serialize(request)?;
+        self.send(S::from_call(token, payload)).await?;
         Ok(())
     }
 
-    #[must_use]
-    #[expect(clippy::missing_panics_doc)]
-    pub fn serialize_to_bytes(&self) -> [u8; 12] {
-        let bytes = unsafe {
-            [
-                self.pipe.raw_handle() as u32,
-                self.send_event.raw_handle() as u32,
-                self.acknowledge_event.raw_handle() as u32,
-            ]
+    /// Issues `request` as a fresh correlation-tagged call, allocating its [`rpc::Token`] from
+    /// `token_source`. Returns the token so the caller can match it against the [`rpc::Response`]
+    /// that comes back on whichever channel the responder replies over.
+    pub async fn send_request<Req: rpc::Request>(
+        &mut self,
+        token_source: &rpc::TokenSource,
+        request: &Req,
+    ) -> Result<rpc::Token, SendRequestError> {
+        let token = token_source.next();
+        self.send_request_with_token(token, request).await?;
+        Ok(token)
+    }
+}
+
+impl<T: SendTransport, C: Codec<rpc::StreamFrame>> Sender<rpc::StreamFrame, T, C> {
+    /// Answers a call identified by `token` with a stream of items: each of `payloads` (already
+    /// serialized by the caller, the same way [`rpc::Response::payload`] is) goes out as a
+    /// [`rpc::StreamFrame::Chunk`], followed by a terminating [`rpc::StreamFrame::End`] so
+    /// [`rpc::ResponseStream`] knows to stop.
+    pub async fn reply_stream(
+        &mut self,
+        token: rpc::Token,
+        payloads: impl IntoIterator<Item = Vec<u8>>,
+    ) -> Result<(), SendError> {
+        for payload in payloads {
+            self.send(rpc::StreamFrame::Chunk { token, payload }).await?;
         }
-        .iter()
-        .flat_map(|h| h.to_ne_bytes())
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-        bytes
+        self.send(rpc::StreamFrame::End { token }).await
     }
+}
 
+impl<S: Debug, C: Codec<S>> Sender<S, PipeSendTransport, C> {
+    #[must_use]
+    pub fn serialize_to_bytes(&self) -> [u8; 16] {
+        self.transport.serialize_to_bytes()
+    }
+
+    /// # Panics
+    /// Panics if the ring buffer mapping handle encoded in `bytes` cannot be mapped into the
+    /// current process.
     #[must_use]
     #[expect(clippy::missing_panics_doc)]
-    pub unsafe fn deserialize_from_bytes(bytes: [u8; 12]) -> Self {
+    pub unsafe fn deserialize_from_bytes(bytes: [u8; 16]) -> Self {
         unsafe {
-            let mut handles = bytes
-                .chunks(4)
-                .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()) as _);
-
             Self {
-                pipe: pipe::Writer::from_raw_handle(handles.next().unwrap()),
-                send_event: ManualResetEvent::from_raw_handle(handles.next().unwrap()),
-                acknowledge_event: ManualResetEvent::from_raw_handle(handles.next().unwrap()),
+                transport: PipeSendTransport::deserialize_from_bytes(bytes),
                 _phantom_data: PhantomData,
             }
         }
     }
+
+    /// Leaks the handles backing this sender's transport, for a sender whose bytes have already
+    /// been captured via [`Self::serialize_to_bytes`] and embedded in a message handed to
+    /// another process. Without this, dropping `self` afterwards would close handles the
+    /// receiving process still needs.
+    pub unsafe fn leak_handles(self) {
+        unsafe { self.transport.leak_handles() }
+    }
+
+    /// Duplicates this sender's handles for `process`, e.g. to stash a reconnect copy of a
+    /// command sender that can be re-duplicated onward to a new conductor process during
+    /// takeover. See [`message::FromConductor::Takeover`].
+    pub fn try_clone_for_process(
+        &self,
+        process: &crate::windows::process::Process,
+    ) -> Result<Self, SenderCloneError> {
+        Ok(Self {
+            transport: self.transport.try_clone_for_process(process)?,
+            _phantom_data: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-pub struct Receiver<R>
+pub struct Receiver<R, T = PipeReceiveTransport, C = Bincode>
 where
-    R: for<'de> Deserialize<'de> + Debug,
+    R: Debug,
+    T: ReceiveTransport,
+    C: Codec<R>,
 {
-    pipe: pipe::Reader,
-    send_event: ManualResetEvent,
-    acknowledge_event: ManualResetEvent,
-    _phantom_data: PhantomData<R>, // circumvents "parameter is never used" error
+    transport: T,
+    /// Frames already read off the transport (possibly several at once, since a single drain
+    /// pass consumes every frame currently available) but not yet returned to the caller.
+    buffered: VecDeque<R>,
+    _phantom_data: PhantomData<(R, C)>, // circumvents "parameter is never used" error
 }
 
-impl<R: for<'de> Deserialize<'de> + Debug> Receiver<R> {
+impl<R: Debug, T: ReceiveTransport, C: Codec<R>> Receiver<R, T, C> {
     pub fn peek(&mut self) -> Result<Option<R>, ReceiveError> {
-        if !self.send_event.get()? {
-            return Ok(None);
-        }
-        self.send_event.reset()?;
-        let received = bincode::deserialize_from(&mut self.pipe)?;
-        self.acknowledge_event.set()?;
-        Ok(Some(received))
+        self.drain_available()?;
+        Ok(self.buffered.pop_front())
     }
 
     pub async fn receive(&mut self) -> Result<R, ReceiveError> {
-        self.send_event.wait().await?;
-        self.send_event.reset()?;
-        let received = bincode::deserialize_from(&mut self.pipe)?;
-        self.acknowledge_event.set()?;
-        Ok(received)
+        if let Some(message) = self.buffered.pop_front() {
+            return Ok(message);
+        }
+        let bytes = self.transport.wait_readable().await?;
+        self.buffered.push_back(C::decode(&bytes)?);
+        self.drain_available()?;
+        Ok(self.buffered.pop_front().unwrap())
     }
 
-    #[must_use]
-    #[expect(clippy::missing_panics_doc)]
-    pub fn serialize_to_bytes(&self) -> [u8; 12] {
-        let bytes = unsafe {
-            [
-                self.pipe.raw_handle() as u32,
-                self.send_event.raw_handle() as u32,
-                self.acknowledge_event.raw_handle() as u32,
-            ]
+    /// Reads every frame currently available from the transport into [`Self::buffered`], in
+    /// order.
+    fn drain_available(&mut self) -> Result<(), ReceiveError> {
+        for bytes in self.transport.drain_available()? {
+            self.buffered.push_back(C::decode(&bytes)?);
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::receive`], but gives up with [`ReceiveError::TimedOut`] once `timeout`
+    /// elapses, rather than waiting forever on a peer that has hung or crashed. If `peer` is
+    /// given, the wait is also abandoned as soon as that process exits.
+    pub async fn receive_timeout(
+        &mut self,
+        timeout: Duration,
+        peer: Option<&crate::windows::process::Process>,
+    ) -> Result<R, ReceiveError> {
+        if let Some(message) = self.buffered.pop_front() {
+            return Ok(message);
+        }
+        let timer = crate::windows::timer::WaitableTimer::new(timeout)?;
+        match race(
+            self.transport.wait_readable(),
+            timed_out_or_peer_exited(&timer, peer),
+        )
+        .await
+        {
+            Ok(bytes) => {
+                self.buffered.push_back(C::decode(&bytes?)?);
+                self.drain_available()?;
+                Ok(self.buffered.pop_front().unwrap())
+            }
+            Err(()) => Err(ReceiveError::TimedOut),
         }
-        .iter()
-        .flat_map(|h| h.to_ne_bytes())
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-        bytes
     }
+}
 
+impl<R: Debug, C: Codec<R>> Receiver<R, PipeReceiveTransport, C> {
+    #[must_use]
+    pub fn serialize_to_bytes(&self) -> [u8; 16] {
+        self.transport.serialize_to_bytes()
+    }
+
+    /// # Panics
+    /// Panics if the ring buffer mapping handle encoded in `bytes` cannot be mapped into the
+    /// current process.
     #[must_use]
     #[expect(clippy::missing_panics_doc)]
-    pub unsafe fn deserialize_from_bytes(bytes: [u8; 12]) -> Self {
+    pub unsafe fn deserialize_from_bytes(bytes: [u8; 16]) -> Self {
         unsafe {
-            let mut handles = bytes
-                .chunks(4)
-                .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()) as _);
-
             Self {
-                pipe: pipe::Reader::from_raw_handle(handles.next().unwrap()),
-                send_event: ManualResetEvent::from_raw_handle(handles.next().unwrap()),
-                acknowledge_event: ManualResetEvent::from_raw_handle(handles.next().unwrap()),
+                transport: PipeReceiveTransport::deserialize_from_bytes(bytes),
+                buffered: VecDeque::new(),
                 _phantom_data: PhantomData,
             }
         }
     }
+
+    /// Leaks the handles backing this receiver's transport, for a receiver whose bytes have
+    /// already been captured via [`Self::serialize_to_bytes`] and embedded in a message handed to
+    /// another process. Without this, dropping `self` afterwards would close handles the
+    /// receiving process still needs.
+    pub unsafe fn leak_handles(self) {
+        unsafe { self.transport.leak_handles() }
+    }
+
+    /// Duplicates this receiver's handles for `process`, e.g. to stash a reconnect copy that can
+    /// be re-duplicated onward to a new conductor process during takeover. See
+    /// [`message::FromConductor::Takeover`].
+    pub fn try_clone_for_process(
+        &self,
+        process: &crate::windows::process::Process,
+    ) -> Result<Self, ReceiverCloneError> {
+        Ok(Self {
+            transport: self.transport.try_clone_for_process(process)?,
+            buffered: VecDeque::new(),
+            _phantom_data: PhantomData,
+        })
+    }
 }
 
 pub fn new_sender_and_receiver<T>(
-    sender_process: &process::Process,
-    receiver_process: &process::Process,
+    sender_process: &crate::windows::process::Process,
+    receiver_process: &crate::windows::process::Process,
+) -> Result<(Sender<T>, Receiver<T>), NewSenderAndReceiverError>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Debug + Debug,
+{
+    let (sender_transport, receiver_transport) =
+        transport::pipe::new_pair(sender_process, receiver_process)?;
+    Ok((
+        Sender {
+            transport: sender_transport,
+            _phantom_data: PhantomData,
+        },
+        Receiver {
+            transport: receiver_transport,
+            buffered: VecDeque::new(),
+            _phantom_data: PhantomData,
+        },
+    ))
+}
+
+/// Like [`new_sender_and_receiver`], but additionally equips the pair with a ring buffer over a
+/// shared memory mapping, so that large payloads skip the pipe entirely. Intended for channels
+/// expected to carry large payloads (e.g. memory snapshots); small/control-only channels should
+/// keep using [`new_sender_and_receiver`].
+pub fn new_sender_and_receiver_with_shared_memory<T>(
+    sender_process: &crate::windows::process::Process,
+    receiver_process: &crate::windows::process::Process,
+    ring_buffer_name: &str,
+    ring_buffer_capacity: usize,
 ) -> Result<(Sender<T>, Receiver<T>), NewSenderAndReceiverError>
 where
     T: Serialize + for<'de> Deserialize<'de> + Debug + Debug,
 {
-    let (pipe_writer, pipe_reader) = pipe::new()?;
-    let send_event = ManualResetEvent::new()?;
-    let acknowledge_event = ManualResetEvent::new()?;
+    let (mut sender, mut receiver) = new_sender_and_receiver(sender_process, receiver_process)?;
+    transport::pipe::attach_ring_buffer(
+        &mut sender.transport,
+        &mut receiver.transport,
+        sender_process,
+        receiver_process,
+        ring_buffer_name,
+        ring_buffer_capacity,
+    )?;
+    Ok((sender, receiver))
+}
+
+/// Like [`new_sender_and_receiver`], but backed entirely by a [`transport::ring`] ring buffer
+/// instead of a pipe, for channels expected to carry a high volume of traffic and that don't need
+/// a pipe's ability to be waited on alongside arbitrary other handles. See
+/// [`transport::ring::RingSendTransport`] for the tradeoffs against the default pipe transport.
+pub fn new_sender_and_receiver_over_ring<T>(
+    sender_process: &crate::windows::process::Process,
+    receiver_process: &crate::windows::process::Process,
+    ring_buffer_name: &str,
+    ring_buffer_capacity: usize,
+) -> Result<
+    (
+        Sender<T, transport::ring::RingSendTransport>,
+        Receiver<T, transport::ring::RingReceiveTransport>,
+    ),
+    NewSenderAndReceiverError,
+>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Debug,
+{
+    let (sender_transport, receiver_transport) = transport::ring::new_pair(
+        sender_process,
+        receiver_process,
+        ring_buffer_name,
+        ring_buffer_capacity,
+    )?;
     Ok((
         Sender {
-            pipe: pipe_writer.try_clone_for_process(sender_process)?,
-            send_event: send_event.try_clone_for_process(sender_process)?,
-            acknowledge_event: acknowledge_event.try_clone_for_process(sender_process)?,
+            transport: sender_transport,
             _phantom_data: PhantomData,
         },
         Receiver {
-            pipe: pipe_reader.try_clone_for_process(receiver_process)?,
-            send_event: send_event.try_clone_for_process(receiver_process)?,
-            acknowledge_event: acknowledge_event.try_clone_for_process(receiver_process)?,
+            transport: receiver_transport,
+            buffered: VecDeque::new(),
             _phantom_data: PhantomData,
         },
     ))
@@ -173,33 +367,81 @@ where
 #[derive(Debug, Error)]
 #[error("failed to create sender/receiver pair")]
 pub enum NewSenderAndReceiverError {
-    NewPipe(#[from] pipe::NewError),
-    NewEvent(#[from] event::NewError),
-    HandleClone(#[from] handle::CloneError),
+    NewPipe(#[from] crate::windows::pipe::NewError),
+    NewSemaphore(#[from] crate::windows::event::NewSemaphoreError),
+    NewEvent(#[from] crate::windows::event::NewError),
+    HandleClone(#[from] crate::windows::handle::CloneError),
+    NewRingBuffer(#[from] ring::CreateError),
+    AttachRingBuffer(#[from] ring::AttachError),
+}
+
+/// Polls `primary` and `cancel` concurrently, returning whichever becomes ready first. Used by
+/// [`Sender::send_timeout`]/[`Receiver::receive_timeout`] to bound an otherwise-unbounded wait
+/// without pulling in an async runtime's `select!` as a dependency of this crate.
+async fn race<A: Future, B: Future<Output = ()>>(primary: A, cancel: B) -> Result<A::Output, ()> {
+    let mut primary = std::pin::pin!(primary);
+    let mut cancel = std::pin::pin!(cancel);
+    std::future::poll_fn(move |cx| {
+        if let Poll::Ready(value) = primary.as_mut().poll(cx) {
+            return Poll::Ready(Ok(value));
+        }
+        if cancel.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(()));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+/// Resolves once `timer` fires or, if `peer` is given, once `peer` exits — whichever comes
+/// first — for use as the `cancel` side of [`race`].
+async fn timed_out_or_peer_exited(
+    timer: &crate::windows::timer::WaitableTimer,
+    peer: Option<&crate::windows::process::Process>,
+) {
+    let _ = match peer {
+        Some(peer) => {
+            crate::windows::handle::Handle::wait_any(&[timer.handle(), peer.handle()], None).await
+        }
+        None => timer.handle().wait().await.map(|()| Some(0)),
+    };
 }
 
 #[derive(Debug, Error)]
 #[error("failed to send message")]
 pub enum SendError {
-    EventWait(#[from] event::WaitError),
-    Bincode(#[from] bincode::Error),
-    EventSet(#[from] event::SetError),
-    EventReset(#[from] event::ResetError),
-    Os(#[from] io::Error),
+    Transport(#[from] TransportError),
+    Encode(#[from] codec::EncodeError),
+    NewTimer(#[from] crate::windows::timer::NewError),
+    #[error("timed out waiting to send a message")]
+    TimedOut,
+}
+
+#[derive(Debug, Error)]
+#[error("failed to send rpc request")]
+pub enum SendRequestError {
+    Serialize(#[from] bincode::Error),
+    Send(#[from] SendError),
 }
 
 #[derive(Debug, Error)]
 #[error("failed to receive message")]
 pub enum ReceiveError {
-    Bincode(#[from] bincode::Error),
-    EventGet(#[from] event::GetError),
-    EventWait(#[from] event::WaitError),
-    EventSet(#[from] event::SetError),
-    EventReset(#[from] event::ResetError),
+    Transport(#[from] TransportError),
+    Decode(#[from] codec::DecodeError),
+    NewTimer(#[from] crate::windows::timer::NewError),
+    #[error("timed out waiting to receive a message")]
+    TimedOut,
 }
 
 #[derive(Debug, Error)]
 #[error("failed to clone sender")]
 pub enum SenderCloneError {
-    HandleClone(#[from] handle::CloneError),
+    Transport(#[from] TransportError),
+}
+
+#[derive(Debug, Error)]
+#[error("failed to clone receiver")]
+pub enum ReceiverCloneError {
+    Transport(#[from] TransportError),
 }
@@ -0,0 +1,106 @@
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// Encodes/decodes the payloads carried by [`super::Sender`]/[`super::Receiver`]. [`Bincode`] is
+/// the default; a hot per-frame message type (e.g. `FromConductor::SetKeyState`/`AdvanceTime`,
+/// `Log`) can swap in a cheaper encoding by parameterizing its channel with a different codec,
+/// without touching the framing or transport layer underneath. [`MessagePack`], [`Postcard`], and
+/// [`Json`] are available behind their respective `serialize_*` cargo features for callers that
+/// want a different compactness/debuggability tradeoff than bincode's.
+pub trait Codec<T> {
+    fn encode(value: &T) -> Result<Vec<u8>, EncodeError>;
+
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError>;
+}
+
+/// The default codec, backing every [`super::Sender`]/[`super::Receiver`] unless overridden.
+#[cfg(feature = "serialize_bincode")]
+#[derive(Debug)]
+pub struct Bincode;
+
+#[cfg(feature = "serialize_bincode")]
+impl<T: Serialize + DeserializeOwned> Codec<T> for Bincode {
+    fn encode(value: &T) -> Result<Vec<u8>, EncodeError> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+/// MessagePack encoding, for payloads shared with non-Rust tooling that already speaks msgpack.
+#[cfg(feature = "serialize_messagepack")]
+#[derive(Debug)]
+pub struct MessagePack;
+
+#[cfg(feature = "serialize_messagepack")]
+impl<T: Serialize + DeserializeOwned> Codec<T> for MessagePack {
+    fn encode(value: &T) -> Result<Vec<u8>, EncodeError> {
+        Ok(rmp_serde::to_vec(value)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+/// Postcard encoding, for the smallest on-wire frames at the cost of a less self-describing
+/// format than bincode or MessagePack.
+#[cfg(feature = "serialize_postcard")]
+#[derive(Debug)]
+pub struct Postcard;
+
+#[cfg(feature = "serialize_postcard")]
+impl<T: Serialize + DeserializeOwned> Codec<T> for Postcard {
+    fn encode(value: &T) -> Result<Vec<u8>, EncodeError> {
+        Ok(postcard::to_allocvec(value)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError> {
+        Ok(postcard::from_bytes(bytes)?)
+    }
+}
+
+/// JSON encoding, for when frames need to be human-readable while debugging the IPC layer (e.g.
+/// dumping captured traffic).
+#[cfg(feature = "serialize_json")]
+#[derive(Debug)]
+pub struct Json;
+
+#[cfg(feature = "serialize_json")]
+impl<T: Serialize + DeserializeOwned> Codec<T> for Json {
+    fn encode(value: &T) -> Result<Vec<u8>, EncodeError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to encode message")]
+pub enum EncodeError {
+    #[cfg(feature = "serialize_bincode")]
+    Bincode(#[from] bincode::Error),
+    #[cfg(feature = "serialize_messagepack")]
+    MessagePack(#[from] rmp_serde::encode::Error),
+    #[cfg(feature = "serialize_postcard")]
+    Postcard(#[from] postcard::Error),
+    #[cfg(feature = "serialize_json")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("failed to decode message")]
+pub enum DecodeError {
+    #[cfg(feature = "serialize_bincode")]
+    Bincode(#[from] bincode::Error),
+    #[cfg(feature = "serialize_messagepack")]
+    MessagePack(#[from] rmp_serde::decode::Error),
+    #[cfg(feature = "serialize_postcard")]
+    Postcard(#[from] postcard::Error),
+    #[cfg(feature = "serialize_json")]
+    Json(#[from] serde_json::Error),
+}
@@ -1,12 +1,8 @@
-use super::{Receiver, Sender};
-use crate::{
-    input::MouseButton,
-    windows::{event, pipe},
-};
+use super::{rpc, Receiver, Sender};
+use crate::input::{ConsoleCtrlEvent, GamepadAxis, GamepadButton, GamepadTrigger, MouseButton};
 use serde::{Deserialize, Serialize};
-use std::{io::Read, marker::PhantomData, time::Duration};
+use std::{io::Read, time::Duration};
 use thiserror::Error;
-use winapi::ctypes::c_void;
 
 pub trait Message: Sized {
     unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError>;
@@ -24,6 +20,8 @@ pub struct Initial {
     pub initialized_message_sender: Sender<Initialized>,
     pub log_message_sender: Sender<Log>,
     pub message_receiver: Receiver<FromConductor>,
+    pub rpc_response_sender: Sender<rpc::Response>,
+    pub spawned_process_sender: Sender<SpawnedProcess>,
 }
 
 impl Message for Initial {
@@ -33,21 +31,17 @@ impl Message for Initial {
             &self.initialized_message_sender.serialize_to_bytes(),
             &self.log_message_sender.serialize_to_bytes(),
             &self.message_receiver.serialize_to_bytes(),
+            &self.rpc_response_sender.serialize_to_bytes(),
+            &self.spawned_process_sender.serialize_to_bytes(),
         ]
         .concat();
 
         unsafe {
-            self.initialized_message_sender.pipe.leak_handle();
-            self.initialized_message_sender.send_event.leak_handle();
-            self.initialized_message_sender
-                .acknowledge_event
-                .leak_handle();
-            self.log_message_sender.pipe.leak_handle();
-            self.log_message_sender.send_event.leak_handle();
-            self.log_message_sender.acknowledge_event.leak_handle();
-            self.message_receiver.pipe.leak_handle();
-            self.message_receiver.send_event.leak_handle();
-            self.message_receiver.acknowledge_event.leak_handle();
+            self.initialized_message_sender.leak_handles();
+            self.log_message_sender.leak_handles();
+            self.message_receiver.leak_handles();
+            self.rpc_response_sender.leak_handles();
+            self.spawned_process_sender.leak_handles();
         }
 
         Ok(bytes)
@@ -61,9 +55,11 @@ impl Message for Initial {
         }
 
         let serialized_main_thread_id = read::<4>(&mut reader)?;
-        let serialized_initialized_message_sender = read::<12>(&mut reader)?;
-        let serialized_log_message_sender = read::<12>(&mut reader)?;
-        let serialized_message_receiver = read::<12>(&mut reader)?;
+        let serialized_initialized_message_sender = read::<16>(&mut reader)?;
+        let serialized_log_message_sender = read::<16>(&mut reader)?;
+        let serialized_message_receiver = read::<16>(&mut reader)?;
+        let serialized_rpc_response_sender = read::<16>(&mut reader)?;
+        let serialized_spawned_process_sender = read::<16>(&mut reader)?;
         unsafe {
             Ok(Self {
                 main_thread_id: u32::from_ne_bytes(serialized_main_thread_id),
@@ -72,6 +68,12 @@ impl Message for Initial {
                 ),
                 log_message_sender: Sender::deserialize_from_bytes(serialized_log_message_sender),
                 message_receiver: Receiver::deserialize_from_bytes(serialized_message_receiver),
+                rpc_response_sender: Sender::deserialize_from_bytes(
+                    serialized_rpc_response_sender,
+                ),
+                spawned_process_sender: Sender::deserialize_from_bytes(
+                    serialized_spawned_process_sender,
+                ),
             })
         }
     }
@@ -85,10 +87,79 @@ pub enum FromConductor {
     SetKeyState { id: u8, state: bool },
     SetMousePosition { x: u16, y: u16 },
     SetMouseButtonState { button: MouseButton, state: bool },
-    IdleRequest { response_sender: Sender<Idle> },
+    ScrollMouseWheel { delta: i32, horizontal: bool },
+    SetGamepadButton { index: u8, button: GamepadButton, state: bool },
+    SetGamepadAxis { index: u8, axis: GamepadAxis, value: i16 },
+    SetGamepadTrigger { index: u8, trigger: GamepadTrigger, value: u8 },
+    ArmDirtyTracking {
+        regions: Vec<(usize, usize)>,
+        response_sender: Sender<Armed>,
+    },
+    TakeDirtyPages { response_sender: Sender<DirtyPages> },
+    /// A correlation-id-tagged RPC call, dispatched and answered on the shared RPC response
+    /// channel rather than a dedicated [`Sender`]/[`Receiver`] pair. See `winter::rpc::Client`.
+    Rpc { token: rpc::Token, payload: Vec<u8> },
+    /// Sent over the existing command channel by the connected conductor to hand the injected
+    /// process over to another conductor process. The log/RPC response senders are re-duplicated
+    /// for `new_process_id` (via [`Sender::try_clone_for_process`]) so the new conductor can pick
+    /// up where the old one left off. A process whose conductor has gone away without sending this
+    /// is not reclaimed. See `hooks::takeover`.
+    Takeover { new_process_id: u32 },
+    /// Reconfigures the reported `QueryPerformanceFrequency` value and the scale factor applied
+    /// uniformly across every other time hook, so the target can be run at an arbitrary fraction
+    /// or multiple of virtual-real time. `time_scale_denominator` of `0` is rejected by the hooks
+    /// DLL. See `hooks::state::set_time_configuration`.
+    SetTimeConfiguration {
+        performance_counter_frequency: u64,
+        time_scale_numerator: u64,
+        time_scale_denominator: u64,
+    },
+    /// Delivers `event` to every handler registered via `SetConsoleCtrlHandler`, newest-first, on
+    /// a dedicated thread - mirroring how real console control events are dispatched. See
+    /// `hooks::state::deliver_console_ctrl_event`.
+    DeliverConsoleCtrlEvent(ConsoleCtrlEvent),
+    /// Pins the `HKL` that `TranslateMessage` computes `WM_CHAR`/`WM_SYSCHAR` against, so the
+    /// characters an injected key sequence produces don't depend on the host machine's active
+    /// layout. See `hooks::state::set_keyboard_layout`.
+    SetKeyboardLayout(usize),
+    /// Patches an `int3` breakpoint trap at `address`, replacing whatever byte was there. See
+    /// `hooks::debugger::set_breakpoint`.
+    SetBreakpoint {
+        address: usize,
+        response_sender: Sender<BreakpointArmed>,
+    },
+    /// Removes a not-yet-hit breakpoint previously armed by [`FromConductor::SetBreakpoint`]. See
+    /// `hooks::debugger::clear_breakpoint`.
+    ClearBreakpoint {
+        address: usize,
+        response_sender: Sender<BreakpointCleared>,
+    },
+    /// Registers to be notified the next time any thread pauses at a breakpoint or single-step
+    /// trap (see `hooks::debugger`). Like [`IsIdleRequest`], the reply may arrive well after the
+    /// call is made - only once some thread actually hits a trap.
+    WaitForPause { response_sender: Sender<DebugPause> },
+    /// Wakes the thread paused at `thread_id` (see [`FromConductor::WaitForPause`]); if
+    /// `single_step` is set, it pauses again after exactly one more instruction instead of
+    /// running freely. See `hooks::debugger::resume`.
+    ResumeFromPause { thread_id: u32, single_step: bool },
+    /// Switches every virtual socket hook between recording real network traffic and replaying it
+    /// from the log instead. See `hooks::state::set_socket_mode`.
+    SetSocketMode(SocketMode),
+}
+
+/// Whether the virtual socket hooks (`hooks::socket::socket` and friends) talk to a real
+/// socket and log what it sends/receives, or serve `recv` entirely from a previously recorded log
+/// without touching the network at all. See [`FromConductor::SetSocketMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SocketMode {
+    Record,
+    Replay,
 }
 
-// TODO: cleaner implementation
+/// Mirrors [`FromConductor`], but with every `Sender<T>` field replaced by the 16 bytes
+/// [`Sender::serialize_to_bytes`] encodes it as, since a raw handle doesn't survive
+/// (de)serialization on its own. See [`Message::serialize`]/[`Message::deserialize_from`] below
+/// for the handle-leaking/reconstruction that pairs with this.
 #[derive(Debug, Serialize, Deserialize)]
 enum FromConductorSerializable {
     Resume,
@@ -96,7 +167,41 @@ enum FromConductorSerializable {
     SetKeyState { id: u8, state: bool },
     SetMousePosition { x: u16, y: u16 },
     SetMouseButtonState { button: MouseButton, state: bool },
-    IdleRequest { response_sender: (u32, u32, u32) },
+    ScrollMouseWheel { delta: i32, horizontal: bool },
+    SetGamepadButton { index: u8, button: GamepadButton, state: bool },
+    SetGamepadAxis { index: u8, axis: GamepadAxis, value: i16 },
+    SetGamepadTrigger { index: u8, trigger: GamepadTrigger, value: u8 },
+    ArmDirtyTracking {
+        regions: Vec<(usize, usize)>,
+        response_sender: [u8; 16],
+    },
+    TakeDirtyPages { response_sender: [u8; 16] },
+    Rpc { token: rpc::Token, payload: Vec<u8> },
+    Takeover { new_process_id: u32 },
+    SetTimeConfiguration {
+        performance_counter_frequency: u64,
+        time_scale_numerator: u64,
+        time_scale_denominator: u64,
+    },
+    DeliverConsoleCtrlEvent(ConsoleCtrlEvent),
+    SetKeyboardLayout(usize),
+    SetBreakpoint {
+        address: usize,
+        response_sender: [u8; 16],
+    },
+    ClearBreakpoint {
+        address: usize,
+        response_sender: [u8; 16],
+    },
+    WaitForPause { response_sender: [u8; 16] },
+    ResumeFromPause { thread_id: u32, single_step: bool },
+    SetSocketMode(SocketMode),
+}
+
+impl rpc::CarriesCall for FromConductor {
+    fn from_call(token: rpc::Token, payload: Vec<u8>) -> Self {
+        FromConductor::Rpc { token, payload }
+    }
 }
 
 impl Message for FromConductor {
@@ -115,17 +220,108 @@ impl Message for FromConductor {
             FromConductor::SetMouseButtonState { button, state } => {
                 FromConductorSerializable::SetMouseButtonState { button, state }
             }
-            FromConductor::IdleRequest { response_sender } => {
-                FromConductorSerializable::IdleRequest {
-                    response_sender: unsafe {
-                        (
-                            response_sender.pipe.leak_handle() as u32,
-                            response_sender.send_event.leak_handle() as u32,
-                            response_sender.acknowledge_event.leak_handle() as u32,
-                        )
+            FromConductor::ScrollMouseWheel { delta, horizontal } => {
+                FromConductorSerializable::ScrollMouseWheel { delta, horizontal }
+            }
+            FromConductor::SetGamepadButton {
+                index,
+                button,
+                state,
+            } => FromConductorSerializable::SetGamepadButton {
+                index,
+                button,
+                state,
+            },
+            FromConductor::SetGamepadAxis { index, axis, value } => {
+                FromConductorSerializable::SetGamepadAxis { index, axis, value }
+            }
+            FromConductor::SetGamepadTrigger {
+                index,
+                trigger,
+                value,
+            } => FromConductorSerializable::SetGamepadTrigger {
+                index,
+                trigger,
+                value,
+            },
+            FromConductor::ArmDirtyTracking {
+                regions,
+                response_sender,
+            } => FromConductorSerializable::ArmDirtyTracking {
+                regions,
+                response_sender: {
+                    let bytes = response_sender.serialize_to_bytes();
+                    unsafe { response_sender.leak_handles() };
+                    bytes
+                },
+            },
+            FromConductor::TakeDirtyPages { response_sender } => {
+                FromConductorSerializable::TakeDirtyPages {
+                    response_sender: {
+                        let bytes = response_sender.serialize_to_bytes();
+                        unsafe { response_sender.leak_handles() };
+                        bytes
                     },
                 }
             }
+            FromConductor::Rpc { token, payload } => FromConductorSerializable::Rpc { token, payload },
+            FromConductor::Takeover { new_process_id } => {
+                FromConductorSerializable::Takeover { new_process_id }
+            }
+            FromConductor::SetTimeConfiguration {
+                performance_counter_frequency,
+                time_scale_numerator,
+                time_scale_denominator,
+            } => FromConductorSerializable::SetTimeConfiguration {
+                performance_counter_frequency,
+                time_scale_numerator,
+                time_scale_denominator,
+            },
+            FromConductor::DeliverConsoleCtrlEvent(event) => {
+                FromConductorSerializable::DeliverConsoleCtrlEvent(event)
+            }
+            FromConductor::SetKeyboardLayout(hkl) => {
+                FromConductorSerializable::SetKeyboardLayout(hkl)
+            }
+            FromConductor::SetBreakpoint {
+                address,
+                response_sender,
+            } => FromConductorSerializable::SetBreakpoint {
+                address,
+                response_sender: {
+                    let bytes = response_sender.serialize_to_bytes();
+                    unsafe { response_sender.leak_handles() };
+                    bytes
+                },
+            },
+            FromConductor::ClearBreakpoint {
+                address,
+                response_sender,
+            } => FromConductorSerializable::ClearBreakpoint {
+                address,
+                response_sender: {
+                    let bytes = response_sender.serialize_to_bytes();
+                    unsafe { response_sender.leak_handles() };
+                    bytes
+                },
+            },
+            FromConductor::WaitForPause { response_sender } => {
+                FromConductorSerializable::WaitForPause {
+                    response_sender: {
+                        let bytes = response_sender.serialize_to_bytes();
+                        unsafe { response_sender.leak_handles() };
+                        bytes
+                    },
+                }
+            }
+            FromConductor::ResumeFromPause {
+                thread_id,
+                single_step,
+            } => FromConductorSerializable::ResumeFromPause {
+                thread_id,
+                single_step,
+            },
+            FromConductor::SetSocketMode(mode) => FromConductorSerializable::SetSocketMode(mode),
         })?)
     }
 
@@ -145,24 +341,92 @@ impl Message for FromConductor {
                 FromConductorSerializable::SetMouseButtonState { button, state } => {
                     FromConductor::SetMouseButtonState { button, state }
                 }
-                FromConductorSerializable::IdleRequest { response_sender } => {
-                    FromConductor::IdleRequest {
-                        response_sender: unsafe {
-                            Sender {
-                                pipe: pipe::Writer::from_raw_handle(
-                                    response_sender.0 as *mut c_void,
-                                ),
-                                send_event: event::ManualResetEvent::from_raw_handle(
-                                    response_sender.1 as *mut c_void,
-                                ),
-                                acknowledge_event: event::ManualResetEvent::from_raw_handle(
-                                    response_sender.2 as *mut c_void,
-                                ),
-                                _phantom_data: PhantomData,
-                            }
-                        },
+                FromConductorSerializable::ScrollMouseWheel { delta, horizontal } => {
+                    FromConductor::ScrollMouseWheel { delta, horizontal }
+                }
+                FromConductorSerializable::SetGamepadButton {
+                    index,
+                    button,
+                    state,
+                } => FromConductor::SetGamepadButton {
+                    index,
+                    button,
+                    state,
+                },
+                FromConductorSerializable::SetGamepadAxis { index, axis, value } => {
+                    FromConductor::SetGamepadAxis { index, axis, value }
+                }
+                FromConductorSerializable::SetGamepadTrigger {
+                    index,
+                    trigger,
+                    value,
+                } => FromConductor::SetGamepadTrigger {
+                    index,
+                    trigger,
+                    value,
+                },
+                FromConductorSerializable::ArmDirtyTracking {
+                    regions,
+                    response_sender,
+                } => FromConductor::ArmDirtyTracking {
+                    regions,
+                    response_sender: unsafe { Sender::deserialize_from_bytes(response_sender) },
+                },
+                FromConductorSerializable::TakeDirtyPages { response_sender } => {
+                    FromConductor::TakeDirtyPages {
+                        response_sender: unsafe { Sender::deserialize_from_bytes(response_sender) },
+                    }
+                }
+                FromConductorSerializable::Rpc { token, payload } => {
+                    FromConductor::Rpc { token, payload }
+                }
+                FromConductorSerializable::Takeover { new_process_id } => {
+                    FromConductor::Takeover { new_process_id }
+                }
+                FromConductorSerializable::SetTimeConfiguration {
+                    performance_counter_frequency,
+                    time_scale_numerator,
+                    time_scale_denominator,
+                } => FromConductor::SetTimeConfiguration {
+                    performance_counter_frequency,
+                    time_scale_numerator,
+                    time_scale_denominator,
+                },
+                FromConductorSerializable::DeliverConsoleCtrlEvent(event) => {
+                    FromConductor::DeliverConsoleCtrlEvent(event)
+                }
+                FromConductorSerializable::SetKeyboardLayout(hkl) => {
+                    FromConductor::SetKeyboardLayout(hkl)
+                }
+                FromConductorSerializable::SetBreakpoint {
+                    address,
+                    response_sender,
+                } => FromConductor::SetBreakpoint {
+                    address,
+                    response_sender: unsafe { Sender::deserialize_from_bytes(response_sender) },
+                },
+                FromConductorSerializable::ClearBreakpoint {
+                    address,
+                    response_sender,
+                } => FromConductor::ClearBreakpoint {
+                    address,
+                    response_sender: unsafe { Sender::deserialize_from_bytes(response_sender) },
+                },
+                FromConductorSerializable::WaitForPause { response_sender } => {
+                    FromConductor::WaitForPause {
+                        response_sender: unsafe { Sender::deserialize_from_bytes(response_sender) },
                     }
                 }
+                FromConductorSerializable::ResumeFromPause {
+                    thread_id,
+                    single_step,
+                } => FromConductor::ResumeFromPause {
+                    thread_id,
+                    single_step,
+                },
+                FromConductorSerializable::SetSocketMode(mode) => {
+                    FromConductor::SetSocketMode(mode)
+                }
             },
         )
     }
@@ -181,13 +445,25 @@ impl Message for Initialized {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Log {
     pub level: LogLevel,
+    pub target: String,
     pub message: String,
 }
 
-impl Message for Log {
+/// A batch of [`Log`] entries flushed together from the hooks DLL's in-memory ring buffer,
+/// rather than one message per log call, so verbose tracing doesn't cost a pipe round trip per
+/// line. See `hooks::logging::RingBufferLogger`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogBatch {
+    pub entries: Vec<Log>,
+    /// Entries the ring buffer dropped (evicting the oldest to make room for a new one) because
+    /// it was full since the last flush; 0 if nothing was dropped.
+    pub dropped: u32,
+}
+
+impl Message for LogBatch {
     unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
         Ok(bincode::serialize(&self)?)
     }
@@ -197,7 +473,27 @@ impl Message for Log {
     }
 }
 
+/// A child process a hooked `CreateProcess` call forced suspended (see `hooks::process`),
+/// reported over its own dedicated channel rather than batched alongside [`LogBatch`] so the
+/// conductor can react to it (by injecting the hooks DLL) without waiting on log traffic to drain
+/// first.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct SpawnedProcess {
+    pub process_id: u32,
+    pub main_thread_id: u32,
+}
+
+impl Message for SpawnedProcess {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -206,10 +502,84 @@ pub enum LogLevel {
     Error,
 }
 
+/// Queries whether the hooks DLL's event loop has drained every queued input and gone idle,
+/// answered over the shared RPC response channel (see [`rpc::Request`]) rather than a dedicated
+/// per-call [`Sender`]/[`Receiver`] pair. The reply is deferred until the target thread actually
+/// reaches the point of waiting for the next event, so it may arrive well after the call is made.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Idle;
+pub struct IsIdleRequest;
+
+impl rpc::Request for IsIdleRequest {
+    type Response = IsIdleResponse;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IsIdleResponse;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Armed;
+
+impl Message for Armed {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirtyPages {
+    pub base_addresses: Vec<usize>,
+}
+
+impl Message for DirtyPages {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreakpointArmed;
+
+impl Message for BreakpointArmed {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BreakpointCleared;
+
+impl Message for BreakpointCleared {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+/// Reports that `thread_id` paused at `address`, either because it hit a breakpoint (see
+/// `FromConductor::SetBreakpoint`) or because it finished a single-stepped instruction (see
+/// `FromConductor::ResumeFromPause`). See `FromConductor::WaitForPause`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DebugPause {
+    pub thread_id: u32,
+    pub address: usize,
+}
 
-impl Message for Idle {
+impl Message for DebugPause {
     unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
         Ok(bincode::serialize(&self)?)
     }
@@ -0,0 +1,61 @@
+use super::{
+    codec::{Bincode, Codec},
+    transport::pipe::PipeReceiveTransport,
+    ReceiveError, Receiver, TransportError,
+};
+use crate::windows::handle::Handle;
+use std::fmt::Debug;
+
+/// Demultiplexes several [`Receiver`]s carrying the same message type into a single await point,
+/// tagging every message with the `Id` the caller registered its receiver under. Lets a host
+/// process wait on however many worker processes it has spawned at once, rather than spawning a
+/// task per worker to fan messages back in.
+pub struct MultiReceiver<Id, R, C = Bincode>
+where
+    R: Debug,
+    C: Codec<R>,
+{
+    entries: Vec<(Id, Receiver<R, PipeReceiveTransport, C>)>,
+}
+
+impl<Id: Copy, R: Debug, C: Codec<R>> MultiReceiver<Id, R, C> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds `receiver` to the set, tagging every message it produces with `id`.
+    pub fn register(&mut self, id: Id, receiver: Receiver<R, PipeReceiveTransport, C>) {
+        self.entries.push((id, receiver));
+    }
+
+    /// Waits for the next message from any registered receiver and returns it together with the
+    /// id it was registered under. Checks every receiver's already-buffered messages before
+    /// waiting on any handle, so a receiver that fired first can't starve the others out of a
+    /// message they already have waiting.
+    pub async fn receive(&mut self) -> Result<(Id, R), ReceiveError> {
+        loop {
+            for (id, receiver) in &mut self.entries {
+                if let Some(message) = receiver.peek()? {
+                    return Ok((*id, message));
+                }
+            }
+            let handles = self
+                .entries
+                .iter()
+                .map(|(_, receiver)| receiver.transport.frame_semaphore.handle())
+                .collect::<Vec<_>>();
+            Handle::wait_any(&handles, None)
+                .await
+                .map_err(TransportError::from)?;
+        }
+    }
+}
+
+impl<Id: Copy, R: Debug, C: Codec<R>> Default for MultiReceiver<Id, R, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,320 @@
+use crate::windows::{process, shared_memory::SharedMemory};
+use std::{
+    ops::Deref,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use thiserror::Error;
+
+#[repr(C)]
+struct Header {
+    write_offset: AtomicU32,
+    read_offset: AtomicU32,
+    /// Data region capacity in bytes, written once at creation so that a process attaching to an
+    /// existing mapping can confirm it agrees with the creator on the ring's size instead of
+    /// trusting a value carried alongside the handle out of band.
+    capacity: u32,
+}
+
+/// A single-producer/single-consumer ring buffer over a [`SharedMemory`] mapping, used by
+/// [`super::Sender`]/[`super::Receiver`] to move large payloads between processes without copying
+/// them through a pipe. Frames are length-prefixed (`u32` length followed by the frame bytes) and
+/// never wrap mid-frame: if a frame doesn't fit before the end of the data region, the writer
+/// skips to the start instead, leaving a zero-length sentinel frame behind for the reader to skip
+/// over.
+#[derive(Debug)]
+pub struct RingBuffer {
+    memory: SharedMemory,
+}
+
+impl RingBuffer {
+    /// Creates a new ring buffer backed by a fresh shared memory mapping of `capacity` data bytes
+    /// (plus a small header).
+    pub fn create(name: &str, capacity: usize) -> Result<Self, CreateError> {
+        let capacity_field: u32 = capacity.try_into().map_err(|_| CreateError::CapacityTooLarge)?;
+        let memory = SharedMemory::create(name, size_of::<Header>() + capacity)?;
+        unsafe {
+            memory.as_ptr().cast::<Header>().write(Header {
+                write_offset: AtomicU32::new(0),
+                read_offset: AtomicU32::new(0),
+                capacity: capacity_field,
+            });
+        }
+        Ok(Self { memory })
+    }
+
+    /// Attaches to a ring buffer whose mapping handle was received from another process, checking
+    /// that `capacity` (carried alongside the handle) agrees with the capacity the creator wrote
+    /// into the header.
+    pub unsafe fn from_mapping(
+        mapping: crate::windows::shared_memory::Mapping,
+        capacity: usize,
+    ) -> Result<Self, AttachError> {
+        let memory =
+            unsafe { SharedMemory::from_mapping(mapping, size_of::<Header>() + capacity)? };
+        let ring = Self { memory };
+        let header_capacity = ring.header().capacity as usize;
+        if header_capacity != capacity {
+            return Err(AttachError::CapacityMismatch {
+                expected: capacity,
+                actual: header_capacity,
+            });
+        }
+        Ok(ring)
+    }
+
+    /// Wraps a mapping handle that has been duplicated for a different (foreign) process and
+    /// will only ever be carried along by raw handle value, e.g. as the "other side" of a pair
+    /// returned by [`super::new_sender_and_receiver_with_shared_memory`] before it crosses the
+    /// wire. Never call [`Self::push_frame`]/[`Self::pop_frame`] on the result.
+    #[must_use]
+    pub unsafe fn from_foreign_mapping(
+        mapping: crate::windows::shared_memory::Mapping,
+        capacity: usize,
+    ) -> Self {
+        Self {
+            memory: unsafe {
+                SharedMemory::from_foreign_mapping(mapping, size_of::<Header>() + capacity)
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn mapping(&self) -> &crate::windows::shared_memory::Mapping {
+        self.memory.mapping()
+    }
+
+    /// Hands off the underlying mapping handle without closing it, for embedding the raw handle
+    /// value in a hand-rolled [`Message`](super::message::Message) impl.
+    #[expect(clippy::must_use_candidate)]
+    pub unsafe fn leak_mapping_handle(self) -> *mut winapi::ctypes::c_void {
+        unsafe { self.memory.leak_mapping() }
+    }
+
+    pub fn try_clone_mapping_for_process(
+        &self,
+        process: &process::Process,
+    ) -> Result<crate::windows::shared_memory::Mapping, crate::windows::handle::CloneError> {
+        self.memory.try_clone_mapping_for_process(process)
+    }
+
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.header().capacity as usize
+    }
+
+    /// Whether the buffer currently has no unread frames, i.e. whether [`Self::pop_frame`] would
+    /// return `None`.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        let header = self.header();
+        header.read_offset.load(Ordering::Acquire) == header.write_offset.load(Ordering::Acquire)
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.memory.as_ptr().cast::<Header>() }
+    }
+
+    fn data_ptr(&self) -> *mut u8 {
+        unsafe { self.memory.as_ptr().add(size_of::<Header>()) }
+    }
+
+    /// Writes `bytes` as a single frame. Returns [`PushError::WouldOverwriteUnreadData`] if the
+    /// reader hasn't caught up enough to leave room; callers are expected to fall back to another
+    /// transport rather than block.
+    pub fn push_frame(&mut self, bytes: &[u8]) -> Result<(), PushError> {
+        let header = self.header();
+        let capacity = self.capacity();
+        let frame_len = u32::try_from(bytes.len()).map_err(|_| PushError::FrameTooLarge)?;
+        let total_len = size_of::<u32>() + bytes.len();
+
+        let current_write_offset = header.write_offset.load(Ordering::Relaxed) as usize;
+        let read_offset = header.read_offset.load(Ordering::Acquire) as usize;
+
+        let available_until_wrap = capacity - current_write_offset;
+        let wraps = total_len > available_until_wrap;
+        let write_at = if wraps { 0 } else { current_write_offset };
+
+        let free_space = if wraps {
+            // the skipped tail is given up, so only the space before the reader is usable, and
+            // none while unread data still runs past the current write offset
+            if read_offset > current_write_offset {
+                0
+            } else {
+                read_offset
+            }
+        } else if read_offset <= write_at {
+            capacity - write_at + read_offset
+        } else {
+            read_offset - write_at
+        };
+        // leave at least one byte free so write_offset == read_offset is unambiguously "empty"
+        if total_len >= free_space {
+            return Err(PushError::WouldOverwriteUnreadData);
+        }
+
+        unsafe {
+            let data = self.data_ptr();
+            if wraps {
+                // mark the skipped tail with a zero-length sentinel frame for the reader to skip
+                data.add(current_write_offset).cast::<u32>().write_unaligned(0);
+            }
+            data.add(write_at).cast::<u32>().write_unaligned(frame_len);
+            data.add(write_at + size_of::<u32>())
+                .copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+        }
+
+        #[expect(clippy::cast_possible_truncation)]
+        header
+            .write_offset
+            .store(((write_at + total_len) % capacity) as u32, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Reads the next frame written by [`Self::push_frame`], if any is available.
+    #[must_use]
+    pub fn pop_frame(&mut self) -> Option<Vec<u8>> {
+        let header = self.header();
+        let capacity = self.capacity();
+
+        loop {
+            let write_offset = header.write_offset.load(Ordering::Acquire) as usize;
+            let mut read_offset = header.read_offset.load(Ordering::Relaxed) as usize;
+            if read_offset == write_offset {
+                return None;
+            }
+
+            let frame_len = unsafe {
+                self.data_ptr()
+                    .add(read_offset)
+                    .cast::<u32>()
+                    .read_unaligned()
+            };
+            if frame_len == 0 {
+                // sentinel left behind by a writer that wrapped to the start
+                read_offset = 0;
+                header
+                    .read_offset
+                    .store(read_offset as u32, Ordering::Release);
+                continue;
+            }
+
+            let frame_len = frame_len as usize;
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    self.data_ptr().add(read_offset + size_of::<u32>()),
+                    frame_len,
+                )
+                .to_vec()
+            };
+
+            #[expect(clippy::cast_possible_truncation)]
+            header.read_offset.store(
+                ((read_offset + size_of::<u32>() + frame_len) % capacity) as u32,
+                Ordering::Release,
+            );
+
+            return Some(bytes);
+        }
+    }
+
+    /// Like [`Self::pop_frame`], but borrows the frame directly out of the mapping instead of
+    /// copying it into a `Vec`, for callers (e.g. a future bulk memory-snapshot transfer) that can
+    /// consume it in place. The read cursor only advances — making the frame's region available
+    /// for the writer to reuse — once the returned [`FrameView`] is dropped, so the borrow stays
+    /// valid for as long as the caller holds onto it.
+    #[must_use]
+    pub fn pop_frame_view(&mut self) -> Option<FrameView<'_>> {
+        let header = self.header();
+        let capacity = self.capacity();
+
+        loop {
+            let write_offset = header.write_offset.load(Ordering::Acquire) as usize;
+            let mut read_offset = header.read_offset.load(Ordering::Relaxed) as usize;
+            if read_offset == write_offset {
+                return None;
+            }
+
+            let frame_len = unsafe {
+                self.data_ptr()
+                    .add(read_offset)
+                    .cast::<u32>()
+                    .read_unaligned()
+            };
+            if frame_len == 0 {
+                // sentinel left behind by a writer that wrapped to the start
+                read_offset = 0;
+                header
+                    .read_offset
+                    .store(read_offset as u32, Ordering::Release);
+                continue;
+            }
+
+            let frame_len = frame_len as usize;
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    self.data_ptr().add(read_offset + size_of::<u32>()),
+                    frame_len,
+                )
+            };
+
+            return Some(FrameView {
+                ring: self,
+                bytes,
+                next_read_offset: (read_offset + size_of::<u32>() + frame_len) % capacity,
+            });
+        }
+    }
+}
+
+/// A zero-copy view of a frame popped by [`RingBuffer::pop_frame_view`]. See that method for the
+/// read-cursor-advances-on-drop contract.
+pub struct FrameView<'a> {
+    ring: &'a RingBuffer,
+    bytes: &'a [u8],
+    next_read_offset: usize,
+}
+
+impl Deref for FrameView<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl Drop for FrameView<'_> {
+    fn drop(&mut self) {
+        #[expect(clippy::cast_possible_truncation)]
+        self.ring
+            .header()
+            .read_offset
+            .store(self.next_read_offset as u32, Ordering::Release);
+    }
+}
+
+unsafe impl Send for RingBuffer {}
+
+#[derive(Debug, Error)]
+#[error("failed to create ring buffer")]
+pub enum CreateError {
+    SharedMemoryCreate(#[from] crate::windows::shared_memory::CreateError),
+    #[error("ring buffer capacity does not fit in the header's capacity field")]
+    CapacityTooLarge,
+}
+
+#[derive(Debug, Error)]
+#[error("failed to attach to ring buffer")]
+pub enum AttachError {
+    SharedMemoryCreate(#[from] crate::windows::shared_memory::CreateError),
+    #[error("ring buffer capacity {expected} does not match {actual} stored in its header")]
+    CapacityMismatch { expected: usize, actual: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum PushError {
+    #[error("frame is too large to ever fit in the ring buffer")]
+    FrameTooLarge,
+    #[error("ring buffer does not have enough free space for this frame yet")]
+    WouldOverwriteUnreadData,
+}
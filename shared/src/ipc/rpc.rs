@@ -0,0 +1,178 @@
+use super::{
+    codec::Codec,
+    message::{DeserializeError, Message, SerializeError},
+    transport::ReceiveTransport,
+    ReceiveError, Receiver,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Read,
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use thiserror::Error;
+
+/// Correlates an RPC response with the call that produced it. Packs the id of the
+/// requester that issued the call into the high 32 bits and a sequence number local
+/// to that requester into the low 32 bits, so that a single shared response channel
+/// can be used by more than one requester without their sequence numbers colliding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Token(u64);
+
+impl Token {
+    #[must_use]
+    pub fn new(requester_id: u32, sequence: u32) -> Self {
+        Self((u64::from(requester_id) << 32) | u64::from(sequence))
+    }
+
+    #[must_use]
+    pub fn requester_id(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    #[must_use]
+    pub fn sequence(self) -> u32 {
+        self.0 as u32
+    }
+}
+
+/// Issues a monotonically increasing sequence of [`Token`]s on behalf of a single
+/// requester.
+#[derive(Debug)]
+pub struct TokenSource {
+    requester_id: u32,
+    next_sequence: AtomicU32,
+}
+
+impl TokenSource {
+    #[must_use]
+    pub fn new(requester_id: u32) -> Self {
+        Self {
+            requester_id,
+            next_sequence: AtomicU32::new(0),
+        }
+    }
+
+    pub fn next(&self) -> Token {
+        Token::new(
+            self.requester_id,
+            self.next_sequence.fetch_add(1, Ordering::Relaxed),
+        )
+    }
+}
+
+/// Reply to a call made with a [`Token`] obtained from a [`TokenSource`]. The payload
+/// is pre-serialized by the responder, so that `Response` itself doesn't need to be
+/// generic over the response type and a single response channel can carry replies to
+/// calls of differing types.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub token: Token,
+    pub payload: Vec<u8>,
+}
+
+impl Message for Response {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+/// A request type that can be issued through an RPC call, paired with the response type its call
+/// resolves to. Implementing this (a plain serde struct/enum on each side) is all a new conductor
+/// query needs, rather than hand-rolling a dedicated request/response message pair.
+pub trait Request: Serialize {
+    type Response: for<'de> Deserialize<'de>;
+}
+
+/// A channel message type with a variant shaped like `FromConductor::Rpc`, letting
+/// [`super::Sender::send_request`] and [`super::Sender::send_request_with_token`] tag an outgoing
+/// call with a [`Token`] generically.
+pub trait CarriesCall {
+    fn from_call(token: Token, payload: Vec<u8>) -> Self;
+}
+
+/// One frame of a streamed reply to a call identified by `token`. A responder answering over a
+/// dedicated `Sender<StreamFrame>` (handed to it the same way `ArmDirtyTracking::response_sender`
+/// hands over a one-off reply channel) sends zero or more [`Self::Chunk`]s via
+/// [`super::Sender::reply_stream`], followed by exactly one [`Self::End`], so the caller knows
+/// the stream is complete rather than having to guess from the sender being dropped.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum StreamFrame {
+    Chunk { token: Token, payload: Vec<u8> },
+    End { token: Token },
+}
+
+impl Message for StreamFrame {
+    unsafe fn serialize(self) -> Result<Vec<u8>, SerializeError> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    unsafe fn deserialize_from(reader: impl Read) -> Result<Self, DeserializeError> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+/// A request type whose call resolves to a stream of items rather than exactly one response.
+/// Paired with [`super::Sender::reply_stream`] on the responder side and [`ResponseStream`] on
+/// the calling side.
+pub trait StreamingRequest: Serialize {
+    type Item: for<'de> Deserialize<'de>;
+}
+
+/// Yields the `R`-typed items of a single call's streamed reply, read off a dedicated
+/// `Receiver<StreamFrame>` the caller handed to the responder. Stops once the [`StreamFrame::End`]
+/// frame for `token` arrives; frames carrying a different token (e.g. a stale reply left behind by
+/// an earlier call that reused the same receiver) are skipped rather than treated as an error.
+pub struct ResponseStream<'r, R, T: ReceiveTransport, C: Codec<StreamFrame>> {
+    receiver: &'r mut Receiver<StreamFrame, T, C>,
+    token: Token,
+    done: bool,
+    _phantom_data: PhantomData<R>,
+}
+
+impl<'r, R, T, C> ResponseStream<'r, R, T, C>
+where
+    R: for<'de> Deserialize<'de>,
+    T: ReceiveTransport,
+    C: Codec<StreamFrame>,
+{
+    #[must_use]
+    pub fn new(receiver: &'r mut Receiver<StreamFrame, T, C>, token: Token) -> Self {
+        Self {
+            receiver,
+            token,
+            done: false,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns the next item in the stream, or `None` once the call's `End` frame has arrived.
+    pub async fn next(&mut self) -> Result<Option<R>, ResponseStreamError> {
+        if self.done {
+            return Ok(None);
+        }
+        loop {
+            match self.receiver.receive().await? {
+                StreamFrame::Chunk { token, payload } if token == self.token => {
+                    return Ok(Some(bincode::deserialize(&payload)?));
+                }
+                StreamFrame::End { token } if token == self.token => {
+                    self.done = true;
+                    return Ok(None);
+                }
+                StreamFrame::Chunk { .. } | StreamFrame::End { .. } => continue,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to read next item from response stream")]
+pub enum ResponseStreamError {
+    Receive(#[from] ReceiveError),
+    Deserialize(#[from] bincode::Error),
+}
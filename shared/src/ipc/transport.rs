@@ -0,0 +1,59 @@
+pub mod pipe;
+pub mod ring;
+pub mod tcp;
+
+use crate::windows::{event, handle};
+use std::{fmt::Debug, io};
+use thiserror::Error;
+
+/// The write side of a message channel's underlying transport. [`super::Sender`] is generic over
+/// this so that the same framing/backpressure contract can be satisfied either by a local pipe
+/// (see [`pipe`]) or by an encrypted link to a remote host (see [`tcp`]).
+pub trait SendTransport: Debug {
+    /// Writes one complete frame of `bytes`, making it visible to the peer's
+    /// [`ReceiveTransport::drain_available`]. Applies this transport's own backpressure (awaiting
+    /// rather than dropping frames) if the peer hasn't kept up.
+    async fn send_frame(&mut self, bytes: &[u8]) -> Result<(), TransportError>;
+
+    /// Blocks until the peer has consumed every frame sent so far.
+    async fn flush_pending(&self) -> Result<(), TransportError>;
+
+    fn try_clone(&self) -> Result<Self, TransportError>
+    where
+        Self: Sized;
+}
+
+/// The read side of a message channel's underlying transport.
+pub trait ReceiveTransport: Debug {
+    /// Reads every frame currently available, in order, without blocking if none are.
+    fn drain_available(&mut self) -> Result<Vec<Vec<u8>>, TransportError>;
+
+    /// Blocks (asynchronously) until at least one frame is available, then reads and returns it.
+    async fn wait_readable(&mut self) -> Result<Vec<u8>, TransportError>;
+}
+
+#[derive(Debug, Error)]
+#[error("transport failed")]
+pub enum TransportError {
+    Io(#[from] io::Error),
+    SemaphoreWait(#[from] event::WaitError),
+    SemaphoreTryWait(#[from] event::TryWaitError),
+    SemaphoreRelease(#[from] event::ReleaseError),
+    EventSet(#[from] event::SetError),
+    EventReset(#[from] event::ResetError),
+    RingBufferPush(#[from] crate::ipc::ring::PushError),
+    RingBufferAttach(#[from] crate::ipc::ring::AttachError),
+    HandleClone(#[from] handle::CloneError),
+    #[error("message was sent over the ring buffer transport, but this channel has none")]
+    NoRingBuffer,
+    #[error("ring buffer transport tag was set, but no frame was available to read")]
+    RingBufferEmpty,
+    #[error("a ring buffer transport cannot be cloned, since a ring buffer has exactly one producer")]
+    RingTransportNotCloneable,
+    HandleWait(#[from] handle::WaitError),
+    Handshake(#[from] tcp::HandshakeError),
+    #[error("peer sent a frame with an invalid authentication tag")]
+    TagMismatch,
+    #[error("peer closed the connection")]
+    ConnectionClosed,
+}
@@ -0,0 +1,364 @@
+use super::{ReceiveTransport, SendTransport, TransportError};
+use crate::{
+    ipc::{ring::RingBuffer, NewSenderAndReceiverError},
+    windows::{
+        event::Semaphore,
+        pipe,
+        process::Process,
+        shared_memory::Mapping,
+    },
+};
+use std::io::{Read, Write};
+
+/// Payloads at or above this size are sent over a channel's ring buffer (if it has one) rather
+/// than through its pipe; one the ring buffer has no room for goes through the pipe instead.
+pub(crate) const SHARED_MEMORY_THRESHOLD: usize = 64 * 1024;
+
+/// Default data capacity of a ring buffer created by
+/// [`super::super::new_sender_and_receiver_with_shared_memory`].
+pub const DEFAULT_RING_BUFFER_CAPACITY: usize = 256 * 1024 * 1024;
+
+/// Maximum number of frames a [`PipeSendTransport`] may have written to the pipe without the
+/// [`PipeReceiveTransport`] having read them yet. Bounds how much
+/// [`SendTransport::send_frame`] can run ahead of the receiver before it starts applying
+/// backpressure.
+pub(crate) const PIPELINE_DEPTH: u32 = 64;
+
+/// The local-machine transport: a named/anonymous pipe for framed control traffic, plus two
+/// semaphores for flow control and an optional shared-memory ring buffer for large payloads. This
+/// is the default transport for every channel created by [`super::super::new_sender_and_receiver`].
+#[derive(Debug)]
+pub struct PipeSendTransport {
+    pub(crate) pipe: pipe::Writer,
+    /// Released once per frame fully written to the pipe; the receiver waits on this to know a
+    /// frame is ready.
+    pub(crate) frame_semaphore: Semaphore,
+    /// Starts at [`PIPELINE_DEPTH`] permits. Acquired before writing each frame (providing
+    /// backpressure once [`PIPELINE_DEPTH`] frames are outstanding) and released by the receiver
+    /// once it has read a frame off the pipe.
+    pub(crate) credit_semaphore: Semaphore,
+    pub(crate) ring: Option<RingBuffer>,
+}
+
+impl SendTransport for PipeSendTransport {
+    async fn send_frame(&mut self, bytes: &[u8]) -> Result<(), TransportError> {
+        self.credit_semaphore.wait().await?;
+
+        // small control messages stay on the pipe; large payloads (e.g. memory snapshots) go
+        // through the ring buffer, if this channel has one and it has room, and otherwise fall
+        // back to the pipe
+        let pushed_to_ring = match &mut self.ring {
+            Some(ring) if bytes.len() >= SHARED_MEMORY_THRESHOLD => ring.push_frame(bytes).is_ok(),
+            _ => false,
+        };
+        if pushed_to_ring {
+            self.pipe.write_all(&[1])?;
+            self.pipe.write_all(&0u32.to_le_bytes())?;
+        } else {
+            self.pipe.write_all(&[0])?;
+            self.pipe
+                .write_all(&u32::try_from(bytes.len()).unwrap().to_le_bytes())?;
+            self.pipe.write_all(bytes)?;
+        }
+        self.pipe.flush()?;
+        self.frame_semaphore.release(1)?;
+        Ok(())
+    }
+
+    /// Draining every permit proves no frame we have sent is still outstanding (the receiver
+    /// releases one back per frame it reads), after which they are simply handed back.
+    async fn flush_pending(&self) -> Result<(), TransportError> {
+        for _ in 0..PIPELINE_DEPTH {
+            self.credit_semaphore.wait().await?;
+        }
+        self.credit_semaphore.release(PIPELINE_DEPTH)?;
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Self, TransportError> {
+        Ok(Self {
+            pipe: self.pipe.try_clone()?,
+            frame_semaphore: self.frame_semaphore.try_clone()?,
+            credit_semaphore: self.credit_semaphore.try_clone()?,
+            ring: None,
+        })
+    }
+}
+
+impl PipeSendTransport {
+    /// Duplicates this transport's handles for `process`, e.g. to hand a fresh copy of a sender
+    /// to a newly-attached process during conductor takeover (see
+    /// [`crate::ipc::message::FromConductor::Takeover`]).
+    pub fn try_clone_for_process(&self, process: &Process) -> Result<Self, TransportError> {
+        Ok(Self {
+            pipe: self.pipe.try_clone_for_process(process)?,
+            frame_semaphore: self.frame_semaphore.try_clone_for_process(process)?,
+            credit_semaphore: self.credit_semaphore.try_clone_for_process(process)?,
+            ring: self
+                .ring
+                .as_ref()
+                .map(|ring| -> Result<_, TransportError> {
+                    let mapping = ring.try_clone_mapping_for_process(process)?;
+                    Ok(unsafe { RingBuffer::from_foreign_mapping(mapping, ring.capacity()) })
+                })
+                .transpose()?,
+        })
+    }
+
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub fn serialize_to_bytes(&self) -> [u8; 16] {
+        let bytes = unsafe {
+            [
+                self.pipe.raw_handle() as u32,
+                self.frame_semaphore.raw_handle() as u32,
+                self.credit_semaphore.raw_handle() as u32,
+                self.ring
+                    .as_ref()
+                    .map_or(0, |ring| ring.mapping().raw_handle() as u32),
+            ]
+        }
+        .iter()
+        .flat_map(|h| h.to_ne_bytes())
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+        bytes
+    }
+
+    /// # Panics
+    /// Panics if the ring buffer mapping handle encoded in `bytes` cannot be mapped into the
+    /// current process.
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub unsafe fn deserialize_from_bytes(bytes: [u8; 16]) -> Self {
+        unsafe {
+            let mut handles = bytes
+                .chunks(4)
+                .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()) as _);
+
+            let pipe = pipe::Writer::from_raw_handle(handles.next().unwrap());
+            let frame_semaphore = Semaphore::from_raw_handle(handles.next().unwrap());
+            let credit_semaphore = Semaphore::from_raw_handle(handles.next().unwrap());
+            let ring_mapping_handle = handles.next().unwrap();
+            let ring = (!ring_mapping_handle.is_null()).then(|| {
+                RingBuffer::from_mapping(
+                    Mapping::from_raw_handle(ring_mapping_handle),
+                    DEFAULT_RING_BUFFER_CAPACITY,
+                )
+                .unwrap()
+            });
+
+            Self {
+                pipe,
+                frame_semaphore,
+                credit_semaphore,
+                ring,
+            }
+        }
+    }
+
+    /// Leaks every handle backing this transport (the pipe, both semaphores, and the ring buffer
+    /// mapping if any), for a transport whose bytes have already been captured via
+    /// [`Self::serialize_to_bytes`] and embedded in a message handed to another process. Without
+    /// this, dropping `self` afterwards would close handles the receiving process still needs.
+    pub unsafe fn leak_handles(self) {
+        unsafe {
+            self.pipe.leak_handle();
+            self.frame_semaphore.leak_handle();
+            self.credit_semaphore.leak_handle();
+            if let Some(ring) = self.ring {
+                ring.leak_mapping_handle();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PipeReceiveTransport {
+    pub(crate) pipe: pipe::Reader,
+    pub(crate) frame_semaphore: Semaphore,
+    pub(crate) credit_semaphore: Semaphore,
+    pub(crate) ring: Option<RingBuffer>,
+}
+
+impl PipeReceiveTransport {
+    fn read_frame(&mut self) -> Result<Vec<u8>, TransportError> {
+        let mut transport_tag = [0; 1];
+        self.pipe.read_exact(&mut transport_tag)?;
+        let mut length_bytes = [0; 4];
+        self.pipe.read_exact(&mut length_bytes)?;
+        let length = u32::from_le_bytes(length_bytes) as usize;
+
+        if transport_tag[0] == 1 {
+            self.ring
+                .as_mut()
+                .ok_or(TransportError::NoRingBuffer)?
+                .pop_frame()
+                .ok_or(TransportError::RingBufferEmpty)
+        } else {
+            let mut bytes = vec![0; length];
+            self.pipe.read_exact(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+impl ReceiveTransport for PipeReceiveTransport {
+    fn drain_available(&mut self) -> Result<Vec<Vec<u8>>, TransportError> {
+        let mut frames = Vec::new();
+        while self.frame_semaphore.try_wait()? {
+            frames.push(self.read_frame()?);
+            self.credit_semaphore.release(1)?;
+        }
+        Ok(frames)
+    }
+
+    async fn wait_readable(&mut self) -> Result<Vec<u8>, TransportError> {
+        self.frame_semaphore.wait().await?;
+        let frame = self.read_frame()?;
+        self.credit_semaphore.release(1)?;
+        Ok(frame)
+    }
+}
+
+impl PipeReceiveTransport {
+    /// Duplicates this transport's handles for `process`, e.g. to hand a fresh copy of a receiver
+    /// to a newly-attached process during conductor takeover (see
+    /// [`crate::ipc::message::FromConductor::Takeover`]).
+    pub fn try_clone_for_process(&self, process: &Process) -> Result<Self, TransportError> {
+        Ok(Self {
+            pipe: self.pipe.try_clone_for_process(process)?,
+            frame_semaphore: self.frame_semaphore.try_clone_for_process(process)?,
+            credit_semaphore: self.credit_semaphore.try_clone_for_process(process)?,
+            ring: self
+                .ring
+                .as_ref()
+                .map(|ring| -> Result<_, TransportError> {
+                    let mapping = ring.try_clone_mapping_for_process(process)?;
+                    Ok(unsafe { RingBuffer::from_foreign_mapping(mapping, ring.capacity()) })
+                })
+                .transpose()?,
+        })
+    }
+
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub fn serialize_to_bytes(&self) -> [u8; 16] {
+        let bytes = unsafe {
+            [
+                self.pipe.raw_handle() as u32,
+                self.frame_semaphore.raw_handle() as u32,
+                self.credit_semaphore.raw_handle() as u32,
+                self.ring
+                    .as_ref()
+                    .map_or(0, |ring| ring.mapping().raw_handle() as u32),
+            ]
+        }
+        .iter()
+        .flat_map(|h| h.to_ne_bytes())
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+        bytes
+    }
+
+    /// # Panics
+    /// Panics if the ring buffer mapping handle encoded in `bytes` cannot be mapped into the
+    /// current process.
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub unsafe fn deserialize_from_bytes(bytes: [u8; 16]) -> Self {
+        unsafe {
+            let mut handles = bytes
+                .chunks(4)
+                .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()) as _);
+
+            let pipe = pipe::Reader::from_raw_handle(handles.next().unwrap());
+            let frame_semaphore = Semaphore::from_raw_handle(handles.next().unwrap());
+            let credit_semaphore = Semaphore::from_raw_handle(handles.next().unwrap());
+            let ring_mapping_handle = handles.next().unwrap();
+            let ring = (!ring_mapping_handle.is_null()).then(|| {
+                RingBuffer::from_mapping(
+                    Mapping::from_raw_handle(ring_mapping_handle),
+                    DEFAULT_RING_BUFFER_CAPACITY,
+                )
+                .unwrap()
+            });
+
+            Self {
+                pipe,
+                frame_semaphore,
+                credit_semaphore,
+                ring,
+            }
+        }
+    }
+
+    /// Leaks every handle backing this transport (the pipe, both semaphores, and the ring buffer
+    /// mapping if any), for a transport whose bytes have already been captured via
+    /// [`Self::serialize_to_bytes`] and embedded in a message handed to another process. Without
+    /// this, dropping `self` afterwards would close handles the receiving process still needs.
+    pub unsafe fn leak_handles(self) {
+        unsafe {
+            self.pipe.leak_handle();
+            self.frame_semaphore.leak_handle();
+            self.credit_semaphore.leak_handle();
+            if let Some(ring) = self.ring {
+                ring.leak_mapping_handle();
+            }
+        }
+    }
+}
+
+/// Creates the pipe and pair of semaphores backing [`super::super::new_sender_and_receiver`],
+/// duplicating each handle into the respective process.
+pub(crate) fn new_pair(
+    sender_process: &Process,
+    receiver_process: &Process,
+) -> Result<(PipeSendTransport, PipeReceiveTransport), NewSenderAndReceiverError> {
+    let (pipe_writer, pipe_reader) = pipe::new()?;
+    let frame_semaphore = Semaphore::new(0, PIPELINE_DEPTH)?;
+    let credit_semaphore = Semaphore::new(PIPELINE_DEPTH, PIPELINE_DEPTH)?;
+    Ok((
+        PipeSendTransport {
+            pipe: pipe_writer.try_clone_for_process(sender_process)?,
+            frame_semaphore: frame_semaphore.try_clone_for_process(sender_process)?,
+            credit_semaphore: credit_semaphore.try_clone_for_process(sender_process)?,
+            ring: None,
+        },
+        PipeReceiveTransport {
+            pipe: pipe_reader.try_clone_for_process(receiver_process)?,
+            frame_semaphore: frame_semaphore.try_clone_for_process(receiver_process)?,
+            credit_semaphore: credit_semaphore.try_clone_for_process(receiver_process)?,
+            ring: None,
+        },
+    ))
+}
+
+/// Creates a fresh shared-memory ring buffer and attaches a handle to it to each of `sender`'s
+/// and `receiver`'s transports, for [`super::super::new_sender_and_receiver_with_shared_memory`].
+pub(crate) fn attach_ring_buffer(
+    sender: &mut PipeSendTransport,
+    receiver: &mut PipeReceiveTransport,
+    sender_process: &Process,
+    receiver_process: &Process,
+    ring_buffer_name: &str,
+    ring_buffer_capacity: usize,
+) -> Result<(), NewSenderAndReceiverError> {
+    let ring = RingBuffer::create(ring_buffer_name, ring_buffer_capacity)?;
+    let current_process = Process::get_current();
+
+    let ring_for = |process: &Process| -> Result<RingBuffer, NewSenderAndReceiverError> {
+        let mapping = ring.try_clone_mapping_for_process(process)?;
+        let is_current_process = unsafe { process.raw_handle() == current_process.raw_handle() };
+        Ok(if is_current_process {
+            unsafe { RingBuffer::from_mapping(mapping, ring_buffer_capacity) }?
+        } else {
+            unsafe { RingBuffer::from_foreign_mapping(mapping, ring_buffer_capacity) }
+        })
+    };
+    sender.ring = Some(ring_for(sender_process)?);
+    receiver.ring = Some(ring_for(receiver_process)?);
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use super::{ReceiveTransport, SendTransport, TransportError};
+use crate::{
+    ipc::{ring::RingBuffer, NewSenderAndReceiverError},
+    windows::{event::ManualResetEvent, process::Process, shared_memory::Mapping},
+};
+
+/// A single-producer/single-consumer alternative to [`super::pipe`] for channels that exist
+/// purely to move a high volume of data between two processes on the same machine: every frame
+/// goes straight through a [`RingBuffer`] instead of a pipe round trip, and the backing
+/// `ManualResetEvent` is set after every push and reset by the reader before each drain. Unlike
+/// [`super::pipe::PipeSendTransport`], a [`RingSendTransport`] cannot be cloned — the ring has
+/// exactly one producer.
+#[derive(Debug)]
+pub struct RingSendTransport {
+    ring: RingBuffer,
+    readable: ManualResetEvent,
+}
+
+impl SendTransport for RingSendTransport {
+    async fn send_frame(&mut self, bytes: &[u8]) -> Result<(), TransportError> {
+        self.ring.push_frame(bytes)?;
+        self.readable.set()?;
+        Ok(())
+    }
+
+    /// A ring buffer push is visible to the reader as soon as it returns, so there is nothing
+    /// left to flush.
+    async fn flush_pending(&self) -> Result<(), TransportError> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Self, TransportError> {
+        Err(TransportError::RingTransportNotCloneable)
+    }
+}
+
+impl RingSendTransport {
+    /// Duplicates this transport's handles for `process`, e.g. to hand a fresh copy to a
+    /// newly-attached process during conductor takeover.
+    pub fn try_clone_for_process(&self, process: &Process) -> Result<Self, TransportError> {
+        Ok(Self {
+            ring: unsafe {
+                RingBuffer::from_foreign_mapping(
+                    self.ring.try_clone_mapping_for_process(process)?,
+                    self.ring.capacity(),
+                )
+            },
+            readable: self.readable.try_clone_for_process(process)?,
+        })
+    }
+
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub fn serialize_to_bytes(&self) -> [u8; 12] {
+        serialize_handles(&self.ring, &self.readable)
+    }
+
+    /// # Panics
+    /// Panics if the ring buffer mapping handle encoded in `bytes` cannot be mapped into the
+    /// current process.
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub unsafe fn deserialize_from_bytes(bytes: [u8; 12]) -> Self {
+        unsafe {
+            let (mapping_handle, event_handle, capacity) = deserialize_handles(bytes);
+            Self {
+                ring: RingBuffer::from_mapping(Mapping::from_raw_handle(mapping_handle), capacity)
+                    .unwrap(),
+                readable: ManualResetEvent::from_raw_handle(event_handle),
+            }
+        }
+    }
+
+    /// Leaks the mapping and event handles backing this transport, for a transport whose bytes
+    /// have already been captured via [`Self::serialize_to_bytes`] and embedded in a message
+    /// handed to another process.
+    pub unsafe fn leak_handles(self) {
+        unsafe {
+            self.ring.leak_mapping_handle();
+            self.readable.leak_handle();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RingReceiveTransport {
+    ring: RingBuffer,
+    readable: ManualResetEvent,
+}
+
+impl ReceiveTransport for RingReceiveTransport {
+    fn drain_available(&mut self) -> Result<Vec<Vec<u8>>, TransportError> {
+        // Reset before draining (not after), so a push racing with this drain is guaranteed to
+        // leave the event set for the next wait rather than being silently cleared.
+        self.readable.reset()?;
+        let mut frames = Vec::new();
+        while let Some(frame) = self.ring.pop_frame() {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    async fn wait_readable(&mut self) -> Result<Vec<u8>, TransportError> {
+        loop {
+            self.readable.reset()?;
+            if let Some(frame) = self.ring.pop_frame() {
+                return Ok(frame);
+            }
+            self.readable.wait().await?;
+        }
+    }
+}
+
+impl RingReceiveTransport {
+    /// Duplicates this transport's handles for `process`, e.g. to hand a fresh copy to a
+    /// newly-attached process during conductor takeover.
+    pub fn try_clone_for_process(&self, process: &Process) -> Result<Self, TransportError> {
+        Ok(Self {
+            ring: unsafe {
+                RingBuffer::from_foreign_mapping(
+                    self.ring.try_clone_mapping_for_process(process)?,
+                    self.ring.capacity(),
+                )
+            },
+            readable: self.readable.try_clone_for_process(process)?,
+        })
+    }
+
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub fn serialize_to_bytes(&self) -> [u8; 12] {
+        serialize_handles(&self.ring, &self.readable)
+    }
+
+    /// # Panics
+    /// Panics if the ring buffer mapping handle encoded in `bytes` cannot be mapped into the
+    /// current process.
+    #[must_use]
+    #[expect(clippy::missing_panics_doc)]
+    pub unsafe fn deserialize_from_bytes(bytes: [u8; 12]) -> Self {
+        unsafe {
+            let (mapping_handle, event_handle, capacity) = deserialize_handles(bytes);
+            Self {
+                ring: RingBuffer::from_mapping(Mapping::from_raw_handle(mapping_handle), capacity)
+                    .unwrap(),
+                readable: ManualResetEvent::from_raw_handle(event_handle),
+            }
+        }
+    }
+
+    /// Leaks the mapping and event handles backing this transport, for a transport whose bytes
+    /// have already been captured via [`Self::serialize_to_bytes`] and embedded in a message
+    /// handed to another process.
+    pub unsafe fn leak_handles(self) {
+        unsafe {
+            self.ring.leak_mapping_handle();
+            self.readable.leak_handle();
+        }
+    }
+}
+
+#[expect(clippy::missing_panics_doc)]
+fn serialize_handles(ring: &RingBuffer, readable: &ManualResetEvent) -> [u8; 12] {
+    let bytes = unsafe {
+        [ring.mapping().raw_handle() as u32, readable.raw_handle() as u32]
+    }
+    .iter()
+    .flat_map(|h| h.to_ne_bytes())
+    .chain(u32::try_from(ring.capacity()).unwrap().to_ne_bytes())
+    .collect::<Vec<_>>()
+    .try_into()
+    .unwrap();
+    bytes
+}
+
+unsafe fn deserialize_handles(
+    bytes: [u8; 12],
+) -> (windows::Win32::Foundation::HANDLE, windows::Win32::Foundation::HANDLE, usize) {
+    let mut handles = bytes
+        .chunks(4)
+        .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()));
+    let mapping_handle = handles.next().unwrap() as _;
+    let event_handle = handles.next().unwrap() as _;
+    let capacity = handles.next().unwrap() as usize;
+    (mapping_handle, event_handle, capacity)
+}
+
+/// Creates a fresh named ring buffer and `ManualResetEvent`, duplicating each handle into the
+/// respective process, for a channel that wants the ring as its sole transport rather than as an
+/// augmentation to a pipe (see [`super::pipe::attach_ring_buffer`]).
+pub(crate) fn new_pair(
+    sender_process: &Process,
+    receiver_process: &Process,
+    name: &str,
+    capacity: usize,
+) -> Result<(RingSendTransport, RingReceiveTransport), NewSenderAndReceiverError> {
+    let ring = RingBuffer::create(name, capacity)?;
+    let readable = ManualResetEvent::new()?;
+    let current_process = Process::get_current();
+
+    let ring_for = |process: &Process| -> Result<RingBuffer, NewSenderAndReceiverError> {
+        let mapping = ring.try_clone_mapping_for_process(process)?;
+        let is_current_process = unsafe { process.raw_handle() == current_process.raw_handle() };
+        Ok(if is_current_process {
+            unsafe { RingBuffer::from_mapping(mapping, capacity) }?
+        } else {
+            unsafe { RingBuffer::from_foreign_mapping(mapping, capacity) }
+        })
+    };
+
+    Ok((
+        RingSendTransport {
+            ring: ring_for(sender_process)?,
+            readable: readable.try_clone_for_process(sender_process)?,
+        },
+        RingReceiveTransport {
+            ring: ring_for(receiver_process)?,
+            readable: readable.try_clone_for_process(receiver_process)?,
+        },
+    ))
+}
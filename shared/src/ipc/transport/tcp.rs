@@ -0,0 +1,224 @@
+use super::{ReceiveTransport, SendTransport, TransportError};
+use crate::windows::handle::Handle;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    os::windows::io::AsRawSocket,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use thiserror::Error;
+use winapi::um::winsock2::{WSACreateEvent, WSAEventSelect, FD_READ};
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+
+const NONCE_LEN: usize = 12;
+
+/// One end of an ephemeral-X25519-then-ChaCha20-Poly1305-encrypted [`TcpStream`]. The two
+/// directions of the connection are keyed identically (the handshake derives a single shared
+/// key) but authenticate with disjoint nonce spaces, distinguished by `direction`, so that the
+/// initiator's and responder's outgoing frames never reuse a (key, nonce) pair.
+fn nonce_bytes(direction: u8, counter: u64) -> [u8; NONCE_LEN] {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[0] = direction;
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+/// Tunnels a message channel's frames over a TCP socket, authenticated-encrypting each one with
+/// ChaCha20-Poly1305. Used in place of [`super::pipe`] when the conductor and target process
+/// don't share a machine, since the pipe transport's raw handle sharing only works within one.
+/// Construct a pair with [`handshake`].
+#[derive(Debug)]
+pub struct TcpSendTransport {
+    stream: TcpStream,
+    cipher: ChaCha20Poly1305,
+    direction: u8,
+    next_nonce: Arc<AtomicU64>,
+}
+
+impl SendTransport for TcpSendTransport {
+    async fn send_frame(&mut self, bytes: &[u8]) -> Result<(), TransportError> {
+        let nonce = nonce_bytes(self.direction, self.next_nonce.fetch_add(1, Ordering::SeqCst));
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), bytes)
+            .expect("encrypting a frame should never fail");
+        self.stream
+            .write_all(&u32::try_from(ciphertext.len()).unwrap().to_le_bytes())?;
+        self.stream.write_all(&ciphertext)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// A TCP socket already blocks the caller until the kernel has accepted every byte written
+    /// above, so there is nothing further to wait for here.
+    async fn flush_pending(&self) -> Result<(), TransportError> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Self, TransportError> {
+        Ok(Self {
+            stream: self.stream.try_clone()?,
+            cipher: self.cipher.clone(),
+            direction: self.direction,
+            next_nonce: Arc::clone(&self.next_nonce),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TcpReceiveTransport {
+    stream: TcpStream,
+    cipher: ChaCha20Poly1305,
+    direction: u8,
+    next_nonce: u64,
+    /// A Win32 event tied to the socket's readability via `WSAEventSelect`, so this transport can
+    /// be awaited the same way as every other waitable object in this crate (see
+    /// [`Handle::wait`]) instead of dedicating a thread to a blocking read.
+    readable_event: Handle,
+    /// Bytes read off the socket but not yet enough to complete the frame currently being
+    /// reassembled.
+    pending: Vec<u8>,
+}
+
+impl TcpReceiveTransport {
+    /// Reads whatever is currently available on the (non-blocking) socket into `pending`,
+    /// without blocking if nothing is.
+    fn fill_pending(&mut self) -> Result<(), TransportError> {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(TransportError::ConnectionClosed),
+                Ok(read) => self.pending.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Removes and decrypts one frame from the front of `pending`, if it already holds a
+    /// complete one.
+    fn take_pending_frame(&mut self) -> Result<Option<Vec<u8>>, TransportError> {
+        if self.pending.len() < 4 {
+            return Ok(None);
+        }
+        let length = u32::from_le_bytes(self.pending[..4].try_into().unwrap()) as usize;
+        if self.pending.len() < 4 + length {
+            return Ok(None);
+        }
+        let ciphertext = self.pending[4..4 + length].to_vec();
+        self.pending.drain(..4 + length);
+
+        let nonce = nonce_bytes(self.direction, self.next_nonce);
+        self.next_nonce += 1;
+        self.cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map(Some)
+            .map_err(|_| {
+                // The stream's nonce sequence can no longer be trusted to be in sync with the
+                // peer's once a tag fails to verify, so the connection is unusable from here on.
+                let _ = self.stream.shutdown(Shutdown::Both);
+                TransportError::TagMismatch
+            })
+    }
+}
+
+impl ReceiveTransport for TcpReceiveTransport {
+    fn drain_available(&mut self) -> Result<Vec<Vec<u8>>, TransportError> {
+        self.fill_pending()?;
+        let mut frames = Vec::new();
+        while let Some(frame) = self.take_pending_frame()? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    async fn wait_readable(&mut self) -> Result<Vec<u8>, TransportError> {
+        loop {
+            if let Some(frame) = self.take_pending_frame()? {
+                return Ok(frame);
+            }
+            self.readable_event.wait().await?;
+            self.fill_pending()?;
+        }
+    }
+}
+
+/// Performs an ephemeral X25519 key exchange over `stream` (plaintext public keys, 32 bytes each)
+/// and derives a ChaCha20-Poly1305 key from the resulting shared secret, then wraps `stream` into
+/// a [`TcpSendTransport`]/[`TcpReceiveTransport`] pair framing and authenticating every message
+/// sent over it. `is_initiator` must be true on exactly one side of the connection (e.g. the side
+/// that called [`TcpStream::connect`]); it only selects which nonce space each direction uses; it
+/// is not sent over the wire.
+///
+/// Neither public key is authenticated, so this only protects against a passive eavesdropper on
+/// the path between the two processes, not an active attacker able to intercept and re-originate
+/// the TCP connection (a classic Diffie-Hellman MITM: substitute both public keys and relay). This
+/// transport is meant for a conductor and target process that trust the network between them
+/// (e.g. a private link or VPN) the same way [`super::pipe`] trusts the local machine - it is not
+/// a substitute for running over an already-authenticated channel if the network isn't trusted.
+pub fn handshake(
+    stream: TcpStream,
+    is_initiator: bool,
+) -> Result<(TcpSendTransport, TcpReceiveTransport), HandshakeError> {
+    stream.set_nodelay(true)?;
+
+    let mut handshake_stream = stream.try_clone()?;
+    let secret = EphemeralSecret::random_from_rng(OsRng);
+    let public_key = X25519PublicKey::from(&secret);
+
+    let mut peer_public_key_bytes = [0; 32];
+    if is_initiator {
+        handshake_stream.write_all(public_key.as_bytes())?;
+        handshake_stream.read_exact(&mut peer_public_key_bytes)?;
+    } else {
+        handshake_stream.read_exact(&mut peer_public_key_bytes)?;
+        handshake_stream.write_all(public_key.as_bytes())?;
+    }
+
+    let shared_secret = secret.diffie_hellman(&X25519PublicKey::from(peer_public_key_bytes));
+    let key = Sha256::digest(shared_secret.as_bytes());
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+
+    let readable_event = unsafe {
+        let event_handle = WSACreateEvent();
+        if event_handle.is_null() {
+            return Err(io::Error::last_os_error().into());
+        }
+        #[expect(clippy::cast_possible_wrap)]
+        if WSAEventSelect(stream.as_raw_socket() as usize, event_handle, FD_READ) != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Handle::from_raw(event_handle.cast())
+    };
+
+    let send_direction = u8::from(!is_initiator);
+    let receive_direction = u8::from(is_initiator);
+    Ok((
+        TcpSendTransport {
+            stream: stream.try_clone()?,
+            cipher: cipher.clone(),
+            direction: send_direction,
+            next_nonce: Arc::new(AtomicU64::new(0)),
+        },
+        TcpReceiveTransport {
+            stream,
+            cipher,
+            direction: receive_direction,
+            next_nonce: 0,
+            readable_event,
+            pending: Vec::new(),
+        },
+    ))
+}
+
+#[derive(Debug, Error)]
+#[error("failed to establish an encrypted transport")]
+pub struct HandshakeError(#[from] io::Error);
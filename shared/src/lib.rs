@@ -3,7 +3,11 @@
 
 pub mod communication;
 pub mod event;
+pub mod framing;
 pub mod handle;
+pub mod input;
+pub mod ipc;
 pub mod pipe;
 pub mod process;
 pub mod thread;
+pub mod windows;
@@ -0,0 +1,10 @@
+pub mod event;
+pub mod handle;
+pub mod module;
+pub mod pipe;
+pub mod process;
+mod reactor;
+pub mod shared_memory;
+pub mod system;
+pub mod thread;
+pub mod timer;
@@ -1,42 +1,18 @@
 use crate::windows::handle::{self, handle_wrapper};
 use std::io;
 use thiserror::Error;
-use winapi::{
-    shared::{
-        minwindef::{FALSE, TRUE},
-        ntdef::NULL,
-        winerror::WAIT_TIMEOUT,
-    },
-    um::{
-        minwinbase::SECURITY_ATTRIBUTES,
-        synchapi::{CreateEventA, ResetEvent, SetEvent, WaitForSingleObject},
-        winbase::{WAIT_FAILED, WAIT_OBJECT_0},
-    },
+use windows::Win32::{
+    Foundation::{WAIT_FAILED, WAIT_OBJECT_0, WAIT_TIMEOUT},
+    System::Threading::{CreateEventA, CreateSemaphoreA, ReleaseSemaphore, ResetEvent, SetEvent, WaitForSingleObject},
 };
 
 handle_wrapper!(ManualResetEvent);
 
 impl ManualResetEvent {
     pub fn new() -> Result<Self, NewError> {
-        let security_attributes = SECURITY_ATTRIBUTES {
-            #[expect(clippy::cast_possible_truncation)]
-            nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
-            lpSecurityDescriptor: NULL,
-            bInheritHandle: FALSE,
-        };
-
-        unsafe {
-            let handle = CreateEventA(
-                std::ptr::addr_of!(security_attributes).cast_mut(),
-                TRUE,
-                FALSE,
-                NULL.cast(),
-            );
-            if handle == NULL {
-                return Err(io::Error::last_os_error().into());
-            }
-            Ok(Self::from_raw_handle(handle))
-        }
+        let handle = unsafe { CreateEventA(None, true, false, None) }
+            .map_err(|_| io::Error::last_os_error())?;
+        Ok(unsafe { Self::from_raw_handle(handle) })
     }
 
     pub fn get(&self) -> Result<bool, GetError> {
@@ -57,18 +33,14 @@ impl ManualResetEvent {
 
     pub fn set(&mut self) -> Result<(), SetError> {
         unsafe {
-            if SetEvent(self.handle.as_raw()) == 0 {
-                return Err(io::Error::last_os_error().into());
-            }
+            SetEvent(self.handle.as_raw()).map_err(|_| io::Error::last_os_error())?;
             Ok(())
         }
     }
 
     pub fn reset(&mut self) -> Result<(), ResetError> {
         unsafe {
-            if ResetEvent(self.handle.as_raw()) == 0 {
-                return Err(io::Error::last_os_error().into());
-            }
+            ResetEvent(self.handle.as_raw()).map_err(|_| io::Error::last_os_error())?;
             Ok(())
         }
     }
@@ -99,3 +71,113 @@ pub struct SetError(#[from] io::Error);
 #[derive(Debug, Error)]
 #[error("failed to reset event to non-signaled state")]
 pub struct ResetError(#[from] io::Error);
+
+handle_wrapper!(AutoResetEvent);
+
+impl AutoResetEvent {
+    pub fn new() -> Result<Self, NewAutoResetEventError> {
+        let handle = unsafe { CreateEventA(None, false, false, None) }
+            .map_err(|_| io::Error::last_os_error())?;
+        Ok(unsafe { Self::from_raw_handle(handle) })
+    }
+
+    pub fn get(&self) -> Result<bool, GetAutoResetEventError> {
+        unsafe {
+            match WaitForSingleObject(self.handle.as_raw(), 0) {
+                WAIT_OBJECT_0 => Ok(true),
+                WAIT_TIMEOUT => Ok(false),
+                WAIT_FAILED => Err(io::Error::last_os_error().into()),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Waits for the event to become signaled. Unlike [`ManualResetEvent::wait`], a successful
+    /// wait resets the event back to non-signaled as it wakes, so at most one waiter observes any
+    /// given [`Self::set`].
+    pub async fn wait(&self) -> Result<(), WaitAutoResetEventError> {
+        self.handle.wait().await?;
+        Ok(())
+    }
+
+    pub fn set(&mut self) -> Result<(), SetAutoResetEventError> {
+        unsafe {
+            SetEvent(self.handle.as_raw()).map_err(|_| io::Error::last_os_error())?;
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create event")]
+pub struct NewAutoResetEventError(#[from] io::Error);
+
+#[derive(Debug, Error)]
+#[error("failed to get event state")]
+pub struct GetAutoResetEventError(#[from] io::Error);
+
+#[derive(Debug, Error)]
+#[error("failed to wait for event")]
+pub enum WaitAutoResetEventError {
+    HandleWait(#[from] handle::WaitError),
+}
+
+#[derive(Debug, Error)]
+#[error("failed to set event to signaled state")]
+pub struct SetAutoResetEventError(#[from] io::Error);
+
+handle_wrapper!(Semaphore);
+
+impl Semaphore {
+    pub fn new(initial_count: u32, maximum_count: u32) -> Result<Self, NewSemaphoreError> {
+        let handle = unsafe {
+            CreateSemaphoreA(
+                None,
+                initial_count.try_into().unwrap(),
+                maximum_count.try_into().unwrap(),
+                None,
+            )
+        }
+        .map_err(|_| io::Error::last_os_error())?;
+        Ok(unsafe { Self::from_raw_handle(handle) })
+    }
+
+    /// Acquires a permit, waiting for one to become available.
+    pub async fn wait(&self) -> Result<(), WaitError> {
+        self.handle.wait().await?;
+        Ok(())
+    }
+
+    /// Attempts to acquire a permit without waiting, returning whether one was available.
+    pub fn try_wait(&self) -> Result<bool, TryWaitError> {
+        unsafe {
+            match WaitForSingleObject(self.handle.as_raw(), 0) {
+                WAIT_OBJECT_0 => Ok(true),
+                WAIT_TIMEOUT => Ok(false),
+                WAIT_FAILED => Err(io::Error::last_os_error().into()),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Releases `count` permits back to the semaphore.
+    pub fn release(&self, count: u32) -> Result<(), ReleaseError> {
+        unsafe {
+            ReleaseSemaphore(self.handle.as_raw(), count.try_into().unwrap(), None)
+                .map_err(|_| io::Error::last_os_error())?;
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create semaphore")]
+pub struct NewSemaphoreError(#[from] io::Error);
+
+#[derive(Debug, Error)]
+#[error("failed to try to acquire semaphore permit")]
+pub struct TryWaitError(#[from] io::Error);
+
+#[derive(Debug, Error)]
+#[error("failed to release semaphore permit")]
+pub struct ReleaseError(#[from] io::Error);
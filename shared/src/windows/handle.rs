@@ -1,37 +1,32 @@
-use crate::windows::process;
+use crate::windows::{process, reactor, timer};
 use std::{
     future::Future,
     io,
     pin::Pin,
-    sync::{Arc, Mutex},
-    task::{Context, Poll, Waker},
+    task::{Context, Poll},
+    time::Duration,
 };
 use thiserror::Error;
-use winapi::{
-    ctypes::c_void,
-    shared::{minwindef::FALSE, ntdef::NULL, winerror::ERROR_IO_PENDING},
-    um::{
-        handleapi::{CloseHandle, DuplicateHandle},
-        winbase::{RegisterWaitForSingleObject, UnregisterWait, INFINITE},
-        winnt::{DUPLICATE_SAME_ACCESS, WT_EXECUTEINWAITTHREAD, WT_EXECUTEONLYONCE},
-    },
+use windows::Win32::{
+    Foundation::{CloseHandle, DuplicateHandle, DUPLICATE_SAME_ACCESS, HANDLE},
+    System::Threading::{WaitForMultipleObjects, INFINITE},
 };
 
 #[derive(Debug)]
-pub struct Handle(*mut c_void);
+pub struct Handle(HANDLE);
 
 impl Handle {
-    pub unsafe fn from_raw(raw_handle: *mut c_void) -> Self {
+    pub unsafe fn from_raw(raw_handle: HANDLE) -> Self {
         Self(raw_handle)
     }
 
     #[must_use]
-    pub unsafe fn as_raw(&self) -> *mut c_void {
+    pub unsafe fn as_raw(&self) -> HANDLE {
         self.0
     }
 
     #[expect(clippy::must_use_candidate)]
-    pub unsafe fn leak(self) -> *mut c_void {
+    pub unsafe fn leak(self) -> HANDLE {
         let raw = self.0;
         std::mem::forget(self);
         raw
@@ -44,106 +39,153 @@ impl Handle {
     pub fn try_clone_for_process(&self, process: &process::Process) -> Result<Self, CloneError> {
         unsafe {
             let current_process = process::Process::get_current();
-            let mut duplicated_handle = NULL;
-            if DuplicateHandle(
+            let mut duplicated_handle = HANDLE::default();
+            DuplicateHandle(
                 current_process.raw_handle(),
                 self.as_raw(),
                 process.raw_handle(),
                 &mut duplicated_handle,
                 0,
-                FALSE,
+                false,
                 DUPLICATE_SAME_ACCESS,
-            ) == 0
-            {
-                return Err(io::Error::last_os_error().into());
-            }
+            )
+            .map_err(|_| io::Error::last_os_error())?;
             Ok(Self::from_raw(duplicated_handle))
         }
     }
 
     pub async fn wait(&self) -> Result<(), WaitError> {
-        struct WaitFutureState {
-            wait_handle: Option<WaitHandle>,
-            completed: bool,
-            waker: Option<Waker>,
-        }
+        WaitFuture::new(self.try_clone()?).await;
+        Ok(())
+    }
 
-        struct WaitFuture {
-            handle: Handle,
-            state: Arc<Mutex<WaitFutureState>>,
-        }
+    /// Races `self` against a timer armed for `timeout`, returning `true` if `self` became
+    /// signaled first and `false` if `timeout` elapsed first.
+    pub async fn wait_timeout(&self, timeout: Duration) -> Result<bool, WaitError> {
+        Ok(Self::wait_any(&[self], Some(timeout)).await?.is_some())
+    }
 
-        impl WaitFuture {
-            fn new(handle: Handle) -> Self {
-                Self {
-                    handle,
-                    state: Arc::new(Mutex::new(WaitFutureState {
-                        wait_handle: None,
-                        completed: false,
-                        waker: None,
-                    })),
-                }
+    /// Waits for the first of `handles` to become signaled and returns its index, or `None` if
+    /// `timeout` elapses first. Equivalent to racing every handle's [`Self::wait`] against each
+    /// other (and, with a timeout, a [`timer::WaitableTimer`]), but registers all of them with the
+    /// reactor up front instead of spawning a task per handle.
+    pub async fn wait_any(
+        handles: &[&Handle],
+        timeout: Option<Duration>,
+    ) -> Result<Option<usize>, WaitError> {
+        let mut futures = Vec::with_capacity(handles.len() + 1);
+        for handle in handles {
+            futures.push(WaitFuture::new(handle.try_clone()?));
+        }
+        Ok(match timeout {
+            None => Some(WaitAnyFuture { futures }.await),
+            Some(timeout) => {
+                let timer = timer::WaitableTimer::new(timeout)?;
+                futures.push(WaitFuture::new(timer.handle().try_clone()?));
+                let index = WaitAnyFuture { futures }.await;
+                (index < handles.len()).then_some(index)
             }
+        })
+    }
+}
 
-            unsafe extern "system" fn callback(this: *mut c_void, _: u8) {
-                let state = unsafe { Box::from_raw(this.cast::<Arc<Mutex<WaitFutureState>>>()) };
-                let mut state = state.lock().unwrap();
-                state.completed = true;
-                if let Some(waker) = std::mem::take(&mut state.waker) {
-                    waker.wake();
-                }
+/// Blocks the calling thread on a real `WaitForMultipleObjects` call, for callers that need its
+/// exact wait-all/timeout semantics rather than the reactor-backed [`Handle::wait_any`] (which
+/// only ever waits for the first of a set and has no blocking-thread cost to avoid). `timeout` of
+/// `None` waits indefinitely.
+pub fn wait_for_multiple(
+    handles: &[&Handle],
+    wait_all: bool,
+    timeout: Option<Duration>,
+) -> Result<WaitForMultipleResult, WaitForMultipleError> {
+    let raw_handles = handles
+        .iter()
+        .map(|handle| unsafe { handle.as_raw() })
+        .collect::<Vec<_>>();
+    unsafe {
+        let result = WaitForMultipleObjects(
+            &raw_handles,
+            wait_all,
+            timeout.map_or(INFINITE, |timeout| timeout.as_millis().try_into().unwrap()),
+        );
+        match result.0 {
+            win_event if win_event == windows::Win32::Foundation::WAIT_TIMEOUT.0 => {
+                Ok(WaitForMultipleResult::TimedOut)
+            }
+            win_event if win_event == windows::Win32::Foundation::WAIT_FAILED.0 => {
+                Err(io::Error::last_os_error().into())
             }
+            // when waiting for all handles, the return value is WAIT_OBJECT_0 regardless of which
+            // one became signaled last, so the index is only meaningful when `wait_all` is false
+            win_event => Ok(WaitForMultipleResult::Signaled(
+                (win_event - windows::Win32::Foundation::WAIT_OBJECT_0.0) as usize,
+            )),
         }
+    }
+}
 
-        impl Future for WaitFuture {
-            type Output = ();
-
-            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-                let mut state = self.state.lock().unwrap();
-                if state.completed {
-                    Poll::Ready(())
-                } else {
-                    state.waker = Some(cx.waker().clone());
-                    if state.wait_handle.is_none() {
-                        unsafe {
-                            let mut wait_handle = NULL;
-                            let state_clone = Box::new(Arc::clone(&self.state));
-                            if RegisterWaitForSingleObject(
-                                &mut wait_handle,
-                                self.handle.as_raw(),
-                                Some(WaitFuture::callback),
-                                Box::into_raw(state_clone).cast(),
-                                INFINITE,
-                                WT_EXECUTEONLYONCE | WT_EXECUTEINWAITTHREAD,
-                            ) == 0
-                            {
-                                let last_os_error = io::Error::last_os_error();
-                                panic!(
-                                    "failed to register wait callback for handle {:p}: {}",
-                                    self.handle.as_raw(),
-                                    last_os_error,
-                                );
-                            }
-                            state.wait_handle = Some(WaitHandle::from_raw(wait_handle));
-                        }
-                    }
-                    Poll::Pending
-                }
-            }
+#[derive(Debug, Eq, PartialEq)]
+pub enum WaitForMultipleResult {
+    Signaled(usize),
+    TimedOut,
+}
+
+#[derive(Debug, Error)]
+#[error("failed to wait for objects")]
+pub struct WaitForMultipleError(#[from] io::Error);
+
+/// Resolves once the wrapped handle becomes signaled, registering it with the reactor on first
+/// poll rather than at construction so a future that's created but never polled never occupies a
+/// reactor slot.
+struct WaitFuture {
+    handle: Handle,
+    registration: Option<reactor::Registration>,
+}
+
+impl WaitFuture {
+    fn new(handle: Handle) -> Self {
+        Self {
+            handle,
+            registration: None,
         }
+    }
+}
 
-        WaitFuture::new(self.try_clone()?).await;
+impl Future for WaitFuture {
+    type Output = ();
 
-        Ok(())
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.registration.is_none() {
+            this.registration = Some(unsafe { reactor::register(this.handle.as_raw()) });
+        }
+        this.registration.as_ref().unwrap().poll(cx)
+    }
+}
+
+struct WaitAnyFuture {
+    futures: Vec<WaitFuture>,
+}
+
+impl Future for WaitAnyFuture {
+    type Output = usize;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        for (index, future) in this.futures.iter_mut().enumerate() {
+            if Pin::new(future).poll(cx).is_ready() {
+                return Poll::Ready(index);
+            }
+        }
+        Poll::Pending
     }
 }
 
 impl Drop for Handle {
     fn drop(&mut self) {
         unsafe {
-            if CloseHandle(self.0) == 0 {
-                let last_os_error = io::Error::last_os_error();
-                panic!("failed to drop handle {:p}: {}", self.0, last_os_error);
+            if let Err(error) = CloseHandle(self.0) {
+                panic!("failed to drop handle {:?}: {}", self.0, error);
             }
         }
     }
@@ -166,7 +208,7 @@ macro_rules! handle_wrapper {
             }
 
             #[must_use]
-            pub unsafe fn raw_handle(&self) -> *mut winapi::ctypes::c_void {
+            pub unsafe fn raw_handle(&self) -> windows::Win32::Foundation::HANDLE {
                 unsafe { self.handle.as_raw() }
             }
 
@@ -177,12 +219,12 @@ macro_rules! handle_wrapper {
                 }
             }
 
-            pub unsafe fn from_raw_handle(handle: *mut winapi::ctypes::c_void) -> Self {
+            pub unsafe fn from_raw_handle(handle: windows::Win32::Foundation::HANDLE) -> Self {
                 unsafe { Self::from_handle(crate::windows::handle::Handle::from_raw(handle)) }
             }
 
             #[expect(clippy::must_use_candidate)]
-            pub unsafe fn leak_handle(mut self) -> *mut winapi::ctypes::c_void {
+            pub unsafe fn leak_handle(mut self) -> windows::Win32::Foundation::HANDLE {
                 let raw_handle = unsafe { std::mem::ManuallyDrop::take(&mut self.handle).leak() };
                 std::mem::forget(self);
                 raw_handle
@@ -207,13 +249,13 @@ macro_rules! handle_wrapper {
         impl Drop for $name {
             fn drop(&mut self) {
                 unsafe {
-                    if winapi::um::handleapi::CloseHandle(self.raw_handle()) == 0 {
-                        let last_os_error = std::io::Error::last_os_error();
+                    if let Err(error) = windows::Win32::Foundation::CloseHandle(self.raw_handle())
+                    {
                         panic!(
-                            "failed to drop {} handle {:p}: {}",
+                            "failed to drop {} handle {:?}: {}",
                             stringify!($name),
                             self.raw_handle(),
-                            last_os_error
+                            error
                         );
                     }
                 }
@@ -223,39 +265,6 @@ macro_rules! handle_wrapper {
 }
 pub(crate) use handle_wrapper;
 
-struct WaitHandle(*mut c_void);
-
-impl WaitHandle {
-    unsafe fn from_raw(raw_handle: *mut c_void) -> Self {
-        Self(raw_handle)
-    }
-
-    #[must_use]
-    unsafe fn as_raw(&self) -> *mut c_void {
-        self.0
-    }
-}
-
-impl Drop for WaitHandle {
-    fn drop(&mut self) {
-        unsafe {
-            #[expect(clippy::cast_possible_wrap)]
-            if UnregisterWait(self.as_raw()) == 0 {
-                let last_os_error = io::Error::last_os_error();
-                assert!(
-                    last_os_error.raw_os_error() == Some(ERROR_IO_PENDING as i32),
-                    "failed to unregister wait handle {:p}: {}",
-                    self.as_raw(),
-                    last_os_error,
-                );
-            }
-        }
-    }
-}
-
-unsafe impl Send for WaitHandle {}
-unsafe impl Sync for WaitHandle {}
-
 #[derive(Debug, Error)]
 #[error("failed to clone handle")]
 pub struct CloneError(#[from] io::Error);
@@ -264,4 +273,5 @@ pub struct CloneError(#[from] io::Error);
 #[error("failed to wait for object")]
 pub enum WaitError {
     Clone(#[from] CloneError),
+    NewTimer(#[from] timer::NewError),
 }
@@ -3,17 +3,19 @@ use std::{
     ffi::{c_void, OsString},
     io,
     mem::MaybeUninit,
+    ops::Range,
     os::windows::ffi::OsStringExt,
 };
 use thiserror::Error;
-use winapi::{
-    shared::minwindef::HMODULE,
-    um::{
-        psapi::GetModuleBaseNameW,
-        winnt::{
-            IMAGE_DIRECTORY_ENTRY_EXPORT, IMAGE_DOS_HEADER, IMAGE_EXPORT_DIRECTORY,
-            IMAGE_FILE_HEADER, IMAGE_OPTIONAL_HEADER32, IMAGE_OPTIONAL_HEADER64,
+use windows::Win32::{
+    Foundation::HMODULE,
+    System::{
+        Diagnostics::Debug::{
+            IMAGE_DATA_DIRECTORY, IMAGE_DOS_HEADER, IMAGE_EXPORT_DIRECTORY, IMAGE_FILE_HEADER,
+            IMAGE_OPTIONAL_HEADER32, IMAGE_OPTIONAL_HEADER64,
         },
+        ProcessStatus::GetModuleBaseNameW,
+        SystemServices::IMAGE_DIRECTORY_ENTRY_EXPORT,
     },
 };
 
@@ -35,8 +37,7 @@ impl<'p> Module<'p> {
                 len = GetModuleBaseNameW(
                     self.process.raw_handle(),
                     self.handle,
-                    name.as_mut_ptr().cast(),
-                    name.len().try_into().unwrap(),
+                    std::slice::from_raw_parts_mut(name.as_mut_ptr().cast(), name.len()),
                 );
                 if len == 0 {
                     return Err(io::Error::last_os_error().into());
@@ -56,45 +57,91 @@ impl<'p> Module<'p> {
     pub fn get_base_address(&self) -> *mut c_void {
         // https://learn.microsoft.com/en-us/windows/win32/api/psapi/ns-psapi-moduleinfo
         // "The load address of a module is the same as the HMODULE value."
-        self.handle.cast()
+        self.handle.0.cast()
     }
 
-    #[expect(clippy::too_many_lines)] // TODO
     pub fn get_export_address(
         &self,
         export_name: &str,
     ) -> Result<*mut c_void, GetExportAddressError> {
-        enum OptionalHeader {
-            Header32(IMAGE_OPTIONAL_HEADER32),
-            Header64(IMAGE_OPTIONAL_HEADER64),
-        }
-        impl OptionalHeader {
-            fn data_directory_entry_count(&self) -> u32 {
-                match self {
-                    Self::Header32(header) => header.NumberOfRvaAndSizes,
-                    Self::Header64(header) => header.NumberOfRvaAndSizes,
-                }
-            }
+        let export_directory_table = self.read_export_directory_table()?;
 
-            fn export_table_address(&self) -> Option<u32> {
-                if u32::from(IMAGE_DIRECTORY_ENTRY_EXPORT) < self.data_directory_entry_count() {
-                    Some(
-                        match self {
-                            Self::Header32(header) => {
-                                header.DataDirectory[IMAGE_DIRECTORY_ENTRY_EXPORT as usize]
-                            }
-                            Self::Header64(header) => {
-                                header.DataDirectory[IMAGE_DIRECTORY_ENTRY_EXPORT as usize]
-                            }
-                        }
-                        .VirtualAddress,
+        unsafe {
+            for index in 0..export_directory_table.table.NumberOfNames as usize {
+                let export_name_pointer = self
+                    .get_base_address()
+                    .byte_add(
+                        self.process.read_value::<u32>(
+                            self.get_base_address()
+                                .byte_add(
+                                    export_directory_table.table.AddressOfNames as usize
+                                        + index * 4,
+                                )
+                                .cast(),
+                        )? as usize,
                     )
-                } else {
-                    None
+                    .cast();
+                let export_name_at_index = self
+                    .process
+                    .read_nul_terminated_utf8(export_name_pointer)?;
+                if export_name_at_index.to_lowercase() == export_name.to_lowercase() {
+                    let export_ordinal = self.process.read_value::<u16>(
+                        self.get_base_address()
+                            .byte_add(
+                                export_directory_table.table.AddressOfNameOrdinals as usize
+                                    + index * 2,
+                            )
+                            .cast(),
+                    )? as usize;
+                    let export_offset = self.process.read_value::<u32>(
+                        self.get_base_address()
+                            .byte_add(
+                                export_directory_table.table.AddressOfFunctions as usize
+                                    + export_ordinal * 4,
+                            )
+                            .cast(),
+                    )?;
+                    return self.resolve_export_offset(export_offset, &export_directory_table);
                 }
             }
+            Err(ExportNotFoundError.into())
+        }
+    }
+
+    /// Resolves an export by its ordinal, indexing `AddressOfFunctions` directly rather than
+    /// walking `AddressOfNames`, for the many system DLLs that export some functions by ordinal
+    /// only.
+    pub fn get_export_address_by_ordinal(
+        &self,
+        ordinal: u16,
+    ) -> Result<*mut c_void, GetExportAddressError> {
+        let export_directory_table = self.read_export_directory_table()?;
+
+        let index = u32::from(ordinal)
+            .checked_sub(export_directory_table.table.Base)
+            .filter(|&index| index < export_directory_table.table.NumberOfFunctions)
+            .ok_or(ExportNotFoundError)?;
+
+        unsafe {
+            let export_offset = self.process.read_value::<u32>(
+                self.get_base_address()
+                    .byte_add(
+                        export_directory_table.table.AddressOfFunctions as usize
+                            + index as usize * 4,
+                    )
+                    .cast(),
+            )?;
+            self.resolve_export_offset(export_offset, &export_directory_table)
         }
+    }
 
+    /// Reads and validates the DOS/PE headers and returns the 32- or 64-bit optional header,
+    /// whichever the module actually has. Shared by [`Self::read_export_directory_table`] and
+    /// [`Self::get_size`], the two places that need to walk PE headers.
+    fn read_optional_header<E>(&self) -> Result<OptionalHeader, E>
+    where
+        E: From<process::ReadMemoryError> + From<InvalidModuleHeadersError>,
+    {
         unsafe {
             let dos_header_address = self.get_base_address().cast::<IMAGE_DOS_HEADER>();
             let dos_header = self.process.read(dos_header_address)?;
@@ -115,7 +162,7 @@ impl<'p> Module<'p> {
             let optional_header_magic = self
                 .process
                 .read_to_vec(optional_header_address.cast(), 2)?;
-            let optional_header = match (optional_header_magic[0], optional_header_magic[1]) {
+            Ok(match (optional_header_magic[0], optional_header_magic[1]) {
                 (0xb, 0x1) => OptionalHeader::Header32(
                     self.process
                         .read::<IMAGE_OPTIONAL_HEADER32>(optional_header_address.cast())?,
@@ -125,54 +172,110 @@ impl<'p> Module<'p> {
                         .read::<IMAGE_OPTIONAL_HEADER64>(optional_header_address.cast())?,
                 ),
                 _ => return Err(InvalidModuleHeadersError.into()),
-            };
+            })
+        }
+    }
+
+    /// The module's `SizeOfImage`, i.e. the span of virtual memory its sections (and so its
+    /// executable code) occupy starting at [`Self::get_base_address`].
+    pub fn get_size(&self) -> Result<usize, GetSizeError> {
+        Ok(match self.read_optional_header()? {
+            OptionalHeader::Header32(header) => header.SizeOfImage,
+            OptionalHeader::Header64(header) => header.SizeOfImage,
+        } as usize)
+    }
+
+    fn read_export_directory_table(&self) -> Result<ExportDirectoryTable, GetExportAddressError> {
+        let optional_header = self.read_optional_header()?;
 
+        unsafe {
+            let export_table_directory_entry = optional_header
+                .export_table_directory_entry()
+                .ok_or(InvalidModuleHeadersError)?;
             let export_directory_table_address = self
                 .get_base_address()
-                .byte_add(
-                    optional_header
-                        .export_table_address()
-                        .ok_or(InvalidModuleHeadersError)? as usize,
-                )
+                .byte_add(export_table_directory_entry.VirtualAddress as usize)
                 .cast::<IMAGE_EXPORT_DIRECTORY>();
-            let export_directory_table = self.process.read(export_directory_table_address)?;
+            let table = self.process.read(export_directory_table_address)?;
 
-            for index in 0..export_directory_table.NumberOfNames as usize {
-                let export_name_pointer = self
-                    .get_base_address()
-                    .byte_add(
-                        self.process.read_u32(
-                            self.get_base_address()
-                                .byte_add(
-                                    export_directory_table.AddressOfNames as usize + index * 4,
-                                )
-                                .cast(),
-                        )? as usize,
-                    )
-                    .cast();
-                let export_name_at_index = self
-                    .process
-                    .read_nul_terminated_string(export_name_pointer)?;
-                if export_name_at_index.to_lowercase() == export_name.to_lowercase() {
-                    let export_ordinal = self.process.read_u16(
-                        self.get_base_address()
-                            .byte_add(
-                                export_directory_table.AddressOfNameOrdinals as usize + index * 2,
-                            )
-                            .cast(),
-                    )? as usize;
-                    let export_offset = self.process.read_u32(
-                        self.get_base_address()
-                            .byte_add(
-                                export_directory_table.AddressOfFunctions as usize
-                                    + export_ordinal * 4,
-                            )
-                            .cast(),
-                    )? as usize;
-                    return Ok((self.get_base_address().byte_add(export_offset)).cast());
+            Ok(ExportDirectoryTable {
+                table,
+                rva_range: export_table_directory_entry.VirtualAddress
+                    ..(export_table_directory_entry.VirtualAddress
+                        + export_table_directory_entry.Size),
+            })
+        }
+    }
+
+    /// Turns an export's raw RVA into an address, following forwarder strings (e.g.
+    /// `"NTDLL.RtlAllocateHeap"` or `"NTDLL.#31"`) when the RVA falls inside the export
+    /// directory itself rather than pointing at real code.
+    fn resolve_export_offset(
+        &self,
+        export_offset: u32,
+        export_directory_table: &ExportDirectoryTable,
+    ) -> Result<*mut c_void, GetExportAddressError> {
+        if !export_directory_table.rva_range.contains(&export_offset) {
+            return Ok(unsafe { self.get_base_address().byte_add(export_offset as usize) }.cast());
+        }
+
+        let forwarder_string_address = unsafe {
+            self.get_base_address()
+                .byte_add(export_offset as usize)
+                .cast()
+        };
+        let forwarder_string = self
+            .process
+            .read_nul_terminated_utf8(forwarder_string_address)?;
+        let (forwarded_module_name, forwarded_export) = forwarder_string
+            .split_once('.')
+            .ok_or(InvalidModuleHeadersError)?;
+        let forwarded_module_name = OsString::from(format!("{forwarded_module_name}.dll"));
+
+        let forwarded_module = self
+            .process
+            .get_module(&forwarded_module_name)?
+            .ok_or(ForwarderModuleNotLoadedError(forwarded_module_name))?;
+
+        Ok(match forwarded_export.strip_prefix('#') {
+            Some(ordinal) => forwarded_module.get_export_address_by_ordinal(
+                ordinal.parse().map_err(|_| InvalidModuleHeadersError)?,
+            )?,
+            None => forwarded_module.get_export_address(forwarded_export)?,
+        })
+    }
+}
+
+struct ExportDirectoryTable {
+    table: IMAGE_EXPORT_DIRECTORY,
+    rva_range: Range<u32>,
+}
+
+enum OptionalHeader {
+    Header32(IMAGE_OPTIONAL_HEADER32),
+    Header64(IMAGE_OPTIONAL_HEADER64),
+}
+
+impl OptionalHeader {
+    fn data_directory_entry_count(&self) -> u32 {
+        match self {
+            Self::Header32(header) => header.NumberOfRvaAndSizes,
+            Self::Header64(header) => header.NumberOfRvaAndSizes,
+        }
+    }
+
+    fn export_table_directory_entry(&self) -> Option<IMAGE_DATA_DIRECTORY> {
+        if IMAGE_DIRECTORY_ENTRY_EXPORT < self.data_directory_entry_count() {
+            Some(match self {
+                Self::Header32(header) => {
+                    header.DataDirectory[IMAGE_DIRECTORY_ENTRY_EXPORT as usize]
                 }
-            }
-            Err(ExportNotFoundError.into())
+                Self::Header64(header) => {
+                    header.DataDirectory[IMAGE_DIRECTORY_ENTRY_EXPORT as usize]
+                }
+            })
+        } else {
+            None
         }
     }
 }
@@ -181,12 +284,22 @@ impl<'p> Module<'p> {
 #[error("failed to get name of module")]
 pub struct GetNameError(#[from] io::Error);
 
+#[derive(Debug, Error)]
+#[error("failed to get module size")]
+pub enum GetSizeError {
+    ReadMemory(#[from] process::ReadMemoryError),
+    InvalidModuleHeaders(#[from] InvalidModuleHeadersError),
+}
+
 #[derive(Debug, Error)]
 #[error("failed to get export address")]
 pub enum GetExportAddressError {
     ReadMemory(#[from] process::ReadMemoryError),
+    ReadNulTerminatedString(#[from] process::ReadNulTerminatedUtf8Error),
+    GetModules(#[from] process::GetModulesError),
     InvalidModuleHeaders(#[from] InvalidModuleHeadersError),
     ExportNotFound(#[from] ExportNotFoundError),
+    ForwarderModuleNotLoaded(#[from] ForwarderModuleNotLoadedError),
     Os(#[from] io::Error),
 }
 
@@ -197,3 +310,7 @@ pub struct InvalidModuleHeadersError;
 #[derive(Debug, Error)]
 #[error("export not found in module")]
 pub struct ExportNotFoundError;
+
+#[derive(Debug, Error)]
+#[error("target module {0:?} of forwarded export is not loaded")]
+pub struct ForwarderModuleNotLoadedError(OsString);
@@ -1,60 +1,180 @@
-use crate::windows::handle::{self, handle_wrapper};
-use std::io::{Read, Write};
+use crate::windows::{
+    event::ManualResetEvent,
+    handle::{self, handle_wrapper},
+    process, reactor,
+};
+use std::{
+    future::Future,
+    io::{self, Read, Write},
+    os::windows::ffi::OsStrExt,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{Context, Poll},
+    time::Duration,
+};
 use thiserror::Error;
-use winapi::{
-    shared::{minwindef::TRUE, ntdef::NULL},
-    um::{
-        fileapi::{ReadFile, WriteFile},
-        minwinbase::SECURITY_ATTRIBUTES,
-        namedpipeapi::{CreatePipe, PeekNamedPipe},
+use windows::{
+    core::{PCSTR, PCWSTR},
+    Win32::{
+        Foundation::{HANDLE, INVALID_HANDLE_VALUE},
+        Security::SECURITY_ATTRIBUTES,
+        Storage::FileSystem::{
+            CreateFileA, ReadFile, WriteFile, FILE_FLAG_OVERLAPPED, FILE_SHARE_MODE,
+            GENERIC_READ, GENERIC_WRITE, OPEN_EXISTING,
+        },
+        System::{
+            Console::{GetStdHandle, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
+            Pipes::{
+                ConnectNamedPipe, CreateNamedPipeA, CreateNamedPipeW, CreatePipe, PeekNamedPipe,
+                PIPE_ACCESS_DUPLEX, PIPE_ACCESS_INBOUND, PIPE_READMODE_BYTE,
+                PIPE_READMODE_MESSAGE, PIPE_TYPE_BYTE, PIPE_TYPE_MESSAGE, PIPE_WAIT,
+            },
+            IO::{CancelIoEx, GetOverlappedResult, OVERLAPPED},
+        },
     },
 };
 
 pub fn new() -> Result<(Writer, Reader), NewError> {
+    PipeBuilder::new().build()
+}
+
+/// Builds an anonymous pipe with a configurable buffer size and handle inheritability, in place
+/// of [`new`]'s fixed OS-default buffer and always-inheritable handles.
+#[derive(Debug)]
+pub struct PipeBuilder {
+    capacity: u32,
+    inheritable: bool,
+}
+
+impl PipeBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            inheritable: true,
+        }
+    }
+
+    /// Requests a buffer of at least `capacity` bytes; `0` (the default) asks the OS to pick one.
+    #[must_use]
+    pub fn capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Whether a child process created with handle inheritance enabled can inherit the ends of
+    /// this pipe. Defaults to `true`, matching [`new`].
+    #[must_use]
+    pub fn inheritable(mut self, inheritable: bool) -> Self {
+        self.inheritable = inheritable;
+        self
+    }
+
+    pub fn build(self) -> Result<(Writer, Reader), NewError> {
+        unsafe {
+            let mut read_handle = HANDLE::default();
+            let mut write_handle = HANDLE::default();
+            let security_attributes = SECURITY_ATTRIBUTES {
+                #[expect(clippy::cast_possible_truncation)]
+                nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: std::ptr::null_mut(),
+                bInheritHandle: self.inheritable.into(),
+            };
+            CreatePipe(
+                &mut read_handle,
+                &mut write_handle,
+                Some(&security_attributes),
+                self.capacity,
+            )
+            .map_err(|_| std::io::Error::last_os_error())?;
+
+            Ok((
+                Writer::from_raw_handle(write_handle),
+                Reader::from_raw_handle(read_handle),
+            ))
+        }
+    }
+}
+
+impl Default for PipeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a message-mode named pipe server instance under `name` (resolved under
+/// `\\.\pipe\`) and blocks until a peer connects, for an IPC endpoint a separately-launched
+/// process can reach by name instead of requiring handle inheritance at spawn time. Unlike
+/// [`new`]'s anonymous byte-mode pipe, `PIPE_TYPE_MESSAGE` delivers each write as exactly one
+/// atomic read on the other end. `security_attributes` lets the caller restrict which principals
+/// may connect (e.g. with a self-relative security descriptor); `None` falls back to the default
+/// DACL the OS assigns a named pipe with no explicit one.
+pub fn new_named_message(
+    name: &str,
+    in_buffer_size: u32,
+    out_buffer_size: u32,
+    security_attributes: Option<&SECURITY_ATTRIBUTES>,
+) -> Result<(Writer, Reader), NewNamedError> {
+    let wide_name = std::ffi::OsString::from(format!(r"\\.\pipe\{name}"))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect::<Vec<_>>();
     unsafe {
-        let mut read_handle = std::ptr::null_mut();
-        let mut write_handle = std::ptr::null_mut();
-        let security_attributes = SECURITY_ATTRIBUTES {
-            #[expect(clippy::cast_possible_truncation)]
-            nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
-            lpSecurityDescriptor: NULL.cast(),
-            bInheritHandle: TRUE,
-        };
-        if CreatePipe(
-            &mut read_handle,
-            &mut write_handle,
-            std::ptr::addr_of!(security_attributes).cast_mut(),
+        let handle = CreateNamedPipeW(
+            PCWSTR(wide_name.as_ptr()),
+            PIPE_ACCESS_DUPLEX,
+            PIPE_TYPE_MESSAGE | PIPE_READMODE_MESSAGE | PIPE_WAIT,
+            1,
+            out_buffer_size,
+            in_buffer_size,
             0,
-        ) == 0
-        {
-            return Err(std::io::Error::last_os_error().into());
+            security_attributes,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        // a client that connects between CreateNamedPipeW returning and this call reaching the
+        // kernel (vanishingly unlikely for a genuine remote client, but possible) is reported as
+        // ERROR_PIPE_CONNECTED rather than a fresh connection, which is just as good as one
+        if let Err(error) = ConnectNamedPipe(handle, None) {
+            let error = io::Error::from(error);
+            if error.raw_os_error() != Some(windows::Win32::Foundation::ERROR_PIPE_CONNECTED.0 as i32)
+            {
+                handle::Handle::from_raw(handle);
+                return Err(error.into());
+            }
         }
 
+        let duplex = handle::Handle::from_raw(handle);
+        let reader_handle = duplex.try_clone()?;
         Ok((
-            Writer::from_raw_handle(write_handle),
-            Reader::from_raw_handle(read_handle),
+            Writer::from_handle(duplex),
+            Reader::from_handle(reader_handle),
         ))
     }
 }
 
+#[derive(Debug, Error)]
+#[error("failed to create named pipe")]
+pub enum NewNamedError {
+    Io(#[from] io::Error),
+    HandleClone(#[from] handle::CloneError),
+}
+
 handle_wrapper!(Writer);
 
 impl Write for Writer {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         let mut written_count = 0u32;
         unsafe {
-            if WriteFile(
+            WriteFile(
                 self.handle.as_raw(),
-                buf.as_ptr().cast(),
-                buf.len()
-                    .try_into()
-                    .expect("cannot cast data length to u32"),
-                &mut written_count,
-                NULL.cast(),
-            ) == 0
-            {
-                return Err(std::io::Error::last_os_error());
-            }
+                Some(buf),
+                Some(&mut written_count),
+                None,
+            )
+            .map_err(|_| std::io::Error::last_os_error())?;
         }
 
         Ok(written_count as usize)
@@ -71,46 +191,556 @@ pub enum WriterCloneError {
     HandleClone(#[from] handle::CloneError),
 }
 
-handle_wrapper!(Reader);
+/// The read end of a pipe created by [`new`]/[`new_named_message`]. Defaults to blocking: [`Read::read`]
+/// waits until at least one byte is pending rather than returning `Ok(0)` for "nothing yet", which
+/// `std::io` callers would otherwise mistake for EOF. Call [`Self::set_nonblocking`] to instead
+/// have [`Read::read`] return [`io::ErrorKind::WouldBlock`] immediately when nothing is pending.
+#[derive(Debug)]
+pub struct Reader {
+    handle: std::mem::ManuallyDrop<handle::Handle>,
+    nonblocking: bool,
+}
+
+impl Reader {
+    #[must_use]
+    pub fn handle(&self) -> &handle::Handle {
+        &self.handle
+    }
+
+    #[must_use]
+    pub unsafe fn raw_handle(&self) -> HANDLE {
+        unsafe { self.handle.as_raw() }
+    }
+
+    #[must_use]
+    pub unsafe fn from_handle(handle: handle::Handle) -> Self {
+        Self {
+            handle: std::mem::ManuallyDrop::new(handle),
+            nonblocking: false,
+        }
+    }
+
+    pub unsafe fn from_raw_handle(handle: HANDLE) -> Self {
+        unsafe { Self::from_handle(handle::Handle::from_raw(handle)) }
+    }
+
+    #[expect(clippy::must_use_candidate)]
+    pub unsafe fn leak_handle(mut self) -> HANDLE {
+        let raw_handle = unsafe { std::mem::ManuallyDrop::take(&mut self.handle).leak() };
+        std::mem::forget(self);
+        raw_handle
+    }
+
+    pub fn try_clone(&self) -> Result<Self, handle::CloneError> {
+        self.try_clone_for_process(&process::Process::get_current())
+    }
+
+    pub fn try_clone_for_process(&self, process: &process::Process) -> Result<Self, handle::CloneError> {
+        Ok(Self {
+            handle: std::mem::ManuallyDrop::new(self.handle.try_clone_for_process(process)?),
+            nonblocking: self.nonblocking,
+        })
+    }
+
+    /// In non-blocking mode, [`Read::read`] returns [`io::ErrorKind::WouldBlock`] instead of
+    /// blocking when nothing is currently pending; in blocking mode (the default) it waits.
+    pub fn set_nonblocking(&mut self, nonblocking: bool) {
+        self.nonblocking = nonblocking;
+    }
+
+    /// Returns the number of bytes currently pending, without consuming them.
+    pub fn bytes_available(&self) -> std::io::Result<u32> {
+        let mut pending_count = 0;
+        unsafe {
+            PeekNamedPipe(self.handle.as_raw(), None, 0, None, Some(&mut pending_count), None)
+                .map_err(|_| std::io::Error::last_os_error())?;
+        }
+        Ok(pending_count)
+    }
+
+    /// Copies up to `buf.len()` bytes currently pending into `buf` without consuming them, like
+    /// `PeekNamedPipe`'s `lpBuffer` parameter - a later [`Self::peek`]/[`Read::read`] will see the
+    /// same bytes again.
+    pub fn peek(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut read_count = 0;
+        unsafe {
+            PeekNamedPipe(
+                self.handle.as_raw(),
+                Some(buf),
+                buf.len().try_into().unwrap(),
+                Some(&mut read_count),
+                None,
+                None,
+            )
+            .map_err(|_| std::io::Error::last_os_error())?;
+        }
+        Ok(read_count as usize)
+    }
+}
 
 impl Read for Reader {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let mut pending_count = 0;
+        let mut pending_count = self.bytes_available()?;
+        if pending_count == 0 {
+            if self.nonblocking {
+                return Err(std::io::Error::from(std::io::ErrorKind::WouldBlock));
+            }
+            while pending_count == 0 {
+                std::thread::yield_now();
+                pending_count = self.bytes_available()?;
+            }
+        }
+
+        let mut read_count = 0u32;
+        let read_len = u32::min(pending_count, buf.len().try_into().unwrap()) as usize;
         unsafe {
-            if PeekNamedPipe(
+            ReadFile(
                 self.handle.as_raw(),
-                NULL,
-                0,
-                NULL.cast(),
-                &mut pending_count,
-                NULL.cast(),
-            ) == 0
+                Some(&mut buf[..read_len]),
+                Some(&mut read_count),
+                None,
+            )
+            .map_err(|_| std::io::Error::last_os_error())?;
+        }
+
+        Ok(read_count as usize)
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        unsafe {
+            if let Err(error) = windows::Win32::Foundation::CloseHandle(self.raw_handle()) {
+                panic!("failed to drop Reader handle {:?}: {}", self.raw_handle(), error);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create pipe")]
+pub struct NewError(#[from] std::io::Error);
+
+/// Relays bytes from `reader` to `writer` until the write end closes, using a freshly allocated
+/// 64 KiB buffer. See [`pump`] to reuse a caller-owned buffer across many calls instead.
+pub fn pump_until_eof(reader: &mut Reader, writer: &mut Writer) -> Result<u64, PumpError> {
+    pump(reader, writer, &mut vec![0; 64 * 1024])
+}
+
+/// Relays bytes from `reader` to `writer` through `buf`, the way `std::io::copy`'s fast path
+/// reuses a single buffer rather than allocating per chunk, until the write end closes (detected
+/// by [`Reader::read`] failing with `ERROR_BROKEN_PIPE`, distinguished here from a transient read
+/// error) or either side fails. Returns the total byte count relayed; on error,
+/// [`PumpError::bytes_written`] carries how much was relayed first, so a caller can resume rather
+/// than discard the whole transfer. `reader` is expected to be in its default blocking mode (see
+/// [`Reader::set_nonblocking`]) - each `Reader::read` call already sizes itself to the bytes
+/// currently pending, so `buf` only bounds the largest chunk moved per call.
+pub fn pump(reader: &mut Reader, writer: &mut Writer, buf: &mut [u8]) -> Result<u64, PumpError> {
+    let mut total = 0u64;
+    loop {
+        let read = match reader.read(buf) {
+            Ok(read) => read,
+            Err(error)
+                if error.raw_os_error()
+                    == Some(windows::Win32::Foundation::ERROR_BROKEN_PIPE.0 as i32) =>
+            {
+                return Ok(total);
+            }
+            Err(error) => return Err(PumpError { bytes_written: total, source: error }),
+        };
+        writer
+            .write_all(&buf[..read])
+            .map_err(|error| PumpError { bytes_written: total, source: error })?;
+        total += read as u64;
+    }
+}
+
+/// Error returned by [`pump`]/[`pump_until_eof`], carrying how many bytes were relayed before
+/// `reader`/`writer` failed, so a caller can resume instead of redoing the whole transfer.
+#[derive(Debug, Error)]
+#[error("pipe pump failed after relaying {bytes_written} bytes: {source}")]
+pub struct PumpError {
+    pub bytes_written: u64,
+    #[source]
+    source: io::Error,
+}
+
+/// Like [`new`], but the read end supports [`OverlappedReader::read_some`] instead of only the
+/// blocking, poll-via-`PeekNamedPipe` [`Read`] impl [`Reader`] has. Anonymous pipes (what [`new`]
+/// creates) can never be opened in overlapped mode, so this creates a uniquely named pipe instead:
+/// a server instance with `FILE_FLAG_OVERLAPPED` for the read end, and a plain synchronous
+/// `CreateFileA` open for the write end.
+pub fn new_overlapped() -> Result<(Writer, OverlappedReader), NewOverlappedError> {
+    let name = unique_pipe_name();
+    unsafe {
+        let security_attributes = SECURITY_ATTRIBUTES {
+            #[expect(clippy::cast_possible_truncation)]
+            nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: std::ptr::null_mut(),
+            bInheritHandle: true.into(),
+        };
+
+        let read_handle = CreateNamedPipeA(
+            PCSTR(name.as_ptr().cast()),
+            PIPE_ACCESS_INBOUND | FILE_FLAG_OVERLAPPED,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+            1,
+            0,
+            4096,
+            0,
+            Some(&security_attributes),
+        );
+        if read_handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let write_handle = CreateFileA(
+            PCSTR(name.as_ptr().cast()),
+            GENERIC_WRITE.0,
+            FILE_SHARE_MODE(0),
+            Some(&security_attributes),
+            OPEN_EXISTING,
+            Default::default(),
+            None,
+        );
+        let write_handle = match write_handle {
+            Ok(write_handle) => write_handle,
+            Err(_) => {
+                let error = io::Error::last_os_error();
+                handle::Handle::from_raw(read_handle);
+                return Err(error.into());
+            }
+        };
+
+        // the write end already connected above, so this is expected to report
+        // ERROR_PIPE_CONNECTED rather than actually go through the overlapped connect path
+        if let Err(error) = ConnectNamedPipe(read_handle, None) {
+            let error = io::Error::from(error);
+            if error.raw_os_error() != Some(windows::Win32::Foundation::ERROR_PIPE_CONNECTED.0 as i32)
             {
-                return Err(std::io::Error::last_os_error());
+                handle::Handle::from_raw(read_handle);
+                handle::Handle::from_raw(write_handle);
+                return Err(error.into());
             }
         }
-        if pending_count > 0 {
-            let mut read_count = 0u32;
-            unsafe {
-                if ReadFile(
-                    self.handle.as_raw(),
-                    buf.as_mut_ptr().cast(),
-                    u32::min(pending_count, buf.len().try_into().unwrap()),
-                    &mut read_count,
-                    NULL.cast(),
-                ) == 0
+
+        Ok((
+            Writer::from_raw_handle(write_handle),
+            OverlappedReader::from_raw_handle(read_handle),
+        ))
+    }
+}
+
+fn unique_pipe_name() -> std::ffi::CString {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    std::ffi::CString::new(format!(
+        r"\\.\pipe\winter-pipe-{}-{id}",
+        std::process::id()
+    ))
+    .expect("pipe name contains no interior nul bytes")
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create overlapped pipe")]
+pub struct NewOverlappedError(#[from] io::Error);
+
+handle_wrapper!(OverlappedReader);
+
+impl OverlappedReader {
+    /// Asynchronously reads into `buf`, resolving as soon as the overlapped `ReadFile` it issues
+    /// completes: either bytes arrived, or the write end closed (`Ok(0)`). The reactor waits on
+    /// the operation's own completion event instead of this being driven by a polling timer. This
+    /// is the async counterpart to [`Reader`]'s `PeekNamedPipe`-based, poll-on-demand
+    /// `Read` impl, which is left as-is for callers happy to poll synchronously (e.g. one-shot
+    /// reads where spinning up an overlapped operation isn't worth it).
+    pub async fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, ReadSomeError> {
+        ReadSomeFuture::new(self.begin_read(buf)?).await
+    }
+
+    /// Like [`Self::read_some`], but blocks the calling thread on the read's completion event via
+    /// [`super::handle::wait_for_multiple`] instead of going through the reactor - for callers
+    /// (e.g. a dedicated host-loop thread with no executor of its own) that want to wait on one or
+    /// more overlapped pipes without pulling in an async runtime. Returns `None`, and cancels the
+    /// read, if `timeout` elapses first.
+    pub fn read_some_blocking(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> Result<Option<usize>, ReadSomeError> {
+        let mut pending = self.begin_read(buf)?;
+        Ok(match wait_readable_many(&[&pending], timeout)? {
+            Some(_) => pending.try_complete()?,
+            None => None,
+        })
+    }
+
+    /// Issues an overlapped `ReadFile` into `buf` and returns a handle to it rather than awaiting
+    /// completion, so its completion event can be folded into a multi-handle wait - see
+    /// [`wait_readable_many`] and [`Self::read_some_blocking`]. [`Self::read_some`] is the async
+    /// counterpart, built on top of this plus the reactor.
+    pub fn begin_read<'a>(&mut self, buf: &'a mut [u8]) -> Result<PendingRead<'a>, ReadSomeError> {
+        PendingRead::begin(unsafe { self.handle.as_raw() }, buf)
+    }
+}
+
+/// An overlapped `ReadFile` call issued but not yet awaited, exposing its completion event
+/// [`Self::handle`] so it can be waited on directly (e.g. via
+/// [`super::handle::wait_for_multiple`]/[`wait_readable_many`]) instead of only through the
+/// reactor. Cancels the read if dropped before it completes, so the kernel stops writing into its
+/// buffer the instant the borrow backing that buffer goes away.
+pub struct PendingRead<'a> {
+    pipe_handle: HANDLE,
+    overlapped: Box<OVERLAPPED>,
+    completion_event: ManualResetEvent,
+    _buf: &'a mut [u8],
+}
+
+impl<'a> PendingRead<'a> {
+    fn begin(pipe_handle: HANDLE, buf: &'a mut [u8]) -> Result<Self, ReadSomeError> {
+        let completion_event = ManualResetEvent::new()?;
+        let mut overlapped = Box::new(unsafe { std::mem::zeroed::<OVERLAPPED>() });
+        overlapped.hEvent = unsafe { completion_event.handle().as_raw() };
+
+        unsafe {
+            let mut bytes_read = 0u32;
+            if let Err(error) = ReadFile(
+                pipe_handle,
+                Some(buf),
+                Some(&mut bytes_read),
+                Some(overlapped.as_mut()),
+            ) {
+                let error = io::Error::from(error);
+                if error.raw_os_error()
+                    != Some(windows::Win32::Foundation::ERROR_IO_PENDING.0 as i32)
                 {
-                    return Err(std::io::Error::last_os_error());
+                    return Err(error.into());
                 }
             }
+        }
 
-            Ok(read_count as usize)
-        } else {
-            Ok(0)
+        Ok(Self {
+            pipe_handle,
+            overlapped,
+            completion_event,
+            _buf: buf,
+        })
+    }
+
+    /// The event the kernel signals once this read completes.
+    #[must_use]
+    pub fn handle(&self) -> &handle::Handle {
+        self.completion_event.handle()
+    }
+
+    /// Returns the completed byte count (`0` if the write end closed), or `None` if the read
+    /// hasn't completed yet.
+    pub fn try_complete(&mut self) -> Result<Option<usize>, ReadSomeError> {
+        let mut bytes_read = 0u32;
+        unsafe {
+            match GetOverlappedResult(self.pipe_handle, self.overlapped.as_mut(), &mut bytes_read, false)
+            {
+                Ok(()) => Ok(Some(bytes_read as usize)),
+                Err(error) => {
+                    let error = io::Error::from(error);
+                    match error.raw_os_error() {
+                        Some(code)
+                            if code == windows::Win32::Foundation::ERROR_BROKEN_PIPE.0 as i32
+                                || code
+                                    == windows::Win32::Foundation::ERROR_HANDLE_EOF.0 as i32 =>
+                        {
+                            Ok(Some(0))
+                        }
+                        Some(code)
+                            if code == windows::Win32::Foundation::ERROR_IO_INCOMPLETE.0 as i32 =>
+                        {
+                            Ok(None)
+                        }
+                        _ => Err(error.into()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PendingRead<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            // cancel the read so the kernel stops writing into `_buf` as soon as we do (this is a
+            // no-op, and the following wait returns immediately, if the read already completed)
+            let _ = CancelIoEx(self.pipe_handle, Some(self.overlapped.as_mut()));
+            let mut bytes_read = 0u32;
+            let _ = GetOverlappedResult(self.pipe_handle, self.overlapped.as_mut(), &mut bytes_read, true);
+        }
+    }
+}
+
+/// Blocks until at least one of `pending`'s reads completes, or `timeout` elapses: a
+/// `WaitForMultipleObjects`-based counterpart to `WSAPoll`, letting a host loop service several
+/// injected-process pipes from one thread without spinning. Returns the index into `pending` of
+/// one ready read (call [`PendingRead::try_complete`] on it to collect the result), or `None` on
+/// timeout.
+pub fn wait_readable_many(
+    pending: &[&PendingRead],
+    timeout: Option<Duration>,
+) -> Result<Option<usize>, handle::WaitForMultipleError> {
+    let handles = pending.iter().map(|pending| pending.handle()).collect::<Vec<_>>();
+    Ok(match handle::wait_for_multiple(&handles, false, timeout)? {
+        handle::WaitForMultipleResult::Signaled(index) => Some(index),
+        handle::WaitForMultipleResult::TimedOut => None,
+    })
+}
+
+/// Drives a single [`PendingRead`] to completion, registering its completion event with the
+/// reactor on first poll rather than at construction (matching
+/// [`super::handle::WaitFuture`]). The read itself (and its cancel-on-drop behavior) lives on
+/// [`PendingRead`], which this merely polls via the reactor instead of a blocking wait.
+struct ReadSomeFuture<'a> {
+    pending: PendingRead<'a>,
+    registration: Option<reactor::Registration>,
+}
+
+impl<'a> ReadSomeFuture<'a> {
+    fn new(pending: PendingRead<'a>) -> Self {
+        Self {
+            pending,
+            registration: None,
+        }
+    }
+}
+
+impl Future for ReadSomeFuture<'_> {
+    type Output = Result<usize, ReadSomeError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.registration.is_none() {
+            this.registration = Some(unsafe { reactor::register(this.pending.handle().as_raw()) });
+        }
+        if this.registration.as_ref().unwrap().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        match this.pending.try_complete() {
+            Ok(Some(count)) => Poll::Ready(Ok(count)),
+            Ok(None) => Poll::Pending,
+            Err(error) => Poll::Ready(Err(error)),
         }
     }
 }
 
 #[derive(Debug, Error)]
-#[error("failed to create pipe")]
-pub struct NewError(#[from] std::io::Error);
+#[error("failed to read from pipe")]
+pub enum ReadSomeError {
+    NewCompletionEvent(#[from] crate::windows::event::NewError),
+    Io(#[from] io::Error),
+    WaitReadable(#[from] handle::WaitForMultipleError),
+}
+
+/// How one of a spawned child's standard streams should be configured, mirroring
+/// [`std::process::Stdio`]. Stdin (a stream the child reads from) resolves via
+/// [`Self::resolve_stdin`]; stdout and stderr (streams the child writes to) resolve via
+/// [`Self::resolve_stdout`]/[`Self::resolve_stderr`]. The two directions differ in which pipe end
+/// the child is handed and which end, if any, is handed back to the caller.
+#[derive(Debug)]
+pub enum Stdio {
+    /// The child inherits this process's corresponding standard handle.
+    Inherit,
+    /// The child's handle is connected to the null device.
+    Null,
+    /// A new pipe is created; the end the child doesn't use is returned to the caller.
+    Piped,
+}
+
+impl Stdio {
+    /// Resolves this configuration for stdin, returning the [`Reader`] to pass as the child's
+    /// standard input handle and, for [`Self::Piped`], the [`Writer`] the caller uses to feed it
+    /// bytes.
+    pub fn resolve_stdin(self) -> Result<(Reader, Option<Writer>), ResolveError> {
+        Ok(match self {
+            Self::Inherit => (
+                unsafe {
+                    Reader::from_raw_handle(
+                        GetStdHandle(STD_INPUT_HANDLE).map_err(|_| io::Error::last_os_error())?,
+                    )
+                },
+                None,
+            ),
+            Self::Null => (
+                unsafe { Reader::from_raw_handle(open_null_device()?) },
+                None,
+            ),
+            Self::Piped => {
+                let (writer, reader) = new()?;
+                (reader, Some(writer))
+            }
+        })
+    }
+
+    /// Resolves this configuration for stdout, returning the [`Writer`] to pass as the child's
+    /// standard output handle and, for [`Self::Piped`], the [`OverlappedReader`] the caller uses
+    /// to read back what the child wrote.
+    pub fn resolve_stdout(self) -> Result<(Writer, Option<OverlappedReader>), ResolveError> {
+        self.resolve_output(STD_OUTPUT_HANDLE)
+    }
+
+    /// Like [`Self::resolve_stdout`], but for stderr.
+    pub fn resolve_stderr(self) -> Result<(Writer, Option<OverlappedReader>), ResolveError> {
+        self.resolve_output(STD_ERROR_HANDLE)
+    }
+
+    fn resolve_output(
+        self,
+        std_handle: windows::Win32::System::Console::STD_HANDLE,
+    ) -> Result<(Writer, Option<OverlappedReader>), ResolveError> {
+        Ok(match self {
+            Self::Inherit => (
+                unsafe {
+                    Writer::from_raw_handle(
+                        GetStdHandle(std_handle).map_err(|_| io::Error::last_os_error())?,
+                    )
+                },
+                None,
+            ),
+            Self::Null => (
+                unsafe { Writer::from_raw_handle(open_null_device()?) },
+                None,
+            ),
+            Self::Piped => {
+                let (writer, reader) = new_overlapped()?;
+                (writer, Some(reader))
+            }
+        })
+    }
+}
+
+fn open_null_device() -> Result<HANDLE, io::Error> {
+    let path = std::ffi::CString::new("NUL").expect("\"NUL\" contains no interior nul bytes");
+    let security_attributes = SECURITY_ATTRIBUTES {
+        #[expect(clippy::cast_possible_truncation)]
+        nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: std::ptr::null_mut(),
+        bInheritHandle: true.into(),
+    };
+    unsafe {
+        CreateFileA(
+            PCSTR(path.as_ptr().cast()),
+            (GENERIC_READ | GENERIC_WRITE).0,
+            FILE_SHARE_MODE(0),
+            Some(&security_attributes),
+            OPEN_EXISTING,
+            Default::default(),
+            None,
+        )
+        .map_err(|_| io::Error::last_os_error())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to resolve stdio configuration")]
+pub enum ResolveError {
+    NewPipe(#[from] NewError),
+    NewOverlappedPipe(#[from] NewOverlappedError),
+    Os(#[from] io::Error),
+}
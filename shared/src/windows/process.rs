@@ -1,3 +1,10 @@
+pub mod codegen;
+pub mod command_line;
+pub mod environment;
+pub mod map_image;
+pub mod pattern;
+pub mod snapshot;
+
 use super::module::{self, Module};
 use crate::windows::{
     handle::{self, handle_wrapper, Handle},
@@ -12,34 +19,39 @@ use std::{
     path::Path,
 };
 use thiserror::Error;
-use winapi::{
-    shared::{
-        minwindef::{FALSE, HMODULE, TRUE},
-        ntdef::NULL,
-    },
-    um::{
-        handleapi::INVALID_HANDLE_VALUE,
-        jobapi2::{AssignProcessToJobObject, SetInformationJobObject},
-        memoryapi::{
-            ReadProcessMemory, VirtualAllocEx, VirtualFreeEx, VirtualProtectEx, VirtualQueryEx,
-            WriteProcessMemory,
-        },
-        processthreadsapi::{
-            CreateProcessW, CreateRemoteThread, GetCurrentProcess, GetExitCodeProcess,
-            GetProcessId, OpenProcess, PROCESS_INFORMATION, STARTUPINFOW,
-        },
-        psapi::{EnumProcessModulesEx, LIST_MODULES_ALL},
-        tlhelp32::{
-            CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
-        },
-        winbase::{CreateJobObjectA, CREATE_SUSPENDED, STARTF_USESTDHANDLES},
-        winnt::{
-            JobObjectExtendedLimitInformation, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_I386,
-            IMAGE_FILE_MACHINE_IA64, IMAGE_FILE_MACHINE_UNKNOWN,
-            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, MEM_COMMIT,
-            MEM_FREE, MEM_RELEASE, MEM_RESERVE, PROCESS_ALL_ACCESS,
+use zerocopy::{AsBytes, FromBytes};
+use windows::{
+    core::{PCWSTR, PWSTR},
+    Win32::{
+        Foundation::HMODULE,
+        System::{
+            Diagnostics::{
+                Debug::{
+                    ReadProcessMemory, WriteProcessMemory, IMAGE_FILE_MACHINE_AMD64,
+                    IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_IA64, IMAGE_FILE_MACHINE_UNKNOWN,
+                },
+                ToolHelp::{
+                    CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD,
+                    THREADENTRY32,
+                },
+            },
+            JobObjects::{
+                AssignProcessToJobObject, CreateJobObjectA, JobObjectExtendedLimitInformation,
+                SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+                JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+            },
+            Memory::{
+                VirtualAllocEx, VirtualFreeEx, VirtualProtectEx, VirtualQueryEx,
+                MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_FREE, MEM_RELEASE, MEM_RESERVE,
+            },
+            ProcessStatus::{EnumProcessModulesEx, LIST_MODULES_ALL},
+            Threading::{
+                CreateProcessW, CreateRemoteThread, GetCurrentProcess, GetExitCodeProcess,
+                GetProcessId, IsWow64Process2, OpenProcess, CREATE_SUSPENDED,
+                CREATE_UNICODE_ENVIRONMENT, PROCESS_ALL_ACCESS, PROCESS_INFORMATION,
+                STARTF_USESTDHANDLES, STARTUPINFOW,
+            },
         },
-        wow64apiset::IsWow64Process2,
     },
 };
 
@@ -52,20 +64,19 @@ impl Process {
     }
 
     pub fn from_id(id: u32) -> Result<Self, io::Error> {
-        let handle = unsafe { OpenProcess(PROCESS_ALL_ACCESS, FALSE, id) };
-        if handle.is_null() {
-            return Err(io::Error::last_os_error());
-        }
+        let handle = unsafe { OpenProcess(PROCESS_ALL_ACCESS, false, id) }
+            .map_err(|_| io::Error::last_os_error())?;
         unsafe { Ok(Self::from_raw_handle(handle)) }
     }
 
     pub fn create(
         executable_path: impl AsRef<Path>,
         command_line_string: impl AsRef<OsStr>,
+        environment_block: Option<Vec<u16>>,
         suspended: bool,
-        stdin_redirect: Option<pipe::Reader>,
-        stdout_redirect: Option<pipe::Writer>,
-        stderr_redirect: Option<pipe::Writer>,
+        stdin: pipe::Reader,
+        stdout: pipe::Writer,
+        stderr: pipe::Writer,
     ) -> Result<Self, CreateError> {
         let executable_path_raw = executable_path
             .as_ref()
@@ -86,13 +97,22 @@ impl Process {
             .encode_wide()
             .chain([0])
             .collect::<Vec<_>>();
+        let mut environment_block_raw = environment_block;
+        let mut creation_flags = if suspended {
+            CREATE_SUSPENDED
+        } else {
+            Default::default()
+        };
+        if environment_block_raw.is_some() {
+            creation_flags |= CREATE_UNICODE_ENVIRONMENT;
+        }
 
         let mut startup_info = STARTUPINFOW {
             #[expect(clippy::cast_possible_truncation)]
             cb: size_of::<STARTUPINFOW>() as u32,
-            lpReserved: NULL.cast(),
-            lpDesktop: NULL.cast(),
-            lpTitle: NULL.cast(),
+            lpReserved: PWSTR::null(),
+            lpDesktop: PWSTR::null(),
+            lpTitle: PWSTR::null(),
             dwX: 0,
             dwY: 0,
             dwXSize: 0,
@@ -103,41 +123,34 @@ impl Process {
             dwFlags: STARTF_USESTDHANDLES,
             wShowWindow: 0,
             cbReserved2: 0,
-            lpReserved2: NULL.cast(),
-            hStdInput: stdin_redirect
-                .map_or_else(|| NULL.cast(), |reader| unsafe { reader.leak_handle() }),
-            hStdOutput: stdout_redirect
-                .map_or_else(|| NULL.cast(), |writer| unsafe { writer.leak_handle() }),
-            hStdError: stderr_redirect
-                .map_or_else(|| NULL.cast(), |writer| unsafe { writer.leak_handle() }),
-        };
-        let mut process_information = PROCESS_INFORMATION {
-            hProcess: NULL.cast(),
-            hThread: NULL.cast(),
-            dwProcessId: 0,
-            dwThreadId: 0,
+            lpReserved2: std::ptr::null_mut(),
+            hStdInput: unsafe { stdin.leak_handle() },
+            hStdOutput: unsafe { stdout.leak_handle() },
+            hStdError: unsafe { stderr.leak_handle() },
         };
+        let mut process_information = PROCESS_INFORMATION::default();
 
         unsafe {
-            if CreateProcessW(
-                executable_path_raw.as_ptr(),
-                command_line_string_raw.as_mut_ptr(),
-                NULL.cast(),
-                NULL.cast(),
-                TRUE,
-                if suspended { CREATE_SUSPENDED } else { 0 },
-                NULL.cast(),
-                executable_directory_path_raw.as_ptr(),
-                &mut startup_info,
+            CreateProcessW(
+                PCWSTR::from_raw(executable_path_raw.as_ptr()),
+                Some(PWSTR(command_line_string_raw.as_mut_ptr())),
+                None,
+                None,
+                true,
+                creation_flags,
+                environment_block_raw
+                    .as_mut()
+                    .map(|block| block.as_mut_ptr().cast::<c_void>()),
+                PCWSTR::from_raw(executable_directory_path_raw.as_ptr()),
+                &startup_info,
                 &mut process_information,
-            ) == 0
-            {
-                return Err(io::Error::last_os_error().into());
-            }
+            )
+            .map_err(|_| io::Error::last_os_error())?;
 
             // ensure these variables are dropped after the call to CreateProcessW
             drop(executable_path_raw);
             drop(executable_directory_path_raw);
+            drop(environment_block_raw);
 
             // ensure the handle gets cleaned up correctly
             Thread::from_raw_handle(process_information.hThread);
@@ -147,14 +160,15 @@ impl Process {
     }
 
     pub fn is_64_bit(&self) -> Result<bool, CheckIs64BitError> {
-        let mut process_wow64_machine = 0;
-        let mut system_machine = 0;
+        let mut process_wow64_machine = IMAGE_FILE_MACHINE_UNKNOWN;
+        let mut system_machine = IMAGE_FILE_MACHINE_UNKNOWN;
         unsafe {
             IsWow64Process2(
                 self.handle.as_raw(),
                 &mut process_wow64_machine,
-                &mut system_machine,
-            );
+                Some(&mut system_machine),
+            )
+            .map_err(|_| io::Error::last_os_error())?;
         }
 
         let machine = if process_wow64_machine == IMAGE_FILE_MACHINE_UNKNOWN {
@@ -166,16 +180,14 @@ impl Process {
         Ok(match machine {
             IMAGE_FILE_MACHINE_I386 => false,
             IMAGE_FILE_MACHINE_AMD64 | IMAGE_FILE_MACHINE_IA64 => true,
-            _ => return Err(UnknownMachineError(machine).into()),
+            _ => return Err(UnknownMachineError(machine.0).into()),
         })
     }
 
     pub fn kill_on_current_process_exit(&self) -> Result<(), KillOnCurrentProcessExitError> {
         unsafe {
-            let job = CreateJobObjectA(NULL.cast(), NULL.cast());
-            if job == NULL {
-                return Err(io::Error::last_os_error().into());
-            }
+            let job =
+                CreateJobObjectA(None, None).map_err(|_| io::Error::last_os_error())?;
             let job = Handle::from_raw(job);
 
             let information = {
@@ -185,19 +197,16 @@ impl Process {
             };
 
             #[expect(clippy::cast_possible_truncation)]
-            if SetInformationJobObject(
+            SetInformationJobObject(
                 job.as_raw(),
                 JobObjectExtendedLimitInformation,
-                std::ptr::addr_of!(information).cast_mut().cast(),
+                std::ptr::addr_of!(information).cast(),
                 size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
-            ) == 0
-            {
-                return Err(io::Error::last_os_error().into());
-            }
+            )
+            .map_err(|_| io::Error::last_os_error())?;
 
-            if AssignProcessToJobObject(job.as_raw(), self.handle.as_raw()) == 0 {
-                return Err(io::Error::last_os_error().into());
-            }
+            AssignProcessToJobObject(job.as_raw(), self.handle.as_raw())
+                .map_err(|_| io::Error::last_os_error())?;
 
             // purposefully leak handle so that it gets closed on process exit
             let _ = job.leak();
@@ -210,7 +219,7 @@ impl Process {
         self.handle.wait().await?;
         let mut exit_code = 0;
         unsafe {
-            GetExitCodeProcess(self.handle.as_raw(), &mut exit_code);
+            let _ = GetExitCodeProcess(self.handle.as_raw(), &mut exit_code);
         }
         Ok(exit_code)
     }
@@ -238,16 +247,14 @@ impl Process {
             let mut modules = Vec::<MaybeUninit<HMODULE>>::new();
             let mut items_needed = 0;
             loop {
-                if EnumProcessModulesEx(
+                EnumProcessModulesEx(
                     self.raw_handle(),
                     modules.as_mut_ptr().cast(),
                     (modules.len() * size_of::<HMODULE>()).try_into().unwrap(),
                     &mut items_needed,
                     LIST_MODULES_ALL,
-                ) == 0
-                {
-                    return Err(io::Error::last_os_error().into());
-                }
+                )
+                .map_err(|_| io::Error::last_os_error())?;
                 items_needed /= u32::try_from(size_of::<HMODULE>()).unwrap();
 
                 if modules.len() >= items_needed as usize {
@@ -280,17 +287,17 @@ impl Process {
         let pointer = unsafe {
             VirtualAllocEx(
                 self.handle.as_raw(),
-                NULL,
+                None,
                 size,
                 MEM_COMMIT | MEM_RESERVE,
-                permissions.to_winapi_constant(),
+                permissions.to_page_protection(),
             )
         };
         if pointer.is_null() {
             return Err(io::Error::last_os_error().into());
         }
 
-        Ok(pointer.cast())
+        Ok(pointer)
     }
 
     pub fn allocate_memory_at(
@@ -302,24 +309,23 @@ impl Process {
         let pointer = unsafe {
             VirtualAllocEx(
                 self.handle.as_raw(),
-                address.cast(),
+                Some(address.cast()),
                 size,
                 MEM_COMMIT | MEM_RESERVE,
-                permissions.to_winapi_constant(),
+                permissions.to_page_protection(),
             )
         };
         if pointer.is_null() {
             return Err(io::Error::last_os_error().into());
         }
 
-        Ok(pointer.cast())
+        Ok(pointer)
     }
 
     pub fn free_memory(&self, address: *mut c_void) -> Result<(), FreeMemoryError> {
         unsafe {
-            if VirtualFreeEx(self.handle.as_raw(), address.cast(), 0, MEM_RELEASE) == 0 {
-                return Err(io::Error::last_os_error().into());
-            }
+            VirtualFreeEx(self.handle.as_raw(), address, 0, MEM_RELEASE)
+                .map_err(|_| io::Error::last_os_error())?;
         }
         Ok(())
     }
@@ -330,20 +336,18 @@ impl Process {
         size: usize,
         permissions: MemoryPermissions,
     ) -> Result<MemoryPermissions, SetMemoryPermissionsError> {
-        let mut previous_constant = 0;
+        let mut previous_protection = Default::default();
         unsafe {
-            if VirtualProtectEx(
+            VirtualProtectEx(
                 self.handle.as_raw(),
                 address.cast(),
                 size,
-                permissions.to_winapi_constant(),
-                std::ptr::addr_of_mut!(previous_constant),
-            ) == 0
-            {
-                return Err(io::Error::last_os_error().into());
-            }
+                permissions.to_page_protection(),
+                &mut previous_protection,
+            )
+            .map_err(|_| io::Error::last_os_error())?;
         }
-        Ok(MemoryPermissions::from_winapi_constant(previous_constant))
+        Ok(MemoryPermissions::from_page_protection(previous_protection))
     }
 
     pub unsafe fn read<T: Copy>(&self, address: *const T) -> Result<T, ReadMemoryError> {
@@ -356,8 +360,9 @@ impl Process {
                 address.cast(),
                 data.cast(),
                 size_of::<T>(),
-                NULL.cast(),
-            ) == 0
+                None,
+            )
+            .is_err()
             {
                 dealloc(data, Layout::array::<T>(1).unwrap());
                 return Err(io::Error::last_os_error().into());
@@ -372,66 +377,95 @@ impl Process {
     pub fn read_to_vec(&self, address: *const u8, size: usize) -> Result<Vec<u8>, ReadMemoryError> {
         let mut data = vec![0; size];
         unsafe {
-            if ReadProcessMemory(
+            ReadProcessMemory(
                 self.handle.as_raw(),
                 address.cast(),
                 data.as_mut_ptr().cast(),
                 size,
-                NULL.cast(),
-            ) == 0
-            {
-                return Err(io::Error::last_os_error().into());
-            }
+                None,
+            )
+            .map_err(|_| io::Error::last_os_error())?;
         }
         Ok(data)
     }
 
-    pub fn read_u8(&self, address: *const u8) -> Result<u8, ReadMemoryError> {
-        Ok(self.read_to_vec(address, 1)?[0])
+    /// Reads a single `T` out of the process's memory, byte-for-byte. `T` must have no invalid bit
+    /// patterns (see [`FromBytes`]), which rules out accidentally reading e.g. a `bool` or an enum
+    /// out of attacker- or target-controlled memory.
+    #[expect(clippy::missing_panics_doc)]
+    pub fn read_value<T: FromBytes>(&self, address: *const T) -> Result<T, ReadMemoryError> {
+        let bytes = self.read_to_vec(address.cast(), size_of::<T>())?;
+        Ok(T::read_from(&bytes).expect("read_to_vec returns exactly size_of::<T>() bytes"))
     }
 
-    #[expect(clippy::missing_panics_doc)]
-    pub fn read_u16(&self, address: *const u16) -> Result<u16, ReadMemoryError> {
-        Ok(u16::from_le_bytes(
-            <[u8; 2]>::try_from(self.read_to_vec(address.cast(), 2)?).unwrap(),
-        ))
+    /// Writes a single `T` into the process's memory, byte-for-byte.
+    pub fn write_value<T: AsBytes>(
+        &self,
+        address: *mut T,
+        value: &T,
+    ) -> Result<(), WriteMemoryError> {
+        self.write(address.cast(), value.as_bytes())
     }
 
+    /// Reads `count` contiguous `T`s out of the process's memory, starting at `address`.
     #[expect(clippy::missing_panics_doc)]
-    pub fn read_u32(&self, address: *const u32) -> Result<u32, ReadMemoryError> {
-        Ok(u32::from_le_bytes(
-            <[u8; 4]>::try_from(self.read_to_vec(address.cast(), 4)?).unwrap(),
-        ))
+    pub fn read_array<T: FromBytes + Copy>(
+        &self,
+        address: *const T,
+        count: usize,
+    ) -> Result<Vec<T>, ReadMemoryError> {
+        let bytes = self.read_to_vec(address.cast(), count * size_of::<T>())?;
+        Ok(T::slice_from(&bytes)
+            .expect("read_to_vec returns exactly count * size_of::<T>() bytes")
+            .to_vec())
     }
 
+    /// Reads bytes starting at `address` up to (and not including) the first NUL byte, decoding
+    /// them as UTF-8.
     #[expect(clippy::not_unsafe_ptr_arg_deref)]
-    pub fn read_nul_terminated_string(
+    pub fn read_nul_terminated_utf8(
         &self,
         address: *const u8,
-    ) -> Result<String, ReadMemoryError> {
-        let mut string = String::new();
+    ) -> Result<String, ReadNulTerminatedUtf8Error> {
+        let mut bytes = Vec::new();
         for index in 0.. {
-            let next_byte = self.read_u8(unsafe { address.add(index) })?;
+            let next_byte = self.read_value(unsafe { address.add(index) })?;
             if next_byte == 0 {
                 break;
             }
-            string.push(next_byte as char);
+            bytes.push(next_byte);
         }
-        Ok(string)
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    /// Reads `u16` code units starting at `address` up to (and not including) the first NUL unit,
+    /// decoding them as UTF-16.
+    #[expect(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn read_nul_terminated_utf16(
+        &self,
+        address: *const u16,
+    ) -> Result<String, ReadNulTerminatedUtf16Error> {
+        let mut units = Vec::new();
+        for index in 0.. {
+            let next_unit = self.read_value(unsafe { address.add(index) })?;
+            if next_unit == 0 {
+                break;
+            }
+            units.push(next_unit);
+        }
+        Ok(String::from_utf16(&units)?)
     }
 
     pub fn write(&self, address: *mut u8, data: &[u8]) -> Result<(), WriteMemoryError> {
         unsafe {
-            if WriteProcessMemory(
+            WriteProcessMemory(
                 self.handle.as_raw(),
                 address.cast(),
                 data.as_ptr().cast(),
                 data.len(),
-                NULL.cast(),
-            ) == 0
-            {
-                return Err(io::Error::last_os_error().into());
-            }
+                None,
+            )
+            .map_err(|_| io::Error::last_os_error())?;
         }
 
         Ok(())
@@ -446,21 +480,18 @@ impl Process {
         let thread_handle = unsafe {
             CreateRemoteThread(
                 self.handle.as_raw(),
-                NULL.cast(),
+                None,
                 0,
                 Some(std::mem::transmute::<
                     *mut c_void,
-                    unsafe extern "system" fn(*mut winapi::ctypes::c_void) -> u32,
+                    unsafe extern "system" fn(*mut c_void) -> u32,
                 >(start_address)),
-                parameter.map_or(NULL, <*mut _>::cast),
-                if suspended { CREATE_SUSPENDED } else { 0 },
-                NULL.cast(),
+                parameter.map(|parameter| parameter.cast_const()),
+                if suspended { CREATE_SUSPENDED.0 } else { 0 },
+                None,
             )
-        };
-
-        if thread_handle == NULL {
-            return Err(io::Error::last_os_error().into());
         }
+        .map_err(|_| io::Error::last_os_error())?;
 
         Ok(unsafe { Thread::from_raw_handle(thread_handle) })
     }
@@ -470,28 +501,28 @@ impl Process {
         address: *mut c_void,
     ) -> Result<MemoryRegion, GetMemoryRegionError> {
         unsafe {
-            let mut winapi_region = MaybeUninit::zeroed().assume_init();
+            let mut region = MaybeUninit::zeroed().assume_init();
             if VirtualQueryEx(
                 self.handle.as_raw(),
-                address.cast(),
-                &mut winapi_region,
-                size_of_val(&winapi_region),
+                Some(address.cast()),
+                &mut region,
+                size_of_val(&region),
             ) == 0
             {
                 return Err(io::Error::last_os_error().into());
             }
-            Ok(if winapi_region.State == MEM_FREE {
+            Ok(if region.State == MEM_FREE {
                 MemoryRegion::Free(FreeMemoryRegion {
-                    address: winapi_region.BaseAddress.cast(),
-                    size: winapi_region.RegionSize,
+                    address: region.BaseAddress,
+                    size: region.RegionSize,
                 })
             } else {
                 MemoryRegion::Reserved(ReservedMemoryRegion {
-                    address: winapi_region.BaseAddress.cast(),
-                    size: winapi_region.RegionSize,
-                    is_committed: winapi_region.State == MEM_COMMIT,
-                    allocation_address: winapi_region.AllocationBase.cast(),
-                    permissions: MemoryPermissions::from_winapi_constant(winapi_region.Protect),
+                    address: region.BaseAddress,
+                    size: region.RegionSize,
+                    is_committed: region.State == MEM_COMMIT,
+                    allocation_address: region.AllocationBase,
+                    permissions: MemoryPermissions::from_page_protection(region.Protect),
                 })
             })
         }
@@ -535,59 +566,12 @@ impl Process {
             .expect("kernel32.dll module not found");
         let load_library_a_pointer = kernel32_module.get_export_address("LoadLibraryA")?;
         let get_last_error_pointer = kernel32_module.get_export_address("GetLastError")?;
-        let load_dll_function = {
-            if self.is_64_bit()? {
-                let mut function = vec![
-                    // special care must be taken to preserve the initial value of rsp and to
-                    // reserve 32 bytes of shadow store for LoadLibraryA, all while ensuring the
-                    // stack is aligned to a multiple of 16 bytes when calling LoadLibraryA
-                    0x48, 0x89, 0xe0, // mov rax, rsp
-                    0x48, 0x83, 0xe4, 0xf0, // and rsp, 0xfffffffffffffff0 (aligns stack)
-                    0x50, // push rax (misaligns stack)
-                    0x48, 0x83, 0xec, 0x28, // sub rsp, 0x28 (realigns stack)
-                    //
-                    0x48, 0xb9, 0, 0, 0, 0, 0, 0, 0, 0, // mov rcx, injected_dll_path_pointer
-                    0x48, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, // mov rax, load_library_a_pointer
-                    0xff, 0xd0, // call rax
-                    0x48, 0x85, 0xc0, // test rax, rax
-                    0x48, 0xc7, 0xc0, 0x00, 0x00, 0x00, 0x00, // mov rax, 0 (preserves ZF)
-                    0x75, 0x0c, // jne return
-                    0x48, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, // mov rax, get_last_error_pointer
-                    0xff, 0xd0, // call rax
-                    // return:
-                    0x48, 0x83, 0xc4, 0x28, // add rsp, 0x28
-                    0x5c, // pop rsp
-                    0xc3, // ret
-                ];
-                function[14..][..8]
-                    .copy_from_slice(&(injected_dll_path_pointer as usize).to_le_bytes());
-                function[24..][..8]
-                    .copy_from_slice(&(load_library_a_pointer as usize).to_le_bytes());
-                function[48..][..8]
-                    .copy_from_slice(&(get_last_error_pointer as usize).to_le_bytes());
-                function
-            } else {
-                let mut function = vec![
-                    0x68, 0, 0, 0, 0, // push injected_dll_path_pointer
-                    0xb8, 0, 0, 0, 0, // mov eax, load_library_a_pointer
-                    0xff, 0xd0, // call eax
-                    0x85, 0xc0, // test eax, eax
-                    0xb8, 0x00, 0x00, 0x00, 0x00, // mov eax, 0 (preserves ZF)
-                    0x75, 0x07, // jne return
-                    0xb8, 0, 0, 0, 0, // mov eax, get_last_error_pointer
-                    0xff, 0xd0, // call eax
-                    // return:
-                    0xc3, // ret
-                ];
-                function[1..][..4]
-                    .copy_from_slice(&(injected_dll_path_pointer as usize).to_le_bytes()[..4]);
-                function[6..][..4]
-                    .copy_from_slice(&(load_library_a_pointer as usize).to_le_bytes()[..4]);
-                function[22..][..4]
-                    .copy_from_slice(&(get_last_error_pointer as usize).to_le_bytes()[..4]);
-                function
-            }
-        };
+        let load_dll_function = codegen::call_checked_stub(
+            if self.is_64_bit()? { 64 } else { 32 },
+            injected_dll_path_pointer as u64,
+            load_library_a_pointer as u64,
+            get_last_error_pointer as u64,
+        )?;
         let load_dll_function_pointer = self.allocate_memory(
             load_dll_function.len(),
             MemoryPermissions {
@@ -608,6 +592,43 @@ impl Process {
             }
         }
     }
+
+    /// The inverse of [`Self::inject_dll`]: unloads `module` (previously loaded into this process,
+    /// e.g. by `inject_dll`) by running a generated `FreeLibrary` stub on a remote thread, checking
+    /// its `BOOL` return and surfacing `GetLastError` on failure the same way `inject_dll` does for
+    /// `LoadLibraryA`.
+    pub async fn eject_dll(&self, module: &Module<'_>) -> Result<(), EjectDllError> {
+        let kernel32_module = self
+            .get_module(OsStr::new("kernel32.dll"))?
+            .expect("kernel32.dll module not found");
+        let free_library_pointer = kernel32_module.get_export_address("FreeLibrary")?;
+        let get_last_error_pointer = kernel32_module.get_export_address("GetLastError")?;
+        let free_dll_function = codegen::call_checked_stub(
+            if self.is_64_bit()? { 64 } else { 32 },
+            module.get_base_address() as u64,
+            free_library_pointer as u64,
+            get_last_error_pointer as u64,
+        )?;
+        let free_dll_function_pointer = self.allocate_memory(
+            free_dll_function.len(),
+            MemoryPermissions {
+                rwe: MemoryPermissionsRwe::ReadExecute,
+                is_guard: false,
+            },
+        )?;
+        self.write(free_dll_function_pointer.cast(), &free_dll_function)?;
+
+        unsafe {
+            match self
+                .create_thread(free_dll_function_pointer, false, None)?
+                .join()
+                .await?
+            {
+                0 => Ok(()),
+                error_code => Err(FreeLibraryThreadError { error_code }.into()),
+            }
+        }
+    }
 }
 
 pub enum MemoryRegion {
@@ -703,9 +724,23 @@ pub enum MemoryPermissionsRwe {
     ReadWriteExecute = 0x40,
 }
 
+impl MemoryPermissionsRwe {
+    /// Whether memory with these permissions can be read at all, i.e. it isn't [`Self::Unknown`],
+    /// [`Self::None`], or execute-only.
+    pub(crate) fn is_readable(self) -> bool {
+        matches!(
+            self,
+            Self::Read | Self::ReadWrite | Self::ReadWriteCow | Self::ReadExecute | Self::ReadWriteExecute
+        )
+    }
+}
+
 impl MemoryPermissions {
     #[must_use]
-    pub fn from_winapi_constant(constant: u32) -> Self {
+    pub fn from_page_protection(
+        protection: windows::Win32::System::Memory::PAGE_PROTECTION_FLAGS,
+    ) -> Self {
+        let constant = protection.0;
         let guard = constant & 0x100 != 0;
         let rwe = match constant & 0xff {
             0x0 => MemoryPermissionsRwe::Unknown,
@@ -725,7 +760,7 @@ impl MemoryPermissions {
     }
 
     #[must_use]
-    pub fn to_winapi_constant(&self) -> u32 {
+    pub fn to_page_protection(&self) -> windows::Win32::System::Memory::PAGE_PROTECTION_FLAGS {
         let rwe = match self.rwe {
             MemoryPermissionsRwe::Unknown => 0x0,
             MemoryPermissionsRwe::None => 0x1,
@@ -736,7 +771,9 @@ impl MemoryPermissions {
             MemoryPermissionsRwe::ReadExecute => 0x20,
             MemoryPermissionsRwe::ReadWriteExecute => 0x40,
         };
-        (if self.is_guard { 0x100 } else { 0 }) | rwe
+        windows::Win32::System::Memory::PAGE_PROTECTION_FLAGS(
+            (if self.is_guard { 0x100 } else { 0 }) | rwe,
+        )
     }
 }
 
@@ -750,10 +787,8 @@ impl ThreadIdIterator {
     pub(in crate::windows::process) fn new(
         process_id: u32,
     ) -> Result<Self, NewThreadIdIteratorError> {
-        let snapshot_handle = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
-        if snapshot_handle == INVALID_HANDLE_VALUE {
-            return Err(io::Error::last_os_error().into());
-        }
+        let snapshot_handle = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) }
+            .map_err(|_| io::Error::last_os_error())?;
 
         Ok(ThreadIdIterator {
             process_id,
@@ -788,7 +823,8 @@ impl Iterator for ThreadIdIterator {
                     self.called_thread_32_first = true;
                     Thread32First(self.snapshot_handle.as_raw(), &mut entry)
                 }
-            } != 0;
+            }
+            .is_ok();
 
             if !next_thread_exists {
                 return None;
@@ -866,6 +902,22 @@ pub struct ReadMemoryError(#[from] io::Error);
 #[error("failed to write to memory")]
 pub struct WriteMemoryError(#[from] io::Error);
 
+#[derive(Debug, Error)]
+pub enum ReadNulTerminatedUtf8Error {
+    #[error("failed to read from memory")]
+    ReadMemory(#[from] ReadMemoryError),
+    #[error("nul-terminated bytes were not valid UTF-8")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+}
+
+#[derive(Debug, Error)]
+pub enum ReadNulTerminatedUtf16Error {
+    #[error("failed to read from memory")]
+    ReadMemory(#[from] ReadMemoryError),
+    #[error("nul-terminated code units were not valid UTF-16")]
+    InvalidUtf16(#[from] std::string::FromUtf16Error),
+}
+
 #[derive(Debug, Error)]
 #[error("failed to create thread")]
 pub struct CreateThreadError(#[from] io::Error);
@@ -887,6 +939,7 @@ pub enum InjectDllError {
     JoinThread(#[from] crate::windows::thread::JoinError),
     LoadLibraryThread(#[from] LoadLibraryThreadError),
     CheckIs64Bit(#[from] CheckIs64BitError),
+    Codegen(#[from] codegen::CodegenError),
 }
 
 #[derive(Debug, Error)]
@@ -899,6 +952,26 @@ pub struct LoadLibraryThreadError {
 #[error("library path contains nul")]
 pub struct LibraryPathContainsNulError(#[from] NulError);
 
+#[derive(Debug, Error)]
+#[error("failed to eject dll")]
+pub enum EjectDllError {
+    GetModules(#[from] GetModulesError),
+    ModuleGetExportAddress(#[from] module::GetExportAddressError),
+    AllocateMemory(#[from] AllocateMemoryError),
+    WriteMemory(#[from] WriteMemoryError),
+    CreateThread(#[from] CreateThreadError),
+    JoinThread(#[from] crate::windows::thread::JoinError),
+    FreeLibraryThread(#[from] FreeLibraryThreadError),
+    CheckIs64Bit(#[from] CheckIs64BitError),
+    Codegen(#[from] codegen::CodegenError),
+}
+
+#[derive(Debug, Error)]
+#[error("library freeing thread returned with error code 0x{error_code:x}")]
+pub struct FreeLibraryThreadError {
+    error_code: u32,
+}
+
 #[derive(Debug, Error)]
 #[error("failed to create thread id iterator")]
 pub struct NewThreadIdIteratorError(#[from] io::Error);
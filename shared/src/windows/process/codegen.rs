@@ -0,0 +1,102 @@
+use iced_x86::code_asm::{eax, rax, rcx, rsp, CodeAssembler, CodeLabel};
+use iced_x86::IcedError;
+use thiserror::Error;
+
+/// Assembles a stub that calls `target_function(argument)` and leaves `0` in `eax`/`rax` if it
+/// returned nonzero; if it returned zero (a null/`FALSE` failure), the stub instead calls
+/// `GetLastError()` so the failing call's Win32 error code ends up there.
+/// This is the pattern both `Process::inject_dll`'s `LoadLibraryA` call and
+/// `Process::eject_dll`'s `FreeLibrary` call need: run a single-argument Win32 function on a
+/// remote thread and surface why it failed through the thread's exit code. `bitness` (`32` or
+/// `64`) selects which calling convention and register widths to encode; the three addresses are
+/// resolved as assemble-time constants rather than patched into the encoded bytes afterwards.
+pub fn call_checked_stub(
+    bitness: u32,
+    argument: u64,
+    target_function_pointer: u64,
+    get_last_error_pointer: u64,
+) -> Result<Vec<u8>, CodegenError> {
+    let mut assembler = CodeAssembler::new(bitness)?;
+    let mut return_label = assembler.create_label();
+
+    if bitness == 64 {
+        assemble_x64(
+            &mut assembler,
+            &mut return_label,
+            argument,
+            target_function_pointer,
+            get_last_error_pointer,
+        )?;
+    } else {
+        assemble_x86(
+            &mut assembler,
+            &mut return_label,
+            argument,
+            target_function_pointer,
+            get_last_error_pointer,
+        )?;
+    }
+
+    Ok(assembler.assemble(0)?)
+}
+
+fn assemble_x64(
+    assembler: &mut CodeAssembler,
+    return_label: &mut CodeLabel,
+    argument: u64,
+    target_function_pointer: u64,
+    get_last_error_pointer: u64,
+) -> Result<(), IcedError> {
+    // preserve the incoming rsp and reserve 32 bytes of shadow store for the callee, all while
+    // ensuring the stack is aligned to a multiple of 16 bytes when calling it
+    assembler.mov(rax, rsp)?;
+    assembler.and(rsp, 0xffff_ffff_ffff_fff0u64 as i64)?;
+    assembler.push(rax)?;
+    assembler.sub(rsp, 0x28)?;
+
+    assembler.mov(rcx, argument)?;
+    assembler.mov(rax, target_function_pointer)?;
+    assembler.call(rax)?;
+    assembler.test(rax, rax)?;
+    assembler.mov(rax, 0u64)?; // preserves ZF
+    assembler.jne(*return_label)?;
+    assembler.mov(rax, get_last_error_pointer)?;
+    assembler.call(rax)?;
+
+    assembler.set_label(return_label)?;
+    assembler.add(rsp, 0x28)?;
+    assembler.pop(rsp)?;
+    assembler.ret()?;
+    Ok(())
+}
+
+fn assemble_x86(
+    assembler: &mut CodeAssembler,
+    return_label: &mut CodeLabel,
+    argument: u64,
+    target_function_pointer: u64,
+    get_last_error_pointer: u64,
+) -> Result<(), IcedError> {
+    let argument = u32::try_from(argument).expect("argument fits in 32 bits on an x86 target");
+    let target_function_pointer = u32::try_from(target_function_pointer)
+        .expect("function pointer fits in 32 bits on an x86 target");
+    let get_last_error_pointer = u32::try_from(get_last_error_pointer)
+        .expect("function pointer fits in 32 bits on an x86 target");
+
+    assembler.push(argument)?;
+    assembler.mov(eax, target_function_pointer)?;
+    assembler.call(eax)?;
+    assembler.test(eax, eax)?;
+    assembler.mov(eax, 0u32)?; // preserves ZF
+    assembler.jne(*return_label)?;
+    assembler.mov(eax, get_last_error_pointer)?;
+    assembler.call(eax)?;
+
+    assembler.set_label(return_label)?;
+    assembler.ret()?;
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+#[error("failed to assemble stub")]
+pub struct CodegenError(#[from] IcedError);
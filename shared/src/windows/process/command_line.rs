@@ -0,0 +1,76 @@
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+};
+use thiserror::Error;
+
+/// Builds the single command-line string [`super::Process::create`] expects out of individual
+/// arguments, quoting each one (see [`quote_argument`]) so it round-trips back into the same argv
+/// the caller passed in once the child parses it with `CommandLineToArgvW`.
+pub fn command_line_from_args(
+    arguments: impl IntoIterator<Item = impl AsRef<OsStr>>,
+) -> Result<OsString, ArgumentError> {
+    let mut command_line = OsString::new();
+    for (index, argument) in arguments.into_iter().enumerate() {
+        if index > 0 {
+            command_line.push(" ");
+        }
+        command_line.push(quote_argument(argument, false)?);
+    }
+    Ok(command_line)
+}
+
+/// Quotes a single argument the way `CommandLineToArgvW` (and `std`'s own process spawner)
+/// expects: `argument` is wrapped in `"` if it is empty, contains a space or tab, or
+/// `force_quotes` is set. Backslashes are only special immediately before a quote (or the closing
+/// quote added by this function), where they must be doubled to keep the quote they precede from
+/// being read as an escape; elsewhere they pass through unchanged.
+pub fn quote_argument(
+    argument: impl AsRef<OsStr>,
+    force_quotes: bool,
+) -> Result<OsString, ArgumentError> {
+    let units: Vec<u16> = argument.as_ref().encode_wide().collect();
+    if units.contains(&0) {
+        return Err(ArgumentError::InteriorNul);
+    }
+
+    const QUOTE: u16 = b'"' as u16;
+    const BACKSLASH: u16 = b'\\' as u16;
+    const SPACE: u16 = b' ' as u16;
+    const TAB: u16 = b'\t' as u16;
+
+    let needs_quotes =
+        force_quotes || units.is_empty() || units.contains(&SPACE) || units.contains(&TAB);
+
+    let mut output = Vec::with_capacity(units.len() + 2);
+    if needs_quotes {
+        output.push(QUOTE);
+    }
+
+    let mut backslash_count = 0usize;
+    for &unit in &units {
+        if unit == BACKSLASH {
+            backslash_count += 1;
+            output.push(unit);
+        } else {
+            if unit == QUOTE {
+                output.extend(std::iter::repeat(BACKSLASH).take(backslash_count + 1));
+            }
+            backslash_count = 0;
+            output.push(unit);
+        }
+    }
+
+    if needs_quotes {
+        output.extend(std::iter::repeat(BACKSLASH).take(backslash_count));
+        output.push(QUOTE);
+    }
+
+    Ok(OsString::from_wide(&output))
+}
+
+#[derive(Debug, Error)]
+pub enum ArgumentError {
+    #[error("argument contains an interior NUL")]
+    InteriorNul,
+}
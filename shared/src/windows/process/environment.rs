@@ -0,0 +1,36 @@
+use std::{ffi::OsString, os::windows::ffi::OsStrExt};
+use thiserror::Error;
+
+/// Builds the UTF-16 environment block [`super::Process::create`] expects out of `entries`
+/// (already deduplicated and in the order they should appear): each pair is encoded as a
+/// `KEY=VALUE\0` run, with a final NUL terminating the whole block.
+pub fn environment_block(
+    entries: impl IntoIterator<Item = (OsString, OsString)>,
+) -> Result<Vec<u16>, EnvironmentBlockError> {
+    let mut block = Vec::new();
+    for (key, value) in entries {
+        let key_units: Vec<u16> = key.encode_wide().collect();
+        if key_units.contains(&0) || key_units.contains(&u16::from(b'=')) {
+            return Err(EnvironmentBlockError::InvalidKey);
+        }
+        let value_units: Vec<u16> = value.encode_wide().collect();
+        if value_units.contains(&0) {
+            return Err(EnvironmentBlockError::InvalidValue);
+        }
+
+        block.extend(key_units);
+        block.push(u16::from(b'='));
+        block.extend(value_units);
+        block.push(0);
+    }
+    block.push(0);
+    Ok(block)
+}
+
+#[derive(Debug, Error)]
+pub enum EnvironmentBlockError {
+    #[error("environment variable key contains an interior NUL or `=`")]
+    InvalidKey,
+    #[error("environment variable value contains an interior NUL")]
+    InvalidValue,
+}
@@ -0,0 +1,518 @@
+use super::{MemoryPermissions, MemoryPermissionsRwe, Process};
+use crate::windows::module;
+use std::{ffi::c_void, ptr};
+use thiserror::Error;
+use windows::Win32::System::Diagnostics::Debug::{
+    IMAGE_DOS_HEADER, IMAGE_FILE_HEADER, IMAGE_OPTIONAL_HEADER32, IMAGE_OPTIONAL_HEADER64,
+    IMAGE_SECTION_HEADER,
+};
+
+const IMAGE_DIRECTORY_ENTRY_BASERELOC: usize = 5;
+const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+
+const IMAGE_REL_BASED_ABSOLUTE: u16 = 0;
+const IMAGE_REL_BASED_HIGHLOW: u16 = 3;
+const IMAGE_REL_BASED_DIR64: u16 = 10;
+
+const IMAGE_ORDINAL_FLAG32: u32 = 0x8000_0000;
+const IMAGE_ORDINAL_FLAG64: u64 = 0x8000_0000_0000_0000;
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+
+const DLL_PROCESS_ATTACH: u32 = 1;
+
+impl Process {
+    /// Manually maps the PE image `image` (the full, unmodified contents of a DLL file) into this
+    /// process without ever calling `LoadLibraryA` or writing it to disk: sections are copied to a
+    /// freshly allocated region, base relocations and imports are resolved locally against that
+    /// region's address, and `DllMain` is invoked directly from a small stub run via
+    /// [`Self::create_thread`]. Unlike [`Self::inject_dll`], the mapped image never appears in the
+    /// process's module list, so callers that need [`super::Module`]-based lookups against it
+    /// (e.g. [`Self::get_module`]) cannot use those afterwards.
+    ///
+    /// The imported modules referenced by `image` must already be loaded in this process; this
+    /// does not call `LoadLibraryA` to load missing ones.
+    pub async fn map_image(&self, image: &[u8]) -> Result<*mut c_void, MapImageError> {
+        let dos_header = read_struct::<IMAGE_DOS_HEADER>(image, 0)?;
+        if dos_header.e_magic != 0x5a4d {
+            return Err(InvalidImageError.into());
+        }
+
+        #[expect(clippy::cast_sign_loss)]
+        let pe_header_offset = dos_header.e_lfanew as usize;
+        if image.get(pe_header_offset..pe_header_offset + 4) != Some(&[0x50, 0x45, 0x0, 0x0]) {
+            return Err(InvalidImageError.into());
+        }
+        let file_header_offset = pe_header_offset + 4;
+        let file_header = read_struct::<IMAGE_FILE_HEADER>(image, file_header_offset)?;
+        let optional_header_offset = file_header_offset + size_of::<IMAGE_FILE_HEADER>();
+        let optional_header_magic = image
+            .get(optional_header_offset..optional_header_offset + 2)
+            .ok_or(InvalidImageError)?;
+        let header = match (optional_header_magic[0], optional_header_magic[1]) {
+            (0xb, 0x1) => {
+                OptionalHeader::Header32(read_struct(image, optional_header_offset)?)
+            }
+            (0xb, 0x2) => {
+                OptionalHeader::Header64(read_struct(image, optional_header_offset)?)
+            }
+            _ => return Err(InvalidImageError.into()),
+        };
+
+        let section_headers_offset =
+            optional_header_offset + file_header.SizeOfOptionalHeader as usize;
+        let sections: Vec<IMAGE_SECTION_HEADER> = (0..file_header.NumberOfSections as usize)
+            .map(|index| {
+                read_struct(
+                    image,
+                    section_headers_offset + index * size_of::<IMAGE_SECTION_HEADER>(),
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        let size_of_image = header.size_of_image() as usize;
+        let base_address = self.allocate_memory(
+            size_of_image,
+            MemoryPermissions {
+                rwe: MemoryPermissionsRwe::ReadWrite,
+                is_guard: false,
+            },
+        )?;
+        let delta = base_address as i64 - header.image_base() as i64;
+
+        let mut mapped_image = vec![0u8; size_of_image];
+        let size_of_headers = header.size_of_headers() as usize;
+        write_bytes(
+            &mut mapped_image,
+            0,
+            image
+                .get(..size_of_headers)
+                .ok_or(InvalidImageError)?,
+        )?;
+        for section in &sections {
+            let raw_size = section.SizeOfRawData as usize;
+            if raw_size == 0 {
+                continue;
+            }
+            let raw_offset = section.PointerToRawData as usize;
+            let virtual_offset = section.VirtualAddress as usize;
+            write_bytes(
+                &mut mapped_image,
+                virtual_offset,
+                image
+                    .get(raw_offset..raw_offset + raw_size)
+                    .ok_or(InvalidImageError)?,
+            )?;
+        }
+
+        apply_base_relocations(&mut mapped_image, &header, delta)?;
+        self.resolve_imports(&mut mapped_image, &header)?;
+
+        self.write(base_address.cast(), &mapped_image)?;
+        for section in &sections {
+            if section.SizeOfRawData == 0 && unsafe { section.Misc.VirtualSize } == 0 {
+                continue;
+            }
+            let section_address = unsafe {
+                base_address
+                    .byte_add(section.VirtualAddress as usize)
+                    .cast()
+            };
+            let section_size = (section.SizeOfRawData.max(unsafe { section.Misc.VirtualSize }))
+                as usize;
+            self.set_memory_permissions(
+                section_address,
+                section_size,
+                section_characteristics_to_permissions(section.Characteristics),
+            )?;
+        }
+
+        let entry_point = unsafe {
+            base_address
+                .byte_add(header.address_of_entry_point() as usize)
+                .cast()
+        };
+        let call_dll_main = build_call_dll_main_stub(self.is_64_bit()?, base_address, entry_point);
+        let call_dll_main_pointer = self.allocate_memory(
+            call_dll_main.len(),
+            MemoryPermissions {
+                rwe: MemoryPermissionsRwe::ReadExecute,
+                is_guard: false,
+            },
+        )?;
+        self.write(call_dll_main_pointer.cast(), &call_dll_main)?;
+
+        unsafe {
+            match self
+                .create_thread(call_dll_main_pointer, false, None)?
+                .join()
+                .await?
+            {
+                0 => Err(DllMainFailedError.into()),
+                _ => Ok(base_address),
+            }
+        }
+    }
+
+    fn resolve_imports(
+        &self,
+        mapped_image: &mut [u8],
+        header: &OptionalHeader,
+    ) -> Result<(), MapImageError> {
+        let Some(import_directory_entry) = header.data_directory_entry(IMAGE_DIRECTORY_ENTRY_IMPORT)
+        else {
+            return Ok(());
+        };
+        if import_directory_entry.1 == 0 {
+            return Ok(());
+        }
+
+        let is_64_bit = self.is_64_bit()?;
+        let mut descriptor_offset = import_directory_entry.0 as usize;
+        loop {
+            let descriptor = read_struct::<RawImportDescriptor>(mapped_image, descriptor_offset)?;
+            if descriptor.name_rva == 0 {
+                break;
+            }
+
+            let module_name = read_c_str(mapped_image, descriptor.name_rva as usize)?;
+            let module_name = std::ffi::OsString::from(module_name);
+            let imported_module = match self.get_module(&module_name)? {
+                Some(imported_module) => imported_module,
+                None => return Err(ImportedModuleNotLoadedError(module_name).into()),
+            };
+
+            let original_first_thunk = if descriptor.original_first_thunk_rva != 0 {
+                descriptor.original_first_thunk_rva
+            } else {
+                descriptor.first_thunk_rva
+            };
+
+            for entry_index in 0.. {
+                let thunk_size = if is_64_bit { 8 } else { 4 };
+                let name_thunk_offset =
+                    original_first_thunk as usize + entry_index * thunk_size;
+                let iat_offset = descriptor.first_thunk_rva as usize + entry_index * thunk_size;
+
+                let export_address = if is_64_bit {
+                    let thunk = read_struct::<u64>(mapped_image, name_thunk_offset)?;
+                    if thunk == 0 {
+                        break;
+                    }
+                    self.resolve_import_thunk_64(mapped_image, &imported_module, thunk)?
+                } else {
+                    let thunk = read_struct::<u32>(mapped_image, name_thunk_offset)?;
+                    if thunk == 0 {
+                        break;
+                    }
+                    self.resolve_import_thunk_32(mapped_image, &imported_module, thunk)?
+                };
+
+                if is_64_bit {
+                    write_bytes(
+                        mapped_image,
+                        iat_offset,
+                        &(export_address as u64).to_le_bytes(),
+                    )?;
+                } else {
+                    #[expect(clippy::cast_possible_truncation)]
+                    let export_address = export_address as u32;
+                    write_bytes(mapped_image, iat_offset, &export_address.to_le_bytes())?;
+                }
+            }
+
+            descriptor_offset += size_of::<RawImportDescriptor>();
+        }
+
+        Ok(())
+    }
+
+    fn resolve_import_thunk_64(
+        &self,
+        mapped_image: &[u8],
+        imported_module: &module::Module<'_>,
+        thunk: u64,
+    ) -> Result<*mut c_void, MapImageError> {
+        Ok(if thunk & IMAGE_ORDINAL_FLAG64 != 0 {
+            #[expect(clippy::cast_possible_truncation)]
+            imported_module.get_export_address_by_ordinal(thunk as u16)?
+        } else {
+            let name = read_c_str(mapped_image, thunk as usize + 2)?;
+            imported_module.get_export_address(&name)?
+        })
+    }
+
+    fn resolve_import_thunk_32(
+        &self,
+        mapped_image: &[u8],
+        imported_module: &module::Module<'_>,
+        thunk: u32,
+    ) -> Result<*mut c_void, MapImageError> {
+        Ok(if thunk & IMAGE_ORDINAL_FLAG32 != 0 {
+            #[expect(clippy::cast_possible_truncation)]
+            imported_module.get_export_address_by_ordinal(thunk as u16)?
+        } else {
+            let name = read_c_str(mapped_image, thunk as usize + 2)?;
+            imported_module.get_export_address(&name)?
+        })
+    }
+}
+
+/// Walks the `.reloc` directory and fixes up every entry by adding `delta` (the difference
+/// between the chosen allocation base and the image's preferred `ImageBase`) to the value already
+/// at that offset.
+fn apply_base_relocations(
+    mapped_image: &mut [u8],
+    header: &OptionalHeader,
+    delta: i64,
+) -> Result<(), MapImageError> {
+    if delta == 0 {
+        return Ok(());
+    }
+    let Some(reloc_directory_entry) = header.data_directory_entry(IMAGE_DIRECTORY_ENTRY_BASERELOC)
+    else {
+        return Ok(());
+    };
+    let (directory_rva, directory_size) = reloc_directory_entry;
+    if directory_size == 0 {
+        return Ok(());
+    }
+
+    let mut block_offset = directory_rva as usize;
+    let directory_end = directory_rva as usize + directory_size as usize;
+    while block_offset < directory_end {
+        let block = read_struct::<RawBaseRelocationBlock>(mapped_image, block_offset)?;
+        if block.size_of_block == 0 {
+            break;
+        }
+
+        let entry_count =
+            (block.size_of_block as usize - size_of::<RawBaseRelocationBlock>()) / 2;
+        for entry_index in 0..entry_count {
+            let entry_offset =
+                block_offset + size_of::<RawBaseRelocationBlock>() + entry_index * 2;
+            let entry = read_struct::<u16>(mapped_image, entry_offset)?;
+            let relocation_type = entry >> 12;
+            let page_offset = (entry & 0xfff) as usize;
+            let target_offset = block.page_rva as usize + page_offset;
+
+            match relocation_type {
+                t if t == IMAGE_REL_BASED_ABSOLUTE => {}
+                t if t == IMAGE_REL_BASED_HIGHLOW => {
+                    let value = read_struct::<u32>(mapped_image, target_offset)?;
+                    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let patched = (value as i64 + delta) as u32;
+                    write_bytes(mapped_image, target_offset, &patched.to_le_bytes())?;
+                }
+                t if t == IMAGE_REL_BASED_DIR64 => {
+                    let value = read_struct::<u64>(mapped_image, target_offset)?;
+                    #[expect(clippy::cast_sign_loss)]
+                    let patched = (value as i64 + delta) as u64;
+                    write_bytes(mapped_image, target_offset, &patched.to_le_bytes())?;
+                }
+                _ => return Err(InvalidImageError.into()),
+            }
+        }
+
+        block_offset += block.size_of_block as usize;
+    }
+
+    Ok(())
+}
+
+/// Builds a tiny architecture-appropriate stub that calls `entry_point` as
+/// `DllMain(base_address, DLL_PROCESS_ATTACH, 0)`, the same way the Windows loader invokes a
+/// freshly mapped DLL's entry point.
+fn build_call_dll_main_stub(
+    is_64_bit: bool,
+    base_address: *mut c_void,
+    entry_point: *mut c_void,
+) -> Vec<u8> {
+    if is_64_bit {
+        let mut stub = vec![
+            // preserve and realign rsp the same way inject_dll's LoadLibraryA stub does
+            0x48, 0x89, 0xe0, // mov rax, rsp
+            0x48, 0x83, 0xe4, 0xf0, // and rsp, 0xfffffffffffffff0
+            0x50, // push rax
+            0x48, 0x83, 0xec, 0x28, // sub rsp, 0x28
+            //
+            0x48, 0xb9, 0, 0, 0, 0, 0, 0, 0, 0, // mov rcx, base_address
+            0x48, 0xba, 0, 0, 0, 0, 0, 0, 0, 0, // mov rdx, DLL_PROCESS_ATTACH
+            0x4d, 0x31, 0xc0, // xor r8, r8
+            0x48, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, // mov rax, entry_point
+            0xff, 0xd0, // call rax
+            //
+            0x48, 0x83, 0xc4, 0x28, // add rsp, 0x28
+            0x5c, // pop rsp
+            0xc3, // ret
+        ];
+        stub[14..][..8].copy_from_slice(&(base_address as usize).to_le_bytes());
+        stub[24..][..8].copy_from_slice(&u64::from(DLL_PROCESS_ATTACH).to_le_bytes());
+        stub[37..][..8].copy_from_slice(&(entry_point as usize).to_le_bytes());
+        stub
+    } else {
+        let mut stub = vec![
+            0x6a, 0x00, // push 0
+            0x6a, 0x01, // push DLL_PROCESS_ATTACH
+            0x68, 0, 0, 0, 0, // push base_address
+            0xb8, 0, 0, 0, 0, // mov eax, entry_point
+            0xff, 0xd0, // call eax
+            0xc3, // ret
+        ];
+        stub[5..][..4].copy_from_slice(&(base_address as usize).to_le_bytes()[..4]);
+        stub[10..][..4].copy_from_slice(&(entry_point as usize).to_le_bytes()[..4]);
+        stub
+    }
+}
+
+fn section_characteristics_to_permissions(characteristics: u32) -> MemoryPermissions {
+    let readable = characteristics & IMAGE_SCN_MEM_READ != 0;
+    let writable = characteristics & IMAGE_SCN_MEM_WRITE != 0;
+    let executable = characteristics & IMAGE_SCN_MEM_EXECUTE != 0;
+    let rwe = match (readable, writable, executable) {
+        (false, false, false) => MemoryPermissionsRwe::None,
+        (true, false, false) => MemoryPermissionsRwe::Read,
+        (_, true, false) => MemoryPermissionsRwe::ReadWrite,
+        (_, false, true) => MemoryPermissionsRwe::ReadExecute,
+        (_, true, true) => MemoryPermissionsRwe::ReadWriteExecute,
+    };
+    MemoryPermissions {
+        rwe,
+        is_guard: false,
+    }
+}
+
+enum OptionalHeader {
+    Header32(IMAGE_OPTIONAL_HEADER32),
+    Header64(IMAGE_OPTIONAL_HEADER64),
+}
+
+impl OptionalHeader {
+    fn image_base(&self) -> u64 {
+        match self {
+            Self::Header32(header) => u64::from(header.ImageBase),
+            Self::Header64(header) => header.ImageBase,
+        }
+    }
+
+    fn size_of_image(&self) -> u32 {
+        match self {
+            Self::Header32(header) => header.SizeOfImage,
+            Self::Header64(header) => header.SizeOfImage,
+        }
+    }
+
+    fn size_of_headers(&self) -> u32 {
+        match self {
+            Self::Header32(header) => header.SizeOfHeaders,
+            Self::Header64(header) => header.SizeOfHeaders,
+        }
+    }
+
+    fn address_of_entry_point(&self) -> u32 {
+        match self {
+            Self::Header32(header) => header.AddressOfEntryPoint,
+            Self::Header64(header) => header.AddressOfEntryPoint,
+        }
+    }
+
+    fn data_directory_entry_count(&self) -> u32 {
+        match self {
+            Self::Header32(header) => header.NumberOfRvaAndSizes,
+            Self::Header64(header) => header.NumberOfRvaAndSizes,
+        }
+    }
+
+    /// Returns `(VirtualAddress, Size)` of the `index`th data directory entry, or `None` if the
+    /// image's optional header doesn't have that many entries.
+    fn data_directory_entry(&self, index: usize) -> Option<(u32, u32)> {
+        if index as u32 >= self.data_directory_entry_count() {
+            return None;
+        }
+        let entry = match self {
+            Self::Header32(header) => header.DataDirectory[index],
+            Self::Header64(header) => header.DataDirectory[index],
+        };
+        (entry.VirtualAddress != 0 || entry.Size != 0)
+            .then_some((entry.VirtualAddress, entry.Size))
+    }
+}
+
+/// Layout-compatible with `IMAGE_IMPORT_DESCRIPTOR`, named here in terms of what each field is
+/// actually used for rather than the union-typed `windows` crate definition.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawImportDescriptor {
+    original_first_thunk_rva: u32,
+    time_date_stamp: u32,
+    forwarder_chain: u32,
+    name_rva: u32,
+    first_thunk_rva: u32,
+}
+
+/// Layout-compatible with `IMAGE_BASE_RELOCATION`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawBaseRelocationBlock {
+    page_rva: u32,
+    size_of_block: u32,
+}
+
+fn read_struct<T: Copy>(bytes: &[u8], offset: usize) -> Result<T, InvalidImageError> {
+    let end = offset.checked_add(size_of::<T>()).ok_or(InvalidImageError)?;
+    let slice = bytes.get(offset..end).ok_or(InvalidImageError)?;
+    Ok(unsafe { ptr::read_unaligned(slice.as_ptr().cast()) })
+}
+
+/// Copies `value` into `bytes` at `offset`, the write-side counterpart to [`read_struct`]/
+/// [`read_c_str`]'s bounds-checked reads - section headers, relocations, and import thunks are all
+/// untrusted offsets read out of `image` itself, so a malformed one must not be able to write
+/// outside `mapped_image`.
+fn write_bytes(bytes: &mut [u8], offset: usize, value: &[u8]) -> Result<(), InvalidImageError> {
+    let end = offset.checked_add(value.len()).ok_or(InvalidImageError)?;
+    bytes
+        .get_mut(offset..end)
+        .ok_or(InvalidImageError)?
+        .copy_from_slice(value);
+    Ok(())
+}
+
+fn read_c_str(bytes: &[u8], offset: usize) -> Result<String, InvalidImageError> {
+    let rest = bytes.get(offset..).ok_or(InvalidImageError)?;
+    let nul_offset = rest
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or(InvalidImageError)?;
+    String::from_utf8(rest[..nul_offset].to_vec()).map_err(|_| InvalidImageError)
+}
+
+#[derive(Debug, Error)]
+#[error("image is not a valid PE file, or is malformed in a way manual mapping can't handle")]
+pub struct InvalidImageError;
+
+#[derive(Debug, Error)]
+#[error("imported module `{0:?}` is not already loaded in the target process")]
+pub struct ImportedModuleNotLoadedError(std::ffi::OsString);
+
+#[derive(Debug, Error)]
+#[error("DllMain returned FALSE for DLL_PROCESS_ATTACH")]
+pub struct DllMainFailedError;
+
+#[derive(Debug, Error)]
+#[error("failed to manually map image")]
+pub enum MapImageError {
+    InvalidImage(#[from] InvalidImageError),
+    AllocateMemory(#[from] super::AllocateMemoryError),
+    ReadMemory(#[from] super::ReadMemoryError),
+    WriteMemory(#[from] super::WriteMemoryError),
+    SetMemoryPermissions(#[from] super::SetMemoryPermissionsError),
+    GetModules(#[from] super::GetModulesError),
+    ModuleGetExportAddress(#[from] module::GetExportAddressError),
+    ImportedModuleNotLoaded(#[from] ImportedModuleNotLoadedError),
+    CheckIs64Bit(#[from] super::CheckIs64BitError),
+    CreateThread(#[from] super::CreateThreadError),
+    JoinThread(#[from] crate::windows::thread::JoinError),
+    DllMainFailed(#[from] DllMainFailedError),
+}
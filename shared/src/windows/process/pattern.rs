@@ -0,0 +1,177 @@
+use super::{MemoryRegion, Process};
+use std::ffi::c_void;
+use thiserror::Error;
+
+/// Size of the window each [`Process::scan_pattern`] read covers a region with. Chosen to keep a
+/// single `read_to_vec` call's allocation small while still being many times larger than any
+/// realistic pattern, so the per-chunk overlap stays cheap.
+const CHUNK_SIZE: usize = 1 << 20;
+
+impl Process {
+    /// Searches every committed, non-guard, readable region of the process's memory for `pattern`
+    /// and returns the absolute address of every match. `pattern` entries of `None` match any
+    /// byte, so e.g. a pattern parsed from `"48 8b 05 ?? ?? ?? ?? 90"` (see [`parse_pattern`])
+    /// matches a `mov rax, [rip+disp32]` instruction regardless of the displacement.
+    ///
+    /// # Panics
+    /// Panics if `pattern` is empty.
+    pub fn scan_pattern(&self, pattern: &[Option<u8>]) -> Result<Vec<*mut c_void>, ScanPatternError> {
+        let mut matches = Vec::new();
+        self.scan_pattern_with(pattern, |address| {
+            matches.push(address);
+            true
+        })?;
+        Ok(matches)
+    }
+
+    /// Like [`Self::scan_pattern`], but stops at the first match instead of scanning the rest of
+    /// the process's memory.
+    ///
+    /// # Panics
+    /// Panics if `pattern` is empty.
+    pub fn find_first_pattern(
+        &self,
+        pattern: &[Option<u8>],
+    ) -> Result<Option<*mut c_void>, ScanPatternError> {
+        let mut first_match = None;
+        self.scan_pattern_with(pattern, |address| {
+            first_match = Some(address);
+            false
+        })?;
+        Ok(first_match)
+    }
+
+    fn scan_pattern_with(
+        &self,
+        pattern: &[Option<u8>],
+        mut on_match: impl FnMut(*mut c_void) -> bool,
+    ) -> Result<(), ScanPatternError> {
+        assert!(!pattern.is_empty(), "pattern must not be empty");
+        let shift_table = build_shift_table(pattern);
+        let overlap = pattern.len() - 1;
+
+        let mut address: *mut c_void = std::ptr::null_mut();
+        loop {
+            let Ok(region) = self.get_memory_region(address) else {
+                // VirtualQueryEx fails once `address` runs past the addressable range
+                break;
+            };
+
+            let next_address = region.address().wrapping_byte_add(region.size());
+            if next_address <= address {
+                break; // overflow
+            }
+            address = next_address;
+
+            let MemoryRegion::Reserved(region) = region else {
+                continue;
+            };
+            if !region.is_committed()
+                || region.permissions().is_guard
+                || !region.permissions().rwe.is_readable()
+            {
+                continue;
+            }
+            if region.size() < pattern.len() {
+                continue;
+            }
+
+            let mut offset = 0;
+            loop {
+                let chunk_len = CHUNK_SIZE.min(region.size() - offset);
+                let chunk_address = unsafe { region.address().byte_add(offset) };
+                let chunk = self.read_to_vec(chunk_address.cast(), chunk_len)?;
+
+                for match_offset in search_with_wildcards(&chunk, pattern, &shift_table) {
+                    let match_address = unsafe { chunk_address.byte_add(match_offset) };
+                    if !on_match(match_address) {
+                        return Ok(());
+                    }
+                }
+
+                if offset + chunk_len >= region.size() {
+                    break;
+                }
+                offset += chunk_len - overlap;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds a Boyer-Moore-Horspool bad-character shift table over `pattern`'s trailing run of
+/// concrete (non-wildcard) bytes. Bytes at or before the rightmost wildcard are excluded from the
+/// table, since a shift derived from one of them could skip past a position the wildcard would
+/// have matched; the fallback shift used for everything else is instead forced down to the
+/// distance from the rightmost wildcard to the end of the pattern (minimum 1), which is always
+/// safe.
+fn build_shift_table(pattern: &[Option<u8>]) -> [usize; 256] {
+    let length = pattern.len();
+    let rightmost_wildcard = pattern.iter().rposition(Option::is_none);
+    let fallback_shift = rightmost_wildcard
+        .map_or(length, |index| length - 1 - index)
+        .max(1);
+
+    let mut table = [fallback_shift; 256];
+    let trailing_start = rightmost_wildcard.map_or(0, |index| index + 1);
+    for (index, byte) in pattern.iter().enumerate().take(length - 1).skip(trailing_start) {
+        if let Some(byte) = byte {
+            table[*byte as usize] = (length - 1 - index).max(1);
+        }
+    }
+    table
+}
+
+/// Returns the offsets in `haystack` where `pattern` matches, using `shift_table` to skip ahead
+/// on mismatches the way Horspool does for ordinary (wildcard-free) patterns.
+fn search_with_wildcards(
+    haystack: &[u8],
+    pattern: &[Option<u8>],
+    shift_table: &[usize; 256],
+) -> Vec<usize> {
+    let length = pattern.len();
+    let mut matches = Vec::new();
+    if haystack.len() < length {
+        return matches;
+    }
+
+    let mut offset = 0;
+    while offset <= haystack.len() - length {
+        if pattern
+            .iter()
+            .zip(&haystack[offset..offset + length])
+            .all(|(expected, &byte)| expected.is_none_or(|expected| expected == byte))
+        {
+            matches.push(offset);
+        }
+        offset += shift_table[haystack[offset + length - 1] as usize];
+    }
+    matches
+}
+
+/// Parses a Cheat-Engine-style pattern string like `"48 8b 05 ?? ?? ?? ?? 90"` into the mask
+/// [`Process::scan_pattern`] expects: whitespace-separated hex byte pairs, with `?` or `??`
+/// standing in for a wildcard byte.
+pub fn parse_pattern(pattern: &str) -> Result<Vec<Option<u8>>, ParsePatternError> {
+    pattern
+        .split_whitespace()
+        .map(|token| match token {
+            "?" | "??" => Ok(None),
+            _ => u8::from_str_radix(token, 16)
+                .map(Some)
+                .map_err(|_| ParsePatternError(token.to_owned())),
+        })
+        .collect()
+}
+
+#[derive(Debug, Error)]
+#[error("invalid byte or wildcard token in pattern: {0:?}")]
+pub struct ParsePatternError(String);
+
+#[derive(Debug, Error)]
+#[error("failed to scan process memory for pattern")]
+pub enum ScanPatternError {
+    GetMemoryRegion(#[from] super::GetMemoryRegionError),
+    ReadMemory(#[from] super::ReadMemoryError),
+}
@@ -0,0 +1,107 @@
+use super::{MemoryPermissions, MemoryPermissionsRwe, MemoryRegion, Process};
+use lz4_flex::block::{compress_prepend_size, decompress_size_prepended, DecompressError};
+use std::ffi::c_void;
+use thiserror::Error;
+
+impl Process {
+    /// Captures the current contents and permissions of every committed, non-guard, readable
+    /// memory region, compressing each region's bytes independently so that repeated snapshots
+    /// stay cheap in memory. Does not capture thread contexts; callers that need
+    /// those (e.g. a TAS tool's full save state) must capture them separately.
+    pub fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        let mut regions = Vec::new();
+        let mut address: *mut c_void = std::ptr::null_mut();
+        loop {
+            let Ok(region) = self.get_memory_region(address) else {
+                // VirtualQueryEx fails once `address` runs past the addressable range
+                break;
+            };
+
+            let next_address = region.address().wrapping_byte_add(region.size());
+            if next_address <= address {
+                break; // overflow
+            }
+            address = next_address;
+
+            if let MemoryRegion::Reserved(region) = region {
+                if region.is_committed()
+                    && !region.permissions().is_guard
+                    && region.permissions().rwe.is_readable()
+                {
+                    let bytes = self.read_to_vec(region.address().cast(), region.size())?;
+                    regions.push(SnapshotRegion {
+                        address: region.address(),
+                        size: region.size(),
+                        permissions: region.permissions(),
+                        compressed_bytes: compress_prepend_size(&bytes),
+                    });
+                }
+            }
+        }
+
+        Ok(Snapshot { regions })
+    }
+}
+
+/// A compressed capture of a process's readable memory, taken by [`Process::snapshot`] and
+/// applied back with [`Self::restore`].
+pub struct Snapshot {
+    regions: Vec<SnapshotRegion>,
+}
+
+struct SnapshotRegion {
+    address: *mut c_void,
+    size: usize,
+    permissions: MemoryPermissions,
+    compressed_bytes: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Restores every captured region into `process`. Regions that no longer exist, or whose
+    /// address or size has changed since the snapshot was taken, are skipped rather than treated
+    /// as an error, since `process`'s memory layout is expected to drift between snapshots (new
+    /// allocations, freed regions, and so on).
+    pub fn restore(&self, process: &Process) -> Result<(), RestoreError> {
+        for region in &self.regions {
+            let Ok(MemoryRegion::Reserved(current_region)) =
+                process.get_memory_region(region.address)
+            else {
+                continue;
+            };
+            if current_region.address() != region.address || current_region.size() != region.size
+            {
+                continue;
+            }
+
+            let original_permissions = process.set_memory_permissions(
+                region.address,
+                region.size,
+                MemoryPermissions {
+                    rwe: MemoryPermissionsRwe::ReadWrite,
+                    is_guard: false,
+                },
+            )?;
+            let bytes = decompress_size_prepended(&region.compressed_bytes)?;
+            process.write(region.address.cast(), &bytes)?;
+            process.set_memory_permissions(region.address, region.size, original_permissions)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to take process snapshot")]
+pub enum SnapshotError {
+    GetMemoryRegion(#[from] super::GetMemoryRegionError),
+    ReadMemory(#[from] super::ReadMemoryError),
+}
+
+#[derive(Debug, Error)]
+#[error("failed to restore process snapshot")]
+pub enum RestoreError {
+    GetMemoryRegion(#[from] super::GetMemoryRegionError),
+    SetMemoryPermissions(#[from] super::SetMemoryPermissionsError),
+    WriteMemory(#[from] super::WriteMemoryError),
+    Decompress(#[from] DecompressError),
+}
@@ -0,0 +1,204 @@
+use std::{
+    sync::{Arc, LazyLock, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+use windows::Win32::{
+    Foundation::{HANDLE, WAIT_FAILED},
+    System::Threading::{CreateEventA, SetEvent, WaitForMultipleObjects, INFINITE},
+};
+
+/// `WaitForMultipleObjects` accepts at most this many handles in one call.
+const MAX_WAIT_OBJECTS: usize = 64;
+
+/// Handles a [`Group`] batches together, leaving its last slot for
+/// [`Group::registration_changed`].
+const MAX_HANDLES_PER_GROUP: usize = MAX_WAIT_OBJECTS - 1;
+
+/// A background reactor that waits on many handles with a single `WaitForMultipleObjects` call
+/// per [`Group`] of up to [`MAX_HANDLES_PER_GROUP`] handles, rather than consuming an OS
+/// thread-pool wait slot per outstanding wait the way `RegisterWaitForSingleObject` does. Groups
+/// are created on demand as registrations fill up the existing ones.
+static GROUPS: LazyLock<Mutex<Vec<Arc<Group>>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// Registers `handle` with the reactor and returns a handle to the registration. The caller is
+/// responsible for keeping `handle` valid (e.g. by owning a duplicated [`super::handle::Handle`])
+/// for as long as the returned [`Registration`] exists.
+pub(crate) unsafe fn register(handle: HANDLE) -> Registration {
+    let mut groups = GROUPS.lock().unwrap();
+    for group in groups.iter() {
+        if let Some(slot) = group.try_register(handle) {
+            return Registration {
+                group: Arc::clone(group),
+                slot,
+            };
+        }
+    }
+
+    let group = Group::spawn();
+    let slot = group
+        .try_register(handle)
+        .expect("a freshly spawned group has capacity for at least one handle");
+    groups.push(Arc::clone(&group));
+    Registration { group, slot }
+}
+
+pub(crate) struct Registration {
+    group: Arc<Group>,
+    slot: Arc<Slot>,
+}
+
+impl Registration {
+    pub(crate) fn poll(&self, cx: &Context<'_>) -> Poll<()> {
+        self.slot.poll(cx)
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if !self.slot.is_completed() {
+            self.group.remove(&self.slot);
+        }
+    }
+}
+
+struct Slot {
+    inner: Mutex<SlotInner>,
+}
+
+#[derive(Default)]
+struct SlotInner {
+    completed: bool,
+    waker: Option<Waker>,
+}
+
+impl Slot {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(SlotInner::default()),
+        })
+    }
+
+    fn poll(&self, cx: &Context<'_>) -> Poll<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.completed {
+            Poll::Ready(())
+        } else {
+            inner.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn complete(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.completed = true;
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn is_completed(&self) -> bool {
+        self.inner.lock().unwrap().completed
+    }
+}
+
+struct RawHandle(HANDLE);
+unsafe impl Send for RawHandle {}
+
+/// A batch of up to [`MAX_HANDLES_PER_GROUP`] handles waited on together by a single background
+/// thread, plus one reserved slot for `registration_changed`, an auto-reset event the thread waits
+/// on alongside them. Registering or removing a handle signals it so the thread wakes up and
+/// rebuilds its wait set instead of blocking forever on a now-stale one.
+struct Group {
+    registrations: Mutex<Vec<(RawHandle, Arc<Slot>)>>,
+    registration_changed: HANDLE,
+}
+
+unsafe impl Send for Group {}
+unsafe impl Sync for Group {}
+
+impl Group {
+    fn spawn() -> Arc<Self> {
+        let registration_changed = unsafe { CreateEventA(None, false, false, None) }
+            .unwrap_or_else(|error| {
+                panic!("failed to create registration-changed event: {error}")
+            });
+
+        let group = Arc::new(Self {
+            registrations: Mutex::new(Vec::new()),
+            registration_changed,
+        });
+
+        let thread_group = Arc::clone(&group);
+        thread::spawn(move || thread_group.run());
+
+        group
+    }
+
+    fn try_register(&self, handle: HANDLE) -> Option<Arc<Slot>> {
+        let mut registrations = self.registrations.lock().unwrap();
+        if registrations.len() >= MAX_HANDLES_PER_GROUP {
+            return None;
+        }
+        let slot = Slot::new();
+        registrations.push((RawHandle(handle), Arc::clone(&slot)));
+        drop(registrations);
+        self.signal_registration_changed();
+        Some(slot)
+    }
+
+    fn remove(&self, slot: &Arc<Slot>) {
+        let mut registrations = self.registrations.lock().unwrap();
+        if let Some(index) = registrations
+            .iter()
+            .position(|(_, registered_slot)| Arc::ptr_eq(registered_slot, slot))
+        {
+            registrations.remove(index);
+            drop(registrations);
+            self.signal_registration_changed();
+        }
+    }
+
+    fn signal_registration_changed(&self) {
+        unsafe {
+            if let Err(error) = SetEvent(self.registration_changed) {
+                panic!("failed to signal registration-changed event: {error}");
+            }
+        }
+    }
+
+    /// Runs on a dedicated thread for the lifetime of the group: snapshots the currently
+    /// registered handles, blocks on all of them (plus `registration_changed`) at once, and
+    /// either wakes the handle that became signaled or, if `registration_changed` fired instead,
+    /// takes a fresh snapshot and waits again.
+    fn run(self: Arc<Self>) {
+        loop {
+            let snapshot = self.registrations.lock().unwrap();
+            let handles: Vec<HANDLE> = snapshot
+                .iter()
+                .map(|(handle, _)| handle.0)
+                .chain(std::iter::once(self.registration_changed))
+                .collect();
+            let slots: Vec<Arc<Slot>> = snapshot.iter().map(|(_, slot)| Arc::clone(slot)).collect();
+            drop(snapshot);
+
+            let result = unsafe { WaitForMultipleObjects(&handles, false, INFINITE) };
+
+            assert!(
+                result != WAIT_FAILED,
+                "failed to wait on handle group: {}",
+                std::io::Error::last_os_error()
+            );
+            let index = (result.0 - windows::Win32::Foundation::WAIT_OBJECT_0.0) as usize;
+
+            // the last handle in the batch is always registration_changed; it signaling just
+            // means this loop rebuilds the snapshot and waits again, while a real handle
+            // signaling completes (and removes) its slot.
+            if index < slots.len() {
+                let slot = &slots[index];
+                self.remove(slot);
+                slot.complete();
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::windows::handle::{self, handle_wrapper};
+use std::{ffi::c_void, io, ptr::NonNull};
+use thiserror::Error;
+use windows::Win32::{
+    Foundation::INVALID_HANDLE_VALUE,
+    System::Memory::{CreateFileMappingA, MapViewOfFile, OpenFileMappingA, UnmapViewOfFile, FILE_MAP_ALL_ACCESS, PAGE_READWRITE},
+};
+
+handle_wrapper!(Mapping);
+
+/// A named file mapping backed by the system paging file, mapped into the current process. Used
+/// as the backing storage for [`crate::ipc::ring::RingBuffer`] so that large IPC payloads can be
+/// shared between the conductor and injected processes without copying through a pipe.
+#[derive(Debug)]
+pub struct SharedMemory {
+    mapping: Mapping,
+    // `None` for a mapping duplicated for a foreign process that is never dereferenced locally,
+    // see `from_foreign_mapping`
+    view: Option<NonNull<c_void>>,
+    size: usize,
+}
+
+impl SharedMemory {
+    pub fn create(name: &str, size: usize) -> Result<Self, CreateError> {
+        let name = std::ffi::CString::new(name)?;
+        unsafe {
+            #[expect(clippy::cast_possible_truncation)]
+            let handle = CreateFileMappingA(
+                INVALID_HANDLE_VALUE,
+                None,
+                PAGE_READWRITE,
+                (size >> 32) as u32,
+                size as u32,
+                windows::core::PCSTR(name.as_ptr().cast()),
+            )
+            .map_err(|_| io::Error::last_os_error())?;
+            Self::from_mapping(Mapping::from_raw_handle(handle), size)
+        }
+    }
+
+    pub fn open(name: &str, size: usize) -> Result<Self, OpenError> {
+        let name = std::ffi::CString::new(name)?;
+        unsafe {
+            let handle = OpenFileMappingA(
+                FILE_MAP_ALL_ACCESS.0,
+                false,
+                windows::core::PCSTR(name.as_ptr().cast()),
+            )
+            .map_err(|_| io::Error::last_os_error())?;
+            Ok(Self::from_mapping(Mapping::from_raw_handle(handle), size)?)
+        }
+    }
+
+    /// Takes ownership of an already-duplicated mapping handle (e.g. one received from another
+    /// process via [`crate::ipc::Sender::serialize_to_bytes`]) and maps it into the current
+    /// process.
+    pub unsafe fn from_mapping(mapping: Mapping, size: usize) -> Result<Self, CreateError> {
+        unsafe {
+            let view = MapViewOfFile(mapping.raw_handle(), FILE_MAP_ALL_ACCESS, 0, 0, size);
+            let Some(view) = NonNull::new(view.Value) else {
+                return Err(io::Error::last_os_error().into());
+            };
+            Ok(Self {
+                mapping,
+                view: Some(view),
+                size,
+            })
+        }
+    }
+
+    /// Wraps `mapping` without mapping a view into the current process. For use when `mapping`
+    /// has been duplicated for a different process and will only ever be passed along by raw
+    /// handle value (e.g. [`Self::try_clone_mapping_for_process`] followed by
+    /// [`crate::ipc::Sender::serialize_to_bytes`]), never dereferenced here.
+    #[must_use]
+    pub unsafe fn from_foreign_mapping(mapping: Mapping, size: usize) -> Self {
+        Self {
+            mapping,
+            view: None,
+            size,
+        }
+    }
+
+    #[must_use]
+    pub fn mapping(&self) -> &Mapping {
+        &self.mapping
+    }
+
+    /// Hands off the underlying mapping handle without closing it or unmapping the view.
+    #[expect(clippy::must_use_candidate)]
+    pub unsafe fn leak_mapping(self) -> windows::Win32::Foundation::HANDLE {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.mapping).leak_handle() }
+    }
+
+    pub fn try_clone_mapping_for_process(
+        &self,
+        process: &crate::windows::process::Process,
+    ) -> Result<Mapping, handle::CloneError> {
+        self.mapping.try_clone_for_process(process)
+    }
+
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// # Panics
+    /// Panics if this mapping has no local view, i.e. it was constructed via
+    /// [`Self::from_foreign_mapping`].
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.view
+            .expect("shared memory mapping has no view in this process")
+            .as_ptr()
+            .cast()
+    }
+}
+
+unsafe impl Send for SharedMemory {}
+unsafe impl Sync for SharedMemory {}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        if let Some(view) = self.view {
+            unsafe {
+                let view = windows::Win32::System::Memory::MEMORY_MAPPED_VIEW_ADDRESS {
+                    Value: view.as_ptr(),
+                };
+                if let Err(error) = UnmapViewOfFile(view) {
+                    panic!("failed to unmap shared memory view: {error}");
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create shared memory mapping")]
+pub enum CreateError {
+    NameContainsNul(#[from] std::ffi::NulError),
+    Os(#[from] io::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("failed to open shared memory mapping")]
+pub enum OpenError {
+    NameContainsNul(#[from] std::ffi::NulError),
+    Os(#[from] io::Error),
+    Create(#[from] CreateError),
+}
@@ -1,6 +1,6 @@
 use std::mem::MaybeUninit;
 
-use winapi::um::sysinfoapi::{GetNativeSystemInfo, SYSTEM_INFO};
+use windows::Win32::System::SystemInformation::{GetNativeSystemInfo, SYSTEM_INFO};
 
 #[must_use]
 pub fn get_info() -> SYSTEM_INFO {
@@ -1,16 +1,15 @@
 use crate::windows::{handle::handle_wrapper, process};
-use std::{io, mem::MaybeUninit};
+use std::{collections::BTreeMap, io, mem::MaybeUninit};
 use thiserror::Error;
-use winapi::{
-    shared::minwindef::FALSE,
-    um::{
-        processthreadsapi::{
-            GetCurrentThread, GetExitCodeThread, GetProcessIdOfThread, GetThreadContext,
-            GetThreadId, OpenThread, ResumeThread, SetThreadContext, SuspendThread,
-        },
-        synchapi::WaitForSingleObject,
-        winbase::{Wow64GetThreadContext, Wow64SetThreadContext, INFINITE, WAIT_FAILED},
-        winnt::{CONTEXT, CONTEXT_ALL, THREAD_ALL_ACCESS, WOW64_CONTEXT, WOW64_CONTEXT_ALL},
+use windows::Win32::System::{
+    Diagnostics::Debug::{
+        Wow64GetThreadContext, Wow64SetThreadContext, CONTEXT, CONTEXT_ALL, WOW64_CONTEXT,
+        WOW64_CONTEXT_ALL,
+    },
+    Threading::{
+        GetExitCodeThread, GetProcessIdOfThread, GetThreadContext, GetThreadId, OpenThread,
+        ResumeThread, SetThreadContext, SuspendThread, WaitForSingleObject, INFINITE,
+        THREAD_ALL_ACCESS, WAIT_FAILED,
     },
 };
 
@@ -18,11 +17,8 @@ handle_wrapper!(Thread);
 
 impl Thread {
     pub fn from_id(id: u32) -> Result<Self, FromIdError> {
-        let handle = unsafe { OpenThread(THREAD_ALL_ACCESS, FALSE, id) };
-        if handle.is_null() {
-            return Err(FromIdError(io::Error::last_os_error()));
-        }
-
+        let handle = unsafe { OpenThread(THREAD_ALL_ACCESS, false, id) }
+            .map_err(|_| io::Error::last_os_error())?;
         unsafe { Ok(Self::from_raw_handle(handle)) }
     }
 
@@ -43,14 +39,14 @@ impl Thread {
     }
 
     pub fn increment_suspend_count(&self) -> Result<(), ChangeSuspendCountError> {
-        if unsafe { SuspendThread(self.handle.as_raw()) } == 0xffff_ffff {
+        if unsafe { SuspendThread(self.handle.as_raw()) } == u32::MAX {
             return Err(io::Error::last_os_error().into());
         }
         Ok(())
     }
 
     pub fn decrement_suspend_count(&self) -> Result<(), ChangeSuspendCountError> {
-        if unsafe { ResumeThread(self.handle.as_raw()) } == 0xffff_ffff {
+        if unsafe { ResumeThread(self.handle.as_raw()) } == u32::MAX {
             return Err(io::Error::last_os_error().into());
         }
         Ok(())
@@ -63,9 +59,8 @@ impl Thread {
             }
 
             let mut exit_code = 0u32;
-            if GetExitCodeThread(self.handle.as_raw(), &mut exit_code) == 0 {
-                return Err(io::Error::last_os_error().into());
-            }
+            GetExitCodeThread(self.handle.as_raw(), &mut exit_code)
+                .map_err(|_| io::Error::last_os_error())?;
 
             Ok(exit_code)
         }
@@ -80,9 +75,8 @@ impl Thread {
 
                 let mut context = MaybeUninit::<AlignedContext>::zeroed().assume_init();
                 context.0.ContextFlags = CONTEXT_ALL;
-                if GetThreadContext(thread.handle.as_raw(), &mut context.0) == 0 {
-                    return Err(io::Error::last_os_error().into());
-                }
+                GetThreadContext(thread.handle.as_raw(), &mut context.0)
+                    .map_err(|_| io::Error::last_os_error())?;
                 Ok(context.0)
             }
         }
@@ -91,9 +85,8 @@ impl Thread {
             unsafe {
                 let mut context = MaybeUninit::<WOW64_CONTEXT>::zeroed().assume_init();
                 context.ContextFlags = WOW64_CONTEXT_ALL;
-                if Wow64GetThreadContext(thread.handle.as_raw(), &mut context) == 0 {
-                    return Err(io::Error::last_os_error().into());
-                }
+                Wow64GetThreadContext(thread.handle.as_raw(), &mut context)
+                    .map_err(|_| io::Error::last_os_error())?;
                 Ok(context)
             }
         }
@@ -122,9 +115,8 @@ impl Thread {
     pub fn set_context(&self, context: &Context) -> Result<(), SetContextError> {
         fn set_normal_context(thread: &Thread, context: &CONTEXT) -> Result<(), SetContextError> {
             unsafe {
-                if SetThreadContext(thread.handle.as_raw(), context) == 0 {
-                    return Err(io::Error::last_os_error().into());
-                }
+                SetThreadContext(thread.handle.as_raw(), context)
+                    .map_err(|_| io::Error::last_os_error())?;
                 Ok(())
             }
         }
@@ -134,9 +126,8 @@ impl Thread {
             context: &WOW64_CONTEXT,
         ) -> Result<(), SetContextError> {
             unsafe {
-                if Wow64SetThreadContext(thread.handle.as_raw(), context) == 0 {
-                    return Err(io::Error::last_os_error().into());
-                }
+                Wow64SetThreadContext(thread.handle.as_raw(), context)
+                    .map_err(|_| io::Error::last_os_error())?;
                 Ok(())
             }
         }
@@ -172,6 +163,44 @@ impl Context32 {
     pub fn eip(&self) -> u32 {
         self.0.Eip
     }
+
+    /// General-purpose and flags registers, named as in the Intel/AMD manuals. Useful for
+    /// diagnostics that compare two contexts register-by-register.
+    #[must_use]
+    pub fn registers(&self) -> BTreeMap<&'static str, u64> {
+        BTreeMap::from([
+            ("eax", u64::from(self.0.Eax)),
+            ("ebx", u64::from(self.0.Ebx)),
+            ("ecx", u64::from(self.0.Ecx)),
+            ("edx", u64::from(self.0.Edx)),
+            ("esi", u64::from(self.0.Esi)),
+            ("edi", u64::from(self.0.Edi)),
+            ("ebp", u64::from(self.0.Ebp)),
+            ("esp", u64::from(self.0.Esp)),
+            ("eip", u64::from(self.0.Eip)),
+            ("eflags", u64::from(self.0.EFlags)),
+        ])
+    }
+
+    /// Sets the register named `name` (see [`Self::registers`] for the recognized names) to
+    /// `value`, truncated to the register's real width. Does nothing if `name` isn't recognized.
+    pub fn set_register(&mut self, name: &str, value: u64) {
+        #[expect(clippy::cast_possible_truncation)]
+        let value = value as u32;
+        match name {
+            "eax" => self.0.Eax = value,
+            "ebx" => self.0.Ebx = value,
+            "ecx" => self.0.Ecx = value,
+            "edx" => self.0.Edx = value,
+            "esi" => self.0.Esi = value,
+            "edi" => self.0.Edi = value,
+            "ebp" => self.0.Ebp = value,
+            "esp" => self.0.Esp = value,
+            "eip" => self.0.Eip = value,
+            "eflags" => self.0.EFlags = value,
+            _ => {}
+        }
+    }
 }
 
 #[cfg(target_pointer_width = "64")]
@@ -183,6 +212,59 @@ impl Context64 {
     pub fn rip(&self) -> u64 {
         self.0.Rip
     }
+
+    /// General-purpose and flags registers, named as in the Intel/AMD manuals. Useful for
+    /// diagnostics that compare two contexts register-by-register.
+    #[must_use]
+    pub fn registers(&self) -> BTreeMap<&'static str, u64> {
+        BTreeMap::from([
+            ("rax", self.0.Rax),
+            ("rbx", self.0.Rbx),
+            ("rcx", self.0.Rcx),
+            ("rdx", self.0.Rdx),
+            ("rsi", self.0.Rsi),
+            ("rdi", self.0.Rdi),
+            ("rbp", self.0.Rbp),
+            ("rsp", self.0.Rsp),
+            ("rip", self.0.Rip),
+            ("r8", self.0.R8),
+            ("r9", self.0.R9),
+            ("r10", self.0.R10),
+            ("r11", self.0.R11),
+            ("r12", self.0.R12),
+            ("r13", self.0.R13),
+            ("r14", self.0.R14),
+            ("r15", self.0.R15),
+            ("eflags", u64::from(self.0.EFlags)),
+        ])
+    }
+
+    /// Sets the register named `name` (see [`Self::registers`] for the recognized names) to
+    /// `value`. Does nothing if `name` isn't recognized.
+    pub fn set_register(&mut self, name: &str, value: u64) {
+        match name {
+            "rax" => self.0.Rax = value,
+            "rbx" => self.0.Rbx = value,
+            "rcx" => self.0.Rcx = value,
+            "rdx" => self.0.Rdx = value,
+            "rsi" => self.0.Rsi = value,
+            "rdi" => self.0.Rdi = value,
+            "rbp" => self.0.Rbp = value,
+            "rsp" => self.0.Rsp = value,
+            "rip" => self.0.Rip = value,
+            "r8" => self.0.R8 = value,
+            "r9" => self.0.R9 = value,
+            "r10" => self.0.R10 = value,
+            "r11" => self.0.R11 = value,
+            "r12" => self.0.R12 = value,
+            "r13" => self.0.R13 = value,
+            "r14" => self.0.R14 = value,
+            "r15" => self.0.R15 = value,
+            #[expect(clippy::cast_possible_truncation)]
+            "eflags" => self.0.EFlags = value as u32,
+            _ => {}
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -0,0 +1,36 @@
+use crate::windows::handle::handle_wrapper;
+use std::{io, time::Duration};
+use thiserror::Error;
+use windows::Win32::System::Threading::{
+    CreateWaitableTimerExW, SetWaitableTimer, CREATE_WAITABLE_TIMER_MANUAL_RESET, TIMER_ALL_ACCESS,
+};
+
+handle_wrapper!(WaitableTimer);
+
+impl WaitableTimer {
+    /// Creates a one-shot timer that becomes signaled once `duration` has elapsed, so it can be
+    /// raced against an event/process handle via [`super::handle::Handle::wait_any`] to bound an
+    /// otherwise-unbounded wait.
+    pub fn new(duration: Duration) -> Result<Self, NewError> {
+        unsafe {
+            let handle = CreateWaitableTimerExW(
+                None,
+                None,
+                CREATE_WAITABLE_TIMER_MANUAL_RESET.0,
+                TIMER_ALL_ACCESS.0,
+            )
+            .map_err(|_| io::Error::last_os_error())?;
+            // due time is in 100ns units; negative means relative to the time SetWaitableTimer is
+            // called, rather than an absolute time.
+            #[expect(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+            let due_time = -((duration.as_nanos() / 100).min(i64::MAX as u128) as i64);
+            SetWaitableTimer(handle, &due_time, 0, None, None, false)
+                .map_err(|_| io::Error::last_os_error())?;
+            Ok(Self::from_raw_handle(handle))
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create timer")]
+pub struct NewError(#[from] io::Error);
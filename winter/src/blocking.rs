@@ -0,0 +1,130 @@
+//! A synchronous facade over [`Conductor`] for callers that don't want to pull in their own async
+//! executor: [`BlockingConductor`] owns a single `tokio` runtime internally and blocks on every
+//! call, while [`Conductor`] itself keeps its `async fn` surface for callers already running one.
+
+use crate::{
+    AdvanceTimeError, CommandEnv, CommandLine, Conductor, InactiveState, NewError, ResumeError,
+    SetKeyStateError, WaitUntilInactiveError,
+};
+use shared::windows::pipe::Stdio;
+use std::{future::Future, path::Path, time::Duration};
+use thiserror::Error;
+use tokio::runtime::Runtime;
+
+pub struct BlockingConductor {
+    conductor: Conductor,
+    runtime: Runtime,
+}
+
+impl BlockingConductor {
+    #[expect(clippy::too_many_arguments)]
+    pub fn new<StdoutCallback, StderrCallback>(
+        executable_path: impl AsRef<Path>,
+        command_line: CommandLine,
+        environment: CommandEnv,
+        stdin: Stdio,
+        stdout: Stdio,
+        stdout_callback: Option<StdoutCallback>,
+        stderr: Stdio,
+        stderr_callback: Option<StderrCallback>,
+    ) -> Result<Self, NewBlockingConductorError>
+    where
+        StdoutCallback: Fn(&[u8]) + Send + 'static,
+        StderrCallback: Fn(&[u8]) + Send + 'static,
+    {
+        let runtime = Runtime::new()?;
+        let conductor = runtime.block_on(Conductor::new(
+            executable_path,
+            command_line,
+            environment,
+            stdin,
+            stdout,
+            stdout_callback,
+            stderr,
+            stderr_callback,
+        ))?;
+        Ok(Self { conductor, runtime })
+    }
+
+    pub fn resume(&mut self) -> Result<(), ResumeError> {
+        self.runtime.block_on(self.conductor.resume())
+    }
+
+    pub fn set_key_state(&mut self, id: u8, state: bool) -> Result<(), SetKeyStateError> {
+        self.runtime.block_on(self.conductor.set_key_state(id, state))
+    }
+
+    /// Queues a key state change without waiting for the hooks DLL to consume it — see
+    /// [`Conductor::queue_key_state`]. Intended for batching high-frequency input ahead of a
+    /// single [`Self::wait_until_idle`] settle point.
+    pub fn queue_key_state(&mut self, id: u8, state: bool) -> Result<(), SetKeyStateError> {
+        self.runtime
+            .block_on(self.conductor.queue_key_state(id, state))
+    }
+
+    pub fn advance_time(&mut self, time: Duration) -> Result<(), AdvanceTimeError> {
+        self.runtime.block_on(self.conductor.advance_time(time))
+    }
+
+    /// Blocks until the subprocess goes idle, as a synchronous alternative to awaiting
+    /// [`Conductor::wait_until_inactive`] and matching on [`InactiveState::Idle`] by hand.
+    pub fn wait_until_idle(&mut self) -> Result<(), WaitUntilIdleError> {
+        match self.runtime.block_on(self.conductor.wait_until_inactive())? {
+            InactiveState::Idle => Ok(()),
+            InactiveState::Terminated { exit_code } => {
+                Err(WaitUntilIdleError::UnexpectedTermination { exit_code })
+            }
+            InactiveState::TimedOut => Err(WaitUntilIdleError::TimedOut),
+        }
+    }
+
+    /// Blocks until the subprocess terminates, returning its exit code, as a synchronous
+    /// alternative to awaiting [`Conductor::wait_until_inactive`] in a loop until it reports
+    /// [`InactiveState::Terminated`].
+    pub fn wait_until_exit(&mut self) -> Result<u32, WaitUntilExitError> {
+        loop {
+            match self.runtime.block_on(self.conductor.wait_until_inactive())? {
+                InactiveState::Terminated { exit_code } => return Ok(exit_code),
+                InactiveState::TimedOut => return Err(WaitUntilExitError::TimedOut),
+                InactiveState::Idle => {}
+            }
+        }
+    }
+
+    /// The underlying async [`Conductor`], for calls this facade doesn't wrap directly; drive it
+    /// with [`Self::block_on`] rather than a caller-owned executor, since it was constructed
+    /// against this struct's own runtime.
+    pub fn conductor(&mut self) -> &mut Conductor {
+        &mut self.conductor
+    }
+
+    /// Runs an arbitrary future to completion on this conductor's runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create blocking conductor")]
+pub enum NewBlockingConductorError {
+    Io(#[from] std::io::Error),
+    New(#[from] NewError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while waiting for the subprocess to go idle")]
+pub enum WaitUntilIdleError {
+    WaitUntilInactive(#[from] WaitUntilInactiveError),
+    #[error("subprocess terminated with exit code {exit_code} while waiting to go idle")]
+    UnexpectedTermination { exit_code: u32 },
+    #[error("timed out waiting for the subprocess to go idle")]
+    TimedOut,
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while waiting for the subprocess to exit")]
+pub enum WaitUntilExitError {
+    WaitUntilInactive(#[from] WaitUntilInactiveError),
+    #[error("timed out waiting for the subprocess to exit")]
+    TimedOut,
+}
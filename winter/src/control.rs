@@ -0,0 +1,263 @@
+//! A request/response protocol that lets an external process drive a [`Conductor`] one step at a
+//! time over a named pipe, instead of the fixed, up-front event list `Instance` in the test suite
+//! uses. An interactive TAS editor or fuzzer can connect, send [`ControlRequest`]s one at a time,
+//! and read back the stdout produced up to that virtual instant after each
+//! [`ControlRequest::AdvanceTime`] or [`ControlRequest::RunUntilOutput`]; the input requests are
+//! answered with a bare acknowledgement.
+
+use crate::{CommandEnv, CommandLine, Conductor};
+use serde::{Deserialize, Serialize};
+use shared::{input::MouseButton, windows::pipe::Stdio};
+use std::{
+    ffi::OsStr,
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::windows::named_pipe::{NamedPipeServer, ServerOptions},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ControlRequest {
+    AdvanceTime(Duration),
+    SetKeyState { id: u8, state: bool },
+    SetMousePosition { x: u16, y: u16 },
+    SetMouseButtonState { button: MouseButton, state: bool },
+    RunUntilOutput,
+}
+
+/// The stdout bytes a [`ControlServer`] observed since the previous [`StdoutDelta`] it returned
+/// (or, for the first one, since the conductor started), including any produced while it handled
+/// acknowledged requests in between.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StdoutDelta(pub Vec<u8>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum ControlResponse {
+    StdoutDelta(StdoutDelta),
+    Ack,
+}
+
+/// A send-and-confirm client for the control protocol, implemented for any stream that can carry
+/// it (a connected [`NamedPipeServer`], a [`NamedPipeClient`](tokio::net::windows::named_pipe::NamedPipeClient),
+/// or anything else that reads and writes bytes). Those stream types already implement
+/// [`AsRawHandle`](std::os::windows::io::AsRawHandle), so a caller that wants to multiplex the
+/// connection inside its own event loop can do so without going through this trait at all.
+pub trait ControlClient {
+    async fn advance_time(&mut self, duration: Duration) -> Result<StdoutDelta, ControlError>;
+    async fn set_key_state(&mut self, id: u8, state: bool) -> Result<(), ControlError>;
+    async fn set_mouse_position(&mut self, x: u16, y: u16) -> Result<(), ControlError>;
+    async fn set_mouse_button_state(
+        &mut self,
+        button: MouseButton,
+        state: bool,
+    ) -> Result<(), ControlError>;
+    /// Waits until the conductor on the other end next goes idle, without advancing time itself,
+    /// and returns whatever stdout that produced.
+    async fn run_until_output(&mut self) -> Result<StdoutDelta, ControlError>;
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> ControlClient for S {
+    async fn advance_time(&mut self, duration: Duration) -> Result<StdoutDelta, ControlError> {
+        expect_stdout_delta(call(self, ControlRequest::AdvanceTime(duration)).await?)
+    }
+
+    async fn set_key_state(&mut self, id: u8, state: bool) -> Result<(), ControlError> {
+        expect_ack(call(self, ControlRequest::SetKeyState { id, state }).await?)
+    }
+
+    async fn set_mouse_position(&mut self, x: u16, y: u16) -> Result<(), ControlError> {
+        expect_ack(call(self, ControlRequest::SetMousePosition { x, y }).await?)
+    }
+
+    async fn set_mouse_button_state(
+        &mut self,
+        button: MouseButton,
+        state: bool,
+    ) -> Result<(), ControlError> {
+        expect_ack(call(self, ControlRequest::SetMouseButtonState { button, state }).await?)
+    }
+
+    async fn run_until_output(&mut self) -> Result<StdoutDelta, ControlError> {
+        expect_stdout_delta(call(self, ControlRequest::RunUntilOutput).await?)
+    }
+}
+
+async fn call(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    request: ControlRequest,
+) -> Result<ControlResponse, ControlError> {
+    write_frame(stream, &request).await?;
+    Ok(read_frame(stream).await?)
+}
+
+fn expect_stdout_delta(response: ControlResponse) -> Result<StdoutDelta, ControlError> {
+    match response {
+        ControlResponse::StdoutDelta(delta) => Ok(delta),
+        ControlResponse::Ack => unreachable!("server never acks a request that returns stdout"),
+    }
+}
+
+fn expect_ack(response: ControlResponse) -> Result<(), ControlError> {
+    match response {
+        ControlResponse::Ack => Ok(()),
+        ControlResponse::StdoutDelta(_) => {
+            unreachable!("server only returns stdout for requests that ask for it")
+        }
+    }
+}
+
+/// Drives a [`Conductor`] on behalf of a single connected [`ControlClient`], one
+/// [`ControlRequest`] at a time.
+pub struct ControlServer {
+    conductor: Conductor,
+    stdout: Arc<Mutex<Vec<u8>>>,
+}
+
+impl ControlServer {
+    pub async fn new(
+        executable_path: impl AsRef<Path>,
+        command_line: CommandLine,
+    ) -> Result<Self, NewError> {
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let stdout_callback = {
+            let stdout = Arc::clone(&stdout);
+            move |bytes: &[u8]| stdout.lock().unwrap().extend_from_slice(bytes)
+        };
+        let mut conductor = Conductor::new(
+            executable_path,
+            command_line,
+            CommandEnv::new(),
+            Stdio::Null,
+            Stdio::Piped,
+            Some(stdout_callback),
+            Stdio::Null,
+            None::<fn(&[u8])>,
+        )
+        .await?;
+        conductor.resume().await?;
+        Ok(Self { conductor, stdout })
+    }
+
+    /// Creates `pipe_name` (a Windows named pipe path, e.g. `\\.\pipe\winter-control`), accepts a
+    /// single connection on it, and serves [`ControlRequest`]s from that connection until the
+    /// client disconnects.
+    pub async fn run(mut self, pipe_name: impl AsRef<OsStr>) -> Result<(), RunError> {
+        let listener = ServerOptions::new().create(pipe_name)?;
+        listener.connect().await?;
+        self.serve(listener).await
+    }
+
+    /// Like [`Self::run`], but serves an already-connected stream. Useful when the caller wants
+    /// to accept the connection itself, e.g. to `select!` it against other I/O.
+    pub async fn serve(
+        &mut self,
+        mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    ) -> Result<(), RunError> {
+        loop {
+            let request = match read_frame(&mut stream).await {
+                Ok(request) => request,
+                Err(FrameError::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(());
+                }
+                Err(error) => return Err(error.into()),
+            };
+            let response = self.handle(request).await?;
+            write_frame(&mut stream, &response).await?;
+        }
+    }
+
+    async fn handle(&mut self, request: ControlRequest) -> Result<ControlResponse, HandleError> {
+        Ok(match request {
+            ControlRequest::AdvanceTime(duration) => {
+                self.conductor.advance_time(duration).await?;
+                self.conductor.wait_until_inactive().await?;
+                ControlResponse::StdoutDelta(self.take_stdout())
+            }
+            ControlRequest::SetKeyState { id, state } => {
+                self.conductor.set_key_state(id, state).await?;
+                ControlResponse::Ack
+            }
+            ControlRequest::SetMousePosition { x, y } => {
+                self.conductor.set_mouse_position(x, y).await?;
+                ControlResponse::Ack
+            }
+            ControlRequest::SetMouseButtonState { button, state } => {
+                self.conductor.set_mouse_button_state(button, state).await?;
+                ControlResponse::Ack
+            }
+            ControlRequest::RunUntilOutput => {
+                self.conductor.wait_until_inactive().await?;
+                ControlResponse::StdoutDelta(self.take_stdout())
+            }
+        })
+    }
+
+    fn take_stdout(&self) -> StdoutDelta {
+        StdoutDelta(std::mem::take(&mut *self.stdout.lock().unwrap()))
+    }
+}
+
+async fn write_frame(
+    stream: &mut (impl AsyncWrite + Unpin),
+    value: &impl Serialize,
+) -> Result<(), FrameError> {
+    let payload = bincode::serialize(value)?;
+    stream
+        .write_all(&u32::try_from(payload.len()).unwrap().to_ne_bytes())
+        .await?;
+    stream.write_all(&payload).await?;
+    Ok(())
+}
+
+async fn read_frame<T: for<'de> Deserialize<'de>>(
+    stream: &mut (impl AsyncRead + Unpin),
+) -> Result<T, FrameError> {
+    let mut length_bytes = [0; 4];
+    stream.read_exact(&mut length_bytes).await?;
+    let mut payload = vec![0; u32::from_ne_bytes(length_bytes) as usize];
+    stream.read_exact(&mut payload).await?;
+    Ok(bincode::deserialize(&payload)?)
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while reading or writing a control protocol frame")]
+pub enum FrameError {
+    Io(#[from] io::Error),
+    Bincode(#[from] bincode::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while issuing a control request")]
+pub enum ControlError {
+    Frame(#[from] FrameError),
+}
+
+#[derive(Debug, Error)]
+#[error("failed to create control server")]
+pub enum NewError {
+    ConductorNew(#[from] super::NewError),
+    Resume(#[from] super::ResumeError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while handling a control request")]
+pub enum HandleError {
+    AdvanceTime(#[from] super::AdvanceTimeError),
+    SetKeyState(#[from] super::SetKeyStateError),
+    SetMousePosition(#[from] super::SetMousePositionError),
+    SetMouseButtonState(#[from] super::SetMouseButtonStateError),
+    WaitUntilInactive(#[from] super::WaitUntilInactiveError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while running the control server")]
+pub enum RunError {
+    Io(#[from] io::Error),
+    Frame(#[from] FrameError),
+    Handle(#[from] HandleError),
+}
@@ -1,53 +1,231 @@
+pub mod blocking;
+pub mod control;
+mod log_buffer;
+mod log_publisher;
+pub mod movie;
+mod rpc;
 mod saved_state;
 
 use anyhow::Result;
+use log_buffer::LogBuffer;
+use log_publisher::{LogPublisher, LogSubscriber};
+use rpc::Client as RpcClient;
 use saved_state::SavedState;
 use shared::{
-    input::MouseButton,
+    input::{ConsoleCtrlEvent, GamepadAxis, GamepadButton, GamepadTrigger, MouseButton},
     ipc::{self, message::Message, Sender},
     windows::{
-        event, module, pipe,
+        event, module,
+        pipe::{self, Stdio},
         process::{self, CheckIs64BitError},
         thread,
     },
 };
 use std::{
-    ffi::OsStr,
-    io::{self, Read},
-    path::Path,
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
 use thiserror::Error;
 use tokio::select;
 
+/// The command line passed to the spawned child process: either individual arguments to be
+/// quoted and joined automatically (see [`process::command_line::command_line_from_args`]), or a
+/// pre-escaped string for callers that need to control exactly how the child's argv comes out.
+#[derive(Debug)]
+pub enum CommandLine {
+    Args(Vec<OsString>),
+    Raw(OsString),
+}
+
+impl CommandLine {
+    pub fn args(arguments: impl IntoIterator<Item = impl AsRef<OsStr>>) -> Self {
+        Self::Args(
+            arguments
+                .into_iter()
+                .map(|argument| argument.as_ref().to_os_string())
+                .collect(),
+        )
+    }
+
+    pub fn raw_command_line(command_line: impl AsRef<OsStr>) -> Self {
+        Self::Raw(command_line.as_ref().to_os_string())
+    }
+
+    fn resolve(self) -> Result<OsString, process::command_line::ArgumentError> {
+        match self {
+            Self::Args(arguments) => process::command_line::command_line_from_args(arguments),
+            Self::Raw(command_line) => Ok(command_line),
+        }
+    }
+}
+
+/// The environment passed to the spawned child process, composed the way
+/// `std::process::Command`'s `CommandEnv` is: starting from a snapshot of this process's own
+/// environment (unless cleared with [`Self::env_clear`]), then layering [`Self::env`]/
+/// [`Self::envs`] overrides and [`Self::env_remove`] removals on top. Keys are compared the way
+/// Windows does — case insensitively — so setting `Path` overrides an inherited `PATH` instead of
+/// sitting alongside it.
+#[derive(Debug, Default)]
+pub struct CommandEnv {
+    clear: bool,
+    vars: BTreeMap<OsString, EnvVar>,
+}
+
+#[derive(Debug)]
+enum EnvVar {
+    Set(OsString, OsString),
+    Removed,
+}
+
+impl CommandEnv {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        let key = key.as_ref().to_os_string();
+        let value = value.as_ref().to_os_string();
+        self.vars.insert(uppercase(&key), EnvVar::Set(key, value));
+        self
+    }
+
+    pub fn envs(
+        &mut self,
+        vars: impl IntoIterator<Item = (impl AsRef<OsStr>, impl AsRef<OsStr>)>,
+    ) -> &mut Self {
+        for (key, value) in vars {
+            self.env(key, value);
+        }
+        self
+    }
+
+    pub fn env_remove(&mut self, key: impl AsRef<OsStr>) -> &mut Self {
+        self.vars
+            .insert(uppercase(key.as_ref()), EnvVar::Removed);
+        self
+    }
+
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.clear = true;
+        self
+    }
+
+    fn resolve(self) -> Result<Option<Vec<u16>>, process::environment::EnvironmentBlockError> {
+        if !self.clear && self.vars.is_empty() {
+            return Ok(None);
+        }
+
+        let mut entries = BTreeMap::new();
+        if !self.clear {
+            for (key, value) in std::env::vars_os() {
+                entries.insert(uppercase(&key), (key, value));
+            }
+        }
+        for (uppercased_key, var) in self.vars {
+            match var {
+                EnvVar::Set(key, value) => {
+                    entries.insert(uppercased_key, (key, value));
+                }
+                EnvVar::Removed => {
+                    entries.remove(&uppercased_key);
+                }
+            }
+        }
+
+        Ok(Some(process::environment::environment_block(
+            entries.into_values(),
+        )?))
+    }
+}
+
+fn uppercase(key: &OsStr) -> OsString {
+    OsString::from(key.to_string_lossy().to_uppercase())
+}
+
 pub struct Conductor {
+    executable_path: PathBuf,
     process: process::Process,
+    stdin_pipe_writer: Option<pipe::Writer>,
     #[expect(clippy::type_complexity)]
     stdout_callback: Option<Box<dyn Fn(&[u8]) + Send>>,
-    stdout_pipe_reader: pipe::Reader,
+    stdout_pipe_reader: Option<pipe::OverlappedReader>,
+    #[expect(clippy::type_complexity)]
+    stderr_callback: Option<Box<dyn Fn(&[u8]) + Send>>,
+    stderr_pipe_reader: Option<pipe::OverlappedReader>,
     message_sender: Sender<ipc::message::FromConductor>,
     receive_log_messages_task: Option<tokio::task::JoinHandle<()>>,
+    log_buffer: Arc<LogBuffer>,
+    log_publisher: Arc<LogPublisher>,
+    rpc_client: Arc<RpcClient>,
+    receive_rpc_responses_task: Option<tokio::task::JoinHandle<()>>,
+    /// Further processes this conductor has injected into, beyond the root one above: either the
+    /// root's own direct children, or their descendants in turn, flattened into one list rather
+    /// than mirroring the tree's actual shape, since every fan-out operation below
+    /// (`advance_time`, `save_state`, `wait_until_inactive`) wants to treat the whole tree
+    /// uniformly anyway. Populated as [`ipc::message::SpawnedProcess`] notifications arrive (see
+    /// `attach_spawned_process`), so it grows over the conductor's lifetime as the target spawns
+    /// more children. Held behind a `tokio::sync::Mutex` rather than `std::sync::Mutex` so the
+    /// guard can be kept across the `.await` points in those fan-out loops.
+    children: Arc<tokio::sync::Mutex<Vec<ChildProcess>>>,
+    receive_spawned_processes_task: Option<tokio::task::JoinHandle<()>>,
     saved_state: Option<SavedState>,
+    dirty_tracking_armed: bool,
+    wait_until_inactive_timeout: Option<Duration>,
+}
+
+/// A subprocess the conductor has injected the hooks DLL into and is driving in lockstep with the
+/// root process, tracked in [`Conductor::children`]. Built by [`initialize_injected_process`] for
+/// both the root process (from [`Conductor::new`]) and every process it spawns in turn (from
+/// [`attach_spawned_process`], via a [`ipc::message::SpawnedProcess`] notification).
+struct ChildProcess {
+    process: process::Process,
+    message_sender: Sender<ipc::message::FromConductor>,
+    rpc_client: Arc<RpcClient>,
+    receive_log_messages_task: Option<tokio::task::JoinHandle<()>>,
+    receive_rpc_responses_task: Option<tokio::task::JoinHandle<()>>,
+    receive_spawned_processes_task: Option<tokio::task::JoinHandle<()>>,
+    /// This child's own save-state slot, paralleling [`Conductor::saved_state`]; `None` until the
+    /// first [`Conductor::save_state`]/[`Conductor::branch`] call after it was attached. A child
+    /// that spawns after a save (or disappears before a later load) simply has no counterpart on
+    /// the other side; [`Conductor::load_state`] skips children it has no saved state for, and
+    /// `saved_state` being `Some` for a child [`Conductor::load_state`] can no longer reach is
+    /// silently ignored the same way.
+    saved_state: Option<SavedState>,
+    dirty_tracking_armed: bool,
 }
 
 impl Conductor {
-    pub async fn new<F>(
+    #[expect(clippy::too_many_arguments)]
+    pub async fn new<StdoutCallback, StderrCallback>(
         executable_path: impl AsRef<Path>,
-        command_line_string: impl AsRef<OsStr>,
-        stdout_callback: Option<F>,
+        command_line: CommandLine,
+        environment: CommandEnv,
+        stdin: Stdio,
+        stdout: Stdio,
+        stdout_callback: Option<StdoutCallback>,
+        stderr: Stdio,
+        stderr_callback: Option<StderrCallback>,
     ) -> Result<Self, NewError>
     where
-        F: Fn(&[u8]) + Send + 'static,
+        StdoutCallback: Fn(&[u8]) + Send + 'static,
+        StderrCallback: Fn(&[u8]) + Send + 'static,
     {
-        let (stdout_pipe_writer, stdout_pipe_reader) = pipe::new()?;
+        let (stdin_handle, stdin_pipe_writer) = stdin.resolve_stdin()?;
+        let (stdout_handle, stdout_pipe_reader) = stdout.resolve_stdout()?;
+        let (stderr_handle, stderr_pipe_reader) = stderr.resolve_stderr()?;
 
         let subprocess = process::Process::create(
             executable_path.as_ref(),
-            command_line_string,
+            command_line.resolve()?,
+            environment.resolve()?,
             true,
-            None,
-            Some(stdout_pipe_writer),
-            None,
+            stdin_handle,
+            stdout_handle,
+            stderr_handle,
         )?;
         subprocess.kill_on_current_process_exit()?;
         let main_thread = thread::Thread::from_id(
@@ -57,92 +235,88 @@ impl Conductor {
                 .expect("no threads in subprocess"),
         )?;
 
-        let hooks_library = if subprocess.is_64_bit()? {
-            "hooks64.dll"
-        } else {
-            "hooks32.dll"
-        };
-        subprocess.inject_dll(hooks_library).await?;
-
-        let process = process::Process::get_current();
-        let (conductor_sender, hooks_receiver) =
-            ipc::new_sender_and_receiver(&process, &subprocess)?;
-        let (hooks_initialized_sender, mut conductor_initialized_receiver) =
-            ipc::new_sender_and_receiver(&subprocess, &process)?;
-        let (hooks_log_sender, mut conductor_log_receiver) =
-            ipc::new_sender_and_receiver(&subprocess, &process)?;
-
-        let initial_message = ipc::message::Initial {
-            main_thread_id: main_thread.get_id()?,
-            initialized_message_sender: hooks_initialized_sender,
-            log_message_sender: hooks_log_sender,
-            message_receiver: hooks_receiver,
-        };
-        let initial_message_serialized = unsafe { initial_message.serialize() }.unwrap();
-        let initial_message_pointer = subprocess.allocate_memory(
-            size_of::<u32>() + initial_message_serialized.len(),
-            process::MemoryPermissions {
-                rwe: process::MemoryPermissionsRwe::ReadWrite,
-                is_guard: false,
-            },
-        )?;
-        subprocess.write(
-            initial_message_pointer.cast(),
-            &u32::try_from(initial_message_serialized.len())
-                .unwrap()
-                .to_ne_bytes(),
-        )?;
-        subprocess.write(
-            unsafe { initial_message_pointer.byte_add(size_of::<u32>()).cast() },
-            &initial_message_serialized,
-        )?;
-        unsafe {
-            subprocess.create_thread(
-                subprocess
-                    .get_module(OsStr::new(hooks_library))?
-                    .unwrap()
-                    .get_export_address("initialize")?
-                    .unwrap(),
-                false,
-                Some(initial_message_pointer.cast()),
-            )?;
-        }
-
-        let receive_log_messages_task = {
-            tokio::spawn(async move {
-                loop {
-                    let ipc::message::Log { level, message } =
-                        conductor_log_receiver.receive().await.unwrap();
-                    match level {
-                        ipc::message::LogLevel::Trace => tracing::trace!(target: "hooks", message),
-                        ipc::message::LogLevel::Debug => tracing::debug!(target: "hooks", message),
-                        ipc::message::LogLevel::Info => tracing::info!(target: "hooks", message),
-                        ipc::message::LogLevel::Warning => tracing::warn!(target: "hooks", message),
-                        ipc::message::LogLevel::Error => tracing::error!(target: "hooks", message),
-                    };
-                }
-            })
-        };
+        let log_buffer = Arc::new(LogBuffer::default());
+        let log_publisher = Arc::new(LogPublisher::default());
+        let children = Arc::new(tokio::sync::Mutex::new(Vec::new()));
 
-        conductor_initialized_receiver.receive().await?;
+        let ChildProcess {
+            process: subprocess,
+            message_sender,
+            rpc_client,
+            receive_log_messages_task,
+            receive_rpc_responses_task,
+            receive_spawned_processes_task,
+            saved_state: _,
+            dirty_tracking_armed: _,
+        } = initialize_injected_process(
+            subprocess,
+            main_thread.get_id()?,
+            Arc::clone(&log_buffer),
+            Arc::clone(&log_publisher),
+            Arc::clone(&children),
+        )
+        .await?;
 
         Ok(Self {
+            executable_path: executable_path.as_ref().to_path_buf(),
             process: subprocess,
-            stdout_callback: match stdout_callback {
-                Some(stdout_callback) => Some(Box::new(*Box::new(stdout_callback))),
-                None => todo!(),
-            },
+            stdin_pipe_writer,
+            stdout_callback: stdout_callback
+                .map(|callback| Box::new(callback) as Box<dyn Fn(&[u8]) + Send>),
             stdout_pipe_reader,
-            message_sender: conductor_sender,
-            receive_log_messages_task: Some(receive_log_messages_task),
+            stderr_callback: stderr_callback
+                .map(|callback| Box::new(callback) as Box<dyn Fn(&[u8]) + Send>),
+            stderr_pipe_reader,
+            message_sender,
+            receive_log_messages_task,
+            log_buffer,
+            log_publisher,
+            rpc_client,
+            receive_rpc_responses_task,
+            children,
+            receive_spawned_processes_task,
             saved_state: None,
+            dirty_tracking_armed: false,
+            wait_until_inactive_timeout: None,
         })
     }
 
+    /// Arms a timeout on every future [`Self::wait_until_inactive`] call (and on `save_state`,
+    /// `save_state_incremental`, and `load_state`, which call it internally): if the subprocess
+    /// hasn't gone idle or terminated within `timeout`, the wait returns
+    /// [`InactiveState::TimedOut`] (or, from those three, a `TimedOut` error) instead of hanging
+    /// forever on a hook that deadlocked. The subprocess itself is left running either way, so the
+    /// caller can decide whether to abort, retry, or dump diagnostics. Pass `None` to go back to
+    /// waiting indefinitely, which is the default.
+    pub fn set_wait_until_inactive_timeout(&mut self, timeout: Option<Duration>) {
+        self.wait_until_inactive_timeout = timeout;
+    }
+
+    /// The write end of the child's stdin pipe, if `stdin` was passed as [`Stdio::Piped`] to
+    /// [`Self::new`] — lets a caller (e.g. a movie player) feed bytes to the child interactively.
+    /// `None` if stdin was configured as [`Stdio::Inherit`] or [`Stdio::Null`].
+    pub fn stdin(&mut self) -> Option<&mut pipe::Writer> {
+        self.stdin_pipe_writer.as_mut()
+    }
+
+    /// The path this conductor's subprocess was created from, as passed to [`Self::new`] — lets a
+    /// movie recorder/player confirm a movie is being replayed against the executable it was
+    /// recorded against.
+    #[must_use]
+    pub fn executable_path(&self) -> &Path {
+        &self.executable_path
+    }
+
     pub async fn resume(&mut self) -> Result<(), ResumeError> {
         self.message_sender
             .send(ipc::message::FromConductor::Resume)
             .await?;
+        for child in self.children.lock().await.iter_mut() {
+            child
+                .message_sender
+                .send(ipc::message::FromConductor::Resume)
+                .await?;
+        }
         Ok(())
     }
 
@@ -153,6 +327,16 @@ impl Conductor {
         Ok(())
     }
 
+    /// Like [`Self::set_key_state`], but returns as soon as the message is handed to the
+    /// transport instead of also waiting for the hooks DLL to consume it — for high-frequency
+    /// input where the caller will batch several calls and settle once, with [`Self::wait_until_inactive`].
+    pub async fn queue_key_state(&mut self, id: u8, state: bool) -> Result<(), SetKeyStateError> {
+        self.message_sender
+            .send_async(&ipc::message::FromConductor::SetKeyState { id, state })
+            .await?;
+        Ok(())
+    }
+
     pub async fn set_mouse_position(
         &mut self,
         x: u16,
@@ -175,101 +359,717 @@ impl Conductor {
         Ok(())
     }
 
+    pub async fn set_mouse_wheel(
+        &mut self,
+        delta: i32,
+        horizontal: bool,
+    ) -> Result<(), SetMouseWheelError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::ScrollMouseWheel { delta, horizontal })
+            .await?;
+        Ok(())
+    }
+
+    /// Sets `button`'s pressed state on the virtual gamepad at `index` (`0`-`3`). See
+    /// `hooks::state::set_gamepad_button`.
+    pub async fn set_gamepad_button(
+        &mut self,
+        index: u8,
+        button: GamepadButton,
+        state: bool,
+    ) -> Result<(), SetGamepadButtonError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::SetGamepadButton {
+                index,
+                button,
+                state,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Sets `axis`'s value on the virtual gamepad at `index` (`0`-`3`). See
+    /// `hooks::state::set_gamepad_axis`.
+    pub async fn set_gamepad_axis(
+        &mut self,
+        index: u8,
+        axis: GamepadAxis,
+        value: i16,
+    ) -> Result<(), SetGamepadAxisError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::SetGamepadAxis { index, axis, value })
+            .await?;
+        Ok(())
+    }
+
+    /// Sets `trigger`'s value on the virtual gamepad at `index` (`0`-`3`). See
+    /// `hooks::state::set_gamepad_trigger`.
+    pub async fn set_gamepad_trigger(
+        &mut self,
+        index: u8,
+        trigger: GamepadTrigger,
+        value: u8,
+    ) -> Result<(), SetGamepadTriggerError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::SetGamepadTrigger {
+                index,
+                trigger,
+                value,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Switches every virtual socket hook between recording real network traffic into the
+    /// save-state-persisted log and replaying `recv`s from that log without touching the network
+    /// at all. See `hooks::state::set_socket_mode`.
+    pub async fn set_socket_mode(
+        &mut self,
+        mode: ipc::message::SocketMode,
+    ) -> Result<(), SetSocketModeError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::SetSocketMode(mode))
+            .await?;
+        Ok(())
+    }
+
+    /// Patches an `int3` breakpoint trap at `address` in the subprocess, replacing whatever byte
+    /// was there. The trap fires at most once; call this again to re-arm it after it fires. See
+    /// [`Self::wait_for_pause`]/[`Self::resume_from_pause`] for pausing and continuing past it.
+    pub async fn set_breakpoint(&mut self, address: usize) -> Result<(), SetBreakpointError> {
+        let (response_sender, mut response_receiver) =
+            ipc::new_sender_and_receiver(&self.process, &process::Process::get_current())?;
+        self.message_sender
+            .send(ipc::message::FromConductor::SetBreakpoint {
+                address,
+                response_sender,
+            })
+            .await?;
+        response_receiver.receive().await?;
+        Ok(())
+    }
+
+    /// Removes a breakpoint previously armed by [`Self::set_breakpoint`] that hasn't fired yet,
+    /// restoring the original byte.
+    pub async fn clear_breakpoint(&mut self, address: usize) -> Result<(), ClearBreakpointError> {
+        let (response_sender, mut response_receiver) =
+            ipc::new_sender_and_receiver(&self.process, &process::Process::get_current())?;
+        self.message_sender
+            .send(ipc::message::FromConductor::ClearBreakpoint {
+                address,
+                response_sender,
+            })
+            .await?;
+        response_receiver.receive().await?;
+        Ok(())
+    }
+
+    /// Waits for any subprocess thread to pause at a breakpoint (see [`Self::set_breakpoint`]) or
+    /// a single-stepped instruction (see [`Self::resume_from_pause`]), returning which thread
+    /// paused and at what address. Pausing costs no virtual time: nothing in the simulated clock
+    /// advances while a thread sits blocked waiting to be resumed.
+    pub async fn wait_for_pause(&mut self) -> Result<ipc::message::DebugPause, WaitForPauseError> {
+        let (response_sender, mut response_receiver) =
+            ipc::new_sender_and_receiver(&self.process, &process::Process::get_current())?;
+        self.message_sender
+            .send(ipc::message::FromConductor::WaitForPause { response_sender })
+            .await?;
+        Ok(response_receiver.receive().await?)
+    }
+
+    /// Resumes the thread paused at `thread_id` (see [`Self::wait_for_pause`]); if `single_step`
+    /// is set, it pauses again after exactly one more instruction instead of running freely.
+    pub async fn resume_from_pause(
+        &mut self,
+        thread_id: u32,
+        single_step: bool,
+    ) -> Result<(), ResumeFromPauseError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::ResumeFromPause {
+                thread_id,
+                single_step,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Reads `length` bytes of the subprocess's memory starting at `address`.
+    pub fn read_memory(&self, address: usize, length: usize) -> Result<Vec<u8>, ReadMemoryError> {
+        Ok(self.process.read_to_vec(address as *const u8, length)?)
+    }
+
+    /// Writes `bytes` into the subprocess's memory starting at `address`.
+    pub fn write_memory(&self, address: usize, bytes: &[u8]) -> Result<(), WriteMemoryError> {
+        Ok(self.process.write(address as *mut u8, bytes)?)
+    }
+
+    /// Reads the general-purpose and flags registers of the subprocess thread identified by
+    /// `thread_id`, named as in the Intel/AMD manuals (see [`thread::Context32::registers`]/
+    /// [`thread::Context64::registers`]).
+    pub fn read_registers(
+        &self,
+        thread_id: u32,
+    ) -> Result<BTreeMap<&'static str, u64>, ReadRegistersError> {
+        Ok(match thread::Thread::from_id(thread_id)?.get_context()? {
+            thread::Context::Context32(context) => context.registers(),
+            #[cfg(target_pointer_width = "64")]
+            thread::Context::Context64(context) => context.registers(),
+        })
+    }
+
+    /// Sets the register named `name` (see [`Self::read_registers`] for the recognized names) of
+    /// the subprocess thread identified by `thread_id`; has no effect if `name` isn't recognized.
+    pub fn write_register(
+        &self,
+        thread_id: u32,
+        name: &str,
+        value: u64,
+    ) -> Result<(), WriteRegisterError> {
+        let thread = thread::Thread::from_id(thread_id)?;
+        let mut context = thread.get_context()?;
+        match &mut context {
+            thread::Context::Context32(context) => context.set_register(name, value),
+            #[cfg(target_pointer_width = "64")]
+            thread::Context::Context64(context) => context.set_register(name, value),
+        }
+        thread.set_context(&context)?;
+        Ok(())
+    }
+
     pub async fn advance_time(&mut self, time: Duration) -> Result<(), AdvanceTimeError> {
         self.message_sender
             .send(ipc::message::FromConductor::AdvanceTime(time))
             .await?;
+        for child in self.children.lock().await.iter_mut() {
+            child
+                .message_sender
+                .send(ipc::message::FromConductor::AdvanceTime(time))
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn deliver_console_ctrl_event(
+        &mut self,
+        event: ConsoleCtrlEvent,
+    ) -> Result<(), DeliverConsoleCtrlEventError> {
+        self.message_sender
+            .send(ipc::message::FromConductor::DeliverConsoleCtrlEvent(event))
+            .await?;
         Ok(())
     }
 
     pub async fn save_state(&mut self) -> Result<(), SaveStateError> {
-        self.wait_until_inactive().await?;
+        if self.wait_until_inactive().await? == InactiveState::TimedOut {
+            return Err(TimedOutError.into());
+        }
         self.saved_state = Some(SavedState::new(&self.process)?);
+        self.dirty_tracking_armed = false;
+        for child in self.children.lock().await.iter_mut() {
+            child.saved_state = Some(SavedState::new(&child.process)?);
+            child.dirty_tracking_armed = false;
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::save_state`], but re-reads only the memory pages written to since the
+    /// previous call to this method, rather than the whole address space. Dirty-page tracking is
+    /// (re-)armed against the freshly-built state's regions at the end of every call, rather than
+    /// only once - otherwise memory committed after the last arm (e.g. a heap growing) would never
+    /// be watched by the hooks DLL, and writes to it would silently go unrecorded.
+    pub async fn save_state_incremental(&mut self) -> Result<(), SaveStateIncrementalError> {
+        if self.wait_until_inactive().await? == InactiveState::TimedOut {
+            return Err(TimedOutError.into());
+        }
+
+        let base = self
+            .saved_state
+            .as_ref()
+            .ok_or(NoBaseSavedStateError)?;
+
+        let base_addresses = if self.dirty_tracking_armed {
+            let (response_sender, mut response_receiver) =
+                ipc::new_sender_and_receiver(&self.process, &process::Process::get_current())?;
+            self.message_sender
+                .send(ipc::message::FromConductor::TakeDirtyPages { response_sender })
+                .await?;
+            let ipc::message::DirtyPages { base_addresses } = response_receiver.receive().await?;
+            base_addresses
+        } else {
+            Vec::new()
+        };
+
+        let new_saved_state = SavedState::new_incremental(&self.process, base, &base_addresses)?;
+
+        let (response_sender, mut response_receiver) =
+            ipc::new_sender_and_receiver(&self.process, &process::Process::get_current())?;
+        self.message_sender
+            .send(ipc::message::FromConductor::ArmDirtyTracking {
+                regions: new_saved_state.writable_region_ranges(),
+                response_sender,
+            })
+            .await?;
+        response_receiver.receive().await?;
+        self.dirty_tracking_armed = true;
+        self.saved_state = Some(new_saved_state);
+
+        for child in self.children.lock().await.iter_mut() {
+            let base = child.saved_state.as_ref().ok_or(NoBaseSavedStateError)?;
+
+            let base_addresses = if child.dirty_tracking_armed {
+                let (response_sender, mut response_receiver) = ipc::new_sender_and_receiver(
+                    &child.process,
+                    &process::Process::get_current(),
+                )?;
+                child
+                    .message_sender
+                    .send(ipc::message::FromConductor::TakeDirtyPages { response_sender })
+                    .await?;
+                let ipc::message::DirtyPages { base_addresses } =
+                    response_receiver.receive().await?;
+                base_addresses
+            } else {
+                Vec::new()
+            };
+
+            let new_saved_state =
+                SavedState::new_incremental(&child.process, base, &base_addresses)?;
+
+            let (response_sender, mut response_receiver) =
+                ipc::new_sender_and_receiver(&child.process, &process::Process::get_current())?;
+            child
+                .message_sender
+                .send(ipc::message::FromConductor::ArmDirtyTracking {
+                    regions: new_saved_state.writable_region_ranges(),
+                    response_sender,
+                })
+                .await?;
+            response_receiver.receive().await?;
+            child.dirty_tracking_armed = true;
+            child.saved_state = Some(new_saved_state);
+        }
         Ok(())
     }
 
     pub async fn load_state(&mut self) -> Result<(), LoadStateError> {
-        self.wait_until_inactive().await?;
+        if self.wait_until_inactive().await? == InactiveState::TimedOut {
+            return Err(TimedOutError.into());
+        }
         if let Some(state) = &self.saved_state {
             state.load(&self.process)?;
         } else {
             panic!("no damn state");
         }
+        // a child with no saved state either spawned after the save this is loading, or is one
+        // `load_state` has no way to reconcile against whatever child tree existed back then -
+        // either way, leaving it running untouched is the closest approximation available
+        for child in self.children.lock().await.iter() {
+            if let Some(state) = &child.saved_state {
+                state.load(&child.process)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Captures a [`Branch`] the caller can hold onto (and load back in any order, any number of
+    /// times) without disturbing the single internal slot [`Self::save_state`]/
+    /// [`Self::load_state`] use — for tooling that wants to try several divergent continuations
+    /// from the same point and compare them, rather than overwrite one checkpoint as it goes.
+    pub async fn branch(&mut self) -> Result<Branch, SaveStateError> {
+        if self.wait_until_inactive().await? == InactiveState::TimedOut {
+            return Err(TimedOutError.into());
+        }
+        let mut child_states = Vec::new();
+        for child in self.children.lock().await.iter() {
+            child_states.push(SavedState::new(&child.process)?);
+        }
+        Ok(Branch(SavedState::new(&self.process)?, child_states))
+    }
+
+    /// Restores a previously captured [`Branch`]. The branch itself is left intact, so it can be
+    /// loaded again later to try a different continuation from the same point.
+    pub async fn load_branch(&mut self, branch: &Branch) -> Result<(), LoadStateError> {
+        if self.wait_until_inactive().await? == InactiveState::TimedOut {
+            return Err(TimedOutError.into());
+        }
+        branch.0.load(&self.process)?;
+        // same best-effort reconciliation as `load_state`: a child tree that's grown or shrunk
+        // since this branch was captured doesn't line up with `branch.1` one-to-one, so anything
+        // past the shorter of the two lists is left alone rather than guessed at
+        for (child, state) in self.children.lock().await.iter().zip(&branch.1) {
+            state.load(&child.process)?;
+        }
         Ok(())
     }
 
+    /// Returns recently received log entries from the hooks DLL at or above `min_level`, so a
+    /// client can pull recent history after the fact instead of only seeing a live stream.
+    #[must_use]
+    pub fn recent_logs(&self, min_level: ipc::message::LogLevel) -> Vec<ipc::message::Log> {
+        self.log_buffer.recent(min_level)
+    }
+
+    /// Subscribes to the live log stream from the hooks DLL. Multiple subscribers (e.g. a TUI and
+    /// a file logger) can coexist, each at its own pace; a subscriber that falls too far behind
+    /// misses entries rather than holding the others back. Only sees entries published from this
+    /// call onward — combine with [`Self::recent_logs`] to also pick up history.
+    #[must_use]
+    pub fn subscribe_logs(&self) -> LogSubscriber {
+        self.log_publisher.subscribe()
+    }
+
+    /// Compares this conductor's saved state against `other`'s, to help narrow down where two
+    /// otherwise-identical playthroughs desync. Returns `None` if either conductor has no saved
+    /// state yet.
+    #[must_use]
+    pub fn diff_saved_state(&self, other: &Self) -> Option<saved_state::StateDiff> {
+        Some(
+            self.saved_state
+                .as_ref()?
+                .diff(other.saved_state.as_ref()?),
+        )
+    }
+
     pub async fn wait_until_inactive(&mut self) -> Result<InactiveState, WaitUntilInactiveError> {
-        let mut stdout = Vec::new();
-        let state = select! {
-            result = async {
-                let (response_sender, mut response_receiver) =
-                    ipc::new_sender_and_receiver(&self.process, &process::Process::get_current())?;
-                self.message_sender
-                    .send(ipc::message::FromConductor::IdleRequest { response_sender })
-                    .await?;
-                response_receiver.receive().await?;
-                Ok::<_, WaitUntilInactiveError>(())
-            } => {
-                result?;
-                InactiveState::Idle
+        match self.wait_until_inactive_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.wait_until_inactive_untimed()).await
+            {
+                Ok(result) => result,
+                Err(_elapsed) => Ok(InactiveState::TimedOut),
+            },
+            None => self.wait_until_inactive_untimed().await,
+        }
+    }
+
+    async fn wait_until_inactive_untimed(
+        &mut self,
+    ) -> Result<InactiveState, WaitUntilInactiveError> {
+        let idle = async {
+            self.rpc_client
+                .call(&mut self.message_sender, &ipc::message::IsIdleRequest)
+                .await?;
+            Ok::<_, WaitUntilInactiveError>(())
+        };
+        tokio::pin!(idle);
+        let join = self.process.join();
+        tokio::pin!(join);
+
+        // each loop iteration either settles the wait (idle/terminated) or, on one of the stdio
+        // branches, surfaces a chunk to the corresponding callback the instant it arrives and
+        // loops back around to keep waiting on the other branches, rather than polling on a timer
+        loop {
+            let mut stdout_chunk = [0; 4096];
+            let mut stderr_chunk = [0; 4096];
+            select! {
+                result = &mut idle => {
+                    result?;
+                    return Ok(InactiveState::Idle);
+                }
+                result = &mut join => {
+                    let exit_code = result?;
+                    if let Some(task) = self.receive_log_messages_task.take() {
+                        task.abort();
+                    }
+                    if let Some(task) = self.receive_rpc_responses_task.take() {
+                        task.abort();
+                    }
+                    if let Some(task) = self.receive_spawned_processes_task.take() {
+                        task.abort();
+                    }
+                    // the root process exiting doesn't mean the whole tree has: a child spawned
+                    // late could still be running, so only report Terminated once every tracked
+                    // child has also joined. A child that exits mid `IsIdleRequest` on some other
+                    // call isn't this method's problem to solve - that call just never resolves.
+                    for child in self.children.lock().await.iter_mut() {
+                        child.process.join().await?;
+                        if let Some(task) = child.receive_log_messages_task.take() {
+                            task.abort();
+                        }
+                        if let Some(task) = child.receive_rpc_responses_task.take() {
+                            task.abort();
+                        }
+                        if let Some(task) = child.receive_spawned_processes_task.take() {
+                            task.abort();
+                        }
+                    }
+                    return Ok(InactiveState::Terminated { exit_code });
+                }
+                result = read_piped_stream(&mut self.stdout_pipe_reader, &mut stdout_chunk) => {
+                    let count = result?;
+                    if count > 0 {
+                        self.stdout_callback.as_ref().inspect(|f| f(&stdout_chunk[..count]));
+                    }
+                }
+                result = read_piped_stream(&mut self.stderr_pipe_reader, &mut stderr_chunk) => {
+                    let count = result?;
+                    if count > 0 {
+                        self.stderr_callback.as_ref().inspect(|f| f(&stderr_chunk[..count]));
+                    }
+                }
             }
-            result = self.process.join() => {
-                let exit_code = result?;
-                if let Some(task) = self.receive_log_messages_task.take() {
-                    task.abort();
+        }
+    }
+}
+
+/// Injects the hooks DLL into `subprocess` (already created or attached to, and still suspended at
+/// `main_thread_id`) and runs it through the same handshake [`Conductor::new`] used to bring up
+/// the root process: set up its IPC channels, hand it an [`ipc::message::Initial`] message, start
+/// its hooks thread, and spawn the log/RPC-response relay tasks plus a watcher task that attaches
+/// to any further children *it* spawns (pushing them into `children` as they arrive, via
+/// [`attach_spawned_process`]) so the whole tree is covered no matter how deep it nests. Used both
+/// by [`Conductor::new`] for the root process and by [`attach_spawned_process`] for every
+/// descendant.
+async fn initialize_injected_process(
+    subprocess: process::Process,
+    main_thread_id: u32,
+    log_buffer: Arc<LogBuffer>,
+    log_publisher: Arc<LogPublisher>,
+    children: Arc<tokio::sync::Mutex<Vec<ChildProcess>>>,
+) -> Result<ChildProcess, InjectAndInitializeError> {
+    let hooks_library = if subprocess.is_64_bit()? {
+        "hooks64.dll"
+    } else {
+        "hooks32.dll"
+    };
+    subprocess.inject_dll(hooks_library).await?;
+
+    let process = process::Process::get_current();
+    let (conductor_sender, hooks_receiver) = ipc::new_sender_and_receiver_with_shared_memory(
+        &process,
+        &subprocess,
+        &format!("winter-messages-{}", subprocess.get_id()?),
+        ipc::DEFAULT_RING_BUFFER_CAPACITY,
+    )?;
+    let (hooks_initialized_sender, mut conductor_initialized_receiver) =
+        ipc::new_sender_and_receiver(&subprocess, &process)?;
+    let (hooks_log_sender, mut conductor_log_receiver) =
+        ipc::new_sender_and_receiver(&subprocess, &process)?;
+    let (hooks_rpc_response_sender, mut conductor_rpc_response_receiver) =
+        ipc::new_sender_and_receiver(&subprocess, &process)?;
+    let (hooks_spawned_process_sender, mut conductor_spawned_process_receiver) =
+        ipc::new_sender_and_receiver(&subprocess, &process)?;
+
+    let initial_message = ipc::message::Initial {
+        main_thread_id,
+        initialized_message_sender: hooks_initialized_sender,
+        log_message_sender: hooks_log_sender,
+        message_receiver: hooks_receiver,
+        rpc_response_sender: hooks_rpc_response_sender,
+        spawned_process_sender: hooks_spawned_process_sender,
+    };
+    let initial_message_serialized = unsafe { initial_message.serialize() }.unwrap();
+    let initial_message_pointer = subprocess.allocate_memory(
+        size_of::<u32>() + initial_message_serialized.len(),
+        process::MemoryPermissions {
+            rwe: process::MemoryPermissionsRwe::ReadWrite,
+            is_guard: false,
+        },
+    )?;
+    subprocess.write(
+        initial_message_pointer.cast(),
+        &u32::try_from(initial_message_serialized.len())
+            .unwrap()
+            .to_ne_bytes(),
+    )?;
+    subprocess.write(
+        unsafe { initial_message_pointer.byte_add(size_of::<u32>()).cast() },
+        &initial_message_serialized,
+    )?;
+    unsafe {
+        subprocess.create_thread(
+            subprocess
+                .get_module(OsStr::new(hooks_library))?
+                .unwrap()
+                .get_export_address("initialize")?
+                .unwrap(),
+            false,
+            Some(initial_message_pointer.cast()),
+        )?;
+    }
+
+    let receive_log_messages_task = {
+        let log_buffer = Arc::clone(&log_buffer);
+        let log_publisher = Arc::clone(&log_publisher);
+        tokio::spawn(async move {
+            loop {
+                let ipc::message::LogBatch { entries, dropped } =
+                    conductor_log_receiver.receive().await.unwrap();
+                if dropped > 0 {
+                    tracing::warn!(
+                        target: "hooks",
+                        "dropped {dropped} log entries that overflowed the ring buffer"
+                    );
                 }
-                InactiveState::Terminated { exit_code }
+                for entry in entries {
+                    let ipc::message::Log {
+                        level,
+                        ref target,
+                        ref message,
+                    } = entry;
+                    match level {
+                        ipc::message::LogLevel::Trace => {
+                            tracing::trace!(target: "hooks", "{target}: {message}");
+                        }
+                        ipc::message::LogLevel::Debug => {
+                            tracing::debug!(target: "hooks", "{target}: {message}");
+                        }
+                        ipc::message::LogLevel::Info => {
+                            tracing::info!(target: "hooks", "{target}: {message}");
+                        }
+                        ipc::message::LogLevel::Warning => {
+                            tracing::warn!(target: "hooks", "{target}: {message}");
+                        }
+                        ipc::message::LogLevel::Error => {
+                            tracing::error!(target: "hooks", "{target}: {message}");
+                        }
+                    }
+                    log_publisher.publish(entry.clone());
+                    log_buffer.push(entry);
+                }
+            }
+        })
+    };
+
+    let rpc_client = Arc::new(RpcClient::new(process.get_id()?));
+    let receive_rpc_responses_task = {
+        let rpc_client = Arc::clone(&rpc_client);
+        tokio::spawn(async move {
+            loop {
+                let response = conductor_rpc_response_receiver.receive().await.unwrap();
+                rpc_client.complete(response);
             }
-            error = async {
-                loop {
-                    // stdout is read in a loop with a sleep, as there appears to be no way
-                    // to await a signal indicating that stdout has just been written to
-                    if let Err(err) = self.stdout_pipe_reader.read_to_end(&mut stdout) {
-                        return err;
+        })
+    };
+
+    let receive_spawned_processes_task = {
+        let log_buffer = Arc::clone(&log_buffer);
+        let log_publisher = Arc::clone(&log_publisher);
+        let children = Arc::clone(&children);
+        tokio::spawn(async move {
+            loop {
+                let ipc::message::SpawnedProcess {
+                    process_id,
+                    main_thread_id,
+                } = conductor_spawned_process_receiver.receive().await.unwrap();
+                match attach_spawned_process(
+                    process_id,
+                    main_thread_id,
+                    Arc::clone(&log_buffer),
+                    Arc::clone(&log_publisher),
+                    Arc::clone(&children),
+                )
+                .await
+                {
+                    Ok(child) => children.lock().await.push(child),
+                    Err(error) => {
+                        tracing::error!(
+                            "failed to attach to spawned child process {process_id}: {error}"
+                        );
                     }
-                    tokio::time::sleep(Duration::from_millis(10)).await;
                 }
-            } => {
-                return Err(error.into());
             }
-        };
+        })
+    };
 
-        self.stdout_pipe_reader.read_to_end(&mut stdout).unwrap();
-        if !stdout.is_empty() {
-            self.stdout_callback.as_ref().inspect(|f| f(&stdout));
-        }
+    conductor_initialized_receiver.receive().await?;
+
+    Ok(ChildProcess {
+        process: subprocess,
+        message_sender: conductor_sender,
+        rpc_client,
+        receive_log_messages_task: Some(receive_log_messages_task),
+        receive_rpc_responses_task: Some(receive_rpc_responses_task),
+        receive_spawned_processes_task: Some(receive_spawned_processes_task),
+        saved_state: None,
+        dirty_tracking_armed: false,
+    })
+}
+
+/// Attaches to a child process reported via [`ipc::message::SpawnedProcess`] (already forced
+/// suspended by the hooked `CreateProcess` call that created it, see `hooks::process`) and injects
+/// the hooks DLL into it the same way [`Conductor::new`] does for the root process.
+async fn attach_spawned_process(
+    process_id: u32,
+    main_thread_id: u32,
+    log_buffer: Arc<LogBuffer>,
+    log_publisher: Arc<LogPublisher>,
+    children: Arc<tokio::sync::Mutex<Vec<ChildProcess>>>,
+) -> Result<ChildProcess, InjectAndInitializeError> {
+    let subprocess = process::Process::from_id(process_id)?;
+    initialize_injected_process(
+        subprocess,
+        main_thread_id,
+        log_buffer,
+        log_publisher,
+        children,
+    )
+    .await
+}
 
-        Ok(state)
+/// Reads from `reader` if it's `Some` (a stream configured as [`Stdio::Piped`]); never resolves if
+/// it's `None`, so an unpiped stream's `select!` branch simply never fires instead of needing to
+/// be conditionally excluded from the loop.
+async fn read_piped_stream(
+    reader: &mut Option<pipe::OverlappedReader>,
+    buf: &mut [u8],
+) -> Result<usize, pipe::ReadSomeError> {
+    match reader {
+        Some(reader) => reader.read_some(buf).await,
+        None => std::future::pending().await,
     }
 }
 
+/// A checkpoint of a [`Conductor`]'s deterministic state captured by [`Conductor::branch`], held
+/// independently of the conductor so any number of them can coexist and be loaded back (via
+/// [`Conductor::load_branch`]) in any order — unlike [`Conductor::save_state`]'s single internal
+/// slot. Captures exactly what [`saved_state::SavedState`] does (every thread's registers and all
+/// committed memory): since the hooks DLL's virtual clock, key-state map, and pending timer
+/// deadlines all live in its own static memory inside that snapshot, no separate clock/input-only
+/// format is needed to round-trip them. The second field holds one such snapshot per tracked
+/// child process, in the same order as [`Conductor::children`] at capture time.
+pub struct Branch(saved_state::SavedState, Vec<saved_state::SavedState>);
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum InactiveState {
     Idle,
     Terminated { exit_code: u32 },
+    /// [`Conductor::wait_until_inactive`] gave up after [`Conductor::set_wait_until_inactive_timeout`]'s
+    /// timeout elapsed without the subprocess going idle or terminating. The subprocess is still
+    /// running.
+    TimedOut,
 }
 
 #[derive(Debug, Error)]
 #[error("failed to create conductor")]
 pub enum NewError {
-    NewPipe(#[from] pipe::NewError),
-    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
+    Argument(#[from] process::command_line::ArgumentError),
+    Environment(#[from] process::environment::EnvironmentBlockError),
+    ResolveStdio(#[from] pipe::ResolveError),
     MessageSenderClone(#[from] ipc::SenderCloneError),
     ProcessCreate(#[from] process::CreateError),
     ThreadFromId(#[from] thread::FromIdError),
     ThreadGetId(#[from] thread::GetIdError),
-    CheckIs64Bit(#[from] CheckIs64BitError),
     KillOnCurrentProcessExit(#[from] process::KillOnCurrentProcessExitError),
+    NewEvent(#[from] event::NewError),
+    IterThreadIds(#[from] process::IterThreadIdsError),
+    InjectAndInitialize(#[from] InjectAndInitializeError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while injecting into and initializing a subprocess")]
+pub enum InjectAndInitializeError {
+    ProcessGetId(#[from] process::GetIdError),
+    CheckIs64Bit(#[from] CheckIs64BitError),
     InjectDll(#[from] process::InjectDllError),
+    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
     ProcessAllocateMemory(#[from] process::AllocateMemoryError),
     ProcessWriteMemory(#[from] process::WriteMemoryError),
     GetModules(#[from] process::GetModulesError),
     ModuleGetExportAddress(#[from] module::GetExportAddressError),
-    NewEvent(#[from] event::NewError),
     ProcessCreateThread(#[from] process::CreateThreadError),
     MessageReceive(#[from] ipc::ReceiveError),
     Bincode(#[from] bincode::Error),
-    IterThreadIds(#[from] process::IterThreadIdsError),
+    FromId(#[from] std::io::Error),
 }
 
 #[derive(Debug, Error)]
@@ -296,18 +1096,134 @@ pub enum SetMouseButtonStateError {
     MessageSend(#[from] ipc::SendError),
 }
 
+#[derive(Debug, Error)]
+#[error("error occurred while scrolling the mouse wheel")]
+pub enum SetMouseWheelError {
+    MessageSend(#[from] ipc::SendError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while setting a gamepad button")]
+pub enum SetGamepadButtonError {
+    MessageSend(#[from] ipc::SendError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while setting a gamepad axis")]
+pub enum SetGamepadAxisError {
+    MessageSend(#[from] ipc::SendError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while setting a gamepad trigger")]
+pub enum SetGamepadTriggerError {
+    MessageSend(#[from] ipc::SendError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while setting socket mode")]
+pub enum SetSocketModeError {
+    MessageSend(#[from] ipc::SendError),
+}
+
 #[derive(Debug, Error)]
 #[error("error occurred while advancing time")]
 pub enum AdvanceTimeError {
     MessageSend(#[from] ipc::SendError),
 }
 
+#[derive(Debug, Error)]
+#[error("error occurred while setting a breakpoint")]
+pub enum SetBreakpointError {
+    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
+    MessageSend(#[from] ipc::SendError),
+    MessageReceive(#[from] ipc::ReceiveError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while clearing a breakpoint")]
+pub enum ClearBreakpointError {
+    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
+    MessageSend(#[from] ipc::SendError),
+    MessageReceive(#[from] ipc::ReceiveError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while waiting for a thread to pause")]
+pub enum WaitForPauseError {
+    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
+    MessageSend(#[from] ipc::SendError),
+    MessageReceive(#[from] ipc::ReceiveError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while resuming from a pause")]
+pub enum ResumeFromPauseError {
+    MessageSend(#[from] ipc::SendError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while reading guest memory")]
+pub enum ReadMemoryError {
+    Process(#[from] process::ReadMemoryError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while writing guest memory")]
+pub enum WriteMemoryError {
+    Process(#[from] process::WriteMemoryError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while reading thread registers")]
+pub enum ReadRegistersError {
+    ThreadFromId(#[from] thread::FromIdError),
+    ThreadGetContext(#[from] thread::GetContextError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while writing a thread register")]
+pub enum WriteRegisterError {
+    ThreadFromId(#[from] thread::FromIdError),
+    ThreadGetContext(#[from] thread::GetContextError),
+    ThreadSetContext(#[from] thread::SetContextError),
+}
+
+#[derive(Debug, Error)]
+#[error("error occurred while delivering console control event")]
+pub enum DeliverConsoleCtrlEventError {
+    MessageSend(#[from] ipc::SendError),
+}
+
 #[derive(Debug, Error)]
 #[error("error occurred while saving state")]
 pub enum SaveStateError {
     WaitUntilInactive(#[from] WaitUntilInactiveError),
     ThreadFromId(#[from] thread::FromIdError),
     NewSavedState(#[from] saved_state::NewError),
+    TimedOut(#[from] TimedOutError),
+}
+
+#[derive(Debug, Error)]
+#[error("no previous saved state to build on top of")]
+pub struct NoBaseSavedStateError;
+
+/// Returned by [`movie::Event::WriteStdin`] replay when [`Conductor::stdin`] is `None` — the
+/// conductor's `stdin` wasn't configured as [`pipe::Stdio::Piped`] to [`Conductor::new`].
+#[derive(Debug, Error)]
+#[error("conductor was not created with a piped stdin")]
+pub struct NoStdinError;
+
+#[derive(Debug, Error)]
+#[error("error occurred while incrementally saving state")]
+pub enum SaveStateIncrementalError {
+    NoBaseSavedState(#[from] NoBaseSavedStateError),
+    WaitUntilInactive(#[from] WaitUntilInactiveError),
+    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
+    MessageSend(#[from] ipc::SendError),
+    MessageReceive(#[from] ipc::ReceiveError),
+    NewSavedState(#[from] saved_state::NewError),
+    TimedOut(#[from] TimedOutError),
 }
 
 #[derive(Debug, Error)]
@@ -315,14 +1231,17 @@ pub enum SaveStateError {
 pub enum LoadStateError {
     WaitUntilInactive(#[from] WaitUntilInactiveError),
     SavedStateLoad(#[from] saved_state::LoadError),
+    TimedOut(#[from] TimedOutError),
 }
 
+#[derive(Debug, Error)]
+#[error("timed out waiting for the subprocess to become inactive")]
+pub struct TimedOutError;
+
 #[derive(Debug, Error)]
 #[error("error occurred while waiting for the subprocess to become inactive")]
 pub enum WaitUntilInactiveError {
     ProcessJoin(#[from] process::JoinError),
-    NewSenderAndReceiver(#[from] ipc::NewSenderAndReceiverError),
-    MessageSend(#[from] ipc::SendError),
-    MessageReceive(#[from] ipc::ReceiveError),
-    Os(#[from] io::Error),
+    ReadPipe(#[from] pipe::ReadSomeError),
+    RpcCall(#[from] rpc::CallError),
 }
@@ -0,0 +1,47 @@
+use shared::ipc::message::{Log, LogLevel};
+use std::{collections::VecDeque, sync::Mutex};
+
+/// Default number of recent log entries a [`LogBuffer`] retains.
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+/// Retains the most recently received log entries from the hooks DLL, mirroring ARTIQ's
+/// `BufferLogger`, so a client can pull recent history after the fact instead of only seeing a
+/// live stream.
+pub(crate) struct LogBuffer {
+    entries: Mutex<VecDeque<Log>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(&self, entry: Log) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Returns every retained entry at or above `min_level`, oldest first.
+    pub(crate) fn recent(&self, min_level: LogLevel) -> Vec<Log> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.level >= min_level)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
@@ -0,0 +1,66 @@
+use shared::ipc::message::Log;
+use tokio::sync::broadcast;
+
+/// Default number of entries a [`LogSubscriber`] can fall behind before it starts missing
+/// entries, per [`LogPublisher::new`].
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 256;
+
+/// Fans out [`Log`] entries to any number of independently-paced [`LogSubscriber`]s, modeled on
+/// embassy-sync's `PubSubChannel`: each subscriber gets its own bounded backlog rather than
+/// sharing one queue, so a slow subscriber (e.g. a file logger) can't stall a fast one (e.g. a
+/// live TUI) or, in turn, the hooks DLL publishing into it.
+pub(crate) struct LogPublisher {
+    sender: broadcast::Sender<Log>,
+}
+
+impl LogPublisher {
+    pub(crate) fn new(subscriber_capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(subscriber_capacity);
+        Self { sender }
+    }
+
+    /// Fans `entry` out to every current subscriber. A moment with no subscribers at all is not
+    /// an error.
+    pub(crate) fn publish(&self, entry: Log) {
+        let _ = self.sender.send(entry);
+    }
+
+    /// Hands back a new subscription starting from the current position; it will not see
+    /// entries published before this call.
+    pub fn subscribe(&self) -> LogSubscriber {
+        LogSubscriber {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+impl Default for LogPublisher {
+    fn default() -> Self {
+        Self::new(DEFAULT_SUBSCRIBER_CAPACITY)
+    }
+}
+
+pub struct LogSubscriber {
+    receiver: broadcast::Receiver<Log>,
+}
+
+impl LogSubscriber {
+    /// Waits for the next published entry. If this subscriber fell more than its backlog's
+    /// capacity behind, returns [`LogSubscriberRecvError::Lagged`] with the number of entries
+    /// that were dropped instead of stalling the publisher; calling this again resumes from the
+    /// oldest entry still retained.
+    pub async fn recv(&mut self) -> Result<Log, LogSubscriberRecvError> {
+        self.receiver.recv().await.map_err(|error| match error {
+            broadcast::error::RecvError::Lagged(dropped) => LogSubscriberRecvError::Lagged(dropped),
+            broadcast::error::RecvError::Closed => LogSubscriberRecvError::Closed,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LogSubscriberRecvError {
+    #[error("subscriber fell behind and missed {0} log entries")]
+    Lagged(u64),
+    #[error("log publisher was dropped")]
+    Closed,
+}
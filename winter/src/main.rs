@@ -1,5 +1,6 @@
 use anyhow::Result;
 use clap::Parser;
+use shared::windows::{pipe::Stdio, process::command_line::quote_argument};
 use std::{
     ffi::OsString,
     path::PathBuf,
@@ -7,7 +8,7 @@ use std::{
 };
 use tokio::time::sleep;
 use tracing::info;
-use winter::Conductor;
+use winter::{CommandEnv, CommandLine, Conductor};
 
 #[derive(clap::Parser)]
 struct Arguments {
@@ -48,21 +49,27 @@ async fn main() -> Result<()> {
         .init();
 
     let arguments = Arguments::parse();
+    let command_line = match arguments.command_line_string {
+        Some(command_line_string) => CommandLine::raw_command_line(command_line_string),
+        None => CommandLine::raw_command_line(quote_argument(&arguments.executable_path, true)?),
+    };
     let mut conductor = Conductor::new(
         &arguments.executable_path,
-        arguments.command_line_string.unwrap_or_else(|| {
-            let executable_path = arguments.executable_path.as_os_str();
-            let mut string = OsString::with_capacity(executable_path.len() + 2);
-            string.push("\"");
-            string.push(executable_path);
-            string.push("\"");
-            string
-        }),
+        command_line,
+        CommandEnv::new(),
+        Stdio::Null,
+        Stdio::Piped,
         Some(|bytes: &_| {
             for line in String::from_utf8_lossy(bytes).lines() {
                 info!("stdout: {}", line);
             }
         }),
+        Stdio::Piped,
+        Some(|bytes: &_| {
+            for line in String::from_utf8_lossy(bytes).lines() {
+                tracing::warn!("stderr: {}", line);
+            }
+        }),
     )
     .await?;
     conductor.resume().await?;
@@ -0,0 +1,385 @@
+//! Deterministic record/replay of a [`Conductor`] session to a "movie" file, so a
+//! tool-assisted-speedrun run can be reproduced bit-for-bit.
+
+pub mod text;
+
+use crate::{
+    AdvanceTimeError, Conductor, LoadStateError, NoStdinError, SaveStateError,
+    SetGamepadAxisError, SetGamepadButtonError, SetGamepadTriggerError, SetKeyStateError,
+    SetMousePositionError, SetMouseButtonStateError, SetMouseWheelError,
+};
+use serde::{Deserialize, Serialize};
+use shared::input::{GamepadAxis, GamepadButton, GamepadTrigger, MouseButton};
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    num::NonZeroU32,
+    path::Path,
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Identifies the movie file format; written as the first four bytes of every movie.
+const MAGIC: [u8; 4] = *b"WMOV";
+/// Bumped whenever [`Record`]'s wire format changes.
+const FORMAT_VERSION: u32 = 2;
+
+/// A single recorded action, mirroring the calls exposed by [`Conductor`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Event {
+    AdvanceTime(Duration),
+    SetKeyState { id: u8, state: bool },
+    SetMousePosition { x: u16, y: u16 },
+    SetMouseButtonState { button: MouseButton, state: bool },
+    ScrollMouseWheel { delta: i32, horizontal: bool },
+    SetGamepadButton { index: u8, button: GamepadButton, state: bool },
+    SetGamepadAxis { index: u8, axis: GamepadAxis, value: i16 },
+    SetGamepadTrigger { index: u8, trigger: GamepadTrigger, value: u8 },
+    WriteStdin(Vec<u8>),
+    SaveState,
+    LoadState,
+}
+
+/// One entry in a movie: an [`Event`], tagged with the accumulated virtual time (the sum of
+/// every preceding [`Event::AdvanceTime`] duration) at which it occurred. The virtual time lets
+/// [`Player::seek_to_nearest_save_state`] find a resume point without replaying from the start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub virtual_time: Duration,
+    pub event: Event,
+}
+
+/// Appends [`Event`]s to a movie file as they occur.
+pub struct Recorder<W> {
+    writer: W,
+    virtual_time: Duration,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Writes the movie header — identifying `executable_identity` (conventionally a
+    /// [`Conductor::executable_path`] rendered to a string) as the target this movie was recorded
+    /// against — and returns a [`Recorder`] ready to append events.
+    pub fn new(mut writer: W, executable_identity: &str) -> Result<Self, io::Error> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&u32::try_from(executable_identity.len()).unwrap().to_le_bytes())?;
+        writer.write_all(executable_identity.as_bytes())?;
+        Ok(Self {
+            writer,
+            virtual_time: Duration::ZERO,
+        })
+    }
+
+    /// Appends `event` to the movie as a length-prefixed record, so a truncated file can be
+    /// detected by [`Player`] instead of silently misparsed.
+    pub fn record(&mut self, event: Event) -> Result<(), RecordError> {
+        if let Event::AdvanceTime(duration) = event {
+            self.virtual_time += duration;
+        }
+        let bytes = bincode::serialize(&Record {
+            virtual_time: self.virtual_time,
+            event,
+        })?;
+        self.writer
+            .write_all(&u32::try_from(bytes.len()).unwrap().to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to record movie event")]
+pub enum RecordError {
+    Bincode(#[from] bincode::Error),
+    Io(#[from] io::Error),
+}
+
+impl Recorder<std::fs::File> {
+    /// Creates `path` and starts recording `conductor`'s session to it, tagging the movie header
+    /// with `conductor`'s [`Conductor::executable_path`].
+    pub fn create(path: impl AsRef<Path>, conductor: &Conductor) -> Result<Self, io::Error> {
+        Self::new(
+            std::fs::File::create(path)?,
+            &conductor.executable_path().to_string_lossy(),
+        )
+    }
+}
+
+/// Reads [`Record`]s back out of a movie file written by [`Recorder`], in order.
+pub struct Player<R> {
+    reader: R,
+    executable_identity: String,
+    first_record_offset: u64,
+}
+
+impl<R: Read + Seek> Player<R> {
+    /// Reads and validates the movie header, positioning `reader` at the first record.
+    pub fn new(mut reader: R) -> Result<Self, OpenError> {
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| OpenError::Truncated)?;
+        if magic != MAGIC {
+            return Err(OpenError::BadMagic);
+        }
+
+        let mut version_bytes = [0; 4];
+        reader
+            .read_exact(&mut version_bytes)
+            .map_err(|_| OpenError::Truncated)?;
+        let version = u32::from_le_bytes(version_bytes);
+        if version != FORMAT_VERSION {
+            return Err(OpenError::UnsupportedVersion(version));
+        }
+
+        let mut identity_length_bytes = [0; 4];
+        reader
+            .read_exact(&mut identity_length_bytes)
+            .map_err(|_| OpenError::Truncated)?;
+        let mut identity_bytes = vec![0; u32::from_le_bytes(identity_length_bytes) as usize];
+        reader
+            .read_exact(&mut identity_bytes)
+            .map_err(|_| OpenError::Truncated)?;
+        let executable_identity =
+            String::from_utf8(identity_bytes).map_err(|_| OpenError::BadIdentity)?;
+
+        let first_record_offset = reader.stream_position()?;
+        Ok(Self {
+            reader,
+            executable_identity,
+            first_record_offset,
+        })
+    }
+
+    /// The executable identity this movie was recorded against, as passed to [`Recorder::new`].
+    #[must_use]
+    pub fn executable_identity(&self) -> &str {
+        &self.executable_identity
+    }
+
+    /// Reads the next record, or `None` at a clean end of file.
+    pub fn next(&mut self) -> Result<Option<Record>, NextError> {
+        let mut length_bytes = [0; 4];
+        match self.reader.read_exact(&mut length_bytes) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error.into()),
+        }
+        let length = u32::from_le_bytes(length_bytes) as usize;
+
+        let mut bytes = vec![0; length];
+        self.reader
+            .read_exact(&mut bytes)
+            .map_err(|_| NextError::Truncated)?;
+        Ok(Some(bincode::deserialize(&bytes)?))
+    }
+
+    /// Rewinds to the start of the movie, before its first record.
+    pub fn rewind(&mut self) -> Result<(), io::Error> {
+        self.reader.seek(SeekFrom::Start(self.first_record_offset))?;
+        Ok(())
+    }
+
+    /// Rewinds to the last [`Event::SaveState`] at or before `virtual_time`, so
+    /// [`replay`] can resume from there instead of from the start of the movie. Returns `false`
+    /// (leaving the player rewound to the start) if the movie has no such marker.
+    pub fn seek_to_nearest_save_state(
+        &mut self,
+        virtual_time: Duration,
+    ) -> Result<bool, NextError> {
+        self.rewind()?;
+
+        let mut nearest_offset = None;
+        loop {
+            let offset_before_record = self.reader.stream_position()?;
+            let Some(record) = self.next()? else {
+                break;
+            };
+            if record.virtual_time > virtual_time {
+                break;
+            }
+            if matches!(record.event, Event::SaveState) {
+                nearest_offset = Some(offset_before_record);
+            }
+        }
+
+        match nearest_offset {
+            Some(offset) => {
+                self.reader.seek(SeekFrom::Start(offset))?;
+                Ok(true)
+            }
+            None => {
+                self.rewind()?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OpenError {
+    #[error("failed to read movie header")]
+    Io(#[from] io::Error),
+    #[error("movie file is truncated")]
+    Truncated,
+    #[error("movie file does not start with the expected magic bytes")]
+    BadMagic,
+    #[error("movie file format version {0} is not supported by this build")]
+    UnsupportedVersion(u32),
+    #[error("movie file's executable identity is not valid UTF-8")]
+    BadIdentity,
+}
+
+#[derive(Debug, Error)]
+pub enum NextError {
+    #[error("failed to read movie record")]
+    Io(#[from] io::Error),
+    #[error("movie file is truncated partway through a record")]
+    Truncated,
+    #[error("failed to deserialize movie record")]
+    Bincode(#[from] bincode::Error),
+}
+
+/// Paces how [`replay`] advances virtual time against real time. [`Immediate`] (the default)
+/// resolves every [`Event::AdvanceTime`] as fast as the conductor can keep up, which is what the
+/// test suite wants. [`RealTime`] instead sleeps between advances, using the same drift-corrected
+/// pacing `main`'s `wait` helper uses for live sessions, so a recorded movie can be watched back at
+/// (a multiple of) the speed it was recorded at instead of resolving instantly.
+pub trait TimeSource {
+    /// Called just before replaying an [`Event::AdvanceTime`] of `duration`; may sleep to pace it.
+    async fn before_advance(&mut self, duration: Duration);
+}
+
+/// Resolves every [`Event::AdvanceTime`] immediately. The default [`TimeSource`] for [`replay`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Immediate;
+
+impl TimeSource for Immediate {
+    async fn before_advance(&mut self, _duration: Duration) {}
+}
+
+/// Sleeps before each [`Event::AdvanceTime`] so playback tracks the wall clock at
+/// `speed_numerator / speed_denominator` times real speed (`2`/`1` for double speed, `1`/`2` for
+/// half speed), falling behind by no more than four advances' worth of real time before catching
+/// back up - the same drift correction `main`'s `wait` helper uses for live sessions.
+pub struct RealTime {
+    speed_numerator: NonZeroU32,
+    speed_denominator: NonZeroU32,
+    sleep_target: std::time::Instant,
+}
+
+impl RealTime {
+    #[must_use]
+    pub fn new(speed_numerator: NonZeroU32, speed_denominator: NonZeroU32) -> Self {
+        Self {
+            speed_numerator,
+            speed_denominator,
+            sleep_target: std::time::Instant::now(),
+        }
+    }
+}
+
+impl TimeSource for RealTime {
+    async fn before_advance(&mut self, duration: Duration) {
+        let real_duration =
+            duration * self.speed_denominator.get() / self.speed_numerator.get();
+        let now = std::time::Instant::now();
+        self.sleep_target += real_duration;
+        self.sleep_target = self
+            .sleep_target
+            .max(now.checked_sub(real_duration * 4).unwrap_or(now));
+        tokio::time::sleep(self.sleep_target - now).await;
+    }
+}
+
+/// Opens the movie at `path` and [`replay`]s it against `conductor`, first checking that the
+/// movie's recorded executable identity matches `conductor.executable_path()` so a movie isn't
+/// silently replayed against the wrong target.
+pub async fn replay_file(
+    path: impl AsRef<Path>,
+    conductor: &mut Conductor,
+    time_source: &mut impl TimeSource,
+) -> Result<(), ReplayFileError> {
+    let mut player = Player::new(io::BufReader::new(std::fs::File::open(path)?))?;
+    let expected_identity = conductor.executable_path().to_string_lossy();
+    if player.executable_identity() != expected_identity {
+        return Err(ReplayFileError::ExecutableMismatch {
+            expected: expected_identity.into_owned(),
+            actual: player.executable_identity().to_owned(),
+        });
+    }
+    Ok(replay(&mut player, conductor, time_source).await?)
+}
+
+#[derive(Debug, Error)]
+#[error("failed to replay movie file")]
+pub enum ReplayFileError {
+    Io(#[from] io::Error),
+    Open(#[from] OpenError),
+    #[error("movie was recorded against \"{expected}\" but is being replayed against \"{actual}\"")]
+    ExecutableMismatch { expected: String, actual: String },
+    Replay(#[from] ReplayError),
+}
+
+/// Replays every remaining record from `player` against `conductor`, in order, reproducing the
+/// recorded run. `time_source` paces how fast [`Event::AdvanceTime`] records are replayed - pass
+/// [`Immediate`] (the default) to resolve the movie as fast as the conductor can keep up, or
+/// [`RealTime`] to pace it against the wall clock for a human to watch.
+pub async fn replay(
+    player: &mut Player<impl Read + Seek>,
+    conductor: &mut Conductor,
+    time_source: &mut impl TimeSource,
+) -> Result<(), ReplayError> {
+    while let Some(Record { event, .. }) = player.next()? {
+        match event {
+            Event::AdvanceTime(duration) => {
+                time_source.before_advance(duration).await;
+                conductor.advance_time(duration).await?;
+            }
+            Event::SetKeyState { id, state } => conductor.set_key_state(id, state).await?,
+            Event::SetMousePosition { x, y } => conductor.set_mouse_position(x, y).await?,
+            Event::SetMouseButtonState { button, state } => {
+                conductor.set_mouse_button_state(button, state).await?;
+            }
+            Event::ScrollMouseWheel { delta, horizontal } => {
+                conductor.set_mouse_wheel(delta, horizontal).await?;
+            }
+            Event::SetGamepadButton {
+                index,
+                button,
+                state,
+            } => conductor.set_gamepad_button(index, button, state).await?,
+            Event::SetGamepadAxis { index, axis, value } => {
+                conductor.set_gamepad_axis(index, axis, value).await?;
+            }
+            Event::SetGamepadTrigger {
+                index,
+                trigger,
+                value,
+            } => conductor.set_gamepad_trigger(index, trigger, value).await?,
+            Event::WriteStdin(bytes) => {
+                conductor.stdin().ok_or(NoStdinError)?.write_all(&bytes)?;
+            }
+            Event::SaveState => conductor.save_state().await?,
+            Event::LoadState => conductor.load_state().await?,
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+#[error("failed to replay movie")]
+pub enum ReplayError {
+    Next(#[from] NextError),
+    AdvanceTime(#[from] AdvanceTimeError),
+    SetKeyState(#[from] SetKeyStateError),
+    SetMousePosition(#[from] SetMousePositionError),
+    SetMouseButtonState(#[from] SetMouseButtonStateError),
+    SetMouseWheel(#[from] SetMouseWheelError),
+    SetGamepadButton(#[from] SetGamepadButtonError),
+    SetGamepadAxis(#[from] SetGamepadAxisError),
+    SetGamepadTrigger(#[from] SetGamepadTriggerError),
+    NoStdin(#[from] NoStdinError),
+    WriteStdin(#[from] io::Error),
+    SaveState(#[from] SaveStateError),
+    LoadState(#[from] LoadStateError),
+}
@@ -0,0 +1,283 @@
+//! A human-editable, line-based movie format that compiles down to the same [`Event`] stream the
+//! binary format (see [`super`]) records, so a replay can be authored as a checked-in text file
+//! instead of a hand-built `Vec<Event>` in Rust.
+//!
+//! Each non-empty, non-comment (`#`) line is one record: an event name followed by
+//! `field:conversion=value` tokens, e.g.:
+//!
+//! ```text
+//! advance_time duration:duration_ms=16
+//! set_key_state id:key=a state:bool=true
+//! set_key_state id:vk=0x41 state:bool=false
+//! set_mouse_position x:int=100 y:int=100
+//! set_mouse_button_state button:button=left state:bool=true
+//! save_state
+//! load_state
+//! ```
+//!
+//! A field's raw string is stored as-is and converted on demand by the [`Conversion`] named
+//! before the `=`, rather than each field having one fixed type, so e.g. a duration can be
+//! written in milliseconds or seconds depending on which reads better at the call site.
+//!
+//! [`parse_line`] only covers the [`Event`] variants listed above - `ScrollMouseWheel`,
+//! `SetGamepadButton`/`SetGamepadAxis`/`SetGamepadTrigger`, and `WriteStdin` have no text-format
+//! record yet, so a movie exercising any of those can only be authored through the binary format
+//! or built up as a `Vec<Event>` in Rust. Add the corresponding record kind (and `Conversion`s, if
+//! a new one's needed) here when a text movie first needs one of them.
+
+use super::Event;
+use shared::input::MouseButton;
+use std::{str::FromStr, time::Duration};
+use thiserror::Error;
+
+/// Parses `text` into an ordered list of [`Event`]s, ready to hand to
+/// [`crate::Conductor`] one at a time (or, in the test suite, to `Instance::with_events`).
+pub fn parse(text: &str) -> Result<Vec<Event>, ParseError> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            parse_line(line).map_err(|error| ParseError { line_number, error })
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Event, LineError> {
+    let mut tokens = line.split_ascii_whitespace();
+    let name = tokens.next().expect("line is non-empty after trimming");
+    let fields = tokens
+        .map(parse_field)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let field = |name: &'static str| {
+        fields
+            .iter()
+            .find(|(field_name, ..)| field_name.as_str() == name)
+            .ok_or(LineError::MissingField(name))
+    };
+    let convert = |name: &'static str| -> Result<Value, LineError> {
+        let (_, conversion, raw) = field(name)?;
+        conversion
+            .convert(raw)
+            .map_err(|error| LineError::Conversion { field: name, error })
+    };
+
+    Ok(match name {
+        "advance_time" => Event::AdvanceTime(convert("duration")?.into_duration(name)?),
+        "set_key_state" => Event::SetKeyState {
+            id: convert("id")?.into_key_id(name)?,
+            state: convert("state")?.into_bool(name)?,
+        },
+        "set_mouse_position" => Event::SetMousePosition {
+            x: convert("x")?.into_u16(name)?,
+            y: convert("y")?.into_u16(name)?,
+        },
+        "set_mouse_button_state" => Event::SetMouseButtonState {
+            button: convert("button")?.into_button(name)?,
+            state: convert("state")?.into_bool(name)?,
+        },
+        "save_state" => Event::SaveState,
+        "load_state" => Event::LoadState,
+        _ => return Err(LineError::UnknownEvent(name.to_string())),
+    })
+}
+
+/// Splits a `field:conversion=value` token into its field name, [`Conversion`], and raw value.
+fn parse_field(token: &str) -> Result<(String, Conversion, String), LineError> {
+    let (name_and_conversion, raw) = token
+        .split_once('=')
+        .ok_or_else(|| LineError::MalformedField(token.to_string()))?;
+    let (name, conversion_name) = name_and_conversion
+        .split_once(':')
+        .ok_or_else(|| LineError::MalformedField(token.to_string()))?;
+    let conversion = conversion_name
+        .parse()
+        .map_err(|error| LineError::UnknownConversion {
+            field: name.to_string(),
+            error,
+        })?;
+    Ok((name.to_string(), conversion, raw.to_string()))
+}
+
+/// A named way to turn a field's raw string value into a typed [`Value`].
+#[derive(Debug, Clone, Copy)]
+enum Conversion {
+    DurationMs,
+    DurationS,
+    Key,
+    Vk,
+    Button,
+    Bool,
+    Int,
+}
+
+impl FromStr for Conversion {
+    type Err = UnknownConversionError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "duration_ms" => Self::DurationMs,
+            "duration_s" => Self::DurationS,
+            "key" => Self::Key,
+            "vk" => Self::Vk,
+            "button" => Self::Button,
+            "bool" => Self::Bool,
+            "int" => Self::Int,
+            _ => return Err(UnknownConversionError(name.to_string())),
+        })
+    }
+}
+
+impl Conversion {
+    fn convert(self, raw: &str) -> Result<Value, ConversionError> {
+        let malformed = || ConversionError::malformed_value(raw);
+        Ok(match self {
+            Self::DurationMs => Value::Duration(Duration::from_millis(
+                raw.parse().map_err(|_| malformed())?,
+            )),
+            Self::DurationS => {
+                Value::Duration(Duration::from_secs_f64(raw.parse().map_err(|_| malformed())?))
+            }
+            Self::Key => Value::KeyId(key_name_to_vk(raw).ok_or_else(malformed)?),
+            Self::Vk => Value::KeyId(parse_int(raw).ok_or_else(malformed)?),
+            Self::Button => Value::Button(match raw {
+                "left" => MouseButton::Left,
+                "right" => MouseButton::Right,
+                "middle" => MouseButton::Middle,
+                "x1" => MouseButton::X1,
+                "x2" => MouseButton::X2,
+                _ => return Err(malformed()),
+            }),
+            Self::Bool => Value::Bool(raw.parse().map_err(|_| malformed())?),
+            Self::Int => Value::Int(parse_int(raw).ok_or_else(malformed)?),
+        })
+    }
+}
+
+fn parse_int<T: TryFrom<i64>>(raw: &str) -> Option<T> {
+    let value = if let Some(hex) = raw.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else {
+        raw.parse().ok()?
+    };
+    T::try_from(value).ok()
+}
+
+/// Maps a single ASCII letter or digit directly to its virtual-key code (Win32 VK codes for
+/// `'0'..='9'` and `'A'..='Z'` are just their ASCII values), plus a handful of named keys common
+/// in recorded input.
+fn key_name_to_vk(name: &str) -> Option<u8> {
+    if let [character] = name.to_ascii_uppercase().as_bytes() {
+        if character.is_ascii_alphanumeric() {
+            return Some(*character);
+        }
+    }
+    Some(match name {
+        "shift" => winapi::um::winuser::VK_SHIFT,
+        "control" => winapi::um::winuser::VK_CONTROL,
+        "menu" | "alt" => winapi::um::winuser::VK_MENU,
+        "enter" | "return" => winapi::um::winuser::VK_RETURN,
+        "escape" => winapi::um::winuser::VK_ESCAPE,
+        "space" => winapi::um::winuser::VK_SPACE,
+        "tab" => winapi::um::winuser::VK_TAB,
+        "backspace" => winapi::um::winuser::VK_BACK,
+        "left" => winapi::um::winuser::VK_LEFT,
+        "right" => winapi::um::winuser::VK_RIGHT,
+        "up" => winapi::um::winuser::VK_UP,
+        "down" => winapi::um::winuser::VK_DOWN,
+        _ => return None,
+    } as u8)
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Duration(Duration),
+    KeyId(u8),
+    Bool(bool),
+    Int(i64),
+    Button(MouseButton),
+}
+
+impl Value {
+    fn into_duration(self, field: &'static str) -> Result<Duration, LineError> {
+        match self {
+            Self::Duration(duration) => Ok(duration),
+            _ => Err(LineError::WrongValueType(field)),
+        }
+    }
+
+    fn into_key_id(self, field: &'static str) -> Result<u8, LineError> {
+        match self {
+            Self::KeyId(id) => Ok(id),
+            _ => Err(LineError::WrongValueType(field)),
+        }
+    }
+
+    fn into_bool(self, field: &'static str) -> Result<bool, LineError> {
+        match self {
+            Self::Bool(state) => Ok(state),
+            _ => Err(LineError::WrongValueType(field)),
+        }
+    }
+
+    fn into_u16(self, field: &'static str) -> Result<u16, LineError> {
+        match self {
+            Self::Int(value) => u16::try_from(value).map_err(|_| LineError::WrongValueType(field)),
+            _ => Err(LineError::WrongValueType(field)),
+        }
+    }
+
+    fn into_button(self, field: &'static str) -> Result<MouseButton, LineError> {
+        match self {
+            Self::Button(button) => Ok(button),
+            _ => Err(LineError::WrongValueType(field)),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown conversion {0:?}")]
+struct UnknownConversionError(String);
+
+#[derive(Debug, Error)]
+#[error("malformed value {0:?} for this conversion")]
+struct ConversionError(String);
+
+impl ConversionError {
+    fn malformed_value(raw: &str) -> Self {
+        Self(raw.to_string())
+    }
+}
+
+#[derive(Debug, Error)]
+enum LineError {
+    #[error("unknown event {0:?}")]
+    UnknownEvent(String),
+    #[error("field {0:?} is not in the expected name:conversion=value form")]
+    MalformedField(String),
+    #[error("missing field {0:?}")]
+    MissingField(&'static str),
+    #[error("field {field:?}: {error}")]
+    UnknownConversion {
+        field: String,
+        #[source]
+        error: UnknownConversionError,
+    },
+    #[error("field {field:?}: {error}")]
+    Conversion {
+        field: &'static str,
+        #[source]
+        error: ConversionError,
+    },
+    #[error("field {0:?} converted to the wrong type for this event")]
+    WrongValueType(&'static str),
+}
+
+#[derive(Debug, Error)]
+#[error("line {line_number}: {error}")]
+pub struct ParseError {
+    line_number: usize,
+    #[source]
+    error: LineError,
+}
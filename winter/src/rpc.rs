@@ -0,0 +1,69 @@
+use shared::ipc::{
+    self,
+    message::FromConductor,
+    rpc::{Request, Response, Token, TokenSource},
+    Sender,
+};
+use std::{collections::HashMap, sync::Mutex};
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+/// Tracks calls made to the injected process over the shared RPC response channel,
+/// matching each [`Response`] that arrives back to the [`oneshot::Receiver`] its
+/// caller is awaiting.
+pub(crate) struct Client {
+    token_source: TokenSource,
+    pending: Mutex<HashMap<Token, oneshot::Sender<Vec<u8>>>>,
+}
+
+impl Client {
+    pub(crate) fn new(requester_id: u32) -> Self {
+        Self {
+            token_source: TokenSource::new(requester_id),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Allocates a [`Token`] for a new call and registers a slot to receive its
+    /// response. The returned receiver resolves once [`Self::complete`] is called
+    /// with a matching token.
+    pub(crate) fn begin_call(&self) -> (Token, oneshot::Receiver<Vec<u8>>) {
+        let token = self.token_source.next();
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(token, sender);
+        (token, receiver)
+    }
+
+    /// Resolves the pending call matching `response.token`, if one is still
+    /// outstanding (it may already have been abandoned by its caller).
+    pub(crate) fn complete(&self, response: Response) {
+        if let Some(sender) = self.pending.lock().unwrap().remove(&response.token) {
+            let _ = sender.send(response.payload);
+        }
+    }
+
+    /// Issues `request` and awaits its matching reply, so that new [`Request`] types can be
+    /// added as plain serde structs without inventing a dedicated [`Sender`]/[`Receiver`] pair
+    /// for each one. `request` is serialized and sent to the hooks DLL as a
+    /// [`FromConductor::Rpc`] message tagged with a fresh [`Token`]; the reply is matched up by
+    /// [`Self::complete`] once it arrives on the shared RPC response channel.
+    pub(crate) async fn call<Req: Request>(
+        &self,
+        message_sender: &mut Sender<FromConductor>,
+        request: &Req,
+    ) -> Result<Req::Response, CallError> {
+        let (token, receiver) = self.begin_call();
+        message_sender.send_request_with_token(token, request).await?;
+        let response_payload = receiver.await.map_err(|_| CallError::ResponderDropped)?;
+        Ok(bincode::deserialize(&response_payload)?)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("rpc call failed")]
+pub(crate) enum CallError {
+    SendRequest(#[from] ipc::SendRequestError),
+    Deserialize(#[from] bincode::Error),
+    #[error("rpc response channel closed before a reply arrived")]
+    ResponderDropped,
+}
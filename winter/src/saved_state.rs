@@ -170,6 +170,191 @@ impl SavedState {
         Ok(())
     }
 
+    /// Builds on top of `base` by re-reading only the pages at `dirty_page_addresses`, which is
+    /// far cheaper than [`Self::new`] for frame-by-frame save states. Thread contexts and the set
+    /// of memory allocations are always captured fresh, as they are comparatively cheap and the
+    /// dirty-page tracking installed by the hooks DLL only covers plain memory writes.
+    #[instrument(name = "save_state_incremental", skip(base))]
+    pub(crate) fn new_incremental(
+        process: &process::Process,
+        base: &Self,
+        dirty_page_addresses: &[usize],
+    ) -> Result<Self, NewError> {
+        for thread_id in process.iter_thread_ids()? {
+            thread::Thread::from_id(thread_id)?.increment_suspend_count()?;
+        }
+
+        let mut thread_contexts = BTreeMap::new();
+        for thread_id in process.iter_thread_ids()? {
+            let thread = thread::Thread::from_id(thread_id)?;
+            thread_contexts.insert(thread_id, thread.get_context()?);
+        }
+
+        let memory_allocations = Self::get_all_memory_allocations(process)?;
+
+        let mut memory = base.memory.clone();
+        let page_size = system::get_info().dwPageSize as usize;
+        for &page_address in dirty_page_addresses {
+            let page_address = page_address as *mut c_void;
+            let Some((&region_address, region_bytes)) = memory
+                .range_mut(..=page_address)
+                .next_back()
+                .filter(|(&address, bytes)| {
+                    (page_address as usize) < address as usize + bytes.len()
+                })
+            else {
+                // the page isn't part of any base region (e.g. allocated since the base
+                // snapshot); fall back to tracking it as its own region
+                memory.insert(
+                    page_address,
+                    process.read_to_vec(page_address.cast(), page_size)?,
+                );
+                continue;
+            };
+
+            let offset = page_address as usize - region_address as usize;
+            let len = page_size.min(region_bytes.len() - offset);
+            let fresh_bytes = process.read_to_vec(page_address.cast(), len)?;
+            region_bytes[offset..offset + len].copy_from_slice(&fresh_bytes);
+        }
+
+        for thread_id in process.iter_thread_ids()? {
+            thread::Thread::from_id(thread_id)?.decrement_suspend_count()?;
+        }
+
+        Ok(Self {
+            thread_contexts,
+            memory_allocations,
+            memory,
+        })
+    }
+
+    /// Returns the base address and size of every committed, non-guard memory region, suitable
+    /// for handing to the hooks DLL's dirty-page tracker.
+    pub(crate) fn writable_region_ranges(&self) -> Vec<(usize, usize)> {
+        self.memory_allocations
+            .iter()
+            .flat_map(|allocation| &allocation.regions)
+            .filter(|region| region.is_committed() && !region.permissions().is_guard)
+            .map(|region| (region.address() as usize, region.size()))
+            .collect()
+    }
+
+    /// Compares this snapshot against `other`, locating the memory regions and thread
+    /// registers that diverge between them. Intended to help narrow down where two
+    /// otherwise-identical playthroughs desync.
+    #[instrument(name = "diff_saved_states", skip(self, other))]
+    pub(crate) fn diff(&self, other: &Self) -> StateDiff {
+        let page_size = system::get_info().dwPageSize as usize;
+
+        let mut memory = Vec::new();
+        let mut self_regions = self.memory.iter().peekable();
+        let mut other_regions = other.memory.iter().peekable();
+        loop {
+            match (self_regions.peek(), other_regions.peek()) {
+                (Some(&(&self_address, self_bytes)), Some(&(&other_address, other_bytes)))
+                    if self_address == other_address =>
+                {
+                    let byte_ranges = diff_byte_ranges(self_bytes, other_bytes, page_size);
+                    if !byte_ranges.is_empty() {
+                        memory.push(MemoryDiff::Changed {
+                            address: self_address,
+                            byte_ranges,
+                        });
+                    }
+                    self_regions.next();
+                    other_regions.next();
+                }
+                (Some(&(&self_address, _)), Some(&(&other_address, _)))
+                    if self_address < other_address =>
+                {
+                    memory.push(MemoryDiff::OnlyIn {
+                        address: self_address,
+                        side: Side::Base,
+                    });
+                    self_regions.next();
+                }
+                (Some(_), Some(&(&other_address, _))) => {
+                    memory.push(MemoryDiff::OnlyIn {
+                        address: other_address,
+                        side: Side::Other,
+                    });
+                    other_regions.next();
+                }
+                (Some(&(&self_address, _)), None) => {
+                    memory.push(MemoryDiff::OnlyIn {
+                        address: self_address,
+                        side: Side::Base,
+                    });
+                    self_regions.next();
+                }
+                (None, Some(&(&other_address, _))) => {
+                    memory.push(MemoryDiff::OnlyIn {
+                        address: other_address,
+                        side: Side::Other,
+                    });
+                    other_regions.next();
+                }
+                (None, None) => break,
+            }
+        }
+
+        let mut thread_contexts = Vec::new();
+        let mut self_threads = self.thread_contexts.iter().peekable();
+        let mut other_threads = other.thread_contexts.iter().peekable();
+        loop {
+            match (self_threads.peek(), other_threads.peek()) {
+                (Some(&(&self_id, self_context)), Some(&(&other_id, other_context)))
+                    if self_id == other_id =>
+                {
+                    let registers = diff_registers(self_context, other_context);
+                    if !registers.is_empty() {
+                        thread_contexts.push(ThreadContextDiff {
+                            thread_id: self_id,
+                            kind: ThreadContextDiffKind::Changed { registers },
+                        });
+                    }
+                    self_threads.next();
+                    other_threads.next();
+                }
+                (Some(&(&self_id, _)), Some(&(&other_id, _))) if self_id < other_id => {
+                    thread_contexts.push(ThreadContextDiff {
+                        thread_id: self_id,
+                        kind: ThreadContextDiffKind::OnlyIn(Side::Base),
+                    });
+                    self_threads.next();
+                }
+                (Some(_), Some(&(&other_id, _))) => {
+                    thread_contexts.push(ThreadContextDiff {
+                        thread_id: other_id,
+                        kind: ThreadContextDiffKind::OnlyIn(Side::Other),
+                    });
+                    other_threads.next();
+                }
+                (Some(&(&self_id, _)), None) => {
+                    thread_contexts.push(ThreadContextDiff {
+                        thread_id: self_id,
+                        kind: ThreadContextDiffKind::OnlyIn(Side::Base),
+                    });
+                    self_threads.next();
+                }
+                (None, Some(&(&other_id, _))) => {
+                    thread_contexts.push(ThreadContextDiff {
+                        thread_id: other_id,
+                        kind: ThreadContextDiffKind::OnlyIn(Side::Other),
+                    });
+                    other_threads.next();
+                }
+                (None, None) => break,
+            }
+        }
+
+        StateDiff {
+            memory,
+            thread_contexts,
+        }
+    }
+
     fn get_all_memory_allocations(
         process: &process::Process,
     ) -> Result<Vec<MemoryAllocation>, process::GetMemoryRegionError> {
@@ -224,6 +409,107 @@ struct MemoryAllocation {
     regions: Vec<process::ReservedMemoryRegion>,
 }
 
+/// The result of [`SavedState::diff`]: the memory regions and thread contexts that differ
+/// between two snapshots.
+#[derive(Debug)]
+pub(crate) struct StateDiff {
+    pub(crate) memory: Vec<MemoryDiff>,
+    pub(crate) thread_contexts: Vec<ThreadContextDiff>,
+}
+
+#[derive(Debug)]
+pub(crate) enum MemoryDiff {
+    /// The region exists in both snapshots, but some byte ranges within it differ. Ranges are
+    /// offsets relative to `address`, with adjacent differing pages coalesced together.
+    Changed {
+        address: *mut c_void,
+        byte_ranges: Vec<(usize, usize)>,
+    },
+    /// The region is present in only one of the two snapshots.
+    OnlyIn { address: *mut c_void, side: Side },
+}
+
+#[derive(Debug)]
+pub(crate) struct ThreadContextDiff {
+    pub(crate) thread_id: u32,
+    pub(crate) kind: ThreadContextDiffKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum ThreadContextDiffKind {
+    /// Registers whose values differ, as `(name, value in self, value in other)`.
+    Changed { registers: Vec<(&'static str, u64, u64)> },
+    /// The thread is present in only one of the two snapshots.
+    OnlyIn(Side),
+}
+
+/// Which of the two snapshots passed to [`SavedState::diff`] something was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Side {
+    /// The snapshot `diff` was called on.
+    Base,
+    /// The snapshot passed to `diff`.
+    Other,
+}
+
+/// Compares `base` and `other` page by page, returning coalesced `(start, end)` byte ranges
+/// (relative to the start of both slices) that differ. A trailing length mismatch is reported
+/// as one final range covering the extra bytes.
+fn diff_byte_ranges(base: &[u8], other: &[u8], page_size: usize) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let common_len = base.len().min(other.len());
+
+    let mut chunk_start = 0;
+    while chunk_start < common_len {
+        let chunk_end = (chunk_start + page_size).min(common_len);
+        if base[chunk_start..chunk_end] != other[chunk_start..chunk_end] {
+            match ranges.last_mut() {
+                Some(last_range) if last_range.1 == chunk_start => last_range.1 = chunk_end,
+                _ => ranges.push((chunk_start, chunk_end)),
+            }
+        }
+        chunk_start = chunk_end;
+    }
+
+    if base.len() != other.len() {
+        let end = base.len().max(other.len());
+        match ranges.last_mut() {
+            Some(last_range) if last_range.1 == common_len => last_range.1 = end,
+            _ => ranges.push((common_len, end)),
+        }
+    }
+
+    ranges
+}
+
+/// Returns the registers whose values differ between `base` and `other`, as
+/// `(name, value in base, value in other)`. Contexts of differing kinds (e.g. a WOW64 thread
+/// compared against a native 64-bit one) aren't comparable and yield no differences.
+fn diff_registers(base: &thread::Context, other: &thread::Context) -> Vec<(&'static str, u64, u64)> {
+    let registers = match (base, other) {
+        (thread::Context::Context32(base), thread::Context::Context32(other)) => {
+            Some((base.registers(), other.registers()))
+        }
+        #[cfg(target_pointer_width = "64")]
+        (thread::Context::Context64(base), thread::Context::Context64(other)) => {
+            Some((base.registers(), other.registers()))
+        }
+        #[cfg(target_pointer_width = "64")]
+        _ => None,
+    };
+    let Some((base_registers, other_registers)) = registers else {
+        return Vec::new();
+    };
+
+    base_registers
+        .into_iter()
+        .filter_map(|(name, base_value)| {
+            let other_value = *other_registers.get(name)?;
+            (base_value != other_value).then_some((name, base_value, other_value))
+        })
+        .collect()
+}
+
 #[derive(Debug, Error)]
 #[error("failed to create saved state")]
 pub enum NewError {
@@ -0,0 +1,85 @@
+#![allow(non_snake_case)]
+
+use anyhow::Result;
+use shared::input::MouseButton;
+use std::time::Duration;
+use test_utilities::{init_test, Architecture, Event, Instance};
+use test_utilities_macros::test_for;
+
+fn key_event(id: u8, state: bool) -> Event {
+    Event::SetKeyState { id, state }
+}
+
+fn button_event(button: MouseButton, state: bool) -> Event {
+    Event::SetMouseButtonState { button, state }
+}
+
+/// Records `events` driven against `program_name` to a movie file, then replays that movie from
+/// scratch against a fresh instance - the replay should reproduce the recorded run's stdout
+/// byte-for-byte, which is the entire guarantee a movie file exists to provide (see
+/// `winter::movie`).
+async fn round_trip_helper(
+    program_name: impl AsRef<str>,
+    architecture: Architecture,
+    events: impl IntoIterator<Item = Event>,
+) -> Result<()> {
+    init_test();
+    let movie_path = std::env::temp_dir().join(format!(
+        "winter-test-movie-{}-{}.wmov",
+        std::process::id(),
+        program_name.as_ref().replace('/', "-"),
+    ));
+
+    let recorded_stdout = Instance::new(program_name.as_ref(), architecture)
+        .with_events(events)
+        .record_to(&movie_path)
+        .await?;
+
+    let replayed_stdout = Instance::new(program_name.as_ref(), architecture)
+        .with_movie(&movie_path)
+        .stdout_by_instant()
+        .await?;
+
+    std::fs::remove_file(&movie_path)?;
+
+    assert_eq!(recorded_stdout, replayed_stdout);
+    Ok(())
+}
+
+#[test_for(architecture)]
+async fn round_trip_key_state(architecture: Architecture) -> Result<()> {
+    round_trip_helper(
+        "hooks/input/GetKeyState",
+        architecture,
+        [
+            key_event(65, true),
+            key_event(66, true),
+            Event::AdvanceTime(Duration::from_millis(20)),
+            key_event(65, false),
+            key_event(67, true),
+            Event::AdvanceTime(Duration::from_millis(20)),
+            key_event(66, false),
+            key_event(67, false),
+            Event::AdvanceTime(Duration::from_millis(20)),
+        ],
+    )
+    .await
+}
+
+#[test_for(architecture)]
+async fn round_trip_mouse_messages(architecture: Architecture) -> Result<()> {
+    round_trip_helper(
+        "hooks/misc/PeekMessage",
+        architecture,
+        [
+            button_event(MouseButton::Left, true),
+            Event::SetMousePosition { x: 111, y: 222 },
+            button_event(MouseButton::Left, false),
+            button_event(MouseButton::Right, true),
+            Event::SetMousePosition { x: 44, y: 33 },
+            button_event(MouseButton::Right, false),
+            Event::AdvanceTime(Duration::from_millis(20)),
+        ],
+    )
+    .await
+}
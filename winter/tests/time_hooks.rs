@@ -298,6 +298,38 @@ async fn QueryPerformanceCounter_busy_wait(architecture: Architecture) -> Result
     Ok(())
 }
 
+#[test_for(architecture)]
+async fn rdtsc(architecture: Architecture) -> Result<()> {
+    init_test();
+    let stdout = Instance::new("hooks/time/rdtsc", architecture)
+        .with_events(
+            [
+                &Event::AdvanceTime(Duration::from_millis(46)),
+                &Event::AdvanceTime(Duration::from_millis(1)),
+            ]
+            .repeat(10)
+            .into_iter()
+            .cloned(),
+        )
+        .stdout_by_instant_from_utf8_lossy()
+        .await?;
+    let frequency =
+        str::parse::<u64>(stdout[0].lines().next().unwrap().split_once('/').unwrap().1).unwrap();
+
+    let mut expected_stdout = Vec::new();
+    for index in 0..10 {
+        expected_stdout.push(format!(
+            "{}/{}\r\n",
+            frequency * index * 47 / 1000,
+            frequency
+        ));
+        expected_stdout.push(String::new());
+    }
+    expected_stdout.push(String::new());
+    assert_eq!(stdout, expected_stdout);
+    Ok(())
+}
+
 #[test_for(architecture, unicode)]
 async fn waitable_timer(architecture: Architecture, unicode: bool) -> Result<()> {
     init_test();
@@ -2,17 +2,21 @@
 #![allow(clippy::missing_panics_doc)]
 
 use anyhow::Result;
-use shared::input::MouseButton;
+use shared::windows::pipe::Stdio;
 use std::{
     collections::BTreeMap,
     ffi::OsString,
+    fs::File,
+    io::{BufReader, Write},
     path::{Path, PathBuf},
     process::Command,
     str::FromStr,
     sync::{Arc, Mutex, Once, OnceLock},
-    time::Duration,
 };
 use tracing::info;
+use winter::movie::{Player, Recorder};
+
+pub use winter::movie::Event;
 
 pub fn init_test() {
     static ONCE: Once = Once::new();
@@ -73,6 +77,23 @@ impl<'a> Instance<'a> {
         self
     }
 
+    /// Replaces this instance's event timeline with one read back from a movie file previously
+    /// written by [`Self::record_to`], so the same session can be replayed without restating its
+    /// `with_events` call.
+    pub fn with_movie(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        let mut player = Player::new(BufReader::new(File::open(path).unwrap())).unwrap();
+        assert_eq!(
+            player.executable_identity(),
+            self.binary_file_path().to_string_lossy(),
+            "movie was recorded against a different executable"
+        );
+        self.events.clear();
+        while let Some(record) = player.next().unwrap() {
+            self.events.push(record.event);
+        }
+        self
+    }
+
     fn source_file_path(&self) -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
             .join(format!("../programs/src/{}.c", self.program_name))
@@ -106,6 +127,42 @@ impl<'a> Instance<'a> {
     }
 
     pub async fn stdout_by_instant(&self) -> Result<Vec<Vec<u8>>> {
+        Ok(self.run(None).await?.0)
+    }
+
+    pub async fn stderr(&self) -> Result<Vec<u8>> {
+        Ok(self
+            .stderr_by_instant()
+            .await?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    pub async fn stderr_by_instant(&self) -> Result<Vec<Vec<u8>>> {
+        Ok(self.run(None).await?.1)
+    }
+
+    /// Drives this instance's event timeline exactly as [`Self::stdout_by_instant`] does, but also
+    /// records every event to a movie file at `path` as it's driven — tagged with this instance's
+    /// binary path, so [`Self::with_movie`] can later check it's being replayed against the
+    /// executable it was recorded against. The core guarantee this exists to let a caller check:
+    /// replaying the movie produces byte-identical stdout to this run.
+    pub async fn record_to(&self, path: impl AsRef<Path>) -> Result<Vec<Vec<u8>>> {
+        self.build();
+        let mut recorder = Recorder::new(
+            File::create(path)?,
+            &self.binary_file_path().to_string_lossy(),
+        )?;
+        Ok(self.run(Some(&mut recorder)).await?.0)
+    }
+
+    /// Drives this instance's event timeline, returning the stdout and stderr captured at each
+    /// [`Event::AdvanceTime`] boundary as a `(stdout_by_instant, stderr_by_instant)` pair.
+    async fn run(
+        &self,
+        mut recorder: Option<&mut Recorder<File>>,
+    ) -> Result<(Vec<Vec<u8>>, Vec<Vec<u8>>)> {
         self.build();
 
         let stdout = Arc::new(Mutex::new(Vec::new()));
@@ -124,22 +181,48 @@ impl<'a> Instance<'a> {
                 stdout.lock().unwrap().extend_from_slice(bytes);
             }
         };
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let stderr_callback = {
+            let stderr = Arc::clone(&stderr);
+            move |bytes: &_| {
+                for line in String::from_utf8_lossy(bytes).lines() {
+                    const LINE_LENGTH_LIMIT: usize = 256;
+                    if line.len() <= LINE_LENGTH_LIMIT {
+                        info!("stderr: {}", line);
+                    } else {
+                        info!("stderr: {} (...)", &line[..LINE_LENGTH_LIMIT]);
+                    }
+                }
+
+                stderr.lock().unwrap().extend_from_slice(bytes);
+            }
+        };
         let mut stdout_by_instant = Vec::new();
+        let mut stderr_by_instant = Vec::new();
         let mut conductor = winter::Conductor::new(
             &self.binary_file_path(),
-            &self.command_line_string,
+            winter::CommandLine::raw_command_line(&self.command_line_string),
+            winter::CommandEnv::new(),
+            Stdio::Piped,
+            Stdio::Piped,
             Some(stdout_callback),
+            Stdio::Piped,
+            Some(stderr_callback),
         )
         .await?;
         conductor.resume().await?;
         for event in &self.events {
-            match *event {
+            if let Some(recorder) = &mut recorder {
+                recorder.record(event.clone())?;
+            }
+            match event.clone() {
                 Event::AdvanceTime(duration) => {
                     assert_eq!(
                         conductor.wait_until_inactive().await?,
                         winter::InactiveState::Idle
                     );
                     stdout_by_instant.push(std::mem::take(&mut *stdout.lock().unwrap()));
+                    stderr_by_instant.push(std::mem::take(&mut *stderr.lock().unwrap()));
                     conductor.advance_time(duration).await?;
                 }
                 Event::SetKeyState { id, state } => {
@@ -151,6 +234,29 @@ impl<'a> Instance<'a> {
                 Event::SetMouseButtonState { button, state } => {
                     conductor.set_mouse_button_state(button, state).await?;
                 }
+                Event::ScrollMouseWheel { delta, horizontal } => {
+                    conductor.set_mouse_wheel(delta, horizontal).await?;
+                }
+                Event::SetGamepadButton {
+                    index,
+                    button,
+                    state,
+                } => {
+                    conductor.set_gamepad_button(index, button, state).await?;
+                }
+                Event::SetGamepadAxis { index, axis, value } => {
+                    conductor.set_gamepad_axis(index, axis, value).await?;
+                }
+                Event::SetGamepadTrigger {
+                    index,
+                    trigger,
+                    value,
+                } => {
+                    conductor.set_gamepad_trigger(index, trigger, value).await?;
+                }
+                Event::WriteStdin(bytes) => {
+                    conductor.stdin().unwrap().write_all(&bytes)?;
+                }
                 Event::SaveState => {
                     conductor.save_state().await?;
                 }
@@ -167,7 +273,8 @@ impl<'a> Instance<'a> {
             panic!("the final checked inactive state is not the terminated state")
         }
         stdout_by_instant.push(std::mem::take(&mut *stdout.lock().unwrap()));
-        Ok(stdout_by_instant)
+        stderr_by_instant.push(std::mem::take(&mut *stderr.lock().unwrap()));
+        Ok((stdout_by_instant, stderr_by_instant))
     }
 
     pub async fn stdout_from_utf8_lossy(&self) -> Result<String> {
@@ -183,6 +290,19 @@ impl<'a> Instance<'a> {
             .collect::<Vec<_>>())
     }
 
+    pub async fn stderr_from_utf8_lossy(&self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.stderr().await?).to_string())
+    }
+
+    pub async fn stderr_by_instant_from_utf8_lossy(&self) -> Result<Vec<String>> {
+        Ok(self
+            .stderr_by_instant()
+            .await?
+            .iter()
+            .map(|b| String::from_utf8_lossy(b).to_string())
+            .collect::<Vec<_>>())
+    }
+
     fn build(&self) {
         static ENVIRONMENT_VARIABLES_X86: OnceLock<Vec<(OsString, OsString)>> = OnceLock::new();
         static ENVIRONMENT_VARIABLES_X64: OnceLock<Vec<(OsString, OsString)>> = OnceLock::new();
@@ -287,13 +407,3 @@ impl<'a> Instance<'a> {
             .collect::<Vec<_>>()
     }
 }
-
-#[derive(Clone)]
-pub enum Event {
-    AdvanceTime(Duration),
-    SetKeyState { id: u8, state: bool },
-    SetMousePosition { x: u16, y: u16 },
-    SetMouseButtonState { button: MouseButton, state: bool },
-    SaveState,
-    LoadState,
-}
@@ -35,22 +35,57 @@ pub fn test_for(
         }
     }
 
+    // Maps a category name to the `Variant`s it expands into; add new axes (e.g. a "heap layout"
+    // or "locale" axis) here without touching the cartesian-product logic below.
+    let category_variants: &[(&str, fn() -> Vec<Variant>)] = &[
+        ("architecture", || {
+            vec![
+                Variant {
+                    suffix: "_x86",
+                    argument: quote!(test_utilities::Architecture::X86),
+                },
+                Variant {
+                    suffix: "_x64",
+                    argument: quote!(test_utilities::Architecture::X64),
+                },
+            ]
+        }),
+        ("unicode", || {
+            vec![
+                Variant {
+                    suffix: "_unicode",
+                    argument: quote!(true),
+                },
+                Variant {
+                    suffix: "_ansi",
+                    argument: quote!(false),
+                },
+            ]
+        }),
+    ];
+
     let mut variant_groups = Vec::new();
     for category in categories {
-        match &*category.to_string() {
-            "architecture" => {
-                variant_groups.push(vec![
-                    Variant {
-                        suffix: "_x86",
-                        argument: quote!(test_utilities::Architecture::X86),
-                    },
-                    Variant {
-                        suffix: "_x64",
-                        argument: quote!(test_utilities::Architecture::X64),
-                    },
-                ]);
+        match category_variants
+            .iter()
+            .find(|(name, _)| *name == category.to_string())
+        {
+            Some((_, variants)) => variant_groups.push(variants()),
+            None => {
+                let known_categories = category_variants
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return syn::Error::new(
+                    category.span(),
+                    format!(
+                        "unknown test_for category \"{category}\"; expected one of: {known_categories}"
+                    ),
+                )
+                .to_compile_error()
+                .into();
             }
-            _ => panic!("expected \"architecture\""),
         }
     }
 
@@ -281,3 +281,137 @@ async fn PeekMessage_with_mouse_messages(architecture: Architecture, unicode: bo
     );
     Ok(())
 }
+
+#[test_for(architecture, unicode)]
+async fn PeekMessage_with_mouse_wheel_messages(
+    architecture: Architecture,
+    unicode: bool,
+) -> Result<()> {
+    const VK_SHIFT: u8 = 16;
+    const WM_MOUSEWHEEL: u32 = 522;
+    const WM_MOUSEHWHEEL: u32 = 526;
+
+    fn key_event(id: u8, state: bool) -> Event {
+        Event::SetKeyState { id, state }
+    }
+    fn button_event(button: MouseButton, state: bool) -> Event {
+        Event::SetMouseButtonState { button, state }
+    }
+    fn wheel_event(delta: i32, horizontal: bool) -> Event {
+        Event::ScrollMouseWheel { delta, horizontal }
+    }
+
+    init_test();
+    let messages = extract_messages_from_stdout(
+        &Instance::new("hooks/misc/PeekMessage", architecture)
+            .with_unicode_flag(unicode)
+            .with_events([
+                Event::SetMousePosition { x: 50, y: 60 },
+                wheel_event(120, false),
+                key_event(VK_SHIFT, true),
+                wheel_event(-120, true),
+                button_event(MouseButton::Left, true),
+                wheel_event(240, false),
+                Event::AdvanceTime(Duration::from_millis(100)),
+            ])
+            .stdout()
+            .await?,
+        &[WM_MOUSEWHEEL, WM_MOUSEHWHEEL],
+    );
+
+    let message = |a, b, c| Message::new(1, a, b, c);
+    assert_eq!(
+        messages,
+        [
+            message(WM_MOUSEWHEEL, 120 << 16, (60 << 16) | 50),
+            message(WM_MOUSEHWHEEL, (0xff88 << 16) | 0x4, (60 << 16) | 50),
+            message(WM_MOUSEWHEEL, (240 << 16) | 0x5, (60 << 16) | 50),
+        ]
+    );
+    Ok(())
+}
+
+#[test_for(architecture, unicode)]
+async fn PeekMessage_with_character_input(architecture: Architecture, unicode: bool) -> Result<()> {
+    const VK_A: u8 = 65;
+    const VK_SHIFT: u8 = 16;
+    const VK_CONTROL: u8 = 17;
+    const VK_CAPITAL: u8 = 20;
+    const VK_LEFT: u8 = 37;
+    const WM_KEYDOWN: u32 = 256;
+    const WM_KEYUP: u32 = 257;
+    const WM_CHAR: u32 = 258;
+
+    fn key_event(id: u8, state: bool) -> Event {
+        Event::SetKeyState { id, state }
+    }
+
+    init_test();
+    let messages = extract_messages_from_stdout(
+        &Instance::new("hooks/misc/PeekMessage", architecture)
+            .with_unicode_flag(unicode)
+            .with_events([
+                // unshifted, then auto-repeated
+                key_event(VK_A, true),
+                key_event(VK_A, true),
+                key_event(VK_A, false),
+                // shifted
+                key_event(VK_SHIFT, true),
+                key_event(VK_A, true),
+                key_event(VK_A, false),
+                key_event(VK_SHIFT, false),
+                // Caps Lock toggled on: letters invert case without Shift held
+                key_event(VK_CAPITAL, true),
+                key_event(VK_CAPITAL, false),
+                key_event(VK_A, true),
+                key_event(VK_A, false),
+                // Ctrl+letter produces a control code, regardless of Caps Lock
+                key_event(VK_CONTROL, true),
+                key_event(VK_A, true),
+                key_event(VK_A, false),
+                key_event(VK_CONTROL, false),
+                // Caps Lock toggled back off
+                key_event(VK_CAPITAL, true),
+                key_event(VK_CAPITAL, false),
+                // non-printable key: no WM_CHAR
+                key_event(VK_LEFT, true),
+                key_event(VK_LEFT, false),
+                Event::AdvanceTime(Duration::from_millis(50)),
+            ])
+            .stdout()
+            .await?,
+        &[WM_KEYDOWN, WM_KEYUP, WM_CHAR],
+    );
+
+    let message = |a, b, c| Message::new(1, a, b, c);
+    assert_eq!(
+        messages,
+        [
+            message(WM_KEYDOWN, u32::from(VK_A), 1),
+            message(WM_CHAR, u32::from(b'a'), 1),
+            message(WM_KEYDOWN, u32::from(VK_A), (1 << 30) | 1),
+            message(WM_CHAR, u32::from(b'a'), (1 << 30) | 1),
+            message(WM_KEYUP, u32::from(VK_A), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYDOWN, u32::from(VK_SHIFT), 1),
+            message(WM_KEYDOWN, u32::from(VK_A), 1),
+            message(WM_CHAR, u32::from(b'A'), 1),
+            message(WM_KEYUP, u32::from(VK_A), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYUP, u32::from(VK_SHIFT), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYDOWN, u32::from(VK_CAPITAL), 1),
+            message(WM_KEYUP, u32::from(VK_CAPITAL), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYDOWN, u32::from(VK_A), 1),
+            message(WM_CHAR, u32::from(b'A'), 1),
+            message(WM_KEYUP, u32::from(VK_A), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYDOWN, u32::from(VK_CONTROL), 1),
+            message(WM_KEYDOWN, u32::from(VK_A), 1),
+            message(WM_CHAR, 1, 1),
+            message(WM_KEYUP, u32::from(VK_A), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYUP, u32::from(VK_CONTROL), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYDOWN, u32::from(VK_CAPITAL), 1),
+            message(WM_KEYUP, u32::from(VK_CAPITAL), (1 << 31) | (1 << 30) | 1),
+            message(WM_KEYDOWN, u32::from(VK_LEFT), 1),
+            message(WM_KEYUP, u32::from(VK_LEFT), (1 << 31) | (1 << 30) | 1),
+        ]
+    );
+    Ok(())
+}